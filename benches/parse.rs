@@ -0,0 +1,134 @@
+//! Parse throughput of the class and dex parsers.
+//!
+//! The class inputs are synthetic and each one is dominated by a single parse
+//! phase (constant pool, method table, bytecode), so a regression can be
+//! pinned to the phase that caused it. The dex inputs are the sample app in
+//! `resource/` and a large generated file.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[path = "../libs/class_parser/src/test_util.rs"]
+mod class_util;
+#[path = "../libs/dex_parser/src/test_util.rs"]
+mod dex_util;
+mod encode {
+  pub use dex_parser::encode::encode_mutf8;
+}
+
+use class_util::ClassBuilder;
+use dex_util::{ClassDef, DexBuilder, Method};
+
+/// A small class shaped like typical javac output.
+fn representative_class() -> Vec<u8> {
+  let mut builder = ClassBuilder::new("com/example/Rectangle", Some("java/lang/Object"));
+  builder.interface("java/lang/Comparable");
+  builder.field(0x0002, "width", "I", vec![]);
+  builder.field(0x0002, "length", "I", vec![]);
+  let init = builder.method_ref("java/lang/Object", "<init>", "()V");
+  let width = builder.field_ref("com/example/Rectangle", "width", "I");
+  let [init_hi, init_lo] = init.to_be_bytes();
+  let [width_hi, width_lo] = width.to_be_bytes();
+  let code = builder.code_attribute(1, 1, &[0x2a, 0xb7, init_hi, init_lo, 0xb1], &[], vec![]);
+  builder.method(0x0001, "<init>", "()V", vec![code]);
+  // aload_0; iconst_0; putfield width; return
+  let code = builder.code_attribute(
+    2,
+    1,
+    &[0x2a, 0x03, 0xb5, width_hi, width_lo, 0xb1],
+    &[],
+    vec![],
+  );
+  builder.method(0x0001, "reset", "()V", vec![code]);
+  let source_file = builder.utf8("Rectangle.java").to_be_bytes();
+  let source_file = builder.attribute("SourceFile", &source_file);
+  builder.class_attribute(source_file);
+  builder.build()
+}
+
+fn constant_pool_heavy_class(entries: usize) -> Vec<u8> {
+  let mut builder = ClassBuilder::new("com/example/Constants", Some("java/lang/Object"));
+  for i in 0..entries / 8 {
+    builder.integer(i as i32);
+    builder.long(i as i64);
+    builder.string(&format!("string constant {i}"));
+    builder.method_ref("com/example/Callee", &format!("m{i}"), "(IJ)V");
+  }
+  builder.build()
+}
+
+fn method_heavy_class(methods: usize) -> Vec<u8> {
+  let mut builder = ClassBuilder::new("com/example/Methods", Some("java/lang/Object"));
+  for i in 0..methods {
+    builder.method(0x0401, &format!("m{i}"), "(ILjava/lang/String;)V", vec![]);
+  }
+  builder.build()
+}
+
+fn code_heavy_class(methods: usize, code_length: usize) -> Vec<u8> {
+  let mut builder = ClassBuilder::new("com/example/Code", Some("java/lang/Object"));
+  // bipush 1; sipush 2; ... return
+  let mut code = [0x10, 0x01, 0x11, 0x00, 0x02].repeat(code_length / 5);
+  code.push(0xb1);
+  for i in 0..methods {
+    let attribute = builder.code_attribute(1, 1, &code, &[], vec![]);
+    builder.method(0x0009, &format!("m{i}"), "()V", vec![attribute]);
+  }
+  builder.build()
+}
+
+fn synthetic_dex(classes: usize, methods_per_class: usize) -> Vec<u8> {
+  let mut builder = DexBuilder::default();
+  let object = builder.type_id("Ljava/lang/Object;");
+  // nop; ... return-void
+  let mut insns = vec![0x0000; 32];
+  insns.push(0x000e);
+  for i in 0..classes {
+    let class = format!("Lcom/example/C{i};");
+    let mut class_def = ClassDef::new(builder.type_id(&class)).superclass(object);
+    let field = builder.field(&class, "I", "value");
+    class_def = class_def.instance_field(field, 0x0002);
+    for j in 0..methods_per_class {
+      let method = builder.method(&class, &format!("m{j}"), "V", &["I", "Ljava/lang/String;"]);
+      class_def = class_def.virtual_method(Method::new(method, 0x0001).code(3, 3, 0, &insns));
+    }
+    builder.class(class_def);
+  }
+  builder.build()
+}
+
+fn bench_class(c: &mut Criterion) {
+  let mut group = c.benchmark_group("class");
+  let inputs = [
+    ("representative", representative_class()),
+    ("constant_pool", constant_pool_heavy_class(16_000)),
+    ("methods", method_heavy_class(2_000)),
+    ("code", code_heavy_class(200, 2_000)),
+  ];
+  for (name, bytes) in &inputs {
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_with_input(BenchmarkId::from_parameter(name), bytes, |b, bytes| {
+      b.iter(|| class_parser::parse(bytes).unwrap())
+    });
+  }
+  group.finish();
+}
+
+fn bench_dex(c: &mut Criterion) {
+  let mut group = c.benchmark_group("dex");
+  let inputs = [
+    (
+      "resource",
+      include_bytes!("../resource/classes.dex").to_vec(),
+    ),
+    ("synthetic", synthetic_dex(2_000, 10)),
+  ];
+  for (name, bytes) in &inputs {
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_with_input(BenchmarkId::from_parameter(name), bytes, |b, bytes| {
+      b.iter(|| dex_parser::parse(bytes).unwrap())
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_class, bench_dex);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Parse time of a code heavy class with and without
+//! `ParseOptions::skip_code`.
+//!
+//! Run with `cargo bench --bench skip_code`.
+use class_parser::ParseOptions;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+#[path = "../libs/class_parser/src/test_util.rs"]
+mod class_util;
+
+use class_util::ClassBuilder;
+
+/// `Large`, a class with `methods` static methods of `blocks * 4 + 1` bytes of
+/// code each.
+fn large_class(methods: usize, blocks: usize) -> Vec<u8> {
+  let mut builder = ClassBuilder::new("Large", Some("java/lang/Object"));
+  // iconst_1; iconst_2; iadd; pop; ... return
+  let mut code = [0x04, 0x05, 0x60, 0x57].repeat(blocks);
+  code.push(0xb1);
+  for i in 0..methods {
+    let attribute = builder.code_attribute(2, 0, &code, &[], vec![]);
+    builder.method(0x0009, &format!("m{i}"), "()V", vec![attribute]);
+  }
+  builder.build()
+}
+
+fn bench_skip_code(c: &mut Criterion) {
+  let bytes = large_class(200, 4_000);
+  let mut group = c.benchmark_group("skip_code");
+  group.throughput(Throughput::Bytes(bytes.len() as u64));
+  group.bench_function("full", |b| {
+    b.iter(|| class_parser::parse_with_options(&bytes, ParseOptions::default()).unwrap())
+  });
+  group.bench_function("skip_code", |b| {
+    let options = ParseOptions {
+      skip_code: true,
+      ..Default::default()
+    };
+    b.iter(|| class_parser::parse_with_options(&bytes, options).unwrap())
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench_skip_code);
+criterion_main!(benches);
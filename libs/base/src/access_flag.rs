@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AccessFlag {
   Public,
   Private,
@@ -46,6 +46,21 @@ const ACC_ENUM: u16 = 0x4000;
 // const ACC_CONSTRUCTOR: u32 = 0x10000;
 // const ACC_DECLARED_SYNCHRONIZED: u32 = 0x20000;
 
+/// Flags that are Java source modifiers, in the order the JLS recommends.
+const MODIFIERS: &[(AccessFlag, &str)] = &[
+  (AccessFlag::Public, "public"),
+  (AccessFlag::Protected, "protected"),
+  (AccessFlag::Private, "private"),
+  (AccessFlag::Abstract, "abstract"),
+  (AccessFlag::Static, "static"),
+  (AccessFlag::Final, "final"),
+  (AccessFlag::Transient, "transient"),
+  (AccessFlag::Volatile, "volatile"),
+  (AccessFlag::Synchronized, "synchronized"),
+  (AccessFlag::Native, "native"),
+  (AccessFlag::Strict, "strictfp"),
+];
+
 const CLASS_ACC: &[(u16, AccessFlag)] = &[
   (ACC_PUBLIC, AccessFlag::Public),
   (ACC_FINAL, AccessFlag::Final),
@@ -113,6 +128,56 @@ impl AccessFlags {
       .collect();
     Self(flags, flag)
   }
+
+  pub fn value(&self) -> u16 {
+    self.1
+  }
+
+  pub fn is_static(&self) -> bool {
+    self.1 & ACC_STATIC != 0
+  }
+
+  pub fn is_native(&self) -> bool {
+    self.1 & ACC_NATIVE != 0
+  }
+
+  pub fn is_abstract(&self) -> bool {
+    self.1 & ACC_ABSTRACT != 0
+  }
+
+  pub fn is_varargs(&self) -> bool {
+    self.contains(AccessFlag::Varargs)
+  }
+
+  /// Whether `flag` is set, as interpreted for the kind of item these flags
+  /// were parsed for.
+  pub fn contains(&self, flag: AccessFlag) -> bool {
+    self.0.contains(&flag)
+  }
+
+  /// The keyword declaring a class with these flags: `annotation` (an
+  /// `@interface`), `interface`, `enum` or `class`.
+  pub fn class_kind(&self) -> &'static str {
+    if self.contains(AccessFlag::Annotation) {
+      "annotation"
+    } else if self.contains(AccessFlag::Interface) {
+      "interface"
+    } else if self.contains(AccessFlag::Enum) {
+      "enum"
+    } else {
+      "class"
+    }
+  }
+
+  /// The flags that are source modifiers, e.g. `["public", "static"]`.
+  /// Flags with no keyword (`ACC_SYNTHETIC`, `ACC_BRIDGE`, ...) are left out.
+  pub fn to_modifiers(&self) -> Vec<&'static str> {
+    MODIFIERS
+      .iter()
+      .filter(|(flag, _)| self.0.contains(flag))
+      .map(|(_, modifier)| *modifier)
+      .collect()
+  }
 }
 
 impl Display for AccessFlags {
@@ -148,7 +213,7 @@ impl Display for AccessFlag {
       AccessFlag::Varargs => write!(f, "ACC_VARARGS"),
       AccessFlag::Native => write!(f, "ACC_NATIVE"),
       AccessFlag::Interface => write!(f, "ACC_INTERFACE"),
-      AccessFlag::Abstract => write!(f, "ACC_ABSTRACE"),
+      AccessFlag::Abstract => write!(f, "ACC_ABSTRACT"),
       AccessFlag::Strict => write!(f, "ACC_STRICT"),
       AccessFlag::Synthetic => write!(f, "ACC_SYNTHETIC"),
       AccessFlag::Annotation => write!(f, "ACC_ANNOTATION"),
@@ -157,3 +222,43 @@ impl Display for AccessFlag {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::AccessFlags;
+
+  #[test]
+  fn test_to_modifiers() {
+    // public static final synchronized varargs
+    let flags = AccessFlags::new_method_flag(0x00b9);
+    assert_eq!(
+      flags.to_modifiers(),
+      vec!["public", "static", "final", "synchronized"]
+    );
+    assert!(flags.is_varargs());
+    // private volatile transient: 0x0040 is volatile for a field, bridge for a
+    // method.
+    let flags = AccessFlags::new_field_flag(0x00c2);
+    assert_eq!(
+      flags.to_modifiers(),
+      vec!["private", "transient", "volatile"]
+    );
+    assert!(AccessFlags::new_method_flag(0x1040)
+      .to_modifiers()
+      .is_empty());
+  }
+
+  #[test]
+  fn test_class_kind() {
+    assert_eq!(AccessFlags::new_class_flag(0x0021).class_kind(), "class");
+    assert_eq!(AccessFlags::new_class_flag(0x4031).class_kind(), "enum");
+    assert_eq!(
+      AccessFlags::new_class_flag(0x0601).class_kind(),
+      "interface"
+    );
+    assert_eq!(
+      AccessFlags::new_class_flag(0x2601).class_kind(),
+      "annotation"
+    );
+  }
+}
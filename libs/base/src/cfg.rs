@@ -0,0 +1,350 @@
+//! Control-flow graphs of method bodies, built the same way for JVM and
+//! Dalvik bytecode from a description of how each instruction passes
+//! control on. Program counters are in the code's own unit: bytes for class
+//! files, 16-bit code units for dex.
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::Display,
+};
+
+/// How an instruction passes control on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Flow {
+  /// To the next instruction.
+  Continue,
+  /// To `target` or the next instruction: `ifeq`, `if-eqz`.
+  Branch(u32),
+  /// Only to `target`: `goto`.
+  Goto(u32),
+  /// To the subroutine at `target`, which returns to the next instruction:
+  /// `jsr`.
+  Call(u32),
+  /// To one of the targets, default included: `tableswitch`,
+  /// `packed-switch`.
+  Switch(Vec<u32>),
+  /// Out of the method, or to a target only known at run time: returns,
+  /// `athrow`.
+  Exit,
+  /// Back from the subroutine it ends to the instruction after each call of
+  /// it: `ret`.
+  Ret,
+}
+
+/// One instruction as the graph builder needs it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowInfo {
+  pub pc: u32,
+  /// Encoded size, so the pc right after the instruction is `pc + len`.
+  pub len: u32,
+  pub flow: Flow,
+}
+
+/// A `[start, end)` range of code whose exceptions `handler` catches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handler {
+  pub start: u32,
+  pub end: u32,
+  pub handler: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EdgeKind {
+  Fallthrough,
+  Branch,
+  Switch,
+  Exception,
+  /// From a `jsr` to the subroutine.
+  Call,
+  /// From a subroutine's `ret` to the instruction after a `jsr` calling it.
+  Return,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+  /// Start pc of the successor block.
+  pub target: u32,
+  pub kind: EdgeKind,
+}
+
+/// Straight-line code: only its first instruction is jumped to and only its
+/// last one passes control anywhere but to the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+  pub start: u32,
+  /// The pc right after the last instruction.
+  pub end: u32,
+  /// Pcs of the instructions, in order.
+  pub instructions: Vec<u32>,
+  /// Each target once, fallthrough first, then branches in instruction
+  /// order, then exception handlers, then subroutine returns.
+  pub successors: Vec<Edge>,
+}
+
+/// Basic blocks keyed by start pc.
+#[derive(Debug, Default)]
+pub struct ControlFlowGraph {
+  blocks: BTreeMap<u32, BasicBlock>,
+}
+
+impl ControlFlowGraph {
+  /// Splits `instructions`, in pc order, into blocks at the method entry,
+  /// at jump and handler targets and at the bounds of handled ranges, and
+  /// after every instruction that doesn't just continue. Targets that aren't
+  /// the start of an instruction get an edge but split nothing.
+  ///
+  /// A subroutine is the code reachable from a `Call` target without
+  /// following other calls; each `Ret` in it gets a `Return` edge to the
+  /// instruction after every call of the subroutine.
+  pub fn new(instructions: &[FlowInfo], handlers: &[Handler]) -> Self {
+    let pcs: BTreeSet<u32> = instructions.iter().map(|info| info.pc).collect();
+    let mut leaders = BTreeSet::new();
+    leaders.extend(instructions.first().map(|info| info.pc));
+    for info in instructions {
+      let next = info.pc + info.len;
+      match &info.flow {
+        Flow::Continue => continue,
+        Flow::Branch(target) | Flow::Goto(target) | Flow::Call(target) => {
+          leaders.insert(*target);
+        }
+        Flow::Switch(targets) => leaders.extend(targets),
+        Flow::Exit | Flow::Ret => {}
+      }
+      leaders.insert(next);
+    }
+    for handler in handlers {
+      leaders.extend([handler.start, handler.end, handler.handler]);
+    }
+    leaders.retain(|pc| pcs.contains(pc));
+
+    let mut graph = Self::default();
+    let mut current: Option<BasicBlock> = None;
+    // (subroutine, return address) of each call, and the blocks ending in
+    // a `Ret`.
+    let mut calls = vec![];
+    let mut rets = BTreeSet::new();
+    for info in instructions {
+      if leaders.contains(&info.pc) {
+        if let Some(mut block) = current.take() {
+          // The previous block ran into this one.
+          push_edge(&mut block.successors, info.pc, EdgeKind::Fallthrough);
+          graph.insert(block, handlers);
+        }
+      }
+      let block = current.get_or_insert_with(|| BasicBlock {
+        start: info.pc,
+        end: info.pc,
+        instructions: vec![],
+        successors: vec![],
+      });
+      block.instructions.push(info.pc);
+      block.end = info.pc + info.len;
+      let next = info.pc + info.len;
+      let edges: Vec<Edge> = match &info.flow {
+        Flow::Continue => continue,
+        Flow::Branch(target) => vec![
+          Edge {
+            target: next,
+            kind: EdgeKind::Fallthrough,
+          },
+          Edge {
+            target: *target,
+            kind: EdgeKind::Branch,
+          },
+        ],
+        Flow::Call(target) => {
+          calls.push((*target, next));
+          vec![
+            Edge {
+              target: next,
+              kind: EdgeKind::Fallthrough,
+            },
+            Edge {
+              target: *target,
+              kind: EdgeKind::Call,
+            },
+          ]
+        }
+        Flow::Goto(target) => vec![Edge {
+          target: *target,
+          kind: EdgeKind::Branch,
+        }],
+        Flow::Switch(targets) => targets
+          .iter()
+          .map(|target| Edge {
+            target: *target,
+            kind: EdgeKind::Switch,
+          })
+          .collect(),
+        Flow::Exit => vec![],
+        Flow::Ret => {
+          rets.insert(current.as_ref().unwrap().start);
+          vec![]
+        }
+      };
+      let mut block = current.take().unwrap();
+      for edge in edges {
+        // A branch past the end of the code leads nowhere.
+        if edge.kind != EdgeKind::Fallthrough || pcs.contains(&edge.target) {
+          push_edge(&mut block.successors, edge.target, edge.kind);
+        }
+      }
+      graph.insert(block, handlers);
+    }
+    if let Some(block) = current {
+      graph.insert(block, handlers);
+    }
+    for (subroutine, return_address) in calls {
+      if pcs.contains(&return_address) {
+        graph.link_returns(subroutine, return_address, &rets);
+      }
+    }
+    graph
+  }
+
+  /// Adds a `Return` edge to `return_address` from each block in `rets`
+  /// reachable from `subroutine` without entering another subroutine.
+  fn link_returns(&mut self, subroutine: u32, return_address: u32, rets: &BTreeSet<u32>) {
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![subroutine];
+    while let Some(start) = pending.pop() {
+      if !visited.insert(start) {
+        continue;
+      }
+      let Some(block) = self.blocks.get_mut(&start) else {
+        continue;
+      };
+      if rets.contains(&start) {
+        push_edge(&mut block.successors, return_address, EdgeKind::Return);
+      }
+      pending.extend(
+        block
+          .successors
+          .iter()
+          .filter(|edge| !matches!(edge.kind, EdgeKind::Call | EdgeKind::Return))
+          .map(|edge| edge.target),
+      );
+    }
+  }
+
+  /// Adds the edges to the handlers covering `block` and stores it.
+  fn insert(&mut self, mut block: BasicBlock, handlers: &[Handler]) {
+    for handler in handlers {
+      if (handler.start..handler.end).contains(&block.start) {
+        push_edge(&mut block.successors, handler.handler, EdgeKind::Exception);
+      }
+    }
+    self.blocks.insert(block.start, block);
+  }
+
+  /// In pc order.
+  pub fn blocks(&self) -> impl Iterator<Item = &BasicBlock> {
+    self.blocks.values()
+  }
+
+  /// The block starting at `start`.
+  pub fn block(&self, start: u32) -> Option<&BasicBlock> {
+    self.blocks.get(&start)
+  }
+
+  pub fn len(&self) -> usize {
+    self.blocks.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.blocks.is_empty()
+  }
+
+  /// Start pcs of the blocks with an edge to `start`, in pc order.
+  pub fn predecessors(&self, start: u32) -> Vec<u32> {
+    self
+      .blocks()
+      .filter(|block| block.successors.iter().any(|edge| edge.target == start))
+      .map(|block| block.start)
+      .collect()
+  }
+}
+
+/// Adds an edge to `target` unless there is one already.
+fn push_edge(successors: &mut Vec<Edge>, target: u32, kind: EdgeKind) {
+  if !successors.iter().any(|edge| edge.target == target) {
+    successors.push(Edge { target, kind });
+  }
+}
+
+impl Display for EdgeKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      EdgeKind::Fallthrough => "fallthrough",
+      EdgeKind::Branch => "branch",
+      EdgeKind::Switch => "switch",
+      EdgeKind::Exception => "exception",
+      EdgeKind::Call => "call",
+      EdgeKind::Return => "return",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl Display for ControlFlowGraph {
+  /// One `[start, end) -> kind target, ...` line per block.
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for block in self.blocks() {
+      write!(f, "[{}, {})", block.start, block.end)?;
+      let successors: Vec<String> = block
+        .successors
+        .iter()
+        .map(|edge| format!("{} {}", edge.kind, edge.target))
+        .collect();
+      if !successors.is_empty() {
+        write!(f, " -> {}", successors.join(", "))?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ControlFlowGraph, Flow, FlowInfo, Handler};
+
+  fn info(pc: u32, len: u32, flow: Flow) -> FlowInfo {
+    FlowInfo { pc, len, flow }
+  }
+
+  #[test]
+  fn test_blocks() {
+    let instructions = [
+      info(0, 1, Flow::Continue),
+      info(1, 3, Flow::Switch(vec![8, 4, 8])),
+      info(4, 2, Flow::Call(10)),
+      info(6, 2, Flow::Goto(0)),
+      info(8, 2, Flow::Exit),
+      info(10, 1, Flow::Continue),
+      info(11, 2, Flow::Ret),
+    ];
+    let handlers = [Handler {
+      start: 4,
+      end: 8,
+      handler: 10,
+    }];
+    let graph = ControlFlowGraph::new(&instructions, &handlers);
+
+    assert_eq!(
+      graph.to_string(),
+      "[0, 4) -> switch 8, switch 4\n\
+       [4, 6) -> fallthrough 6, call 10\n\
+       [6, 8) -> branch 0, exception 10\n\
+       [8, 10)\n\
+       [10, 13) -> return 6\n"
+    );
+    // [4, 6) already has an edge to its handler, the subroutine.
+    assert_eq!(graph.block(4).unwrap().successors.len(), 2);
+    assert_eq!(graph.block(10).unwrap().instructions, [10, 11]);
+    assert_eq!(graph.predecessors(0), [6]);
+    assert_eq!(graph.predecessors(6), [4, 10]);
+    assert_eq!(graph.predecessors(10), [4, 6]);
+    assert!(ControlFlowGraph::new(&[], &[]).is_empty());
+  }
+}
@@ -0,0 +1,140 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  fmt::Display,
+};
+
+/// How often each opcode was seen while decoding, split into the opcodes the
+/// decoder knows and the ones that fell through to its unknown case.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OpcodeCoverage {
+  known: BTreeMap<u8, (&'static str, usize)>,
+  unknown: BTreeMap<u8, usize>,
+}
+
+impl OpcodeCoverage {
+  /// Counts one instruction; `mnemonic` is `None` for an unknown opcode.
+  pub fn record(&mut self, opcode: u8, mnemonic: Option<&'static str>) {
+    match mnemonic {
+      Some(mnemonic) => self.known.entry(opcode).or_insert((mnemonic, 0)).1 += 1,
+      None => *self.unknown.entry(opcode).or_insert(0) += 1,
+    }
+  }
+
+  pub fn merge(&mut self, other: &OpcodeCoverage) {
+    for (opcode, (mnemonic, count)) in &other.known {
+      self.known.entry(*opcode).or_insert((mnemonic, 0)).1 += count;
+    }
+    for (opcode, count) in &other.unknown {
+      *self.unknown.entry(*opcode).or_insert(0) += count;
+    }
+  }
+
+  pub fn count(&self, opcode: u8) -> usize {
+    self
+      .known
+      .get(&opcode)
+      .map(|(_, count)| *count)
+      .unwrap_or(0)
+      + self.unknown.get(&opcode).copied().unwrap_or(0)
+  }
+
+  /// `(opcode, mnemonic, count)` of the known opcodes, most frequent first.
+  pub fn known(&self) -> Vec<(u8, &'static str, usize)> {
+    let mut known: Vec<_> = self
+      .known
+      .iter()
+      .map(|(opcode, (mnemonic, count))| (*opcode, *mnemonic, *count))
+      .collect();
+    known.sort_by_key(|entry| std::cmp::Reverse(entry.2));
+    known
+  }
+
+  /// Count of each known opcode keyed by its mnemonic; unknown opcodes have
+  /// no mnemonic and are left out.
+  pub fn histogram(&self) -> HashMap<&'static str, usize> {
+    let mut histogram = HashMap::new();
+    for (mnemonic, count) in self.known.values() {
+      *histogram.entry(*mnemonic).or_insert(0) += count;
+    }
+    histogram
+  }
+
+  /// `(opcode, count)` of the unknown opcodes, most frequent first.
+  pub fn unknown(&self) -> Vec<(u8, usize)> {
+    let mut unknown: Vec<_> = self
+      .unknown
+      .iter()
+      .map(|(opcode, count)| (*opcode, *count))
+      .collect();
+    unknown.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+    unknown
+  }
+}
+
+impl Display for OpcodeCoverage {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let known = self.known();
+    let unknown = self.unknown();
+    writeln!(
+      f,
+      "opcodes: {} distinct, {} instructions",
+      known.len(),
+      known.iter().map(|(_, _, count)| count).sum::<usize>()
+    )?;
+    for (opcode, mnemonic, count) in known {
+      writeln!(f, "\t0x{:02x}\t{}\t{}", opcode, mnemonic, count)?;
+    }
+    writeln!(
+      f,
+      "unknown opcodes: {} distinct, {} instructions",
+      unknown.len(),
+      unknown.iter().map(|(_, count)| count).sum::<usize>()
+    )?;
+    for (opcode, count) in unknown {
+      writeln!(f, "\t0x{:02x}\t{}", opcode, count)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::OpcodeCoverage;
+
+  #[test]
+  fn test_record_and_merge() {
+    let mut coverage = OpcodeCoverage::default();
+    coverage.record(0x0e, Some("return-void"));
+    coverage.record(0x12, Some("const/4"));
+    coverage.record(0x12, Some("const/4"));
+    coverage.record(0x3e, None);
+
+    let mut other = OpcodeCoverage::default();
+    other.record(0x3e, None);
+    other.record(0x0e, Some("return-void"));
+    other.record(0x0e, Some("return-void"));
+    coverage.merge(&other);
+
+    assert_eq!(
+      coverage.known(),
+      vec![(0x0e, "return-void", 3), (0x12, "const/4", 2)]
+    );
+    assert_eq!(coverage.unknown(), vec![(0x3e, 2)]);
+    assert_eq!(coverage.count(0x3e), 2);
+    assert_eq!(coverage.count(0x00), 0);
+    assert_eq!(
+      coverage.histogram(),
+      HashMap::from([("return-void", 3), ("const/4", 2)])
+    );
+    assert_eq!(
+      coverage.to_string(),
+      "opcodes: 2 distinct, 5 instructions\n\
+       \t0x0e\treturn-void\t3\n\
+       \t0x12\tconst/4\t2\n\
+       unknown opcodes: 1 distinct, 2 instructions\n\
+       \t0x3e\t2\n"
+    );
+  }
+}
@@ -0,0 +1,59 @@
+//! `Display` with a bound on how deeply nested structures are expanded, for
+//! attributes that contain attributes or annotations that contain
+//! annotations.
+
+use std::fmt::{Display, Formatter, Result};
+
+/// Printed in place of children past the depth limit.
+pub const ELIDED: &str = "...";
+
+/// How many more levels of nesting to expand; `None` for no limit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Depth(Option<usize>);
+
+impl Depth {
+  pub const UNLIMITED: Depth = Depth(None);
+
+  pub fn new(max_depth: Option<usize>) -> Self {
+    Self(max_depth)
+  }
+
+  /// The depth left for the children of an item printed at this depth, or
+  /// `None` if they should be replaced by `ELIDED`.
+  pub fn nested(self) -> Option<Depth> {
+    match self.0 {
+      Some(0) => None,
+      Some(depth) => Some(Depth(Some(depth - 1))),
+      None => Some(self),
+    }
+  }
+}
+
+pub trait DepthDisplay {
+  fn fmt_depth(&self, f: &mut Formatter<'_>, depth: Depth) -> Result;
+
+  /// A `Display` of `self` expanding at most `depth` levels of nesting.
+  fn display_depth(&self, depth: Depth) -> DepthLimited<'_, Self> {
+    DepthLimited(self, depth)
+  }
+}
+
+pub struct DepthLimited<'a, T: ?Sized>(&'a T, Depth);
+
+impl<T: DepthDisplay + ?Sized> Display for DepthLimited<'_, T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    self.0.fmt_depth(f, self.1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Depth;
+
+  #[test]
+  fn test_nested() {
+    assert_eq!(Depth::new(Some(1)).nested(), Some(Depth::new(Some(0))));
+    assert_eq!(Depth::new(Some(0)).nested(), None);
+    assert_eq!(Depth::UNLIMITED.nested(), Some(Depth::UNLIMITED));
+  }
+}
@@ -0,0 +1,310 @@
+//! Conversion between field descriptors (`[Ljava/lang/String;`) and Java
+//! source names (`java.lang.String[]`), shared by the class and dex parsers,
+//! and normalization of dex and class file type names to one form.
+//!
+//! Nested classes keep their binary `$` separator in both directions: the
+//! descriptor alone can't tell `Outer$Inner` from a class named `Outer$Inner`.
+
+use crate::access_flag::AccessFlags;
+
+const PRIMITIVES: &[(char, &str)] = &[
+  ('B', "byte"),
+  ('C', "char"),
+  ('D', "double"),
+  ('F', "float"),
+  ('I', "int"),
+  ('J', "long"),
+  ('S', "short"),
+  ('Z', "boolean"),
+  ('V', "void"),
+];
+
+/// `[[I` -> `int[][]`, `Ljava/lang/Object;` -> `java.lang.Object`.
+///
+/// Anything that isn't a well-formed descriptor is treated as an internal
+/// class name (`java/lang/Object`), as found in class file `Class` constants.
+pub fn descriptor_to_source(descriptor: &str) -> String {
+  let element = descriptor.trim_start_matches('[');
+  let dimensions = descriptor.len() - element.len();
+  let mut chars = element.chars();
+  let source = match (chars.next(), chars.next()) {
+    (Some(c), None) => PRIMITIVES
+      .iter()
+      .find(|(primitive, _)| *primitive == c)
+      .map(|(_, name)| name.to_string()),
+    (Some('L'), Some(_)) => element
+      .strip_prefix('L')
+      .and_then(|element| element.strip_suffix(';'))
+      .map(|name| name.replace('/', ".")),
+    _ => None,
+  };
+  let source = source.unwrap_or_else(|| element.replace('/', "."));
+  source + &"[]".repeat(dimensions)
+}
+
+/// `int[][]` -> `[[I`, `java.lang.Object` -> `Ljava/lang/Object;`.
+pub fn source_to_descriptor(source: &str) -> String {
+  let mut element = source.trim();
+  let mut dimensions = 0;
+  while let Some(stripped) = element.strip_suffix("[]") {
+    element = stripped.trim_end();
+    dimensions += 1;
+  }
+  let element = match PRIMITIVES.iter().find(|(_, name)| *name == element) {
+    Some((primitive, _)) => primitive.to_string(),
+    None => format!("L{};", element.replace('.', "/")),
+  };
+  "[".repeat(dimensions) + &element
+}
+
+/// Splits a method descriptor into its parameter and return type
+/// descriptors: `(Ljava/lang/String;[I)V` -> `(["Ljava/lang/String;", "[I"], "V")`.
+/// `None` if the descriptor is malformed.
+pub fn parse_method_descriptor(descriptor: &str) -> Option<(Vec<&str>, &str)> {
+  let mut rest = descriptor.strip_prefix('(')?;
+  let mut parameters = vec![];
+  while !rest.starts_with(')') {
+    let (parameter, remainder) = split_field_descriptor(rest)?;
+    parameters.push(parameter);
+    rest = remainder;
+  }
+  let return_type = &rest[1..];
+  let (_, remainder) = split_field_descriptor(return_type)?;
+  if !remainder.is_empty() {
+    return None;
+  }
+  Some((parameters, return_type))
+}
+
+/// Java source signature of a method, `public static int compute(java.lang.String, int)`.
+/// Constructors keep their `<init>` name and have no return type, the
+/// class initializer is `static {}`. A malformed descriptor is shown raw
+/// after the name.
+pub fn method_signature(access_flags: &AccessFlags, name: &str, descriptor: &str) -> String {
+  if name == "<clinit>" {
+    return "static {}".to_string();
+  }
+  let mut signature = modifiers(access_flags);
+  let Some((parameters, return_type)) = parse_method_descriptor(descriptor) else {
+    return format!("{}{}{}", signature, name, descriptor);
+  };
+  if name != "<init>" {
+    signature.push_str(&descriptor_to_source(return_type));
+    signature.push(' ');
+  }
+  let mut parameters: Vec<String> = parameters.into_iter().map(descriptor_to_source).collect();
+  if access_flags.is_varargs() {
+    if let Some(last) = parameters.last_mut() {
+      if let Some(element) = last.strip_suffix("[]") {
+        *last = format!("{}...", element);
+      }
+    }
+  }
+  format!("{}{}({})", signature, name, parameters.join(", "))
+}
+
+/// Java source declaration of a field, `private static final int MAX`.
+pub fn field_signature(access_flags: &AccessFlags, name: &str, descriptor: &str) -> String {
+  format!(
+    "{}{} {}",
+    modifiers(access_flags),
+    descriptor_to_source(descriptor),
+    name
+  )
+}
+
+/// The source modifiers, each followed by a space.
+fn modifiers(access_flags: &AccessFlags) -> String {
+  access_flags
+    .to_modifiers()
+    .iter()
+    .map(|modifier| format!("{} ", modifier))
+    .collect()
+}
+
+/// Internal name of the class a field descriptor refers to, through any
+/// array dimensions: `[[Ljava/lang/String;` -> `java/lang/String`. `None` for
+/// primitives and primitive arrays.
+pub fn referenced_class(descriptor: &str) -> Option<&str> {
+  descriptor
+    .trim_start_matches('[')
+    .strip_prefix('L')?
+    .strip_suffix(';')
+}
+
+/// The field descriptor at the start of `descriptor` and what follows it.
+fn split_field_descriptor(descriptor: &str) -> Option<(&str, &str)> {
+  let element = descriptor.trim_start_matches('[');
+  let dimensions = descriptor.len() - element.len();
+  let len = match element.chars().next()? {
+    'L' => element.find(';')? + 1,
+    c if PRIMITIVES.iter().any(|(primitive, _)| *primitive == c) => 1,
+    _ => return None,
+  };
+  Some(descriptor.split_at(dimensions + len))
+}
+
+/// Canonical form of a type named by either format: the field descriptor.
+/// Dex type descriptors are kept, class file internal names are wrapped,
+/// `com/foo/Bar` -> `Lcom/foo/Bar;`.
+///
+/// Class files name array classes by their descriptor already. A lone
+/// primitive letter is ambiguous; it is read as the primitive (`I` is `int`),
+/// not as a class `I` in the unnamed package.
+pub fn normalize_type_name(name: &str) -> String {
+  let is_descriptor = name.starts_with('[')
+    || (name.starts_with('L') && name.ends_with(';'))
+    || (name.len() == 1 && PRIMITIVES.iter().any(|(c, _)| name.starts_with(*c)));
+  if is_descriptor {
+    name.to_string()
+  } else {
+    format!("L{};", name)
+  }
+}
+
+/// Whether two type names, each a dex descriptor or a class file internal
+/// name, refer to the same type.
+pub fn same_type(a: &str, b: &str) -> bool {
+  normalize_type_name(a) == normalize_type_name(b)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_field_signature() {
+    let flags = AccessFlags::new_field_flag(0x001a);
+    assert_eq!(
+      field_signature(&flags, "MAX", "I"),
+      "private static final int MAX"
+    );
+    let flags = AccessFlags::new_field_flag(0x0000);
+    assert_eq!(
+      field_signature(&flags, "names", "[Ljava/lang/String;"),
+      "java.lang.String[] names"
+    );
+  }
+
+  #[test]
+  fn test_primitives() {
+    for (descriptor, source) in [
+      ("B", "byte"),
+      ("C", "char"),
+      ("D", "double"),
+      ("F", "float"),
+      ("I", "int"),
+      ("J", "long"),
+      ("S", "short"),
+      ("Z", "boolean"),
+      ("V", "void"),
+    ] {
+      assert_eq!(descriptor_to_source(descriptor), source);
+      assert_eq!(source_to_descriptor(source), descriptor);
+    }
+  }
+
+  #[test]
+  fn test_reference_types() {
+    assert_eq!(
+      descriptor_to_source("Ljava/lang/String;"),
+      "java.lang.String"
+    );
+    assert_eq!(
+      source_to_descriptor("java.lang.String"),
+      "Ljava/lang/String;"
+    );
+    assert_eq!(descriptor_to_source("LFoo;"), "Foo");
+    assert_eq!(source_to_descriptor("Foo"), "LFoo;");
+  }
+
+  #[test]
+  fn test_inner_classes() {
+    assert_eq!(
+      descriptor_to_source("Lcom/example/Outer$Inner;"),
+      "com.example.Outer$Inner"
+    );
+    assert_eq!(
+      source_to_descriptor("com.example.Outer$Inner$1"),
+      "Lcom/example/Outer$Inner$1;"
+    );
+  }
+
+  #[test]
+  fn test_arrays() {
+    assert_eq!(descriptor_to_source("[I"), "int[]");
+    assert_eq!(descriptor_to_source("[[[J"), "long[][][]");
+    assert_eq!(
+      descriptor_to_source("[[Ljava/lang/Object;"),
+      "java.lang.Object[][]"
+    );
+    assert_eq!(source_to_descriptor("int[]"), "[I");
+    assert_eq!(source_to_descriptor("boolean[][][]"), "[[[Z");
+    assert_eq!(
+      source_to_descriptor("java.lang.Object[][]"),
+      "[[Ljava/lang/Object;"
+    );
+    assert_eq!(source_to_descriptor("char [] []"), "[[C");
+  }
+
+  #[test]
+  fn test_internal_names() {
+    assert_eq!(descriptor_to_source("java/lang/Object"), "java.lang.Object");
+    // A single-letter class is not mistaken for a primitive once wrapped.
+    assert_eq!(descriptor_to_source("LI;"), "I");
+  }
+
+  #[test]
+  fn test_parse_method_descriptor() {
+    assert_eq!(
+      parse_method_descriptor("(Ljava/lang/String;I[[JLa/B;)V"),
+      Some((vec!["Ljava/lang/String;", "I", "[[J", "La/B;"], "V"))
+    );
+    assert_eq!(
+      parse_method_descriptor("()[Ljava/lang/Object;"),
+      Some((vec![], "[Ljava/lang/Object;"))
+    );
+    assert_eq!(parse_method_descriptor("(I"), None);
+    assert_eq!(parse_method_descriptor("(Ljava/lang/String)V"), None);
+    assert_eq!(parse_method_descriptor("(Q)V"), None);
+    assert_eq!(parse_method_descriptor("()VV"), None);
+    assert_eq!(parse_method_descriptor("I"), None);
+  }
+
+  #[test]
+  fn test_referenced_class() {
+    assert_eq!(
+      referenced_class("Ljava/lang/String;"),
+      Some("java/lang/String")
+    );
+    assert_eq!(referenced_class("[[La/B$C;"), Some("a/B$C"));
+    assert_eq!(referenced_class("[I"), None);
+    assert_eq!(referenced_class("J"), None);
+  }
+
+  #[test]
+  fn test_normalize_type_name() {
+    // Class file internal names and dex descriptors meet in descriptor form.
+    assert_eq!(normalize_type_name("com/foo/Bar"), "Lcom/foo/Bar;");
+    assert_eq!(normalize_type_name("Lcom/foo/Bar;"), "Lcom/foo/Bar;");
+    assert_eq!(
+      normalize_type_name("com/foo/Outer$Inner"),
+      "Lcom/foo/Outer$Inner;"
+    );
+    assert!(same_type("com/foo/Outer$Inner", "Lcom/foo/Outer$Inner;"));
+    assert!(!same_type("com/foo/Outer$Inner", "Lcom/foo/Outer;"));
+
+    // Array classes are descriptors in both formats.
+    assert_eq!(normalize_type_name("[[I"), "[[I");
+    assert!(same_type("[Ljava/lang/String;", "[Ljava/lang/String;"));
+    assert!(!same_type("[Ljava/lang/String;", "java/lang/String"));
+
+    // Primitives.
+    assert_eq!(normalize_type_name("I"), "I");
+    assert!(same_type("Z", "Z"));
+    assert!(!same_type("I", "J"));
+    // A one-letter class that isn't a primitive code.
+    assert_eq!(normalize_type_name("A"), "LA;");
+    assert!(same_type("A", "LA;"));
+  }
+}
@@ -31,6 +31,12 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+impl Error {
+  pub fn kind(&self) -> &ErrorKind {
+    &self.kind
+  }
+}
+
 impl<E: Into<ErrorKind>> From<E> for Error {
   fn from(error: E) -> Self {
     let kind = error.into();
@@ -49,6 +55,110 @@ pub enum ErrorKind {
   },
   #[error(transparent)]
   IoError(#[from] std::io::Error),
+  /// A recognized file format this crate can't parse.
+  #[error("unsupported format: {0}")]
+  UnsupportedFormat(String),
+  /// The input doesn't start with the expected magic. `signature_offset` is
+  /// where the magic was found further in, e.g. after a length prefix.
+  #[error("bad magic 0x{found:08x}, expected 0x{expected:08x}{}", skip_hint(.signature_offset))]
+  BadMagic {
+    found: u32,
+    expected: u32,
+    signature_offset: Option<usize>,
+  },
+  /// Input shorter than the fixed-size header of its format.
+  #[error("truncated header: expected at least {expected} bytes, got {actual}")]
+  TruncatedHeader { expected: usize, actual: usize },
+  /// A start offset past the end of the input.
+  #[error("offset {offset} is past the end of the {len} byte input")]
+  OffsetOutOfRange { offset: usize, len: usize },
+  /// A mandatory constant pool reference that is 0, past the pool, or names
+  /// the wrong kind of constant.
+  #[error("invalid constant pool index #{index}, expected a {expected} constant")]
+  InvalidConstantIndex { index: u16, expected: &'static str },
+  /// A method lookup, by name or name and descriptor, that matched nothing.
+  #[error("no method matches {query}")]
+  MethodNotFound { query: String },
+  /// A method lookup by name alone that matched several overloads.
+  #[error("{query} matches {}, add the descriptor", .candidates.join(", "))]
+  AmbiguousMethod {
+    query: String,
+    candidates: Vec<String>,
+  },
+  /// A class looked up by name among a set of inputs that none defines.
+  #[error("no input defines class {name}")]
+  ClassNotFound { name: String },
+  /// Code requested from an abstract or native method.
+  #[error("method {method} has no code")]
+  NoCode { method: String },
+  /// A declared count, nesting depth or code length over the
+  /// `ParseLimits` the input was parsed with.
+  #[error("the input exceeds the parse limits")]
+  LimitExceeded,
+  /// The parser panicked on malformed input, caught by a `parse_catching`.
+  #[error("parser panicked: {message}")]
+  Panicked { message: String },
+}
+
+fn skip_hint(signature_offset: &Option<usize>) -> String {
+  match signature_offset {
+    Some(offset) => format!(
+      " (the magic appears at offset {}, try skipping {} bytes)",
+      offset, offset
+    ),
+    None => String::new(),
+  }
+}
+
+/// An inconsistency in a file that still parsed, reported instead of failing
+/// so tampered or obfuscated inputs can be inspected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Warning {
+  /// An abstract or native method carrying a `Code` attribute.
+  #[error("method {method} is {flag} but has a Code attribute")]
+  UnexpectedCode { method: String, flag: &'static str },
+  /// A method that is neither abstract nor native without a `Code` attribute.
+  #[error("method {method} has no Code attribute")]
+  MissingCode { method: String },
+  /// Data appended after the end of a class or dex file.
+  #[error("{count} trailing bytes after the end of the file")]
+  TrailingBytes { count: usize },
+  /// A dex `code_item` whose `ins_size` exceeds `registers_size` or doesn't
+  /// match the method's signature.
+  #[error("method {method} has {registers} registers and {ins} ins, its signature needs {expected_ins} ins")]
+  RegisterCounts {
+    method: String,
+    registers: u16,
+    ins: u16,
+    expected_ins: u16,
+  },
+  /// A dex `header_item` whose `header_size` isn't the standard 0x70.
+  #[error("header_size is 0x{header_size:x}, expected 0x{expected:x}; the file may be a compact dex or corrupt")]
+  HeaderSize { header_size: u32, expected: u32 },
+  /// A dex `map_list` entry out of order, overlapping another, past the end
+  /// of the file or disagreeing with the header.
+  #[error("map item {item}: {problem}")]
+  InvalidMap { item: String, problem: String },
+  /// A dex `string_ids` entry out of UTF-16 order, repeating the previous
+  /// one or pointing outside the data section.
+  #[error("string_ids[{index}]: {problem}")]
+  InvalidStringId { index: usize, problem: String },
+  /// An instruction using a local variable past `max_locals`, or loading a
+  /// constant `ldc` can't load.
+  #[error("method {method} at pc {pc}: {problem}")]
+  InvalidCode {
+    method: String,
+    pc: u32,
+    problem: String,
+  },
+  /// A dex file close to or over its 16-bit `method_ids`/`field_ids` limit.
+  #[error("dex file {file}: {count} of {limit} {kind} references used")]
+  ReferenceLimit {
+    file: usize,
+    kind: &'static str,
+    count: usize,
+    limit: usize,
+  },
 }
 
 // impl Display for ErrorKind {
@@ -63,6 +173,11 @@ pub enum ErrorKind {
 
 impl<'a> From<Err<error::Error<&'a [u8]>>> for ErrorKind {
   fn from(e: Err<error::Error<&'a [u8]>>) -> Self {
-    ErrorKind::NomError { kind: e.to_owned() }
+    match &e {
+      Err::Error(error) | Err::Failure(error) if error.code == error::ErrorKind::TooLarge => {
+        ErrorKind::LimitExceeded
+      }
+      _ => ErrorKind::NomError { kind: e.to_owned() },
+    }
   }
 }
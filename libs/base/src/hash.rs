@@ -0,0 +1,18 @@
+//! Printing content hashes, so classes can be told apart or matched up across
+//! artifacts without keeping their bytes around.
+
+/// Lowercase hex, as `sha256sum` prints it.
+pub fn to_hex(hash: &[u8]) -> String {
+  hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::to_hex;
+
+  #[test]
+  fn test_to_hex() {
+    assert_eq!(to_hex(&[]), "");
+    assert_eq!(to_hex(&[0x00, 0x0f, 0xab, 0xff]), "000fabff");
+  }
+}
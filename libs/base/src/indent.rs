@@ -0,0 +1,60 @@
+//! `Display` with configurable indentation, for plain-text dumps read by
+//! tools that expand tabs differently.
+
+use std::fmt::{Display, Formatter, Result};
+
+/// How one level of nesting, and the gap between a label and its `:`, is
+/// written: a tab, or `width` spaces.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Indent(Option<usize>);
+
+impl Indent {
+  pub const TABS: Indent = Indent(None);
+
+  /// `None` for tabs.
+  pub fn new(width: Option<usize>) -> Self {
+    Self(width)
+  }
+
+  pub fn spaces(width: usize) -> Self {
+    Self(Some(width))
+  }
+
+  /// Leading whitespace for `level` levels of nesting.
+  pub fn level(self, level: usize) -> String {
+    match self.0 {
+      None => "\t".repeat(level),
+      Some(width) => " ".repeat(width * level),
+    }
+  }
+}
+
+pub trait IndentDisplay {
+  fn fmt_indent(&self, f: &mut Formatter<'_>, indent: Indent) -> Result;
+
+  /// A `Display` of `self` indented with `indent`.
+  fn display_indent(&self, indent: Indent) -> Indented<'_, Self> {
+    Indented(self, indent)
+  }
+}
+
+pub struct Indented<'a, T: ?Sized>(&'a T, Indent);
+
+impl<T: IndentDisplay + ?Sized> Display for Indented<'_, T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    self.0.fmt_indent(f, self.1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Indent;
+
+  #[test]
+  fn test_level() {
+    assert_eq!(Indent::TABS.level(2), "\t\t");
+    assert_eq!(Indent::spaces(4).level(2), " ".repeat(8));
+    assert_eq!(Indent::spaces(2).level(0), "");
+    assert_eq!(Indent::default(), Indent::new(None));
+  }
+}
@@ -0,0 +1,212 @@
+//! A class's members merged with those it inherits along its superclass
+//! chain, shared by the class and dex parsers, which resolve the chain
+//! across a set of class files or the files of a multidex app.
+
+use std::{collections::HashSet, fmt::Display};
+
+use crate::access_flag::{AccessFlag, AccessFlags};
+
+/// A field or method as the merge needs it.
+pub struct Member<'a> {
+  pub name: &'a str,
+  pub descriptor: String,
+  pub access_flags: &'a AccessFlags,
+  /// Java source declaration, see `descriptor::method_signature`.
+  pub signature: String,
+}
+
+/// A member of the merged listing; `declaring_class` is `None` for the
+/// class's own members.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InheritedMember {
+  pub declaring_class: Option<String>,
+  pub signature: String,
+}
+
+/// Own members first, then those of each superclass, nearest first.
+///
+/// Private members, constructors and static initializers are not inherited.
+/// A method overridden closer to the class is listed once, at its nearest
+/// declaration; hidden fields are still listed, as they still exist.
+#[derive(Debug, Default)]
+pub struct InheritedMembers {
+  class: String,
+  superclasses: Vec<String>,
+  missing_superclass: Option<String>,
+  fields: Vec<InheritedMember>,
+  methods: Vec<InheritedMember>,
+  /// `(name, descriptor)` of the methods listed so far.
+  seen_methods: HashSet<(String, String)>,
+}
+
+impl InheritedMembers {
+  /// `class` and the superclass names are Java source names, `a.b.C`.
+  pub fn new<'a>(
+    class: &str,
+    fields: impl IntoIterator<Item = Member<'a>>,
+    methods: impl IntoIterator<Item = Member<'a>>,
+  ) -> Self {
+    let mut members = Self {
+      class: class.to_string(),
+      ..Default::default()
+    };
+    members.add(None, fields, methods);
+    members
+  }
+
+  /// Adds the inherited members of the next superclass up the chain.
+  pub fn add_superclass<'a>(
+    &mut self,
+    superclass: &str,
+    fields: impl IntoIterator<Item = Member<'a>>,
+    methods: impl IntoIterator<Item = Member<'a>>,
+  ) {
+    self.superclasses.push(superclass.to_string());
+    self.add(Some(superclass), fields, methods);
+  }
+
+  /// Ends the chain at `superclass`, which is not among the inputs.
+  pub fn set_missing_superclass(&mut self, superclass: &str) {
+    self.missing_superclass = Some(superclass.to_string());
+  }
+
+  fn add<'a>(
+    &mut self,
+    declaring_class: Option<&str>,
+    fields: impl IntoIterator<Item = Member<'a>>,
+    methods: impl IntoIterator<Item = Member<'a>>,
+  ) {
+    let inherited = declaring_class.is_some();
+    let member = |signature: String| InheritedMember {
+      declaring_class: declaring_class.map(str::to_string),
+      signature,
+    };
+    for field in fields {
+      if !inherited || !field.access_flags.contains(AccessFlag::Private) {
+        self.fields.push(member(field.signature));
+      }
+    }
+    for method in methods {
+      let is_inherited = !method.access_flags.contains(AccessFlag::Private)
+        && method.name != "<init>"
+        && method.name != "<clinit>";
+      if inherited && !is_inherited {
+        continue;
+      }
+      let key = (method.name.to_string(), method.descriptor);
+      if self.seen_methods.insert(key) || !inherited {
+        self.methods.push(member(method.signature));
+      }
+    }
+  }
+
+  pub fn class(&self) -> &str {
+    &self.class
+  }
+
+  /// The superclasses found among the inputs, nearest first.
+  pub fn superclasses(&self) -> &[String] {
+    &self.superclasses
+  }
+
+  /// The first superclass that is not among the inputs, if the chain did
+  /// not end at a root class.
+  pub fn missing_superclass(&self) -> Option<&str> {
+    self.missing_superclass.as_deref()
+  }
+
+  pub fn fields(&self) -> &[InheritedMember] {
+    &self.fields
+  }
+
+  pub fn methods(&self) -> &[InheritedMember] {
+    &self.methods
+  }
+}
+
+impl Display for InheritedMember {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.signature)?;
+    if let Some(declaring_class) = &self.declaring_class {
+      write!(f, "\t(from {})", declaring_class)?;
+    }
+    Ok(())
+  }
+}
+
+impl Display for InheritedMembers {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "class {}", self.class)?;
+    write!(f, "superclasses: {}", self.superclasses.join(", "))?;
+    if let Some(missing) = &self.missing_superclass {
+      if !self.superclasses.is_empty() {
+        write!(f, " ")?;
+      }
+      write!(f, "({} is not among the inputs)", missing)?;
+    }
+    writeln!(f)?;
+    writeln!(f, "fields:")?;
+    for field in &self.fields {
+      writeln!(f, "\t{}", field)?;
+    }
+    writeln!(f, "methods:")?;
+    for method in &self.methods {
+      writeln!(f, "\t{}", method)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{InheritedMembers, Member};
+  use crate::access_flag::AccessFlags;
+
+  #[test]
+  fn test_merge() {
+    let public = AccessFlags::new_method_flag(0x0001);
+    let private = AccessFlags::new_method_flag(0x0002);
+    let member = |name, descriptor: &str, access_flags, signature: &str| Member {
+      name,
+      descriptor: descriptor.to_string(),
+      access_flags,
+      signature: signature.to_string(),
+    };
+    let mut members = InheritedMembers::new(
+      "a.B",
+      [member("count", "I", &private, "private int count")],
+      [
+        member("<init>", "()V", &public, "public <init>()"),
+        member("run", "()V", &public, "public void run()"),
+      ],
+    );
+    members.add_superclass(
+      "a.A",
+      [
+        member("secret", "I", &private, "private int secret"),
+        member("base", "I", &public, "public int base"),
+      ],
+      [
+        member("<init>", "()V", &public, "public <init>()"),
+        member("run", "()V", &public, "public void run()"),
+        member("helper", "()V", &private, "private void helper()"),
+        member("stop", "()V", &public, "public void stop()"),
+      ],
+    );
+    members.set_missing_superclass("java.lang.Object");
+
+    assert_eq!(members.superclasses(), ["a.A"]);
+    assert_eq!(
+      members.to_string(),
+      "class a.B\n\
+       superclasses: a.A (java.lang.Object is not among the inputs)\n\
+       fields:\n\
+       \tprivate int count\n\
+       \tpublic int base\t(from a.A)\n\
+       methods:\n\
+       \tpublic <init>()\n\
+       \tpublic void run()\n\
+       \tpublic void stop()\t(from a.A)\n"
+    );
+  }
+}
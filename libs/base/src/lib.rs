@@ -1,5 +1,16 @@
 pub mod access_flag;
+pub mod cfg;
+pub mod coverage;
+pub mod depth;
+pub mod descriptor;
 pub mod error;
+pub mod hash;
+pub mod indent;
+pub mod inherit;
+pub mod literal;
+pub mod method_handle;
+pub mod mutf8;
+pub mod parse_limits;
 
 pub trait RenderSource {
   fn render_file_info(&self) -> Vec<String>;
@@ -26,4 +37,15 @@ pub trait Parsable {
       .map(|(_, v)| v)
       .map_err(|e| e.into())
   }
+
+  /// Like `parse_from_u8`, also returning the input left after the parsed
+  /// item, so appended data can be detected.
+  fn parse_with_remainder(bytes: &[u8]) -> Result<(Self, &[u8]), crate::error::Error>
+  where
+    Self: Sized,
+  {
+    Self::parse::<nom::error::Error<_>>(bytes)
+      .map(|(remainder, v)| (v, remainder))
+      .map_err(|e| e.into())
+  }
 }
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+/// The initial value of a static field, as given by a class file
+/// `ConstantValue` attribute or a dex class's static values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralValue {
+  Boolean(bool),
+  Byte(i8),
+  Short(i16),
+  Char(u16),
+  Int(i32),
+  Long(i64),
+  Float(f32),
+  Double(f64),
+  String(String),
+  Null,
+}
+
+impl LiteralValue {
+  /// Narrows the `int` constant a class file stores for every field type
+  /// shorter than `long` to the type of `descriptor`.
+  pub fn from_int(value: i32, descriptor: &str) -> Self {
+    match descriptor {
+      "Z" => LiteralValue::Boolean(value != 0),
+      "B" => LiteralValue::Byte(value as i8),
+      "S" => LiteralValue::Short(value as i16),
+      "C" => LiteralValue::Char(value as u16),
+      _ => LiteralValue::Int(value),
+    }
+  }
+}
+
+/// Java source syntax: `10L`, `1.5f`, `'a'`, `"text"`.
+impl Display for LiteralValue {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LiteralValue::Boolean(value) => write!(f, "{}", value),
+      LiteralValue::Byte(value) => write!(f, "{}", value),
+      LiteralValue::Short(value) => write!(f, "{}", value),
+      LiteralValue::Char(value) => match char::from_u32(*value as u32) {
+        Some(c) => write!(f, "{:?}", c),
+        None => write!(f, "'\\u{:04x}'", value),
+      },
+      LiteralValue::Int(value) => write!(f, "{}", value),
+      LiteralValue::Long(value) => write!(f, "{}L", value),
+      LiteralValue::Float(value) => write!(f, "{:?}f", value),
+      LiteralValue::Double(value) => write!(f, "{:?}", value),
+      LiteralValue::String(value) => write!(f, "{:?}", value),
+      LiteralValue::Null => write!(f, "null"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::LiteralValue;
+
+  #[test]
+  fn test_from_int() {
+    assert_eq!(LiteralValue::from_int(1, "Z"), LiteralValue::Boolean(true));
+    assert_eq!(LiteralValue::from_int(-1, "B"), LiteralValue::Byte(-1));
+    assert_eq!(LiteralValue::from_int(0x61, "C"), LiteralValue::Char(0x61));
+    assert_eq!(LiteralValue::from_int(300, "S"), LiteralValue::Short(300));
+    assert_eq!(LiteralValue::from_int(7, "I"), LiteralValue::Int(7));
+  }
+
+  #[test]
+  fn test_display() {
+    assert_eq!(LiteralValue::Char(0x61).to_string(), "'a'");
+    assert_eq!(LiteralValue::Char(0xd800).to_string(), "'\\ud800'");
+    assert_eq!(LiteralValue::Long(10).to_string(), "10L");
+    assert_eq!(LiteralValue::Float(1.5).to_string(), "1.5f");
+    assert_eq!(LiteralValue::Double(2.0).to_string(), "2.0");
+    assert_eq!(
+      LiteralValue::String("a\"b".to_string()).to_string(),
+      "\"a\\\"b\""
+    );
+    assert_eq!(LiteralValue::Null.to_string(), "null");
+  }
+}
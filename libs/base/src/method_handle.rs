@@ -0,0 +1,51 @@
+//! Method handle reference kinds, shared by class file `CONSTANT_MethodHandle`
+//! entries and dex `method_handle_item`s.
+//!
+//! https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.3.5
+
+pub const REF_GET_FIELD: u8 = 1;
+pub const REF_GET_STATIC: u8 = 2;
+pub const REF_PUT_FIELD: u8 = 3;
+pub const REF_PUT_STATIC: u8 = 4;
+pub const REF_INVOKE_VIRTUAL: u8 = 5;
+pub const REF_INVOKE_STATIC: u8 = 6;
+pub const REF_INVOKE_SPECIAL: u8 = 7;
+pub const REF_NEW_INVOKE_SPECIAL: u8 = 8;
+pub const REF_INVOKE_INTERFACE: u8 = 9;
+
+/// Name of a JVM `reference_kind`, as `javap` prints it: `REF_invokeStatic`.
+pub fn method_handle_kind_name(kind: u8) -> &'static str {
+  match kind {
+    REF_GET_FIELD => "REF_getField",
+    REF_GET_STATIC => "REF_getStatic",
+    REF_PUT_FIELD => "REF_putField",
+    REF_PUT_STATIC => "REF_putStatic",
+    REF_INVOKE_VIRTUAL => "REF_invokeVirtual",
+    REF_INVOKE_STATIC => "REF_invokeStatic",
+    REF_INVOKE_SPECIAL => "REF_invokeSpecial",
+    REF_NEW_INVOKE_SPECIAL => "REF_newInvokeSpecial",
+    REF_INVOKE_INTERFACE => "REF_invokeInterface",
+    _ => "REF_unknown",
+  }
+}
+
+/// Whether `kind` refers to a field rather than a method.
+pub fn is_field_kind(kind: u8) -> bool {
+  (REF_GET_FIELD..=REF_PUT_STATIC).contains(&kind)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{is_field_kind, method_handle_kind_name};
+
+  #[test]
+  fn test_method_handle_kind_name() {
+    assert_eq!(method_handle_kind_name(1), "REF_getField");
+    assert_eq!(method_handle_kind_name(6), "REF_invokeStatic");
+    assert_eq!(method_handle_kind_name(8), "REF_newInvokeSpecial");
+    assert_eq!(method_handle_kind_name(0), "REF_unknown");
+    assert_eq!(method_handle_kind_name(10), "REF_unknown");
+    assert!(is_field_kind(4));
+    assert!(!is_field_kind(5));
+  }
+}
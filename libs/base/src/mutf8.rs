@@ -0,0 +1,128 @@
+//! Modified UTF-8, the string encoding shared by dex string data and class
+//! file `CONSTANT_Utf8` entries: NUL is written as `C0 80` and supplementary
+//! characters as two 3-byte surrogates.
+
+use nom::number::complete::be_u8;
+
+/// Decodes one character into its UTF-16 code units: the first unit in the
+/// low 16 bits, and for a 4-byte sequence the low surrogate in the high 16
+/// bits. Modified UTF-8 encodes supplementary characters as two 3-byte
+/// surrogates, which come back one unit at a time.
+fn parse_utf16<'a, E: nom::error::ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+) -> nom::IResult<&'a [u8], u32, E> {
+  let (bytes, one) = be_u8(bytes)?;
+  if one & 0x80 == 0 {
+    return Ok((bytes, one as u32));
+  }
+  let (bytes, two) = be_u8(bytes)?;
+  if one & 0x20 == 0 {
+    return Ok((bytes, ((one & 0x1f) as u32) << 6 | ((two & 0x3f) as u32)));
+  }
+  let (bytes, three) = be_u8(bytes)?;
+  if one & 0x10 == 0 {
+    return Ok((
+      bytes,
+      ((one & 0x0f) as u32) << 12 | ((two & 0x3f) as u32) << 6 | ((three & 0x3f) as u32),
+    ));
+  }
+  let (bytes, four) = be_u8(bytes)?;
+  let code_point = ((one & 0x07) as u32) << 18
+    | ((two & 0x3f) as u32) << 12
+    | ((three & 0x3f) as u32) << 6
+    | ((four & 0x3f) as u32);
+  let surrogate_pair =
+    (((code_point >> 10) + 0xd7c0) & 0xffff) | (((code_point & 0x03ff) + 0xdc00) << 16);
+  Ok((bytes, surrogate_pair))
+}
+
+fn parse_utf16_while<'a, E: nom::error::ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+  more: impl Fn(u8) -> bool,
+) -> nom::IResult<&'a [u8], Vec<u16>, E> {
+  let mut m_bytes = bytes;
+  let mut res = vec![];
+  while m_bytes.first().is_some_and(|&byte| more(byte)) {
+    let (bytes, ch) = parse_utf16::<E>(m_bytes)?;
+    let (leading, tailing) = (ch & 0xffff, ch >> 16);
+    res.push(leading as u16);
+    if tailing != 0 {
+      res.push(tailing as u16);
+    }
+    m_bytes = bytes;
+  }
+  Ok((m_bytes, res))
+}
+
+pub fn parse_utf16_str<'a, E: nom::error::ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+) -> nom::IResult<&'a [u8], Vec<u16>, E> {
+  parse_utf16_while(bytes, |byte| byte != 0)
+}
+
+/// Decodes NUL-terminated dex MUTF-8 string data, leaving the NUL in the
+/// returned input. Unpaired surrogates can't be held by a `String` and are
+/// replaced with U+FFFD.
+pub fn decode_mutf8<'a, E: nom::error::ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+) -> nom::IResult<&'a [u8], String, E> {
+  let (bytes, utf16) = parse_utf16_str(bytes)?;
+  Ok((bytes, String::from_utf16_lossy(&utf16)))
+}
+
+/// Decodes all of `bytes` as MUTF-8, as a class file's length-prefixed
+/// `CONSTANT_Utf8` is stored. A sequence cut off by the end of `bytes` is an
+/// error; unpaired surrogates become U+FFFD as in `decode_mutf8`.
+pub fn decode_mutf8_exact<'a, E: nom::error::ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+) -> nom::IResult<&'a [u8], String, E> {
+  let (bytes, utf16) = parse_utf16_while(bytes, |_| true)?;
+  Ok((bytes, String::from_utf16_lossy(&utf16)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{decode_mutf8, decode_mutf8_exact, parse_utf16_str};
+
+  fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    let (rest, units) = parse_utf16_str::<nom::error::Error<_>>(bytes).unwrap();
+    assert_eq!(rest, &[0]);
+    units
+  }
+
+  #[test]
+  fn test_surrogate_pairs() {
+    // U+1F600 as a MUTF-8 surrogate pair and as a 4-byte UTF-8 sequence.
+    let mutf8 = [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, 0];
+    let utf8 = [0xf0, 0x9f, 0x98, 0x80, 0];
+    assert_eq!(utf16_units(&mutf8), [0xd83d, 0xde00]);
+    assert_eq!(utf16_units(&utf8), [0xd83d, 0xde00]);
+    // U+10000 and U+10FFFF, the ends of the supplementary range.
+    assert_eq!(utf16_units(&[0xf0, 0x90, 0x80, 0x80, 0]), [0xd800, 0xdc00]);
+    assert_eq!(utf16_units(&[0xf4, 0x8f, 0xbf, 0xbf, 0]), [0xdbff, 0xdfff]);
+
+    let (_, decoded) = decode_mutf8::<nom::error::Error<_>>(&mutf8).unwrap();
+    assert_eq!(decoded, "\u{1f600}");
+    let (_, decoded) = decode_mutf8::<nom::error::Error<_>>(&utf8).unwrap();
+    assert_eq!(decoded, "\u{1f600}");
+
+    // A high surrogate with no low one can't go into a String.
+    let (_, decoded) = decode_mutf8::<nom::error::Error<_>>(&[b'a', 0xed, 0xa0, 0xbd, 0]).unwrap();
+    assert_eq!(decoded, "a\u{fffd}");
+  }
+
+  #[test]
+  fn test_decode_exact() {
+    // javac writes "a\u0000b" with a 2-byte NUL and no terminator.
+    let (rest, decoded) =
+      decode_mutf8_exact::<nom::error::Error<_>>(&[b'a', 0xc0, 0x80, b'b']).unwrap();
+    assert_eq!((decoded.as_str(), rest), ("a\0b", &[][..]));
+
+    let mutf8 = [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+    let (_, decoded) = decode_mutf8_exact::<nom::error::Error<_>>(&mutf8).unwrap();
+    assert_eq!(decoded, "\u{1f600}");
+
+    // A sequence cut short by the end of the bytes is an error.
+    assert!(decode_mutf8_exact::<nom::error::Error<_>>(&[b'a', 0xc0]).is_err());
+  }
+}
@@ -0,0 +1,131 @@
+//! Bounds on what the parsers accept from untrusted input: a declared count
+//! of billions of items or values nested thousands deep is rejected up front
+//! instead of exhausting memory or the stack.
+
+use nom::{
+  error::{ErrorKind, ParseError},
+  Err, IResult, Parser,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+  /// Most items a single declared count may ask for: ids, members,
+  /// attributes, table entries, array elements.
+  pub max_items: usize,
+  /// How deep arrays and annotations may nest inside a value.
+  pub max_depth: usize,
+  /// Longest method body, in the code's own unit: bytes for class files,
+  /// 16-bit code units for dex.
+  pub max_code_len: usize,
+}
+
+impl ParseLimits {
+  /// Well above what compilers produce: the largest dex files hold a few
+  /// million strings, and annotations stay within a few levels.
+  pub const DEFAULT: ParseLimits = ParseLimits {
+    max_items: 1 << 24,
+    max_depth: 64,
+    max_code_len: 1 << 20,
+  };
+
+  pub const UNLIMITED: ParseLimits = ParseLimits {
+    max_items: usize::MAX,
+    max_depth: usize::MAX,
+    max_code_len: usize::MAX,
+  };
+
+  /// `nom::multi::count`, failing before parsing anything when `n` is over
+  /// `max_items`.
+  pub fn count<'a, O, E, F>(
+    &self,
+    f: F,
+    n: usize,
+  ) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<O>, E>
+  where
+    F: Parser<&'a [u8], O, E>,
+    E: ParseError<&'a [u8]>,
+  {
+    let limits = *self;
+    let mut count = nom::multi::count(f, n);
+    move |bytes| {
+      limits.check_items(bytes, n)?;
+      count(bytes)
+    }
+  }
+
+  /// For counts read without `count`, e.g. a loop over wide constants.
+  pub fn check_items<'a, E: ParseError<&'a [u8]>>(
+    &self,
+    bytes: &'a [u8],
+    n: usize,
+  ) -> Result<(), Err<E>> {
+    if n > self.max_items {
+      return Err(too_large(bytes));
+    }
+    Ok(())
+  }
+
+  /// Fails when opening an array or annotation `depth` levels deep, counted
+  /// from 0, would nest more than `max_depth` levels.
+  pub fn check_depth<'a, E: ParseError<&'a [u8]>>(
+    &self,
+    bytes: &'a [u8],
+    depth: usize,
+  ) -> Result<(), Err<E>> {
+    if depth >= self.max_depth {
+      return Err(too_large(bytes));
+    }
+    Ok(())
+  }
+
+  pub fn check_code_len<'a, E: ParseError<&'a [u8]>>(
+    &self,
+    bytes: &'a [u8],
+    code_len: usize,
+  ) -> Result<(), Err<E>> {
+    if code_len > self.max_code_len {
+      return Err(too_large(bytes));
+    }
+    Ok(())
+  }
+}
+
+impl Default for ParseLimits {
+  fn default() -> Self {
+    Self::DEFAULT
+  }
+}
+
+/// A `Failure`, so that no alternative is tried on the same input. It
+/// becomes `error::ErrorKind::LimitExceeded`.
+fn too_large<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> Err<E> {
+  Err::Failure(E::from_error_kind(bytes, ErrorKind::TooLarge))
+}
+
+#[cfg(test)]
+mod tests {
+  use nom::{number::complete::be_u8, IResult};
+
+  use super::ParseLimits;
+
+  #[test]
+  fn test_count() {
+    let limits = ParseLimits {
+      max_items: 2,
+      ..ParseLimits::DEFAULT
+    };
+    let result: IResult<_, _, ()> = limits.count(be_u8, 2)(&[1, 2, 3][..]);
+    assert_eq!(result, Ok((&[3][..], vec![1, 2])));
+    // Rejected without reading, even with enough input.
+    let result: IResult<_, _, ()> = limits.count(be_u8, 3)(&[1, 2, 3][..]);
+    assert_eq!(result, Err(nom::Err::Failure(())));
+    let result: IResult<_, _, ()> = ParseLimits::UNLIMITED.count(be_u8, 3)(&[1, 2, 3][..]);
+    assert!(result.is_ok());
+
+    // 64 levels, 0 to 63, may be opened.
+    assert!(limits.check_depth::<()>(&[], 63).is_ok());
+    assert!(limits.check_depth::<()>(&[], 64).is_err());
+    assert!(limits.check_code_len::<()>(&[], 1 << 20).is_ok());
+    assert!(limits.check_code_len::<()>(&[], (1 << 20) + 1).is_err());
+  }
+}
@@ -0,0 +1,597 @@
+use std::{fmt::Display, sync::Arc};
+
+use nom::{
+  error::ParseError,
+  number::complete::{be_u16, be_u8},
+  IResult,
+};
+
+use base::{
+  depth::{Depth, DepthDisplay, ELIDED},
+  error::Error,
+  parse_limits::ParseLimits,
+};
+
+use crate::context::ResolveContext;
+
+pub const RETENTION_DESCRIPTOR: &str = "Ljava/lang/annotation/Retention;";
+pub const TARGET_DESCRIPTOR: &str = "Ljava/lang/annotation/Target;";
+pub const DOCUMENTED_DESCRIPTOR: &str = "Ljava/lang/annotation/Documented;";
+
+/// How deep arrays and annotations may nest inside an element value by
+/// default, see `ParseLimits::max_depth`. Real annotations stay within a few
+/// levels; the bound keeps crafted input from overflowing the stack.
+pub const MAX_ELEMENT_VALUE_NESTING: usize = ParseLimits::DEFAULT.max_depth;
+
+/// `RuntimeVisibleAnnotations` and `RuntimeInvisibleAnnotations`.
+///
+/// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.16
+#[derive(Clone)]
+pub struct Annotations {
+  annotations: Vec<Annotation>,
+}
+
+impl Annotations {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, num_annotations) = be_u16(bytes)?;
+    let (bytes, annotations) = context.options().limits.count(
+      |bytes| Annotation::parse(bytes, context),
+      num_annotations as usize,
+    )(bytes)?;
+    Ok((bytes, Self { annotations }))
+  }
+
+  pub fn annotations(&self) -> &[Annotation] {
+    &self.annotations
+  }
+
+  /// See `Annotation::check_references`.
+  pub(crate) fn check_references(&self) -> Result<(), Error> {
+    self
+      .annotations
+      .iter()
+      .try_for_each(Annotation::check_references)
+  }
+}
+
+impl Display for Annotations {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for Annotations {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
+    write!(f, "annotations({}): ", self.annotations.len())?;
+    let Some(depth) = depth.nested() else {
+      if !self.annotations.is_empty() {
+        write!(f, "{}", ELIDED)?;
+      }
+      return Ok(());
+    };
+    for annotation in &self.annotations {
+      write!(f, "{} ", annotation.display_depth(depth))?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Clone)]
+pub struct Annotation {
+  type_index: u16,
+  element_value_pairs: Vec<(u16, ElementValue)>,
+  context: Arc<ResolveContext>,
+}
+
+/// The value of an annotation element. Every variant holds constant pool
+/// indices, resolved through the `Annotation` it belongs to.
+#[derive(Clone)]
+pub enum ElementValue {
+  /// One of the tags `B C D F I J S Z s` and the index of its constant.
+  Const(u8, u16),
+  Enum {
+    type_name_index: u16,
+    const_name_index: u16,
+  },
+  Class(u16),
+  Annotation(Annotation),
+  Array(Vec<ElementValue>),
+}
+
+impl Annotation {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
+    Self::parse_nested(bytes, context, 0)
+  }
+
+  /// `parse` for an annotation `nesting` levels inside an element value.
+  fn parse_nested<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+    nesting: usize,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, type_index) = be_u16(bytes)?;
+    let (bytes, num_element_value_pairs) = be_u16(bytes)?;
+    let (bytes, element_value_pairs) = context.options().limits.count(
+      |bytes| {
+        let (bytes, element_name_index) = be_u16(bytes)?;
+        let (bytes, value) = ElementValue::parse_nested(bytes, context, nesting)?;
+        Ok((bytes, (element_name_index, value)))
+      },
+      num_element_value_pairs as usize,
+    )(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        type_index,
+        element_value_pairs,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  /// Fails with `ErrorKind::InvalidConstantIndex` if the type or an element
+  /// name, here or in a nested annotation, isn't a `Utf8` constant.
+  pub(crate) fn check_references(&self) -> Result<(), Error> {
+    self.context.require_str_const(self.type_index)?;
+    for (name_index, value) in &self.element_value_pairs {
+      self.context.require_str_const(*name_index)?;
+      value.check_references()?;
+    }
+    Ok(())
+  }
+
+  /// Field descriptor of the annotation type, e.g. `Ljava/lang/Deprecated;`;
+  /// `None` if `type_index` isn't a `Utf8` constant.
+  pub fn type_descriptor(&self) -> Option<&str> {
+    self.context.find_str_const(self.type_index)
+  }
+
+  pub fn element(&self, name: &str) -> Option<&ElementValue> {
+    self
+      .element_value_pairs
+      .iter()
+      .find(|(name_index, _)| self.context.find_str_const(*name_index) == Some(name))
+      .map(|(_, value)| value)
+  }
+
+  /// Constant names of the enum element `name`, which may be a single value
+  /// or an array of values.
+  pub fn enum_constants(&self, name: &str) -> Vec<&str> {
+    match self.element(name) {
+      Some(ElementValue::Array(values)) => values
+        .iter()
+        .filter_map(|value| self.enum_constant(value))
+        .collect(),
+      Some(value) => self.enum_constant(value).into_iter().collect(),
+      None => vec![],
+    }
+  }
+
+  fn enum_constant(&self, value: &ElementValue) -> Option<&str> {
+    match value {
+      ElementValue::Enum {
+        const_name_index, ..
+      } => self.context.find_str_const(*const_name_index),
+      _ => None,
+    }
+  }
+
+  /// `value` as an element of this annotation, expanding nested annotations
+  /// and arrays at most `depth` levels.
+  fn fmt_value(
+    &self,
+    value: &ElementValue,
+    f: &mut std::fmt::Formatter<'_>,
+    depth: Depth,
+  ) -> std::fmt::Result {
+    match value {
+      ElementValue::Const(b's', index) => match self.context.find_str_const(*index) {
+        Some(value) => write!(f, "{:?}", value),
+        None => write!(f, "#{}", index),
+      },
+      ElementValue::Const(_, index) => match self.context.find_constant(*index) {
+        Some(constant) => write!(f, "{}", constant.info()),
+        None => write!(f, "#{}", index),
+      },
+      ElementValue::Enum {
+        type_name_index,
+        const_name_index,
+      } => write!(
+        f,
+        "{}.{}",
+        self.context.find_str_const(*type_name_index).unwrap_or("?"),
+        self
+          .context
+          .find_str_const(*const_name_index)
+          .unwrap_or("?")
+      ),
+      ElementValue::Class(index) => {
+        write!(f, "{}", self.context.find_str_const(*index).unwrap_or("?"))
+      }
+      ElementValue::Annotation(annotation) => annotation.fmt_depth(f, depth),
+      ElementValue::Array(values) => {
+        write!(f, "{{")?;
+        match depth.nested() {
+          Some(depth) => {
+            for (i, value) in values.iter().enumerate() {
+              if i > 0 {
+                write!(f, ", ")?;
+              }
+              self.fmt_value(value, f, depth)?;
+            }
+          }
+          None if !values.is_empty() => write!(f, "{}", ELIDED)?,
+          None => {}
+        }
+        write!(f, "}}")
+      }
+    }
+  }
+}
+
+impl Display for Annotation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for Annotation {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
+    match self.type_descriptor() {
+      Some(descriptor) => write!(f, "@{}", descriptor)?,
+      None => write!(f, "@#{}", self.type_index)?,
+    }
+    if self.element_value_pairs.is_empty() {
+      return Ok(());
+    }
+    let Some(depth) = depth.nested() else {
+      return write!(f, "({})", ELIDED);
+    };
+    write!(f, "(")?;
+    for (i, (name_index, value)) in self.element_value_pairs.iter().enumerate() {
+      if i > 0 {
+        write!(f, ", ")?;
+      }
+      write!(
+        f,
+        "{}=",
+        self.context.find_str_const(*name_index).unwrap_or("?")
+      )?;
+      self.fmt_value(value, f, depth)?;
+    }
+    write!(f, ")")
+  }
+}
+
+impl ElementValue {
+  /// The annotations nested in the value.
+  fn check_references(&self) -> Result<(), Error> {
+    match self {
+      ElementValue::Annotation(annotation) => annotation.check_references(),
+      ElementValue::Array(values) => values.iter().try_for_each(ElementValue::check_references),
+      _ => Ok(()),
+    }
+  }
+
+  /// Fails with `ErrorKind::TooLarge` on arrays and annotations nested
+  /// deeper than the context's `ParseLimits::max_depth`,
+  /// `MAX_ELEMENT_VALUE_NESTING` by default.
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
+    Self::parse_nested(bytes, context, 0)
+  }
+
+  fn parse_nested<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+    nesting: usize,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, tag) = be_u8(bytes)?;
+    let limits = context.options().limits;
+    if matches!(tag, b'@' | b'[') {
+      limits.check_depth(bytes, nesting)?;
+    }
+    match tag {
+      b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => {
+        let (bytes, const_value_index) = be_u16(bytes)?;
+        Ok((bytes, ElementValue::Const(tag, const_value_index)))
+      }
+      b'e' => {
+        let (bytes, type_name_index) = be_u16(bytes)?;
+        let (bytes, const_name_index) = be_u16(bytes)?;
+        Ok((
+          bytes,
+          ElementValue::Enum {
+            type_name_index,
+            const_name_index,
+          },
+        ))
+      }
+      b'c' => {
+        let (bytes, class_info_index) = be_u16(bytes)?;
+        Ok((bytes, ElementValue::Class(class_info_index)))
+      }
+      b'@' => {
+        let (bytes, annotation) = Annotation::parse_nested(bytes, context, nesting + 1)?;
+        Ok((bytes, ElementValue::Annotation(annotation)))
+      }
+      b'[' => {
+        let (bytes, num_values) = be_u16(bytes)?;
+        let (bytes, values) = limits.count(
+          |bytes| ElementValue::parse_nested(bytes, context, nesting + 1),
+          num_values as usize,
+        )(bytes)?;
+        Ok((bytes, ElementValue::Array(values)))
+      }
+      _ => Err(nom::Err::Error(E::from_error_kind(
+        bytes,
+        nom::error::ErrorKind::Tag,
+      ))),
+    }
+  }
+}
+
+/// `java.lang.annotation.RetentionPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPolicy {
+  Source,
+  Class,
+  Runtime,
+}
+
+impl RetentionPolicy {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "SOURCE" => Some(Self::Source),
+      "CLASS" => Some(Self::Class),
+      "RUNTIME" => Some(Self::Runtime),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Source => "SOURCE",
+      Self::Class => "CLASS",
+      Self::Runtime => "RUNTIME",
+    }
+  }
+}
+
+impl Display for RetentionPolicy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// `java.lang.annotation.ElementType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+  Type,
+  Field,
+  Method,
+  Parameter,
+  Constructor,
+  LocalVariable,
+  AnnotationType,
+  Package,
+  TypeParameter,
+  TypeUse,
+  Module,
+  RecordComponent,
+}
+
+const ELEMENT_TYPES: &[(ElementType, &str)] = &[
+  (ElementType::Type, "TYPE"),
+  (ElementType::Field, "FIELD"),
+  (ElementType::Method, "METHOD"),
+  (ElementType::Parameter, "PARAMETER"),
+  (ElementType::Constructor, "CONSTRUCTOR"),
+  (ElementType::LocalVariable, "LOCAL_VARIABLE"),
+  (ElementType::AnnotationType, "ANNOTATION_TYPE"),
+  (ElementType::Package, "PACKAGE"),
+  (ElementType::TypeParameter, "TYPE_PARAMETER"),
+  (ElementType::TypeUse, "TYPE_USE"),
+  (ElementType::Module, "MODULE"),
+  (ElementType::RecordComponent, "RECORD_COMPONENT"),
+];
+
+impl ElementType {
+  pub fn from_name(name: &str) -> Option<Self> {
+    ELEMENT_TYPES
+      .iter()
+      .find(|(_, n)| *n == name)
+      .map(|(element_type, _)| *element_type)
+  }
+
+  pub fn name(&self) -> &'static str {
+    ELEMENT_TYPES
+      .iter()
+      .find(|(element_type, _)| element_type == self)
+      .map(|(_, name)| *name)
+      .unwrap()
+  }
+}
+
+impl Display for ElementType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::RenderSource;
+
+  use super::{ElementType, ElementValue, RetentionPolicy, MAX_ELEMENT_VALUE_NESTING};
+  use crate::{raw_class::ClassFile, test_util::ClassBuilder};
+
+  #[test]
+  fn test_meta_annotations() {
+    let mut builder = ClassBuilder::new("a/Marker", Some("java/lang/Object")).access_flags(0x2601);
+    builder.interface("java/lang/annotation/Annotation");
+    let value = builder.utf8("value");
+    let enum_value = |builder: &mut ClassBuilder, type_name: &str, const_name: &str| {
+      let mut bytes = vec![b'e'];
+      bytes.extend_from_slice(&builder.utf8(type_name).to_be_bytes());
+      bytes.extend_from_slice(&builder.utf8(const_name).to_be_bytes());
+      bytes
+    };
+
+    let mut info = 3u16.to_be_bytes().to_vec();
+    // @Retention(RUNTIME)
+    info.extend_from_slice(
+      &builder
+        .utf8("Ljava/lang/annotation/Retention;")
+        .to_be_bytes(),
+    );
+    info.extend_from_slice(&1u16.to_be_bytes());
+    info.extend_from_slice(&value.to_be_bytes());
+    let runtime = enum_value(
+      &mut builder,
+      "Ljava/lang/annotation/RetentionPolicy;",
+      "RUNTIME",
+    );
+    info.extend_from_slice(&runtime);
+    // @Target({TYPE, METHOD})
+    info.extend_from_slice(&builder.utf8("Ljava/lang/annotation/Target;").to_be_bytes());
+    info.extend_from_slice(&1u16.to_be_bytes());
+    info.extend_from_slice(&value.to_be_bytes());
+    info.push(b'[');
+    info.extend_from_slice(&2u16.to_be_bytes());
+    for name in ["TYPE", "METHOD"] {
+      let target = enum_value(&mut builder, "Ljava/lang/annotation/ElementType;", name);
+      info.extend_from_slice(&target);
+    }
+    // @Documented
+    info.extend_from_slice(
+      &builder
+        .utf8("Ljava/lang/annotation/Documented;")
+        .to_be_bytes(),
+    );
+    info.extend_from_slice(&0u16.to_be_bytes());
+    let attribute = builder.attribute("RuntimeVisibleAnnotations", &info);
+    builder.class_attribute(attribute);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(class_file.retention(), Some(RetentionPolicy::Runtime));
+    assert_eq!(
+      class_file.targets(),
+      Some(vec![ElementType::Type, ElementType::Method])
+    );
+    assert!(class_file.is_documented());
+    assert_eq!(
+      class_file.annotations()[0].to_string(),
+      "@Ljava/lang/annotation/Retention;(value=Ljava/lang/annotation/RetentionPolicy;.RUNTIME)"
+    );
+    let class_info = class_file.render_class_info();
+    assert!(class_info.contains(&"retention: RUNTIME".to_string()));
+    assert!(class_info.contains(&"targets: TYPE, METHOD".to_string()));
+    assert!(class_info.contains(&"documented".to_string()));
+    assert_eq!(
+      class_file.render_attributes(),
+      vec!["RuntimeVisibleAnnotations"]
+    );
+
+    // Plain classes carry none of these.
+    let builder = ClassBuilder::new("a/Plain", Some("java/lang/Object"));
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(class_file.retention(), None);
+    assert_eq!(class_file.targets(), None);
+    assert!(!class_file.is_documented());
+    assert_eq!(class_file.render_class_info().len(), 4);
+  }
+
+  #[test]
+  fn test_unresolved_type_index() {
+    let mut builder = ClassBuilder::new("a/Marker", Some("java/lang/Object")).access_flags(0x2601);
+    // An annotation with type_index 0, then @Documented.
+    let mut info = vec![0, 2, 0, 0, 0, 0];
+    info.extend_from_slice(
+      &builder
+        .utf8("Ljava/lang/annotation/Documented;")
+        .to_be_bytes(),
+    );
+    info.extend_from_slice(&0u16.to_be_bytes());
+    let attribute = builder.attribute("RuntimeVisibleAnnotations", &info);
+    builder.class_attribute(attribute);
+    // `parse` rejects the class, so this exercises the accessors for
+    // the verifier, which goes around it.
+    let (class_file, _) = ClassFile::parse_unchecked(&builder.build()).unwrap();
+
+    let annotations = class_file.annotations();
+    assert_eq!(annotations[0].type_descriptor(), None);
+    assert_eq!(annotations[0].to_string(), "@#0");
+    assert!(class_file.is_documented());
+    assert_eq!(class_file.retention(), None);
+  }
+
+  #[test]
+  fn test_nested_element_values() {
+    let mut builder = ClassBuilder::new("a/Annotated", Some("java/lang/Object"));
+    let value = builder.utf8("value");
+    let mut info = 2u16.to_be_bytes().to_vec();
+    // @Names({"a", "b"})
+    info.extend_from_slice(&builder.utf8("La/Names;").to_be_bytes());
+    info.extend_from_slice(&1u16.to_be_bytes());
+    info.extend_from_slice(&value.to_be_bytes());
+    info.push(b'[');
+    info.extend_from_slice(&2u16.to_be_bytes());
+    for name in ["a", "b"] {
+      info.push(b's');
+      info.extend_from_slice(&builder.utf8(name).to_be_bytes());
+    }
+    // @Outer(value=@Inner(value=7))
+    info.extend_from_slice(&builder.utf8("La/Outer;").to_be_bytes());
+    info.extend_from_slice(&1u16.to_be_bytes());
+    info.extend_from_slice(&value.to_be_bytes());
+    info.push(b'@');
+    info.extend_from_slice(&builder.utf8("La/Inner;").to_be_bytes());
+    info.extend_from_slice(&1u16.to_be_bytes());
+    info.extend_from_slice(&value.to_be_bytes());
+    info.push(b'I');
+    info.extend_from_slice(&builder.integer(7).to_be_bytes());
+    let attribute = builder.attribute("RuntimeVisibleAnnotations", &info);
+    builder.class_attribute(attribute);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let annotations = class_file.annotations();
+    assert_eq!(
+      annotations[0].to_string(),
+      "@La/Names;(value={\"a\", \"b\"})"
+    );
+    assert_eq!(
+      annotations[1].to_string(),
+      "@La/Outer;(value=@La/Inner;(value=Integer: 7))"
+    );
+
+    // Arrays nested up to the limit parse; one more level is rejected
+    // instead of recursing further.
+    let nested = |levels: usize| {
+      let mut bytes = Vec::new();
+      for _ in 0..levels {
+        bytes.push(b'[');
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+      }
+      bytes.push(b'I');
+      bytes.extend_from_slice(&1u16.to_be_bytes());
+      bytes
+    };
+    let context = class_file.context();
+    let bytes = nested(MAX_ELEMENT_VALUE_NESTING);
+    let (rest, _) = ElementValue::parse::<()>(&bytes, context).unwrap();
+    assert!(rest.is_empty());
+    let bytes = nested(MAX_ELEMENT_VALUE_NESTING + 1);
+    assert!(ElementValue::parse::<()>(&bytes, context).is_err());
+    let bytes = nested(100_000);
+    assert!(ElementValue::parse::<()>(&bytes, context).is_err());
+  }
+}
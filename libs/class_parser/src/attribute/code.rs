@@ -1,6 +1,7 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use nom::{
+  bytes::complete::take,
   error::ParseError,
   multi::count,
   number::complete::{be_u16, be_u32, be_u8},
@@ -8,9 +9,23 @@ use nom::{
   IResult,
 };
 
-use crate::opcodes::CodeInfo;
+use base::{
+  cfg::{ControlFlowGraph, Flow, FlowInfo, Handler},
+  coverage::OpcodeCoverage,
+  depth::{Depth, DepthDisplay, ELIDED},
+};
 
-use base::Parsable;
+use crate::{
+  context::ResolveContext,
+  instruction::{Instruction, Operands},
+  opcodes::{
+    opcodes_implied::{
+      ANEWARRAY, ARETURN, ATHROW, DRETURN, FRETURN, GOTO, GOTO_W, IRETURN, JSR, JSR_W, LRETURN,
+      MULTIANEWARRAY, RET, RETURN,
+    },
+    CodeInfo, InstructionIterator,
+  },
+};
 
 use super::{parse_attributes, AttributeInfo};
 
@@ -19,63 +34,128 @@ pub struct CodeAttribute {
   max_stack: u16,
   max_locals: u16,
   code_length: u32,
+  raw_code: Vec<u8>,
   code: Vec<CodeInfo>,
+  /// `code` decoded, in pc order.
+  instructions: Vec<Instruction>,
   exception_table: Vec<ExceptionTable>,
   attributes: Vec<AttributeInfo>,
+  coverage: OpcodeCoverage,
+  context: Arc<ResolveContext>,
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Clone)]
 pub struct ExceptionTable {
   start_pc: u16,
   end_pc: u16,
   handler_pc: u16,
   catch_type: u16,
+  context: Arc<ResolveContext>,
 }
 
+/// Decodes `bytes`, tallying the opcodes into `coverage` as they are read.
 fn parse_code_infos<'a, E: ParseError<&'a [u8]>>(
   bytes: &'a [u8],
+  coverage: &mut OpcodeCoverage,
 ) -> IResult<&'a [u8], Vec<CodeInfo>, E> {
-  let mut code_bytes: &[u8] = bytes;
-  let mut code_infos = vec![];
-  while code_bytes.len() > 0 {
-    let (bytes, code_info) = CodeInfo::parse(code_bytes)?;
-    code_bytes = bytes;
-    code_infos.push(code_info.clone());
+  let mut instructions = InstructionIterator::new(bytes);
+  let code_infos = instructions
+    .by_ref()
+    .map(|(_, code_info)| {
+      coverage.record(code_info.code(), code_info.mnemonic());
+      code_info
+    })
+    .collect();
+  if !instructions.remaining().is_empty() {
+    return Err(nom::Err::Error(E::from_error_kind(
+      instructions.remaining(),
+      nom::error::ErrorKind::Eof,
+    )));
   }
   Ok((bytes, code_infos))
 }
 
-impl Parsable for CodeAttribute {
-  fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+impl CodeAttribute {
+  /// With `ParseOptions::skip_code` set, the instructions are skipped over
+  /// undecoded: `disassemble` returns nothing and the opcode coverage is
+  /// empty, the exception table, nested attributes and raw bytes are still
+  /// read.
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
     let (bytes, (max_stack, max_locals, code_length)) = tuple((be_u16, be_u16, be_u32))(bytes)?;
+    let limits = context.options().limits;
+    limits.check_code_len(bytes, code_length as usize)?;
+    if context.options().skip_code {
+      let (bytes, raw_code) = take(code_length)(bytes)?;
+      let (bytes, exception_table_length) = be_u16(bytes)?;
+      let (bytes, exception_table) = limits.count(
+        |bytes| ExceptionTable::parse(bytes, context),
+        exception_table_length as usize,
+      )(bytes)?;
+      let (bytes, attributes) = parse_attributes(bytes, context)?;
+      return Ok((
+        bytes,
+        Self {
+          max_stack,
+          max_locals,
+          code_length,
+          raw_code: raw_code.to_vec(),
+          code: vec![],
+          instructions: vec![],
+          exception_table,
+          attributes,
+          coverage: OpcodeCoverage::default(),
+          context: context.clone(),
+        },
+      ));
+    }
     let (bytes, code) = count(be_u8, code_length as usize)(bytes)?;
     let (bytes, exception_table_length) = be_u16(bytes)?;
-    let (bytes, exception_table) =
-      count(ExceptionTable::parse, exception_table_length as usize)(bytes)?;
-    let (bytes, attributes) = parse_attributes(bytes)?;
-    let code_infos =
-      parse_code_infos::<nom::error::Error<_>>(&code).map(|(_, code_infos)| code_infos);
-    if let Err(_e) = code_infos {
+    let (bytes, exception_table) = limits.count(
+      |bytes| ExceptionTable::parse(bytes, context),
+      exception_table_length as usize,
+    )(bytes)?;
+    let (bytes, attributes) = parse_attributes(bytes, context)?;
+    let mut coverage = OpcodeCoverage::default();
+    let Ok((_, code_infos)) = parse_code_infos::<nom::error::Error<_>>(&code, &mut coverage) else {
       return Err(nom::Err::Error(E::from_error_kind(
         bytes,
         nom::error::ErrorKind::Tag,
       )));
-    }
+    };
+    let mut pc = 0;
+    let instructions = code_infos
+      .iter()
+      .map(|code_info| {
+        let instruction = Instruction::decode(code_info, pc, context);
+        pc += code_info.encoded_len() as u32;
+        instruction
+      })
+      .collect();
     Ok((
       bytes,
       Self {
         max_stack,
         max_locals,
         code_length,
-        code: code_infos.unwrap(),
+        raw_code: code,
+        code: code_infos,
+        instructions,
         exception_table,
         attributes,
+        coverage,
+        context: context.clone(),
       },
     ))
   }
 }
 
 impl ExceptionTable {
-  fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+  fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
     let (bytes, (start_pc, end_pc, handler_pc, catch_type)) =
       tuple((be_u16, be_u16, be_u16, be_u16))(bytes)?;
     Ok((
@@ -85,23 +165,457 @@ impl ExceptionTable {
         end_pc,
         handler_pc,
         catch_type,
+        context: context.clone(),
       },
     ))
   }
+
+  pub fn start_pc(&self) -> u16 {
+    self.start_pc
+  }
+
+  pub fn end_pc(&self) -> u16 {
+    self.end_pc
+  }
+
+  pub fn handler_pc(&self) -> u16 {
+    self.handler_pc
+  }
+
+  /// Index of the caught `Class` constant, 0 for a handler that catches
+  /// everything (`finally`).
+  pub fn catch_type(&self) -> u16 {
+    self.catch_type
+  }
+
+  /// Internal name of the caught class, `None` when it catches everything.
+  pub fn catch_type_name(&self) -> Option<&str> {
+    self.context.find_class_name(self.catch_type)
+  }
+}
+
+impl Display for ExceptionTable {
+  /// `[start_pc, end_pc) -> handler_pc catch java/io/IOException`, or
+  /// `catch any` for a `finally` handler.
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "[{}, {}) -> {} catch {}",
+      self.start_pc,
+      self.end_pc,
+      self.handler_pc,
+      self.catch_type_name().unwrap_or("any")
+    )
+  }
 }
 
 impl Display for CodeAttribute {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for CodeAttribute {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
     write!(
       f,
       "{{max_stack: {}, max_locals: {}, code_length: {}}}",
       self.max_stack, self.max_locals, self.code_length
     )?;
     write!(f, "\ncode: ")?;
+    let mut pc = 0;
     for code in &self.code {
-      write!(f, "{} ", code)?;
+      self.fmt_code_info(f, code, pc)?;
+      write!(f, " ")?;
+      pc += code.encoded_len() as u32;
+    }
+    if !self.exception_table.is_empty() {
+      write!(f, "\nexception_table({}):", self.exception_table.len())?;
+    }
+    for entry in &self.exception_table {
+      write!(f, "\n\t{}", entry)?;
+    }
+    if !self.attributes.is_empty() {
+      write!(f, "\nattributes({}):", self.attributes.len())?;
+      let Some(depth) = depth.nested() else {
+        return write!(f, " {}", ELIDED);
+      };
+      for attribute in &self.attributes {
+        write!(f, "\n\t{}", attribute.display_depth(depth))?;
+      }
     }
     Ok(())
   }
 }
-impl CodeAttribute {}
+impl CodeAttribute {
+  pub fn max_stack(&self) -> u16 {
+    self.max_stack
+  }
+
+  pub fn max_locals(&self) -> u16 {
+    self.max_locals
+  }
+
+  /// Length of the bytecode in bytes, also known when it was skipped.
+  pub fn code_length(&self) -> u32 {
+    self.code_length
+  }
+
+  /// The undecoded bytecode, `code_length` bytes, also kept when decoding
+  /// was skipped.
+  pub fn raw_code(&self) -> &[u8] {
+    &self.raw_code
+  }
+
+  /// Opcodes seen while decoding this code, including unknown ones.
+  pub fn opcode_coverage(&self) -> &OpcodeCoverage {
+    &self.coverage
+  }
+
+  pub fn attributes(&self) -> &[AttributeInfo] {
+    &self.attributes
+  }
+
+  pub fn exception_table(&self) -> &[ExceptionTable] {
+    &self.exception_table
+  }
+
+  /// Internal name of the class `entry` catches, `None` when it catches
+  /// everything.
+  pub fn catch_type_name<'a>(&'a self, entry: &'a ExceptionTable) -> Option<&'a str> {
+    entry.catch_type_name()
+  }
+
+  /// `code`, at `pc`, with the absolute target of a branch, e.g. `ifeq -> 42`,
+  /// and the class operand of `anewarray` and `multianewarray` resolved, e.g.
+  /// `multianewarray [[I 2`.
+  fn fmt_code_info(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+    code: &CodeInfo,
+    pc: u32,
+  ) -> std::fmt::Result {
+    let instruction = Instruction::decode(code, pc, &self.context);
+    if let Some(target) = instruction.branch_target() {
+      return write!(f, "{} -> {}", instruction.mnemonic, target);
+    }
+    let operands = code.operands();
+    let class = match code.code() {
+      ANEWARRAY | MULTIANEWARRAY => self
+        .context
+        .find_class_name(u16::from_be_bytes([operands[0], operands[1]])),
+      _ => None,
+    };
+    let Some(class) = class else {
+      return write!(f, "{}", code);
+    };
+    write!(f, "{} {}", code.mnemonic().unwrap_or_default(), class)?;
+    if code.code() == MULTIANEWARRAY {
+      write!(f, " {}", operands[2])?;
+    }
+    Ok(())
+  }
+
+  /// Internal names of the classes the exception table catches, each once in
+  /// the order first seen; `finally` handlers catch everything and add none.
+  pub fn catch_types(&self) -> Vec<&str> {
+    let mut catch_types = vec![];
+    for entry in &self.exception_table {
+      if let Some(name) = self.catch_type_name(entry) {
+        if !catch_types.contains(&name) {
+          catch_types.push(name);
+        }
+      }
+    }
+    catch_types
+  }
+
+  /// Splits the code into basic blocks, with edges for fallthrough, branches,
+  /// switches and the exception table's handlers. `jsr` and `jsr_w` call
+  /// the subroutine at their target, and a `ret` returns to after each
+  /// `jsr` calling the subroutine it ends.
+  pub fn cfg(&self) -> ControlFlowGraph {
+    let instructions: Vec<FlowInfo> = self
+      .code
+      .iter()
+      .zip(&self.instructions)
+      .map(|(code_info, instruction)| {
+        let flow = match (instruction.opcode, &instruction.operands) {
+          (GOTO | GOTO_W, Operands::Branch { target, .. }) => Flow::Goto(*target),
+          (JSR | JSR_W, Operands::Branch { target, .. }) => Flow::Call(*target),
+          (_, Operands::Branch { target, .. }) => Flow::Branch(*target),
+          (
+            _,
+            Operands::TableSwitch {
+              default, targets, ..
+            },
+          ) => Flow::Switch(
+            [*default]
+              .into_iter()
+              .chain(targets.iter().copied())
+              .collect(),
+          ),
+          (_, Operands::LookupSwitch { default, pairs }) => Flow::Switch(
+            [*default]
+              .into_iter()
+              .chain(pairs.iter().map(|(_, target)| *target))
+              .collect(),
+          ),
+          (IRETURN | LRETURN | FRETURN | DRETURN | ARETURN | RETURN | ATHROW, _) => Flow::Exit,
+          (RET, _) => Flow::Ret,
+          _ => Flow::Continue,
+        };
+        FlowInfo {
+          pc: instruction.pc,
+          len: code_info.encoded_len() as u32,
+          flow,
+        }
+      })
+      .collect();
+    let handlers: Vec<Handler> = self
+      .exception_table
+      .iter()
+      .map(|entry| Handler {
+        start: entry.start_pc as u32,
+        end: entry.end_pc as u32,
+        handler: entry.handler_pc as u32,
+      })
+      .collect();
+    ControlFlowGraph::new(&instructions, &handlers)
+  }
+
+  /// The code as instructions, with constant pool references resolved.
+  pub fn disassemble(&self) -> Vec<Instruction> {
+    self.instructions.clone()
+  }
+
+  /// The instruction starting at `pc`; `None` if `pc` is inside another
+  /// instruction or past the end of the code.
+  pub fn instruction_at(&self, pc: u32) -> Option<&Instruction> {
+    let index = self
+      .instructions
+      .binary_search_by_key(&pc, |instruction| instruction.pc)
+      .ok()?;
+    Some(&self.instructions[index])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use crate::{
+    instruction::{Constant, Operands},
+    test_util::ClassBuilder,
+  };
+
+  #[test]
+  fn test_exception_table() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let exception = builder.class("java/io/IOException");
+    // 0: nop, 1: goto 5, 4: return, 5: return
+    let code = [0x00, 0xa7, 0x00, 0x04, 0xb1, 0xb1];
+    let code = builder.code_attribute(1, 1, &code, &[(0, 1, 4, exception), (0, 4, 5, 0)], vec![]);
+    builder.method(0x0001, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let code = class_file.methods()[0].attributes[0].get_code().unwrap();
+    let table = code.exception_table();
+    assert_eq!(table.len(), 2);
+    assert_eq!(
+      (
+        table[0].start_pc(),
+        table[0].end_pc(),
+        table[0].handler_pc()
+      ),
+      (0, 1, 4)
+    );
+    assert_eq!(code.catch_type_name(&table[0]), Some("java/io/IOException"));
+    assert_eq!(table[1].catch_type(), 0);
+    assert_eq!(code.catch_type_name(&table[1]), None);
+    assert_eq!(code.catch_types(), vec!["java/io/IOException"]);
+
+    let rendered = code.to_string();
+    assert!(rendered.contains("exception_table(2):"));
+    assert!(rendered.contains("\t[0, 1) -> 4 catch java/io/IOException"));
+    assert!(rendered.contains("\t[0, 4) -> 5 catch any"));
+    assert_eq!(table[0].catch_type_name(), Some("java/io/IOException"));
+    assert_eq!(
+      table[0].to_string(),
+      "[0, 1) -> 4 catch java/io/IOException"
+    );
+  }
+
+  #[test]
+  fn test_catch_types() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let io = builder.class("java/io/IOException");
+    let state = builder.class("java/lang/IllegalStateException");
+    // 0: nop, 1: return, 2: return, 3: return
+    let code = [0x00, 0xb1, 0xb1, 0xb1];
+    let table = [(0, 1, 1, io), (0, 1, 2, state), (0, 1, 3, 0), (0, 2, 1, io)];
+    let code = builder.code_attribute(1, 1, &code, &table, vec![]);
+    builder.method(0x0001, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let code = class_file.methods()[0].attributes[0].get_code().unwrap();
+    assert_eq!(
+      code.catch_types(),
+      vec!["java/io/IOException", "java/lang/IllegalStateException"]
+    );
+  }
+
+  #[test]
+  fn test_nested_attributes() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let line_numbers = builder.attribute("LineNumberTable", &[0, 2, 0, 0, 0, 7, 0, 1, 0, 8]);
+    let code = builder.code_attribute(1, 1, &[0x00, 0xb1], &[], vec![line_numbers]);
+    builder.method(0x0001, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let code = class_file.methods()[0].attributes[0].get_code().unwrap();
+    assert_eq!(code.attributes().len(), 1);
+    assert!(code.to_string().ends_with(
+      "\nattributes(1):\n\tLineNumberTable: line_number_table(2): \
+       {start_pc: 0, line_number: 7} {start_pc: 1, line_number: 8} "
+    ));
+  }
+
+  #[test]
+  fn test_cfg() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // 0: iload_0, 1: ifeq 9, 4: iconst_1, 5: istore_1, 6: goto 11,
+    // 9: iconst_2, 10: istore_1, 11: return
+    let code = [
+      0x1a, 0x99, 0x00, 0x08, 0x04, 0x3c, 0xa7, 0x00, 0x05, 0x05, 0x3c, 0xb1,
+    ];
+    let code = builder.code_attribute(1, 2, &code, &[(0, 4, 11, 0)], vec![]);
+    builder.method(0x0009, "choose", "(Z)V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let cfg = class_file.methods()[0].attributes[0]
+      .get_code()
+      .unwrap()
+      .cfg();
+    assert_eq!(cfg.len(), 4);
+    assert_eq!(
+      cfg.to_string(),
+      "[0, 4) -> fallthrough 4, branch 9, exception 11\n\
+       [4, 9) -> branch 11\n\
+       [9, 11) -> fallthrough 11\n\
+       [11, 12)\n"
+    );
+    assert_eq!(cfg.block(4).unwrap().instructions, [4, 5, 6]);
+    assert_eq!(cfg.predecessors(11), [0, 4, 9]);
+  }
+
+  #[test]
+  fn test_cfg_subroutine() {
+    // try { } finally { } as javac compiled it before class file version 50.
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object")).version(49, 0);
+    #[rustfmt::skip]
+    let code = [
+      0x00,             // 0: nop
+      0xa8, 0x00, 0x0a, // 1: jsr 11
+      0xb1,             // 4: return
+      0x4c,             // 5: astore_1
+      0xa8, 0x00, 0x05, // 6: jsr 11
+      0x2b,             // 9: aload_1
+      0xbf,             // 10: athrow
+      0x4d,             // 11: astore_2
+      0xa9, 0x02,       // 12: ret 2
+    ];
+    let code = builder.code_attribute(1, 3, &code, &[(0, 1, 5, 0)], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let cfg = class_file.methods()[0].attributes[0]
+      .get_code()
+      .unwrap()
+      .cfg();
+    assert_eq!(
+      cfg.to_string(),
+      "[0, 1) -> fallthrough 1, exception 5\n\
+       [1, 4) -> fallthrough 4, call 11\n\
+       [4, 5)\n\
+       [5, 9) -> fallthrough 9, call 11\n\
+       [9, 11)\n\
+       [11, 14) -> return 4, return 9\n"
+    );
+    assert_eq!(cfg.predecessors(11), [1, 5]);
+    assert_eq!(cfg.predecessors(9), [5, 11]);
+  }
+
+  #[test]
+  fn test_instruction_at() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let hello = builder.string("hello");
+    #[rustfmt::skip]
+    let code = [
+      0x03,                               // 0: iconst_0
+      0x3c,                               // 1: istore_1
+      0x12, hello as u8,                  // 2: ldc "hello"
+      0x4d,                               // 4: astore_2
+      0xc4, 0x84, 0x00, 0x01, 0x01, 0x2c, // 5: wide iinc 1 300
+      0xb1,                               // 11: return
+    ];
+    let code = builder.code_attribute(1, 3, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let code = class_file.methods()[0].attributes[0].get_code().unwrap();
+
+    let ldc = code.instruction_at(2).unwrap();
+    assert_eq!((ldc.pc, ldc.mnemonic), (2, "ldc"));
+    assert_eq!(
+      ldc.operands,
+      Operands::Constant(Constant::String("hello".to_string()))
+    );
+    let iinc = code.instruction_at(5).unwrap();
+    assert_eq!((iinc.mnemonic, iinc.wide), ("iinc", true));
+    assert_eq!(code.instruction_at(11).unwrap().mnemonic, "return");
+    // The operand of ldc, inside wide iinc and past the end.
+    assert!(code.instruction_at(3).is_none());
+    assert!(code.instruction_at(7).is_none());
+    assert!(code.instruction_at(12).is_none());
+  }
+
+  #[test]
+  fn test_opcode_coverage() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // 0: iconst_0, 1: unassigned 0xcb, 2: pop, 3: return
+    let run = builder.code_attribute(1, 1, &[0x03, 0xcb, 0x57, 0xb1], &[], vec![]);
+    builder.method(0x0001, "run", "()V", vec![run]);
+    let stop = builder.code_attribute(0, 1, &[0xb1], &[], vec![]);
+    builder.method(0x0001, "stop", "()V", vec![stop]);
+    builder.method(0x0401, "abstract_run", "()V", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let run = class_file.methods()[0].opcode_coverage();
+    assert_eq!(run.unknown(), vec![(0xcb, 1)]);
+    assert!(class_file.methods()[2].opcode_coverage().known().is_empty());
+
+    let coverage = class_file.opcode_coverage();
+    assert_eq!(
+      coverage.known(),
+      vec![(0xb1, "return", 2), (0x03, "iconst_0", 1), (0x57, "pop", 1)]
+    );
+    assert_eq!(coverage.unknown(), vec![(0xcb, 1)]);
+  }
+
+  #[test]
+  fn test_opcode_histogram() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // iconst_1, iconst_1, iadd, ireturn
+    let sum = builder.code_attribute(2, 1, &[0x04, 0x04, 0x60, 0xac], &[], vec![]);
+    builder.method(0x0001, "sum", "()I", vec![sum]);
+    // iconst_1, ireturn
+    let one = builder.code_attribute(1, 1, &[0x04, 0xac], &[], vec![]);
+    builder.method(0x0001, "one", "()I", vec![one]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(
+      class_file.opcode_histogram(),
+      HashMap::from([("iconst_1", 3), ("ireturn", 2), ("iadd", 1)])
+    );
+  }
+}
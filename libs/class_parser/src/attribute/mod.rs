@@ -1,6 +1,7 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use nom::{
+  combinator::all_consuming,
   error::ParseError,
   multi::count,
   number::complete::{be_u16, be_u32, be_u8},
@@ -8,8 +9,16 @@ use nom::{
   IResult,
 };
 
-use crate::{get_constant_pool_ref, get_str_const};
-use base::Parsable;
+use crate::{
+  constant_pool::{ConstantType, CpIndex},
+  context::ResolveContext,
+};
+use base::{
+  depth::{Depth, DepthDisplay, ELIDED},
+  error::{Error, ErrorKind},
+  Parsable,
+};
+pub mod annotation;
 pub mod code;
 pub mod linenumber_table;
 pub mod stack_map_table;
@@ -20,14 +29,23 @@ const STACK_MAP_TABLE_ATTRIBUTE_NAME: &str = "StackMapTable";
 const LINE_NUMBER_TABLE_ATTRIBUTE_NAME: &str = "LineNumberTable";
 pub const SOURCE_FILE_ATTRIBUTE_NAME: &str = "SourceFile";
 const DEPRECATED_ATTRIBUTE_NAME: &str = "Deprecated";
+const EXCEPTIONS_ATTRIBUTE_NAME: &str = "Exceptions";
+const SIGNATURE_ATTRIBUTE_NAME: &str = "Signature";
+const METHOD_PARAMETERS_ATTRIBUTE_NAME: &str = "MethodParameters";
+pub const RUNTIME_VISIBLE_ANNOTATIONS_ATTRIBUTE_NAME: &str = "RuntimeVisibleAnnotations";
+pub const RUNTIME_INVISIBLE_ANNOTATIONS_ATTRIBUTE_NAME: &str = "RuntimeInvisibleAnnotations";
+pub const PERMITTED_SUBCLASSES_ATTRIBUTE_NAME: &str = "PermittedSubclasses";
 
 #[derive(Clone)]
 pub struct AttributeInfo {
-  attribute_name_index: u16,
+  attribute_name_index: CpIndex,
   attribute_length: u32,
+  /// Bytes of `attribute_length` left after parsing a known attribute.
+  unparsed_length: usize,
   // pub info_v: Vec<u8>,
   attribute_info: Attribute,
   // info: AttributeInfoType,
+  context: Arc<ResolveContext>,
 }
 
 #[derive(Clone)]
@@ -35,62 +53,107 @@ pub enum Attribute {
   Code(code::CodeAttribute),
   Constant(ConstantValue),
   StackMapTable(stack_map_table::StackMapTable),
-  LineNumberTable(linenumber_table::LineNumberTable),
+  LineNumberTable(linenumber_table::LineNumberTableAttribute),
   SourceFile(SourceFile),
   Deprecated,
+  Exceptions(Exceptions),
+  Signature(Signature),
+  MethodParameters(MethodParameters),
+  RuntimeVisibleAnnotations(annotation::Annotations),
+  RuntimeInvisibleAnnotations(annotation::Annotations),
+  PermittedSubclasses(PermittedSubclasses),
+  /// A known attribute whose contents failed to parse, kept as raw bytes.
+  /// Only produced with `ParseOptions::relaxed`.
+  Unknown(Vec<u8>),
   None,
 }
 
 pub fn parse_attributes<'a, E: ParseError<&'a [u8]>>(
   bytes: &'a [u8],
+  context: &Arc<ResolveContext>,
 ) -> IResult<&'a [u8], Vec<AttributeInfo>, E> {
   let (bytes, attribute_count) = be_u16(bytes)?;
-  count(AttributeInfo::parse, attribute_count as usize)(bytes)
+  context.options().limits.count(
+    |bytes| AttributeInfo::parse(bytes, context),
+    attribute_count as usize,
+  )(bytes)
 }
 
-impl Parsable for AttributeInfo {
-  fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
-    let (bytes, (attribute_name_index, attribute_length)) = tuple((be_u16, be_u32))(bytes)?;
+impl AttributeInfo {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, (attribute_name_index, attribute_length)) = tuple((CpIndex::parse, be_u32))(bytes)?;
     let (bytes, info_v) = count(be_u8, attribute_length as usize)(bytes)?;
-    // TODO ensure that attribute_length is correct
-    let attr = if let Some(attr_str) =
-      get_constant_pool_ref()[attribute_name_index as usize - 1].as_utf8()
-    {
-      // parse different attributes
-      let ret =
-        Self::parse_attribute::<nom::error::Error<_>>(&info_v, attr_str).map(|(_, attr)| attr);
-      if let Err(_e) = ret {
-        return Err(nom::Err::Error(E::from_error_kind(
-          bytes,
-          nom::error::ErrorKind::Tag,
-        )));
+    // A known attribute that doesn't use all of it is reported by `verify`.
+    let (attr, unparsed_length) =
+      if let Some(attr_str) = attribute_name_index.resolve_utf8(context.constant_pool()) {
+        // parse different attributes
+        let ret = Self::parse_attribute::<nom::error::Error<_>>(&info_v, attr_str, context).map(
+          |(rest, attr)| {
+            // Contents of attributes this crate doesn't model are never read.
+            let unparsed_length = match attr {
+              Attribute::None => 0,
+              _ => rest.len(),
+            };
+            (attr, unparsed_length)
+          },
+        );
+        match ret {
+          Ok(attr) => attr,
+          // Going over the limits fails the class, even when relaxed.
+          Err(nom::Err::Failure(error)) if error.code == nom::error::ErrorKind::TooLarge => {
+            return Err(nom::Err::Failure(E::from_error_kind(
+              bytes,
+              nom::error::ErrorKind::TooLarge,
+            )))
+          }
+          Err(_) if context.options().relaxed => (Attribute::Unknown(info_v), 0),
+          Err(_) => {
+            return Err(nom::Err::Error(E::from_error_kind(
+              bytes,
+              nom::error::ErrorKind::Tag,
+            )))
+          }
+        }
       } else {
-        let attr = ret.unwrap();
-        attr
-      }
-    } else {
-      Attribute::None
-    };
+        (Attribute::None, 0)
+      };
 
     Ok((
       bytes,
       Self {
         attribute_name_index,
         attribute_length,
+        unparsed_length,
         attribute_info: attr,
+        context: context.clone(),
       },
     ))
   }
-}
 
-impl AttributeInfo {
+  /// Parses a standalone attribute, e.g. one cut out of a class file by
+  /// another tool, resolving its indices through `context`, which must hold
+  /// the constant pool of the class it came from.
+  ///
+  /// `bytes` is exactly one `attribute_info`: a big-endian u2
+  /// `attribute_name_index`, a u4 `attribute_length`, then that many bytes
+  /// of contents. Anything after them is an error.
+  pub fn parse_one(bytes: &[u8], context: &Arc<ResolveContext>) -> Result<Self, Error> {
+    all_consuming(|bytes| Self::parse::<nom::error::Error<_>>(bytes, context))(bytes)
+      .map(|(_, attribute)| attribute)
+      .map_err(Error::from)
+  }
+
   fn parse_attribute<'a, E: ParseError<&'a [u8]>>(
     bytes: &'a [u8],
     attr_str: &str,
+    context: &Arc<ResolveContext>,
   ) -> IResult<&'a [u8], Attribute, E> {
     match attr_str {
       CODE_ATTRIBUTE_NAME => {
-        let (bytes, code) = code::CodeAttribute::parse(bytes)?;
+        let (bytes, code) = code::CodeAttribute::parse(bytes, context)?;
         Ok((bytes, Attribute::Code(code)))
       }
       CONSTANT_VALUE_ATTRIBUTE_NAME => {
@@ -102,14 +165,38 @@ impl AttributeInfo {
         Ok((bytes, Attribute::StackMapTable(stack_map_table)))
       }
       LINE_NUMBER_TABLE_ATTRIBUTE_NAME => {
-        let (bytes, line_number_table) = linenumber_table::LineNumberTable::parse(bytes)?;
+        let (bytes, line_number_table) = linenumber_table::LineNumberTableAttribute::parse(bytes)?;
         Ok((bytes, Attribute::LineNumberTable(line_number_table)))
       }
       SOURCE_FILE_ATTRIBUTE_NAME => {
-        let (bytes, source_file) = SourceFile::parse(bytes)?;
+        let (bytes, source_file) = SourceFile::parse(bytes, context)?;
         Ok((bytes, Attribute::SourceFile(source_file)))
       }
       DEPRECATED_ATTRIBUTE_NAME => Ok((bytes, Attribute::Deprecated)),
+      EXCEPTIONS_ATTRIBUTE_NAME => {
+        let (bytes, exceptions) = Exceptions::parse(bytes, context)?;
+        Ok((bytes, Attribute::Exceptions(exceptions)))
+      }
+      SIGNATURE_ATTRIBUTE_NAME => {
+        let (bytes, signature) = Signature::parse(bytes, context)?;
+        Ok((bytes, Attribute::Signature(signature)))
+      }
+      METHOD_PARAMETERS_ATTRIBUTE_NAME => {
+        let (bytes, parameters) = MethodParameters::parse(bytes, context)?;
+        Ok((bytes, Attribute::MethodParameters(parameters)))
+      }
+      RUNTIME_VISIBLE_ANNOTATIONS_ATTRIBUTE_NAME => {
+        let (bytes, annotations) = annotation::Annotations::parse(bytes, context)?;
+        Ok((bytes, Attribute::RuntimeVisibleAnnotations(annotations)))
+      }
+      RUNTIME_INVISIBLE_ANNOTATIONS_ATTRIBUTE_NAME => {
+        let (bytes, annotations) = annotation::Annotations::parse(bytes, context)?;
+        Ok((bytes, Attribute::RuntimeInvisibleAnnotations(annotations)))
+      }
+      PERMITTED_SUBCLASSES_ATTRIBUTE_NAME => {
+        let (bytes, permitted_subclasses) = PermittedSubclasses::parse(bytes, context)?;
+        Ok((bytes, Attribute::PermittedSubclasses(permitted_subclasses)))
+      }
       _ => Ok((bytes, Attribute::None)),
     }
   }
@@ -140,44 +227,240 @@ impl AttributeInfo {
         Attribute::Deprecated => true,
         _ => false,
       },
+      EXCEPTIONS_ATTRIBUTE_NAME => match &self.attribute_info {
+        Attribute::Exceptions(_) => true,
+        _ => false,
+      },
+      SIGNATURE_ATTRIBUTE_NAME => match &self.attribute_info {
+        Attribute::Signature(_) => true,
+        _ => false,
+      },
+      METHOD_PARAMETERS_ATTRIBUTE_NAME => match &self.attribute_info {
+        Attribute::MethodParameters(_) => true,
+        _ => false,
+      },
+      RUNTIME_VISIBLE_ANNOTATIONS_ATTRIBUTE_NAME => match &self.attribute_info {
+        Attribute::RuntimeVisibleAnnotations(_) => true,
+        _ => false,
+      },
+      RUNTIME_INVISIBLE_ANNOTATIONS_ATTRIBUTE_NAME => match &self.attribute_info {
+        Attribute::RuntimeInvisibleAnnotations(_) => true,
+        _ => false,
+      },
+      PERMITTED_SUBCLASSES_ATTRIBUTE_NAME => match &self.attribute_info {
+        Attribute::PermittedSubclasses(_) => true,
+        _ => false,
+      },
       _ => false,
     }
   }
 
+  pub fn get_code(&self) -> Option<&code::CodeAttribute> {
+    match &self.attribute_info {
+      Attribute::Code(code) => Some(code),
+      _ => None,
+    }
+  }
+
+  pub fn get_constant_value(&self) -> Option<&ConstantValue> {
+    match &self.attribute_info {
+      Attribute::Constant(constant) => Some(constant),
+      _ => None,
+    }
+  }
+
+  pub fn get_annotations(&self) -> Option<&annotation::Annotations> {
+    match &self.attribute_info {
+      Attribute::RuntimeVisibleAnnotations(annotations)
+      | Attribute::RuntimeInvisibleAnnotations(annotations) => Some(annotations),
+      _ => None,
+    }
+  }
+
   pub fn get_sourcefile(&self) -> Option<&str> {
     match &self.attribute_info {
-      Attribute::SourceFile(source_file) => Some(source_file.get_sourcefile()),
+      Attribute::SourceFile(source_file) => source_file.get_sourcefile(),
       _ => None,
     }
   }
 
+  pub fn get_exceptions(&self) -> Option<&Exceptions> {
+    match &self.attribute_info {
+      Attribute::Exceptions(exceptions) => Some(exceptions),
+      _ => None,
+    }
+  }
+
+  pub fn get_signature(&self) -> Option<&str> {
+    match &self.attribute_info {
+      Attribute::Signature(signature) => signature.get_signature(),
+      _ => None,
+    }
+  }
+
+  pub fn get_method_parameters(&self) -> Option<&MethodParameters> {
+    match &self.attribute_info {
+      Attribute::MethodParameters(parameters) => Some(parameters),
+      _ => None,
+    }
+  }
+
+  pub fn get_permitted_subclasses(&self) -> Option<&PermittedSubclasses> {
+    match &self.attribute_info {
+      Attribute::PermittedSubclasses(permitted_subclasses) => Some(permitted_subclasses),
+      _ => None,
+    }
+  }
+
+  /// The raw contents of an attribute that failed to parse in relaxed mode.
+  pub fn malformed_bytes(&self) -> Option<&[u8]> {
+    match &self.attribute_info {
+      Attribute::Unknown(bytes) => Some(bytes),
+      _ => None,
+    }
+  }
+
+  /// Fails with `ErrorKind::InvalidConstantIndex` if a mandatory constant
+  /// pool reference of the contents is 0 or names the wrong kind of
+  /// constant, so the accessors that resolve them can't panic.
+  pub(crate) fn check_references(&self) -> Result<(), Error> {
+    match &self.attribute_info {
+      Attribute::Constant(constant) => {
+        let index = constant.constantvalue_index.value();
+        let loadable = self.context.find_constant(index).is_some_and(|constant| {
+          matches!(
+            constant.info(),
+            ConstantType::Integer(_)
+              | ConstantType::Float(_)
+              | ConstantType::Long(_)
+              | ConstantType::Double(_)
+              | ConstantType::String(_)
+          )
+        });
+        if !loadable {
+          Err(ErrorKind::InvalidConstantIndex {
+            index,
+            expected: "numeric or String",
+          })?;
+        }
+      }
+      Attribute::SourceFile(source_file) => {
+        self
+          .context
+          .require_str_const(source_file.sourcefile_index.value())?;
+      }
+      Attribute::Signature(signature) => {
+        self
+          .context
+          .require_str_const(signature.signature_index.value())?;
+      }
+      Attribute::Exceptions(exceptions) => {
+        for index in &exceptions.exception_index_table {
+          self.context.require_class_name(*index)?;
+        }
+      }
+      Attribute::PermittedSubclasses(permitted_subclasses) => {
+        for index in &permitted_subclasses.classes {
+          self.context.require_class_name(*index)?;
+        }
+      }
+      Attribute::RuntimeVisibleAnnotations(annotations)
+      | Attribute::RuntimeInvisibleAnnotations(annotations) => annotations.check_references()?,
+      _ => {}
+    }
+    Ok(())
+  }
+
+  pub fn attribute_length(&self) -> u32 {
+    self.attribute_length
+  }
+
+  /// Bytes the `attribute_length` claims beyond what the attribute's contents
+  /// take; 0 for a consistent or unknown attribute.
+  pub fn unparsed_length(&self) -> usize {
+    self.unparsed_length
+  }
+
   pub fn name(&self) -> &str {
-    get_constant_pool_ref()[self.attribute_name_index as usize - 1]
-      .as_utf8()
-      .unwrap()
+    self
+      .attribute_name_index
+      .resolve_utf8(self.context.constant_pool())
+      .unwrap_or("Unknown")
+  }
+}
+
+/// ` attribute attribute ...` for the attributes of an item printed at
+/// `depth`, or ` ...` past the limit.
+pub(crate) fn fmt_nested_attributes(
+  f: &mut std::fmt::Formatter<'_>,
+  attributes: &[AttributeInfo],
+  depth: Depth,
+) -> std::fmt::Result {
+  match depth.nested() {
+    Some(depth) => {
+      for attribute in attributes {
+        write!(f, " {}", attribute.display_depth(depth))?;
+      }
+    }
+    None if !attributes.is_empty() => write!(f, " {}", ELIDED)?,
+    None => {}
   }
+  Ok(())
 }
 
 impl Display for AttributeInfo {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for AttributeInfo {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
     // write!(f, "attribute_name_index: {}", self.attribute_name_index)?;
-    write!(f, "{}", self.attribute_info)
+    self.attribute_info.fmt_depth(f, depth)
   }
 }
 
 impl Display for Attribute {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for Attribute {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
     match self {
-      Attribute::Code(code) => write!(f, "Code: {}", code),
+      Attribute::Code(code) => write!(f, "Code: {}", code.display_depth(depth)),
       Attribute::Constant(constant) => write!(f, "Constant: {}", constant),
       Attribute::StackMapTable(stack_map_table) => {
-        write!(f, "StackMapTable: {}", stack_map_table)
+        write!(f, "StackMapTable: {}", stack_map_table.display_depth(depth))
       }
       Attribute::LineNumberTable(line_number_table) => {
         write!(f, "LineNumberTable: {}", line_number_table)
       }
       Attribute::SourceFile(source_file) => write!(f, "SourceFile: {}", source_file),
       Attribute::Deprecated => write!(f, "Deprecated"),
+      Attribute::Exceptions(exceptions) => write!(f, "Exceptions: {}", exceptions),
+      Attribute::Signature(signature) => write!(f, "Signature: {}", signature),
+      Attribute::MethodParameters(parameters) => write!(f, "MethodParameters: {}", parameters),
+      Attribute::RuntimeVisibleAnnotations(annotations) => {
+        write!(
+          f,
+          "RuntimeVisibleAnnotations: {}",
+          annotations.display_depth(depth)
+        )
+      }
+      Attribute::RuntimeInvisibleAnnotations(annotations) => {
+        write!(
+          f,
+          "RuntimeInvisibleAnnotations: {}",
+          annotations.display_depth(depth)
+        )
+      }
+      Attribute::PermittedSubclasses(permitted_subclasses) => {
+        write!(f, "PermittedSubclasses: {}", permitted_subclasses)
+      }
+      Attribute::Unknown(bytes) => write!(f, "Unknown: malformed ({} bytes)", bytes.len()),
       Attribute::None => write!(f, "None"),
     }
   }
@@ -185,7 +468,13 @@ impl Display for Attribute {
 
 #[derive(Clone)]
 pub struct ConstantValue {
-  constantvalue_index: u16,
+  constantvalue_index: CpIndex,
+}
+
+impl ConstantValue {
+  pub fn constantvalue_index(&self) -> CpIndex {
+    self.constantvalue_index
+  }
 }
 
 impl Display for ConstantValue {
@@ -196,7 +485,7 @@ impl Display for ConstantValue {
 
 impl Parsable for ConstantValue {
   fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
-    let (bytes, constantvalue_index) = be_u16(bytes)?;
+    let (bytes, constantvalue_index) = CpIndex::parse(bytes)?;
     Ok((
       bytes,
       Self {
@@ -208,29 +497,288 @@ impl Parsable for ConstantValue {
 
 #[derive(Clone)]
 pub struct SourceFile {
-  sourcefile_index: u16,
+  sourcefile_index: CpIndex,
+  context: Arc<ResolveContext>,
 }
 
 impl SourceFile {
-  pub fn get_sourcefile<'a>(&self) -> &'a str {
-    get_str_const(self.sourcefile_index as usize - 1)
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, sourcefile_index) = CpIndex::parse(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        sourcefile_index,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  /// `None` if `sourcefile_index` isn't a `Utf8` constant.
+  pub fn get_sourcefile(&self) -> Option<&str> {
+    self
+      .sourcefile_index
+      .resolve_utf8(self.context.constant_pool())
+  }
+}
+
+impl Display for SourceFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.get_sourcefile() {
+      Some(sourcefile) => write!(f, "{{sourcefile: {}}}", sourcefile),
+      None => write!(f, "{{sourcefile: #{}}}", self.sourcefile_index),
+    }
+  }
+}
+
+/// The checked exceptions a method declares in its `throws` clause.
+#[derive(Clone)]
+pub struct Exceptions {
+  exception_index_table: Vec<u16>,
+  context: Arc<ResolveContext>,
+}
+
+impl Exceptions {
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, number_of_exceptions) = be_u16(bytes)?;
+    let (bytes, exception_index_table) = context
+      .options()
+      .limits
+      .count(be_u16, number_of_exceptions as usize)(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        exception_index_table,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  /// Internal names of the exception classes, in declaration order.
+  pub fn class_names(&self) -> Vec<&str> {
+    self
+      .exception_index_table
+      .iter()
+      .map(|index| self.context.get_class_name(*index))
+      .collect()
   }
 }
 
-impl Parsable for SourceFile {
-  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+impl Display for Exceptions {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{{exceptions: {}}}", self.class_names().join(", "))
+  }
+}
+
+/// The classes and interfaces allowed to extend or implement a sealed
+/// class or interface.
+#[derive(Clone)]
+pub struct PermittedSubclasses {
+  classes: Vec<u16>,
+  context: Arc<ResolveContext>,
+}
+
+impl PermittedSubclasses {
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
     bytes: &'a [u8],
-  ) -> nom::IResult<&'a [u8], Self, E>
-  where
-    Self: Sized,
-  {
-    let (bytes, sourcefile_index) = be_u16(bytes)?;
-    Ok((bytes, Self { sourcefile_index }))
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, number_of_classes) = be_u16(bytes)?;
+    let (bytes, classes) = context
+      .options()
+      .limits
+      .count(be_u16, number_of_classes as usize)(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        classes,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  /// Internal names of the permitted subclasses, in the attribute's order.
+  pub fn class_names(&self) -> Vec<&str> {
+    self
+      .classes
+      .iter()
+      .map(|index| self.context.get_class_name(*index))
+      .collect()
   }
 }
 
-impl Display for SourceFile {
+impl Display for PermittedSubclasses {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{{classes: {}}}", self.class_names().join(", "))
+  }
+}
+
+/// The generic signature of a class, field or method, e.g.
+/// `<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;`.
+#[derive(Clone)]
+pub struct Signature {
+  signature_index: CpIndex,
+  context: Arc<ResolveContext>,
+}
+
+impl Signature {
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, signature_index) = CpIndex::parse(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        signature_index,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  /// `None` if `signature_index` isn't a `Utf8` constant.
+  pub fn get_signature(&self) -> Option<&str> {
+    self
+      .signature_index
+      .resolve_utf8(self.context.constant_pool())
+  }
+}
+
+impl Display for Signature {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.get_signature() {
+      Some(signature) => write!(f, "{{signature: {}}}", signature),
+      None => write!(f, "{{signature: #{}}}", self.signature_index),
+    }
+  }
+}
+
+const ACC_FINAL: u16 = 0x0010;
+const ACC_SYNTHETIC: u16 = 0x1000;
+const ACC_MANDATED: u16 = 0x8000;
+
+/// Names and flags of a method's formal parameters, as `javac -parameters`
+/// records them.
+#[derive(Clone)]
+pub struct MethodParameters {
+  /// `(name_index, access_flags)`; a `name_index` of 0 is an unnamed
+  /// parameter.
+  parameters: Vec<(CpIndex, u16)>,
+  context: Arc<ResolveContext>,
+}
+
+impl MethodParameters {
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, parameters_count) = be_u8(bytes)?;
+    let (bytes, parameters) =
+      context
+        .options()
+        .limits
+        .count(tuple((CpIndex::parse, be_u16)), parameters_count as usize)(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        parameters,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  /// Name of each parameter, `None` if it has none.
+  pub fn names(&self) -> Vec<Option<&str>> {
+    self
+      .parameters
+      .iter()
+      .map(|(name_index, _)| name_index.resolve_utf8(self.context.constant_pool()))
+      .collect()
+  }
+
+  /// `access_flags` of each parameter: `ACC_FINAL`, `ACC_SYNTHETIC` and
+  /// `ACC_MANDATED`.
+  pub fn access_flags(&self) -> Vec<u16> {
+    self.parameters.iter().map(|(_, flags)| *flags).collect()
+  }
+}
+
+impl Display for MethodParameters {
+  /// `{parameters: final a, mandated this$0, <unnamed>}`
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{{sourcefile: {}}}", self.get_sourcefile())
+    let parameters: Vec<String> = self
+      .names()
+      .into_iter()
+      .zip(self.access_flags())
+      .map(|(name, flags)| {
+        let mut words: Vec<&str> = [
+          (ACC_FINAL, "final"),
+          (ACC_SYNTHETIC, "synthetic"),
+          (ACC_MANDATED, "mandated"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, word)| word)
+        .collect();
+        words.push(name.unwrap_or("<unnamed>"));
+        words.join(" ")
+      })
+      .collect();
+    write!(f, "{{parameters: {}}}", parameters.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::AttributeInfo;
+  use crate::test_util::ClassBuilder;
+
+  #[test]
+  fn test_parse_one() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let source_file = builder.utf8("A.java").to_be_bytes();
+    let source_file = builder.attribute("SourceFile", &source_file);
+    let code = builder.code_attribute(1, 1, &[0x03, 0xac], &[], vec![]);
+    // Only the constant pool of this class is used.
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    let attribute = AttributeInfo::parse_one(&source_file, context).unwrap();
+    assert_eq!(attribute.name(), "SourceFile");
+    assert_eq!(attribute.get_sourcefile(), Some("A.java"));
+
+    let attribute = AttributeInfo::parse_one(&code, context).unwrap();
+    assert_eq!(attribute.name(), "Code");
+    let code_attribute = attribute.get_code().unwrap();
+    assert_eq!(code_attribute.max_stack(), 1);
+    assert_eq!(code_attribute.code_length(), 2);
+    assert_eq!(code_attribute.disassemble().len(), 2);
+
+    let mut trailing = code.clone();
+    trailing.push(0);
+    assert!(AttributeInfo::parse_one(&trailing, context).is_err());
+    assert!(AttributeInfo::parse_one(&code[..code.len() - 1], context).is_err());
+  }
+
+  #[test]
+  fn test_invalid_utf8_index() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let signature = builder.attribute("Signature", &[0, 0]);
+    let source_file = builder.attribute("SourceFile", &[0, 0]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    let attribute = AttributeInfo::parse_one(&signature, context).unwrap();
+    assert_eq!(attribute.get_signature(), None);
+    assert_eq!(attribute.to_string(), "Signature: {signature: #0}");
+
+    let attribute = AttributeInfo::parse_one(&source_file, context).unwrap();
+    assert_eq!(attribute.get_sourcefile(), None);
+    assert_eq!(attribute.to_string(), "SourceFile: {sourcefile: #0}");
   }
 }
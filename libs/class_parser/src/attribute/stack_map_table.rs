@@ -2,7 +2,10 @@ use std::fmt::Display;
 
 use nom::number::complete::{be_u16, be_u8};
 
-use base::Parsable;
+use base::{
+  depth::{Depth, DepthDisplay, ELIDED},
+  Parsable,
+};
 
 #[derive(Clone)]
 pub struct StackMapTable {
@@ -152,8 +155,160 @@ impl Parsable for VerificationTypeInfo {
   }
 }
 
+impl StackMapTable {
+  pub fn entries(&self) -> &[StackMapFrame] {
+    &self.entries
+  }
+
+  /// The frames with the bytecode offset each applies to. The first frame is
+  /// at `offset_delta`, every later one at the previous offset plus
+  /// `offset_delta + 1`. Stops at the first frame with a reserved type, whose
+  /// length and delta are unknown.
+  pub fn frames_with_pcs(&self) -> Vec<(u32, &StackMapFrame)> {
+    let mut frames = vec![];
+    let mut previous: Option<u32> = None;
+    for frame in &self.entries {
+      let Some(offset_delta) = frame.offset_delta() else {
+        break;
+      };
+      let pc = match previous {
+        Some(previous) => previous + offset_delta as u32 + 1,
+        None => offset_delta as u32,
+      };
+      frames.push((pc, frame));
+      previous = Some(pc);
+    }
+    frames
+  }
+}
+
+impl StackMapFrame {
+  /// `None` for a reserved frame type.
+  pub fn offset_delta(&self) -> Option<u16> {
+    match self {
+      Self::SameFrame(frame_type) => Some(*frame_type as u16),
+      Self::SameLocals1StackItemFrame((frame_type, _)) => Some(*frame_type as u16 - 64),
+      Self::SameLocals1StackItemFrameExtended((_, offset_delta, _))
+      | Self::ChopFrame((_, offset_delta))
+      | Self::SameFrameExtended((_, offset_delta))
+      | Self::AppendFrame((_, offset_delta, _))
+      | Self::FullFrame((_, offset_delta, _, _)) => Some(*offset_delta),
+      Self::Invalid => None,
+    }
+  }
+}
+
+fn write_types(
+  f: &mut std::fmt::Formatter<'_>,
+  name: &str,
+  types: &[VerificationTypeInfo],
+) -> std::fmt::Result {
+  let types: Vec<String> = types.iter().map(|ty| ty.to_string()).collect();
+  write!(f, " {}=[{}]", name, types.join(", "))
+}
+
+impl Display for StackMapFrame {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::SameFrame(_) => write!(f, "same"),
+      Self::SameLocals1StackItemFrame((_, stack)) => {
+        write!(f, "same_locals_1_stack_item stack=[{}]", stack)
+      }
+      Self::SameLocals1StackItemFrameExtended((_, _, stack)) => {
+        write!(f, "same_locals_1_stack_item_extended stack=[{}]", stack)
+      }
+      Self::ChopFrame((frame_type, _)) => write!(f, "chop {}", 251 - frame_type),
+      Self::SameFrameExtended(_) => write!(f, "same_extended"),
+      Self::AppendFrame((_, _, locals)) => {
+        write!(f, "append")?;
+        write_types(f, "locals", locals)
+      }
+      Self::FullFrame((_, _, locals, stack)) => {
+        write!(f, "full")?;
+        write_types(f, "locals", locals)?;
+        write_types(f, "stack", stack)
+      }
+      Self::Invalid => write!(f, "invalid"),
+    }
+  }
+}
+
+impl Display for VerificationTypeInfo {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Top => write!(f, "top"),
+      Self::Integer => write!(f, "int"),
+      Self::Float => write!(f, "float"),
+      Self::Long => write!(f, "long"),
+      Self::Double => write!(f, "double"),
+      Self::Null => write!(f, "null"),
+      Self::UninitializedThis => write!(f, "uninitialized_this"),
+      Self::Object(cpool_index) => write!(f, "class #{}", cpool_index),
+      Self::Uninitialized(offset) => write!(f, "uninitialized {}", offset),
+      Self::Invalid => write!(f, "invalid"),
+    }
+  }
+}
+
 impl Display for StackMapTable {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    writeln!(f, "StackMapTable({})", self.number_of_entries)
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+/// One line per frame, at its absolute bytecode offset as javap shows it.
+impl DepthDisplay for StackMapTable {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
+    writeln!(f, "StackMapTable({})", self.number_of_entries)?;
+    if depth.nested().is_none() {
+      if !self.entries.is_empty() {
+        writeln!(f, "\t{}", ELIDED)?;
+      }
+      return Ok(());
+    }
+    let frames = self.frames_with_pcs();
+    for (pc, frame) in &frames {
+      writeln!(f, "\t{}: {}", pc, frame)?;
+    }
+    if frames.len() < self.entries.len() {
+      writeln!(
+        f,
+        "\t({} frames after a reserved frame type)",
+        self.entries.len() - frames.len()
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::Parsable;
+
+  use super::StackMapTable;
+
+  #[test]
+  fn test_frames_with_pcs() {
+    #[rustfmt::skip]
+    let bytes = [
+      0, 5,
+      5,                      // same, delta 5
+      64 + 2, 1,              // same_locals_1_stack_item int, delta 2
+      252, 0, 10, 7, 0, 3,    // append class #3, delta 10
+      250, 0, 0,              // chop 1, delta 0
+      255, 0, 4, 0, 1, 4, 0, 1, 5, // full locals=[double] stack=[null], delta 4
+    ];
+    let table = StackMapTable::parse_from_u8(&bytes).unwrap();
+    let pcs: Vec<u32> = table.frames_with_pcs().iter().map(|(pc, _)| *pc).collect();
+    assert_eq!(pcs, vec![5, 8, 19, 20, 25]);
+    assert_eq!(
+      table.to_string(),
+      "StackMapTable(5)\n\
+       \t5: same\n\
+       \t8: same_locals_1_stack_item stack=[int]\n\
+       \t19: append locals=[class #3]\n\
+       \t20: chop 1\n\
+       \t25: full locals=[double] stack=[null]\n"
+    );
   }
 }
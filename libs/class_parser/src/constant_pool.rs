@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use base::{method_handle::method_handle_kind_name, mutf8::decode_mutf8_exact, Parsable};
 use nom::{
   bytes::complete::take,
   combinator::map,
@@ -7,6 +8,56 @@ use nom::{
   number::complete::{be_u16, be_u32, be_u8},
   IResult,
 };
+
+/// A constant pool index as stored in the class file.
+///
+/// Indices are 1-based, 0 stands for "none", and the slot after a `Long` or
+/// `Double` is an unusable placeholder; `resolve` is the one place that knows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CpIndex(pub u16);
+
+impl CpIndex {
+  pub fn value(&self) -> u16 {
+    self.0
+  }
+
+  /// Index 0, which optional references use to mean "none".
+  pub fn is_none(&self) -> bool {
+    self.0 == 0
+  }
+
+  /// The entry this index names in `pool`, or `None` for index 0, indices past
+  /// the pool and the placeholder slot of a wide constant.
+  pub fn resolve<'a>(&self, pool: &'a [ConstantPoolInfo]) -> Option<&'a ConstantPoolInfo> {
+    let constant = pool.get((self.0 as usize).checked_sub(1)?)?;
+    match constant.info {
+      ConstantType::Empty => None,
+      _ => Some(constant),
+    }
+  }
+
+  pub fn resolve_utf8<'a>(&self, pool: &'a [ConstantPoolInfo]) -> Option<&'a str> {
+    self.resolve(pool)?.as_utf8()
+  }
+}
+
+impl From<u16> for CpIndex {
+  fn from(index: u16) -> Self {
+    Self(index)
+  }
+}
+
+impl Parsable for CpIndex {
+  fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+    map(be_u16, CpIndex)(bytes)
+  }
+}
+
+impl Display for CpIndex {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
 #[derive(Clone)]
 pub struct ConstantPoolInfo {
   tag: u8,
@@ -28,7 +79,7 @@ pub enum ConstantType {
   NameAndType(u16, u16),
   MethodHandle(u8, u16),
   MethodType(u16),
-  // Dynamic,
+  Dynamic(u16, u16),
   InvokeDynamic(u16, u16),
   // Module,
   // Package,
@@ -88,6 +139,11 @@ impl ConstantPoolInfo {
     ))
   }
 
+  /// The constant's tag, e.g. 1 for `Utf8` and 7 for `Class`.
+  pub fn tag(&self) -> u8 {
+    self.tag
+  }
+
   pub fn is_double_size(&self) -> bool {
     match self.info {
       ConstantType::Double(_) => true,
@@ -110,6 +166,18 @@ impl ConstantPoolInfo {
     }
   }
 
+  /// `name_index` of a `Class` constant.
+  pub fn as_class(&self) -> Option<u16> {
+    match self.info {
+      ConstantType::Class(name_index) => Some(name_index),
+      _ => None,
+    }
+  }
+
+  pub fn info(&self) -> &ConstantType {
+    &self.info
+  }
+
   pub fn new_empty() -> Self {
     Self {
       tag: 0,
@@ -125,10 +193,8 @@ impl ConstantType {
     match tag {
       1 => {
         let (bytes, length) = be_u16(bytes)?;
-        // TODO parse utf8
-        let (bytes, value) = map(take(length), |bytes: &[u8]| {
-          String::from_utf8(bytes.to_vec()).unwrap()
-        })(bytes)?;
+        let (bytes, data) = take(length)(bytes)?;
+        let (_, value) = decode_mutf8_exact(data)?;
         Ok((bytes, ConstantType::Utf8(value)))
       }
       3 => {
@@ -201,7 +267,14 @@ impl ConstantType {
         let (bytes, descriptor_index) = be_u16(bytes)?;
         Ok((bytes, ConstantType::MethodType(descriptor_index)))
       }
-      // 17 => Ok((bytes, ConstantType::Dynamic)),
+      17 => {
+        let (bytes, bootstrap_method_attr_index) = be_u16(bytes)?;
+        let (bytes, name_and_type_index) = be_u16(bytes)?;
+        Ok((
+          bytes,
+          ConstantType::Dynamic(bootstrap_method_attr_index, name_and_type_index),
+        ))
+      }
       18 => {
         let (bytes, bootstrap_method_attr_index) = be_u16(bytes)?;
         let (bytes, name_and_type_index) = be_u16(bytes)?;
@@ -238,6 +311,7 @@ impl ConstantType {
       ConstantType::NameAndType(_, _) => 12,
       ConstantType::MethodHandle(_, _) => 15,
       ConstantType::MethodType(_) => 16,
+      ConstantType::Dynamic(_, _) => 17,
       ConstantType::InvokeDynamic(_, _) => 18,
       ConstantType::Empty => 0,
     }
@@ -276,12 +350,20 @@ impl Display for ConstantType {
         write!(
           f,
           "MethodHandle: reference_kind: {}, reference_index: {}",
-          reference_kind, reference_index
+          method_handle_kind_name(*reference_kind),
+          reference_index
         )
       }
       ConstantType::MethodType(descriptor) => {
         write!(f, "MethodType: {}", descriptor)
       }
+      ConstantType::Dynamic(bootstrap_method_attr, name_and_type) => {
+        write!(
+          f,
+          "Dynamic: bootstrap_method_attr: {}, name_and_type: {}",
+          bootstrap_method_attr, name_and_type
+        )
+      }
       ConstantType::InvokeDynamic(bootstrap_method_attr, name_and_type) => {
         write!(
           f,
@@ -301,3 +383,91 @@ impl Display for ConstantPoolInfo {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use base::{Parsable, RenderSource};
+
+  use super::{ConstantType, CpIndex};
+  use crate::test_util::ClassBuilder;
+
+  #[test]
+  fn test_cp_index_resolve() {
+    let mut builder = ClassBuilder::new("a/A", None);
+    let wide = builder.long(1);
+    let after = builder.utf8("after");
+    let source_file = builder.utf8("A.java").to_be_bytes();
+    let source_file = builder.attribute("SourceFile", &source_file);
+    builder.class_attribute(source_file);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let pool = class_file.context().constant_pool();
+
+    // a/A (1), Class (2), Long (3) and its placeholder (4), "after" (5).
+    assert_eq!((wide, after), (3, 5));
+    assert!(CpIndex(0).resolve(pool).is_none());
+    assert_eq!(CpIndex(1).resolve_utf8(pool), Some("a/A"));
+    assert!(CpIndex(2).resolve(pool).unwrap().as_class().is_some());
+    assert!(CpIndex(wide).resolve(pool).unwrap().is_double_size());
+    assert!(CpIndex(wide + 1).resolve(pool).is_none());
+    assert_eq!(CpIndex(after).resolve_utf8(pool), Some("after"));
+    assert!(CpIndex(pool.len() as u16 + 1).resolve(pool).is_none());
+    // A Class constant is not a Utf8 one.
+    assert!(CpIndex(2).resolve_utf8(pool).is_none());
+
+    let (rest, index) = CpIndex::parse::<nom::error::Error<_>>(&[0x01, 0x02, 0xff]).unwrap();
+    assert_eq!((index, rest), (CpIndex(0x0102), &[0xff][..]));
+
+    // Attributes resolve their names and values through CpIndex.
+    assert_eq!(class_file.render_attributes(), vec!["SourceFile"]);
+    assert!(class_file
+      .render_file_info()
+      .contains(&"source file: A.java".to_string()));
+    assert!(class_file.context().find_constant(wide + 1).is_none());
+  }
+
+  #[test]
+  fn test_modified_utf8() {
+    // javac's encoding of "a\u0000b": NUL as C0 80.
+    let mut builder = ClassBuilder::new("a/A", None);
+    let nul = builder.raw_constant(vec![1, 0, 4, b'a', 0xc0, 0x80, b'b']);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let pool = class_file.context().constant_pool();
+    assert_eq!(CpIndex(nul).resolve_utf8(pool), Some("a\0b"));
+
+    // A sequence cut off by the entry's length fails the parse.
+    let mut builder = ClassBuilder::new("a/A", None);
+    builder.raw_constant(vec![1, 0, 2, b'a', 0xc0, 0x80]);
+    assert!(crate::parse(&builder.build()).is_err());
+  }
+
+  #[test]
+  fn test_method_handle_kinds() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let count = builder.field_ref("a/A", "count", "I");
+    let getter = builder.method_handle(1, count);
+    let run = builder.interface_method_ref("java/lang/Runnable", "run", "()V");
+    let invoker = builder.method_handle(9, run);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    assert_eq!(
+      context.get_constant(getter).info().to_string(),
+      format!(
+        "MethodHandle: reference_kind: REF_getField, reference_index: {}",
+        count
+      )
+    );
+    assert_eq!(
+      context.render_constant(getter),
+      "MethodHandle -> REF_getField a/A.count:I"
+    );
+    assert_eq!(
+      context.render_constant(invoker),
+      "MethodHandle -> REF_invokeInterface java/lang/Runnable.run:()V"
+    );
+    assert_eq!(
+      ConstantType::MethodHandle(0, 1).to_string(),
+      "MethodHandle: reference_kind: REF_unknown, reference_index: 1"
+    );
+  }
+}
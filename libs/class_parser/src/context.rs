@@ -0,0 +1,493 @@
+use base::{
+  error::{Error, ErrorKind},
+  method_handle::method_handle_kind_name,
+};
+
+use crate::{
+  constant_pool::{ConstantPoolInfo, ConstantType, CpIndex},
+  ParseOptions,
+};
+
+/// Owns the constant pool of a parsed class file and resolves indices into it.
+///
+/// Lookups take the 1-based indices stored in the class file. `Long` and
+/// `Double` entries are followed by an `Empty` placeholder in the pool, so an
+/// index past a wide constant still lands on the entry it names.
+///
+/// `ClassFile` and every item parsed from it keep an `Arc` to the same
+/// context, so several class files can be parsed and inspected side by side.
+#[derive(Default)]
+pub struct ResolveContext {
+  constant_pool: Vec<ConstantPoolInfo>,
+  options: ParseOptions,
+}
+
+impl ResolveContext {
+  pub fn new(constant_pool: Vec<ConstantPoolInfo>) -> Self {
+    Self::with_options(constant_pool, ParseOptions::default())
+  }
+
+  pub fn with_options(constant_pool: Vec<ConstantPoolInfo>, options: ParseOptions) -> Self {
+    Self {
+      constant_pool,
+      options,
+    }
+  }
+
+  /// The options the class was parsed with.
+  pub fn options(&self) -> ParseOptions {
+    self.options
+  }
+
+  pub fn constant_pool(&self) -> &[ConstantPoolInfo] {
+    &self.constant_pool
+  }
+
+  /// The constant at `index`, or `None` for index 0 (which the spec uses to
+  /// mean "none", e.g. a `catch_type` of `finally`), indices past the pool
+  /// and the placeholder after a wide constant. See `CpIndex::resolve`.
+  pub fn find_constant(&self, index: u16) -> Option<&ConstantPoolInfo> {
+    CpIndex(index).resolve(&self.constant_pool)
+  }
+
+  pub fn find_str_const(&self, index: u16) -> Option<&str> {
+    self.find_constant(index)?.as_utf8()
+  }
+
+  pub fn find_class_name(&self, index: u16) -> Option<&str> {
+    self.find_str_const(self.find_constant(index)?.as_class()?)
+  }
+
+  /// Internal name of the class at `index`: a `Class` constant, or the owner
+  /// of a `Fieldref`, `Methodref` or `InterfaceMethodref`.
+  pub fn resolve_class_name(&self, index: u16) -> Option<&str> {
+    match self.find_constant(index)?.info() {
+      ConstantType::Class(_) => self.find_class_name(index),
+      ConstantType::Fieldref(class, _)
+      | ConstantType::Methodref(class, _)
+      | ConstantType::InterfaceMethodref(class, _) => self.find_class_name(*class),
+      _ => None,
+    }
+  }
+
+  /// `(name, descriptor)` of the `NameAndType` constant at `index`, or of the
+  /// one a `Fieldref`, `Methodref`, `InterfaceMethodref`, `Dynamic` or
+  /// `InvokeDynamic` points at.
+  pub fn resolve_name_and_type(&self, index: u16) -> Option<(&str, &str)> {
+    match self.find_constant(index)?.info() {
+      ConstantType::NameAndType(name, descriptor) => Some((
+        self.find_str_const(*name)?,
+        self.find_str_const(*descriptor)?,
+      )),
+      ConstantType::Fieldref(_, name_and_type)
+      | ConstantType::Methodref(_, name_and_type)
+      | ConstantType::InterfaceMethodref(_, name_and_type)
+      | ConstantType::Dynamic(_, name_and_type)
+      | ConstantType::InvokeDynamic(_, name_and_type) => {
+        match self.find_constant(*name_and_type)?.info() {
+          ConstantType::NameAndType(..) => self.resolve_name_and_type(*name_and_type),
+          _ => None,
+        }
+      }
+      _ => None,
+    }
+  }
+
+  /// Like `find_str_const`, for a mandatory reference: index 0 and anything
+  /// but a `Utf8` constant fail with `ErrorKind::InvalidConstantIndex`.
+  pub fn require_str_const(&self, index: u16) -> Result<&str, Error> {
+    self
+      .find_str_const(index)
+      .ok_or_else(|| Self::invalid_index(index, "Utf8"))
+  }
+
+  /// Like `find_class_name`, for a mandatory reference such as `this_class`.
+  pub fn require_class_name(&self, index: u16) -> Result<&str, Error> {
+    self
+      .find_class_name(index)
+      .ok_or_else(|| Self::invalid_index(index, "Class"))
+  }
+
+  /// Like `require_class_name`, for a reference where index 0 means "none",
+  /// such as `super_class`.
+  pub fn optional_class_name(&self, index: u16) -> Result<Option<&str>, Error> {
+    if CpIndex(index).is_none() {
+      return Ok(None);
+    }
+    self.require_class_name(index).map(Some)
+  }
+
+  fn invalid_index(index: u16, expected: &'static str) -> Error {
+    ErrorKind::InvalidConstantIndex { index, expected }.into()
+  }
+
+  /// Like `find_constant`, for indices the spec requires to be valid.
+  pub fn get_constant(&self, index: u16) -> &ConstantPoolInfo {
+    self
+      .find_constant(index)
+      .unwrap_or_else(|| panic!("invalid constant pool index {}", index))
+  }
+
+  pub fn get_str_const(&self, index: u16) -> &str {
+    self.get_constant(index).as_utf8().unwrap()
+  }
+
+  /// Internal name of the `Class` constant at `index`.
+  pub fn get_class_name(&self, index: u16) -> &str {
+    self.get_str_const(self.get_constant(index).as_class().unwrap())
+  }
+
+  /// `name:descriptor` of the `NameAndType` constant at `index`.
+  fn name_and_type(&self, index: u16) -> String {
+    match self.resolve_name_and_type(index) {
+      Some((name, descriptor)) => format!("{}:{}", name, descriptor),
+      None => format!("#{}", index),
+    }
+  }
+
+  /// `owner.name:descriptor` of the `Fieldref`, `Methodref` or
+  /// `InterfaceMethodref` constant at `index`, e.g.
+  /// `java/lang/Object.<init>:()V`; `None` for any other constant or one
+  /// whose class or `NameAndType` index doesn't lead to one.
+  pub fn resolve_member(&self, index: u16) -> Option<String> {
+    match self.find_constant(index)?.info() {
+      ConstantType::Fieldref(..)
+      | ConstantType::Methodref(..)
+      | ConstantType::InterfaceMethodref(..) => {
+        let (name, descriptor) = self.resolve_name_and_type(index)?;
+        Some(format!(
+          "{}.{}:{}",
+          self.resolve_class_name(index)?,
+          name,
+          descriptor
+        ))
+      }
+      _ => None,
+    }
+  }
+
+  /// The constant at `index` with every index it holds followed, e.g.
+  /// `Methodref -> java/lang/Object.<init>:()V`.
+  pub fn render_constant(&self, index: u16) -> String {
+    let Some(constant) = self.find_constant(index) else {
+      // The placeholder after a wide constant, or an index naming nothing.
+      return match (index as usize).checked_sub(1) {
+        Some(slot) if slot < self.constant_pool.len() => self.constant_pool[slot].to_string(),
+        _ => format!("#{}", index),
+      };
+    };
+    let info = constant.info();
+    // Left as indices when they don't lead where they should.
+    match info {
+      ConstantType::Class(_) => match self.find_class_name(index) {
+        Some(name) => format!("Class -> {}", name),
+        None => info.to_string(),
+      },
+      ConstantType::String(value) => match self.find_str_const(*value) {
+        Some(value) => format!("String -> {:?}", value),
+        None => info.to_string(),
+      },
+      ConstantType::Fieldref(..)
+      | ConstantType::Methodref(..)
+      | ConstantType::InterfaceMethodref(..) => {
+        let kind = match info {
+          ConstantType::Fieldref(..) => "Fieldref",
+          ConstantType::Methodref(..) => "Methodref",
+          _ => "InterfaceMethodref",
+        };
+        match self.resolve_member(index) {
+          Some(member) => format!("{} -> {}", kind, member),
+          None => info.to_string(),
+        }
+      }
+      ConstantType::NameAndType(..) => format!("NameAndType -> {}", self.name_and_type(index)),
+      ConstantType::MethodHandle(reference_kind, reference) => format!(
+        "MethodHandle -> {} {}",
+        method_handle_kind_name(*reference_kind),
+        self
+          .resolve_member(*reference)
+          .unwrap_or_else(|| format!("#{}", reference))
+      ),
+      ConstantType::MethodType(descriptor) => match self.find_str_const(*descriptor) {
+        Some(descriptor) => format!("MethodType -> {}", descriptor),
+        None => info.to_string(),
+      },
+      ConstantType::Dynamic(bootstrap_method_attr, name_and_type) => format!(
+        "Dynamic -> #{}:{}",
+        bootstrap_method_attr,
+        self.name_and_type(*name_and_type)
+      ),
+      ConstantType::InvokeDynamic(bootstrap_method_attr, name_and_type) => format!(
+        "InvokeDynamic -> #{}:{}",
+        bootstrap_method_attr,
+        self.name_and_type(*name_and_type)
+      ),
+      _ => info.to_string(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::{error::ErrorKind, RenderSource};
+
+  use crate::test_util::ClassBuilder;
+
+  fn class_with_method(class: &str, method: &str) -> Vec<u8> {
+    let mut builder = ClassBuilder::new(class, Some("java/lang/Object"));
+    builder.method(0x0001, method, "()V", vec![]);
+    builder.build()
+  }
+
+  #[test]
+  fn test_contexts_are_isolated() {
+    // Different pool layouts: "run" lands on a different index than "stop".
+    let first = crate::parse(&class_with_method("a/First", "run")).unwrap();
+    let mut builder = ClassBuilder::new("b/Second", None);
+    builder.utf8("padding");
+    builder.method(0x0001, "stop", "()V", vec![]);
+    let second = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(first.render_methods_verbose()[0].name(), "run");
+    assert_eq!(second.render_methods_verbose()[0].name(), "stop");
+    assert_ne!(
+      first.context().constant_pool().len(),
+      second.context().constant_pool().len()
+    );
+  }
+
+  #[test]
+  fn test_wide_constants_before_referenced_strings() {
+    let mut builder = ClassBuilder::new("a/Wide", Some("java/lang/Object"));
+    builder.long(1);
+    builder.double(2.0);
+    builder.interface("java/lang/Runnable");
+    builder.field(0x0002, "count", "J", vec![]);
+    builder.method(0x0001, "run", "()V", vec![]);
+    let source_file = builder.utf8("Wide.java").to_be_bytes();
+    let source_file = builder.attribute("SourceFile", &source_file);
+    builder.class_attribute(source_file);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let field = &class_file.fields()[0];
+    assert_eq!((field.name(), field.descriptor()), ("count", "J"));
+    let method = &class_file.methods()[0];
+    assert_eq!((method.name(), method.descriptor()), ("run", "()V"));
+    assert_eq!(class_file.render_interfaces(), vec!["java/lang/Runnable"]);
+    assert_eq!(class_file.render_attributes(), vec!["SourceFile"]);
+    assert!(class_file
+      .render_file_info()
+      .contains(&"source file: Wide.java".to_string()));
+
+    // a/Wide (2), java/lang/Object (2), Long (2), Double (2): the next entry is #9.
+    let context = class_file.context();
+    assert_eq!(context.get_str_const(9), "java/lang/Runnable");
+    assert_eq!(context.get_class_name(10), "java/lang/Runnable");
+    assert_eq!(
+      class_file.render_constant_pool()[8],
+      "#9: Utf8: java/lang/Runnable"
+    );
+  }
+
+  #[test]
+  fn test_render_constant_pool_resolved() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let init = builder.method_ref("java/lang/Object", "<init>", "()V");
+    let count = builder.field_ref("a/A", "count", "I");
+    let run = builder.interface_method_ref("java/lang/Runnable", "run", "()V");
+    let greeting = builder.string("hello \"world\"");
+    let wide = builder.long(7);
+    let handle = builder.method_handle(6, init);
+    let method_type = builder.method_type("(I)V");
+    let call_site = builder.invoke_dynamic(0, "apply", "()Ljava/lang/Runnable;");
+    let dynamic = builder.dynamic(1, "_", "Ljava/lang/Class;");
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let pool = class_file.render_constant_pool_resolved();
+    let rendered = |index: u16| pool[index as usize - 1].as_str();
+    assert_eq!(rendered(2), "#2: Class -> a/A");
+    assert_eq!(
+      rendered(init),
+      format!("#{}: Methodref -> java/lang/Object.<init>:()V", init)
+    );
+    assert_eq!(
+      rendered(count),
+      format!("#{}: Fieldref -> a/A.count:I", count)
+    );
+    assert_eq!(
+      rendered(count - 1),
+      format!("#{}: NameAndType -> count:I", count - 1)
+    );
+    assert_eq!(
+      rendered(run),
+      format!("#{}: InterfaceMethodref -> java/lang/Runnable.run:()V", run)
+    );
+    assert_eq!(
+      rendered(greeting),
+      format!("#{}: String -> \"hello \\\"world\\\"\"", greeting)
+    );
+    assert_eq!(rendered(wide), format!("#{}: Long: 7", wide));
+    assert_eq!(
+      rendered(handle),
+      format!(
+        "#{}: MethodHandle -> REF_invokeStatic java/lang/Object.<init>:()V",
+        handle
+      )
+    );
+    assert_eq!(
+      rendered(method_type),
+      format!("#{}: MethodType -> (I)V", method_type)
+    );
+    assert_eq!(
+      rendered(call_site),
+      format!(
+        "#{}: InvokeDynamic -> #0:apply:()Ljava/lang/Runnable;",
+        call_site
+      )
+    );
+    assert_eq!(
+      rendered(dynamic),
+      format!("#{}: Dynamic -> #1:_:Ljava/lang/Class;", dynamic)
+    );
+    // The raw view is unchanged.
+    assert_eq!(class_file.render_constant_pool()[1], "#2: Class: 1");
+  }
+
+  #[test]
+  fn test_zero_index() {
+    let builder = ClassBuilder::new("java/lang/Object", None);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    assert!(context.find_constant(0).is_none());
+    assert!(context.find_str_const(0).is_none());
+    assert!(context.find_class_name(0).is_none());
+    assert!(context.find_constant(3).is_none());
+    assert_eq!(context.find_class_name(2), Some("java/lang/Object"));
+    // A Class constant is not a Utf8 one.
+    assert!(context.find_str_const(2).is_none());
+
+    assert_eq!(class_file.this_class_name(), "java/lang/Object");
+    assert_eq!(class_file.super_class_name(), None);
+
+    // Mandatory references reject index 0, optional ones read it as "none".
+    assert!(matches!(
+      context.require_class_name(0).err().unwrap().kind(),
+      ErrorKind::InvalidConstantIndex {
+        index: 0,
+        expected: "Class"
+      }
+    ));
+    assert!(context.require_str_const(0).is_err());
+    assert_eq!(context.optional_class_name(0).unwrap(), None);
+    assert_eq!(
+      context.optional_class_name(2).unwrap(),
+      Some("java/lang/Object")
+    );
+    // A non-zero optional reference must still be valid.
+    assert!(context.optional_class_name(1).is_err());
+    assert_eq!(context.require_class_name(2).unwrap(), "java/lang/Object");
+    assert_eq!(context.render_constant(0), "#0");
+    assert_eq!(context.render_constant(3), "#3");
+  }
+
+  #[test]
+  fn test_resolve_member_references() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let init = builder.method_ref("java/lang/Object", "<init>", "()V");
+    let count = builder.field_ref("a/A", "count", "I");
+    let run = builder.interface_method_ref("java/lang/Runnable", "run", "()V");
+    let call_site = builder.invoke_dynamic(0, "apply", "()Ljava/lang/Runnable;");
+    let name = builder.utf8("count");
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    assert_eq!(context.resolve_class_name(init), Some("java/lang/Object"));
+    assert_eq!(context.resolve_name_and_type(init), Some(("<init>", "()V")));
+    assert_eq!(context.resolve_class_name(count), Some("a/A"));
+    assert_eq!(context.resolve_name_and_type(count), Some(("count", "I")));
+    // The NameAndType the Fieldref points at resolves on its own too.
+    assert_eq!(
+      context.resolve_name_and_type(count - 1),
+      Some(("count", "I"))
+    );
+    assert_eq!(context.resolve_class_name(run), Some("java/lang/Runnable"));
+    assert_eq!(context.resolve_name_and_type(run), Some(("run", "()V")));
+    assert_eq!(context.resolve_class_name(2), Some("a/A"));
+    assert_eq!(
+      context.resolve_name_and_type(call_site),
+      Some(("apply", "()Ljava/lang/Runnable;"))
+    );
+    assert_eq!(context.resolve_class_name(call_site), None);
+
+    assert_eq!(context.resolve_class_name(name), None);
+    assert_eq!(context.resolve_name_and_type(name), None);
+    assert_eq!(context.resolve_class_name(0), None);
+    assert_eq!(context.resolve_name_and_type(0), None);
+  }
+
+  #[test]
+  fn test_resolve_member() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let init = builder.method_ref("java/lang/Object", "<init>", "()V");
+    let count = builder.field_ref("a/A", "count", "I");
+    // Methodref #1.#1: a Utf8 where the Class and NameAndType should be.
+    let broken = builder.raw_constant(vec![10, 0, 1, 0, 1]);
+    // Fieldref a/A.#200: past the end of the pool.
+    let dangling = builder.raw_constant(vec![9, 0, 2, 0, 200]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    assert_eq!(
+      context.resolve_member(init).as_deref(),
+      Some("java/lang/Object.<init>:()V")
+    );
+    assert_eq!(
+      context.resolve_member(count).as_deref(),
+      Some("a/A.count:I")
+    );
+    assert_eq!(context.resolve_member(broken), None);
+    assert_eq!(context.resolve_member(dangling), None);
+    assert_eq!(context.resolve_member(2), None);
+    assert_eq!(context.resolve_member(0), None);
+
+    // A member that can't be followed is shown with its raw indices.
+    assert_eq!(
+      context.render_constant(broken),
+      "Methodref: class: 1, name_and_type: 1"
+    );
+    assert_eq!(
+      context.render_constant(dangling),
+      "Fieldref: class: 2, name_and_type: 200"
+    );
+  }
+
+  #[test]
+  fn test_render_unresolved_constants() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let number = builder.integer(1);
+    // A Class, a String and a MethodType naming the Integer, and a Class
+    // past the end of the pool.
+    let class = builder.raw_constant(vec![7, 0, number as u8]);
+    let string = builder.raw_constant(vec![8, 0, number as u8]);
+    let method_type = builder.raw_constant(vec![16, 0, number as u8]);
+    let dangling = builder.raw_constant(vec![7, 0, 200]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let context = class_file.context();
+
+    assert_eq!(context.render_constant(class), format!("Class: {}", number));
+    assert_eq!(
+      context.render_constant(string),
+      format!("String: {}", number)
+    );
+    assert_eq!(
+      context.render_constant(method_type),
+      format!("MethodType: {}", number)
+    );
+    assert_eq!(context.render_constant(dangling), "Class: 200");
+    assert_eq!(
+      class_file.render_constant_pool_resolved().len(),
+      context.constant_pool().len()
+    );
+  }
+}
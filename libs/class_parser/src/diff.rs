@@ -0,0 +1,215 @@
+use std::fmt::Display;
+
+use base::access_flag::AccessFlags;
+
+use crate::raw_class::ClassFile;
+
+/// Structural differences between two versions of a class.
+///
+/// Fields and methods are matched by name and descriptor, so a changed
+/// descriptor shows up as one removed and one added member.
+#[derive(Default)]
+pub struct ClassDiff {
+  /// `(major, minor)` before and after.
+  version: Option<((u16, u16), (u16, u16))>,
+  access_flags: Option<(String, String)>,
+  fields: MemberDiff,
+  methods: MemberDiff,
+}
+
+#[derive(Default)]
+pub struct MemberDiff {
+  added: Vec<String>,
+  removed: Vec<String>,
+  /// `(signature, old access flags, new access flags)`.
+  changed: Vec<(String, String, String)>,
+}
+
+impl ClassDiff {
+  pub fn new(old: &ClassFile, new: &ClassFile) -> Self {
+    let old_version = (old.major_version(), old.minor_version());
+    let new_version = (new.major_version(), new.minor_version());
+    let fields = MemberDiff::new(Self::field_signatures(old), Self::field_signatures(new));
+    let methods = MemberDiff::new(Self::method_signatures(old), Self::method_signatures(new));
+    Self {
+      version: (old_version != new_version).then_some((old_version, new_version)),
+      access_flags: (old.access_flags().value() != new.access_flags().value()).then(|| {
+        (
+          old.access_flags().to_string(),
+          new.access_flags().to_string(),
+        )
+      }),
+      fields,
+      methods,
+    }
+  }
+
+  fn field_signatures(class: &ClassFile) -> impl Iterator<Item = (String, &AccessFlags)> {
+    class.fields().iter().map(|field| {
+      let signature = format!("{}:{}", field.name(), field.descriptor());
+      (signature, field.access_flags())
+    })
+  }
+
+  fn method_signatures(class: &ClassFile) -> impl Iterator<Item = (String, &AccessFlags)> {
+    class.methods().iter().map(|method| {
+      let signature = format!("{}:{}", method.name(), method.descriptor());
+      (signature, method.access_flags())
+    })
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.version.is_none()
+      && self.access_flags.is_none()
+      && self.fields.is_empty()
+      && self.methods.is_empty()
+  }
+
+  pub fn fields(&self) -> &MemberDiff {
+    &self.fields
+  }
+
+  pub fn methods(&self) -> &MemberDiff {
+    &self.methods
+  }
+}
+
+impl MemberDiff {
+  fn new<'a>(
+    old: impl Iterator<Item = (String, &'a AccessFlags)>,
+    new: impl Iterator<Item = (String, &'a AccessFlags)>,
+  ) -> Self {
+    let old: Vec<_> = old.collect();
+    let new: Vec<_> = new.collect();
+    let mut diff = Self::default();
+    for (signature, old_flags) in &old {
+      match new.iter().find(|(other, _)| other == signature) {
+        None => diff.removed.push(signature.clone()),
+        Some((_, new_flags)) if new_flags.value() != old_flags.value() => diff.changed.push((
+          signature.clone(),
+          old_flags.to_string(),
+          new_flags.to_string(),
+        )),
+        Some(_) => {}
+      }
+    }
+    for (signature, _) in &new {
+      if !old.iter().any(|(other, _)| other == signature) {
+        diff.added.push(signature.clone());
+      }
+    }
+    diff
+  }
+
+  pub fn added(&self) -> &[String] {
+    &self.added
+  }
+
+  pub fn removed(&self) -> &[String] {
+    &self.removed
+  }
+
+  pub fn changed(&self) -> &[(String, String, String)] {
+    &self.changed
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+impl Display for ClassDiff {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.is_empty() {
+      return writeln!(f, "no differences");
+    }
+    if let Some(((old_major, old_minor), (new_major, new_minor))) = self.version {
+      writeln!(
+        f,
+        "version: {}.{} -> {}.{}",
+        old_major, old_minor, new_major, new_minor
+      )?;
+    }
+    if let Some((old, new)) = &self.access_flags {
+      writeln!(f, "access_flags: {} -> {}", old, new)?;
+    }
+    if !self.fields.is_empty() {
+      write!(f, "fields: {}", self.fields)?;
+    }
+    if !self.methods.is_empty() {
+      write!(f, "methods: {}", self.methods)?;
+    }
+    Ok(())
+  }
+}
+
+impl Display for MemberDiff {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      "{} added, {} removed, {} changed",
+      self.added.len(),
+      self.removed.len(),
+      self.changed.len()
+    )?;
+    for signature in &self.added {
+      writeln!(f, "\t+ {}", signature)?;
+    }
+    for signature in &self.removed {
+      writeln!(f, "\t- {}", signature)?;
+    }
+    for (signature, old, new) in &self.changed {
+      writeln!(f, "\t~ {}\t{} -> {}", signature, old, new)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ClassDiff;
+  use crate::test_util::ClassBuilder;
+
+  #[test]
+  fn test_diff_members() {
+    let mut old = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    old.field(0x0002, "width", "I", vec![]);
+    old.field(0x0002, "height", "I", vec![]);
+    old.method(0x0001, "run", "()V", vec![]);
+    let old = crate::parse(&old.build()).unwrap();
+
+    let mut new = ClassBuilder::new("a/A", Some("java/lang/Object"))
+      .version(61, 0)
+      .access_flags(0x0031);
+    new.field(0x0001, "width", "I", vec![]);
+    new.method(0x0001, "run", "(I)V", vec![]);
+    let new = crate::parse(&new.build()).unwrap();
+
+    let diff = ClassDiff::new(&old, &new);
+    assert!(!diff.is_empty());
+    assert_eq!(diff.fields().removed(), &["height:I"]);
+    assert!(diff.fields().added().is_empty());
+    assert_eq!(diff.fields().changed()[0].0, "width:I");
+    assert_eq!(diff.methods().added(), &["run:(I)V"]);
+    assert_eq!(diff.methods().removed(), &["run:()V"]);
+
+    let summary = diff.to_string();
+    assert!(summary.contains("version: 52.0 -> 61.0"));
+    assert!(summary.contains("access_flags: 0x0021"));
+    assert!(summary.contains("fields: 0 added, 1 removed, 1 changed"));
+    assert!(summary.contains("methods: 1 added, 1 removed, 0 changed"));
+  }
+
+  #[test]
+  fn test_diff_identical() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.method(0x0001, "run", "()V", vec![]);
+    let bytes = builder.build();
+    let old = crate::parse(&bytes).unwrap();
+    let new = crate::parse(&bytes).unwrap();
+
+    let diff = ClassDiff::new(&old, &new);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "no differences\n");
+  }
+}
@@ -0,0 +1,189 @@
+use std::{fmt::Display, sync::Arc};
+
+use nom::{error::ParseError, number::complete::be_u16, sequence::tuple, IResult};
+
+use crate::{
+  attribute::{fmt_nested_attributes, parse_attributes, AttributeInfo},
+  constant_pool::ConstantType,
+  context::ResolveContext,
+};
+
+use base::{
+  access_flag::AccessFlags,
+  depth::{Depth, DepthDisplay},
+  descriptor::field_signature,
+  error::Error,
+  literal::LiteralValue,
+};
+
+pub struct FieldInfo {
+  access_flags: AccessFlags,
+  name_index: u16,
+  descriptor_index: u16,
+  attributes: Vec<AttributeInfo>,
+  context: Arc<ResolveContext>,
+}
+
+impl FieldInfo {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, (access_flags, name_index, descriptor_index)) =
+      tuple((be_u16, be_u16, be_u16))(bytes)?;
+    let (bytes, attributes) = parse_attributes(bytes, context)?;
+    Ok((
+      bytes,
+      Self {
+        access_flags: AccessFlags::new_field_flag(access_flags),
+        name_index,
+        descriptor_index,
+        attributes,
+        context: context.clone(),
+      },
+    ))
+  }
+
+  pub fn name(&self) -> &str {
+    self.context.get_str_const(self.name_index)
+  }
+
+  pub fn descriptor(&self) -> &str {
+    self.context.get_str_const(self.descriptor_index)
+  }
+
+  /// Fails if the name or descriptor index doesn't name a `Utf8` constant.
+  pub(crate) fn check_references(&self) -> Result<(), Error> {
+    self.context.require_str_const(self.name_index)?;
+    self.context.require_str_const(self.descriptor_index)?;
+    Ok(())
+  }
+
+  pub fn attributes(&self) -> &[AttributeInfo] {
+    &self.attributes
+  }
+
+  pub fn access_flags(&self) -> &AccessFlags {
+    &self.access_flags
+  }
+
+  /// Java source declaration, `private static final int MAX`.
+  pub fn signature(&self) -> String {
+    field_signature(&self.access_flags, self.name(), self.descriptor())
+  }
+
+  /// `signature` followed by the constant value, if any:
+  /// `public static final int MAX = 10`.
+  pub fn declaration(&self) -> String {
+    match self.constant_value() {
+      Some(value) => format!("{} = {}", self.signature(), value),
+      None => self.signature(),
+    }
+  }
+
+  /// The value of the field's `ConstantValue` attribute, narrowed to its type.
+  pub fn constant_value(&self) -> Option<LiteralValue> {
+    let index = self
+      .attributes
+      .iter()
+      .find_map(AttributeInfo::get_constant_value)?
+      .constantvalue_index();
+    let value = match index.resolve(self.context.constant_pool())?.info() {
+      ConstantType::Integer(value) => LiteralValue::from_int(*value as i32, self.descriptor()),
+      ConstantType::Float(value) => LiteralValue::Float(*value),
+      ConstantType::Long(value) => LiteralValue::Long(*value as i64),
+      ConstantType::Double(value) => LiteralValue::Double(*value),
+      ConstantType::String(value) => {
+        LiteralValue::String(self.context.find_str_const(*value)?.to_string())
+      }
+      _ => return None,
+    };
+    Some(value)
+  }
+}
+
+impl Display for FieldInfo {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for FieldInfo {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
+    write!(
+      f,
+      "access_flags: {}\tname_index: {}\tdescriptor_index: {}",
+      self.access_flags, self.name_index, self.descriptor_index,
+    )?;
+    if self.attributes.len() > 0 {
+      write!(f, "\tattributes({}):", self.attributes.len())?;
+    }
+    fmt_nested_attributes(f, &self.attributes, depth)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::literal::LiteralValue;
+
+  use crate::test_util::ClassBuilder;
+
+  #[test]
+  fn test_constant_value() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let fields: Vec<(&str, &str, u16)> = vec![
+      ("MAX", "I", builder.integer(10)),
+      ("ENABLED", "Z", builder.integer(1)),
+      ("LETTER", "C", builder.integer(0x61)),
+      ("BIG", "J", builder.long(-3)),
+      ("RATIO", "F", builder.float(0.5)),
+      ("NAME", "Ljava/lang/String;", builder.string("hi")),
+    ];
+    for (name, descriptor, index) in fields {
+      let value = builder.attribute("ConstantValue", &index.to_be_bytes());
+      builder.field(0x0019, name, descriptor, vec![value]);
+    }
+    builder.field(0x0002, "count", "I", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let values: Vec<_> = class_file
+      .fields()
+      .iter()
+      .map(|field| field.constant_value())
+      .collect();
+    assert_eq!(
+      values,
+      vec![
+        Some(LiteralValue::Int(10)),
+        Some(LiteralValue::Boolean(true)),
+        Some(LiteralValue::Char(0x61)),
+        Some(LiteralValue::Long(-3)),
+        Some(LiteralValue::Float(0.5)),
+        Some(LiteralValue::String("hi".to_string())),
+        None,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_declaration() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let max = builder.integer(10);
+    let value = builder.attribute("ConstantValue", &max.to_be_bytes());
+    builder.field(0x0019, "MAX", "I", vec![value]);
+    let name = builder.string("a");
+    let value = builder.attribute("ConstantValue", &name.to_be_bytes());
+    builder.field(0x0018, "NAME", "Ljava/lang/String;", vec![value]);
+    builder.field(0x0002, "names", "[Ljava/lang/String;", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(
+      class_file.render_fields_verbose(),
+      vec![
+        "public static final int MAX = 10",
+        "static final java.lang.String NAME = \"a\"",
+        "private java.lang.String[] names",
+      ]
+    );
+  }
+}
@@ -0,0 +1,167 @@
+use std::collections::{HashMap, HashSet};
+
+use base::{
+  descriptor::descriptor_to_source,
+  inherit::{InheritedMembers, Member},
+};
+
+use crate::raw_class::ClassFile;
+
+/// A set of class files, e.g. a directory of them, looked up by internal
+/// name to resolve superclass chains across files.
+#[derive(Default)]
+pub struct ClassPath<'a> {
+  classes: HashMap<&'a str, &'a ClassFile>,
+}
+
+impl<'a> ClassPath<'a> {
+  /// If several files define the same class, the first one wins.
+  pub fn new(classes: &'a [ClassFile]) -> Self {
+    let mut class_path = Self::default();
+    for class in classes {
+      class_path
+        .classes
+        .entry(class.this_class_name())
+        .or_insert(class);
+    }
+    class_path
+  }
+
+  /// The class named `name`, an internal name (`a/b/C`) or a source name
+  /// (`a.b.C`).
+  pub fn get(&self, name: &str) -> Option<&'a ClassFile> {
+    self.classes.get(name.replace('.', "/").as_str()).copied()
+  }
+
+  /// The superclasses of `class` in the set, nearest first, and the name of
+  /// the first one that isn't, unless the chain reaches a root class. A
+  /// cyclic chain stops before it repeats.
+  pub fn superclass_chain(&self, class: &'a ClassFile) -> (Vec<&'a ClassFile>, Option<&'a str>) {
+    let mut chain = vec![];
+    let mut visited = HashSet::from([class.this_class_name()]);
+    let mut current = class;
+    while let Some(name) = current.super_class_name() {
+      if !visited.insert(name) {
+        break;
+      }
+      match self.classes.get(name) {
+        Some(superclass) => {
+          chain.push(*superclass);
+          current = superclass;
+        }
+        None => return (chain, Some(name)),
+      }
+    }
+    (chain, None)
+  }
+
+  /// The members of `class` together with those it inherits from the
+  /// superclasses in the set.
+  pub fn inherited_members(&self, class: &'a ClassFile) -> InheritedMembers {
+    let mut members = InheritedMembers::new(
+      &descriptor_to_source(class.this_class_name()),
+      field_members(class),
+      method_members(class),
+    );
+    let (chain, missing) = self.superclass_chain(class);
+    for superclass in chain {
+      members.add_superclass(
+        &descriptor_to_source(superclass.this_class_name()),
+        field_members(superclass),
+        method_members(superclass),
+      );
+    }
+    if let Some(missing) = missing {
+      members.set_missing_superclass(&descriptor_to_source(missing));
+    }
+    members
+  }
+}
+
+fn field_members(class: &ClassFile) -> impl Iterator<Item = Member<'_>> {
+  class.fields().iter().map(|field| Member {
+    name: field.name(),
+    descriptor: field.descriptor().to_string(),
+    access_flags: field.access_flags(),
+    signature: field.signature(),
+  })
+}
+
+fn method_members(class: &ClassFile) -> impl Iterator<Item = Member<'_>> {
+  class.methods().iter().map(|method| Member {
+    name: method.name(),
+    descriptor: method.descriptor().to_string(),
+    access_flags: method.access_flags(),
+    signature: method.signature(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ClassPath;
+  use crate::test_util::ClassBuilder;
+
+  fn class(name: &str, super_class: &str, build: impl FnOnce(&mut ClassBuilder)) -> Vec<u8> {
+    let mut builder = ClassBuilder::new(name, Some(super_class));
+    build(&mut builder);
+    builder.build()
+  }
+
+  #[test]
+  fn test_inherited_members() {
+    let classes = [
+      class("a/C", "a/B", |builder| {
+        builder.field(0x0002, "count", "I", vec![]);
+        builder.method(0x0401, "run", "()V", vec![]);
+      }),
+      class("a/B", "a/A", |builder| {
+        builder.field(0x0004, "name", "Ljava/lang/String;", vec![]);
+        builder.method(0x0401, "run", "()V", vec![]);
+        builder.method(0x0402, "helper", "()V", vec![]);
+      }),
+      class("a/A", "java/lang/Object", |builder| {
+        builder.method(0x0001, "<init>", "()V", vec![]);
+        builder.method(0x0009, "create", "()La/A;", vec![]);
+      }),
+    ];
+    let classes: Vec<_> = classes
+      .iter()
+      .map(|bytes| crate::parse(bytes).unwrap())
+      .collect();
+    let class_path = ClassPath::new(&classes);
+    let c = class_path.get("a.C").unwrap();
+
+    let (chain, missing) = class_path.superclass_chain(c);
+    let chain: Vec<_> = chain.iter().map(|class| class.this_class_name()).collect();
+    assert_eq!(chain, ["a/B", "a/A"]);
+    assert_eq!(missing, Some("java/lang/Object"));
+
+    assert_eq!(
+      class_path.inherited_members(c).to_string(),
+      "class a.C\n\
+       superclasses: a.B, a.A (java.lang.Object is not among the inputs)\n\
+       fields:\n\
+       \tprivate int count\n\
+       \tprotected java.lang.String name\t(from a.B)\n\
+       methods:\n\
+       \tpublic abstract void run()\n\
+       \tpublic static a.A create()\t(from a.A)\n"
+    );
+
+    let a = class_path.get("a/A").unwrap();
+    assert!(class_path.inherited_members(a).superclasses().is_empty());
+  }
+
+  #[test]
+  fn test_cyclic_superclasses() {
+    let classes = [class("a/A", "a/B", |_| {}), class("a/B", "a/A", |_| {})];
+    let classes: Vec<_> = classes
+      .iter()
+      .map(|bytes| crate::parse(bytes).unwrap())
+      .collect();
+    let class_path = ClassPath::new(&classes);
+    let (chain, missing) = class_path.superclass_chain(class_path.get("a/A").unwrap());
+    assert_eq!(chain.len(), 1);
+    assert_eq!(missing, None);
+  }
+}
@@ -0,0 +1,701 @@
+use crate::{
+  constant_pool::ConstantType,
+  context::ResolveContext,
+  opcodes::{opcode_category, opcodes_implied::*, CodeInfo, OpcodeCategory},
+};
+
+/// A decoded bytecode instruction.
+///
+/// `wide` forms are folded into the instruction they modify: `opcode` and
+/// `mnemonic` name the modified instruction, `pc` points at the `wide` prefix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+  pub pc: u32,
+  pub opcode: u8,
+  pub mnemonic: &'static str,
+  pub wide: bool,
+  pub operands: Operands,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operands {
+  None,
+  /// `bipush` and `sipush`.
+  Immediate(i32),
+  /// Local variable index of loads, stores and `ret`.
+  Local(u16),
+  Increment {
+    index: u16,
+    value: i16,
+  },
+  /// `target` is the absolute pc, `offset` is relative to this instruction.
+  Branch {
+    offset: i32,
+    target: u32,
+  },
+  Constant(Constant),
+  InvokeInterface {
+    method: Constant,
+    count: u8,
+  },
+  /// `atype` of `newarray`, e.g. 10 for `int`; see `array_type_name`.
+  ArrayType(u8),
+  MultiNewArray {
+    class: Constant,
+    dimensions: u8,
+  },
+  /// Absolute jump targets, `targets[i]` for key `low + i`.
+  TableSwitch {
+    default: u32,
+    low: i32,
+    high: i32,
+    targets: Vec<u32>,
+  },
+  /// `(key, absolute target)` pairs.
+  LookupSwitch {
+    default: u32,
+    pairs: Vec<(i32, u32)>,
+  },
+}
+
+/// A constant pool entry referenced by an instruction, with names resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+  Integer(i32),
+  Float(f32),
+  Long(i64),
+  Double(f64),
+  String(String),
+  /// Internal name, e.g. `java/lang/Object` or `[I`.
+  Class(String),
+  Field {
+    class: String,
+    name: String,
+    descriptor: String,
+  },
+  Method {
+    class: String,
+    name: String,
+    descriptor: String,
+  },
+  InterfaceMethod {
+    class: String,
+    name: String,
+    descriptor: String,
+  },
+  MethodType(String),
+  MethodHandle {
+    reference_kind: u8,
+    reference: Box<Constant>,
+  },
+  /// A dynamically-computed constant (`CONSTANT_Dynamic`), loaded by
+  /// `ldc`: the value the bootstrap method returns for `name:descriptor`.
+  Dynamic {
+    bootstrap_method_attr_index: u16,
+    name: String,
+    descriptor: String,
+  },
+  InvokeDynamic {
+    bootstrap_method_attr_index: u16,
+    name: String,
+    descriptor: String,
+  },
+  /// Index of an entry an instruction can't reference.
+  Unknown(u16),
+}
+
+impl Constant {
+  /// Whether `ldc` and its wide forms may load it: members, call sites and
+  /// unresolved entries can't be loaded.
+  pub fn is_loadable(&self) -> bool {
+    !matches!(
+      self,
+      Constant::Field { .. }
+        | Constant::Method { .. }
+        | Constant::InterfaceMethod { .. }
+        | Constant::InvokeDynamic { .. }
+        | Constant::Unknown(_)
+    )
+  }
+
+  pub fn resolve(index: u16, context: &ResolveContext) -> Self {
+    let name_and_type = || {
+      let (name, descriptor) = context.resolve_name_and_type(index)?;
+      Some((name.to_string(), descriptor.to_string()))
+    };
+    let member = || {
+      let class = context.resolve_class_name(index)?.to_string();
+      let (name, descriptor) = name_and_type()?;
+      Some((class, name, descriptor))
+    };
+    let Some(constant) = context.find_constant(index) else {
+      return Constant::Unknown(index);
+    };
+    let resolved = match constant.info() {
+      ConstantType::Integer(value) => Some(Constant::Integer(*value as i32)),
+      ConstantType::Float(value) => Some(Constant::Float(*value)),
+      ConstantType::Long(value) => Some(Constant::Long(*value as i64)),
+      ConstantType::Double(value) => Some(Constant::Double(*value)),
+      ConstantType::String(value) => context
+        .find_str_const(*value)
+        .map(|value| Constant::String(value.to_string())),
+      ConstantType::Class(_) => context
+        .find_class_name(index)
+        .map(|name| Constant::Class(name.to_string())),
+      ConstantType::Fieldref(..) => member().map(|(class, name, descriptor)| Constant::Field {
+        class,
+        name,
+        descriptor,
+      }),
+      ConstantType::Methodref(..) => member().map(|(class, name, descriptor)| Constant::Method {
+        class,
+        name,
+        descriptor,
+      }),
+      ConstantType::InterfaceMethodref(..) => {
+        member().map(|(class, name, descriptor)| Constant::InterfaceMethod {
+          class,
+          name,
+          descriptor,
+        })
+      }
+      ConstantType::MethodType(descriptor) => context
+        .find_str_const(*descriptor)
+        .map(|descriptor| Constant::MethodType(descriptor.to_string())),
+      ConstantType::MethodHandle(reference_kind, reference) => Some(Constant::MethodHandle {
+        reference_kind: *reference_kind,
+        reference: Box::new(Constant::resolve(*reference, context)),
+      }),
+      ConstantType::Dynamic(bootstrap_method_attr_index, _) => {
+        name_and_type().map(|(name, descriptor)| Constant::Dynamic {
+          bootstrap_method_attr_index: *bootstrap_method_attr_index,
+          name,
+          descriptor,
+        })
+      }
+      ConstantType::InvokeDynamic(bootstrap_method_attr_index, _) => {
+        name_and_type().map(|(name, descriptor)| Constant::InvokeDynamic {
+          bootstrap_method_attr_index: *bootstrap_method_attr_index,
+          name,
+          descriptor,
+        })
+      }
+      ConstantType::Utf8(_) | ConstantType::NameAndType(..) | ConstantType::Empty => None,
+    };
+    resolved.unwrap_or(Constant::Unknown(index))
+  }
+}
+
+/// The primitive element type a `newarray` `atype` code stands for, `None`
+/// for codes the spec doesn't assign.
+pub fn array_type_name(atype: u8) -> Option<&'static str> {
+  Some(match atype {
+    4 => "boolean",
+    5 => "char",
+    6 => "float",
+    7 => "double",
+    8 => "byte",
+    9 => "short",
+    10 => "int",
+    11 => "long",
+    _ => return None,
+  })
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+  u16::from_be_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn i32_at(bytes: &[u8], at: usize) -> i32 {
+  i32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn target(pc: u32, offset: i32) -> u32 {
+  (pc as i64 + offset as i64) as u32
+}
+
+impl Instruction {
+  /// Absolute pc a conditional branch, `goto`, `jsr` or their wide forms
+  /// jumps to; `None` for other instructions, switches included.
+  pub fn branch_target(&self) -> Option<u32> {
+    match self.operands {
+      Operands::Branch { target, .. } => Some(target),
+      _ => None,
+    }
+  }
+
+  /// The local variables a load, store, `iinc` or `ret` uses: its index, and
+  /// 2 slots for a long or double, 1 otherwise. `None` for other
+  /// instructions.
+  pub fn local_slots(&self) -> Option<(u16, u16)> {
+    // i, l, f, d, a: longs and doubles take two slots.
+    let slots = |kind: u8| if kind == 1 || kind == 3 { 2 } else { 1 };
+    match (self.opcode, &self.operands) {
+      (ILOAD..=ALOAD, Operands::Local(index)) => Some((*index, slots(self.opcode - ILOAD))),
+      (ISTORE..=ASTORE, Operands::Local(index)) => Some((*index, slots(self.opcode - ISTORE))),
+      (RET, Operands::Local(index)) | (IINC, Operands::Increment { index, .. }) => {
+        Some((*index, 1))
+      }
+      (ILOAD_0..=ALOAD_3, _) => {
+        let implied = self.opcode - ILOAD_0;
+        Some(((implied % 4) as u16, slots(implied / 4)))
+      }
+      (ISTORE_0..=ASTORE_3, _) => {
+        let implied = self.opcode - ISTORE_0;
+        Some(((implied % 4) as u16, slots(implied / 4)))
+      }
+      _ => None,
+    }
+  }
+
+  /// The category of `opcode`, so of the modified instruction for `wide`.
+  pub fn category(&self) -> OpcodeCategory {
+    opcode_category(self.opcode)
+  }
+
+  pub(crate) fn decode(code_info: &CodeInfo, pc: u32, context: &ResolveContext) -> Self {
+    let (wide, opcode, bytes) = match code_info.code() {
+      WIDE => (true, code_info.operands()[0], &code_info.operands()[1..]),
+      code => (false, code, code_info.operands()),
+    };
+    let constant = |index: u16| Constant::resolve(index, context);
+    let operands = match opcode {
+      BIPUSH => Operands::Immediate(bytes[0] as i8 as i32),
+      SIPUSH => Operands::Immediate(u16_at(bytes, 0) as i16 as i32),
+      LDC => Operands::Constant(constant(bytes[0] as u16)),
+      ILOAD..=ALOAD | ISTORE..=ASTORE | RET if wide => Operands::Local(u16_at(bytes, 0)),
+      ILOAD..=ALOAD | ISTORE..=ASTORE | RET => Operands::Local(bytes[0] as u16),
+      IINC if wide => Operands::Increment {
+        index: u16_at(bytes, 0),
+        value: u16_at(bytes, 2) as i16,
+      },
+      IINC => Operands::Increment {
+        index: bytes[0] as u16,
+        value: bytes[1] as i8 as i16,
+      },
+      IFEQ..=JSR | IFNULL | IFNONNULL => {
+        let offset = u16_at(bytes, 0) as i16 as i32;
+        Operands::Branch {
+          offset,
+          target: target(pc, offset),
+        }
+      }
+      GOTO_W | JSR_W => {
+        let offset = i32_at(bytes, 0);
+        Operands::Branch {
+          offset,
+          target: target(pc, offset),
+        }
+      }
+      LDC_W
+      | LDC2_W
+      | GETSTATIC..=INVOKESTATIC
+      | INVOKEDYNAMIC
+      | NEW
+      | ANEWARRAY
+      | CHECKCAST
+      | INSTANCEOF => Operands::Constant(constant(u16_at(bytes, 0))),
+      INVOKEINTERFACE => Operands::InvokeInterface {
+        method: constant(u16_at(bytes, 0)),
+        count: bytes[2],
+      },
+      NEWARRAY => Operands::ArrayType(bytes[0]),
+      MULTIANEWARRAY => Operands::MultiNewArray {
+        class: constant(u16_at(bytes, 0)),
+        dimensions: bytes[2],
+      },
+      TABLESWITCH => {
+        let padding = ((4 - (pc + 1) % 4) % 4) as usize;
+        let low = i32_at(bytes, padding + 4);
+        let high = i32_at(bytes, padding + 8);
+        let targets = (padding + 12..bytes.len())
+          .step_by(4)
+          .map(|at| target(pc, i32_at(bytes, at)))
+          .collect();
+        Operands::TableSwitch {
+          default: target(pc, i32_at(bytes, padding)),
+          low,
+          high,
+          targets,
+        }
+      }
+      LOOKUPSWITCH => {
+        let padding = ((4 - (pc + 1) % 4) % 4) as usize;
+        let pairs = (padding + 8..bytes.len())
+          .step_by(8)
+          .map(|at| (i32_at(bytes, at), target(pc, i32_at(bytes, at + 4))))
+          .collect();
+        Operands::LookupSwitch {
+          default: target(pc, i32_at(bytes, padding)),
+          pairs,
+        }
+      }
+      _ => Operands::None,
+    };
+    Self {
+      pc,
+      opcode,
+      mnemonic: CODE_NAME_MAP.get(&opcode).copied().unwrap_or("unknown"),
+      wide,
+      operands,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{array_type_name, Constant, Instruction, Operands};
+  use crate::{opcodes::OpcodeCategory, test_util::ClassBuilder};
+
+  fn instruction(
+    pc: u32,
+    mnemonic: &'static str,
+    operands: Operands,
+  ) -> (u32, &'static str, Operands) {
+    (pc, mnemonic, operands)
+  }
+
+  #[test]
+  fn test_disassemble() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let field = builder.field_ref("a/A", "count", "I");
+    let greeting = builder.string("hi");
+    let method = builder.method_ref("java/io/PrintStream", "println", "()V");
+    #[rustfmt::skip]
+    let mut code = vec![
+      0x2a,                                      // 0: aload_0
+      0xb4, (field >> 8) as u8, field as u8,     // 1: getfield
+      0x1b,                                      // 4: iload_1
+      0xaa, 0, 0,                                // 5: tableswitch, 2 bytes padding
+      0, 0, 0, 35,                               //    default -> 40
+      0, 0, 0, 0,                                //    low
+      0, 0, 0, 1,                                //    high
+      0, 0, 0, 23,                               //    0 -> 28
+      0, 0, 0, 26,                               //    1 -> 31
+      0x12, greeting as u8,                      // 28: ldc
+      0x57,                                      // 30: pop
+      0xc4, 0x84, 0x01, 0x2c, 0xff, 0xff,        // 31: wide iinc 300, -1
+      0xb6, (method >> 8) as u8, method as u8,   // 37: invokevirtual
+    ];
+    code.push(0xb1); // 40: return
+    let code = builder.code_attribute(2, 301, &code, &[], vec![]);
+    builder.method(0x0001, "run", "(I)V", vec![code]);
+    builder.method(0x0401, "stop", "()V", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let run = &class_file.methods()[0];
+    let instructions: Vec<_> = run
+      .disassemble()
+      .into_iter()
+      .map(
+        |Instruction {
+           pc,
+           mnemonic,
+           operands,
+           ..
+         }| (pc, mnemonic, operands),
+      )
+      .collect();
+    assert_eq!(
+      instructions,
+      vec![
+        instruction(0, "aload_0", Operands::None),
+        instruction(
+          1,
+          "getfield",
+          Operands::Constant(Constant::Field {
+            class: "a/A".to_string(),
+            name: "count".to_string(),
+            descriptor: "I".to_string(),
+          })
+        ),
+        instruction(4, "iload_1", Operands::None),
+        instruction(
+          5,
+          "tableswitch",
+          Operands::TableSwitch {
+            default: 40,
+            low: 0,
+            high: 1,
+            targets: vec![28, 31],
+          }
+        ),
+        instruction(
+          28,
+          "ldc",
+          Operands::Constant(Constant::String("hi".to_string()))
+        ),
+        instruction(30, "pop", Operands::None),
+        instruction(
+          31,
+          "iinc",
+          Operands::Increment {
+            index: 300,
+            value: -1
+          }
+        ),
+        instruction(
+          37,
+          "invokevirtual",
+          Operands::Constant(Constant::Method {
+            class: "java/io/PrintStream".to_string(),
+            name: "println".to_string(),
+            descriptor: "()V".to_string(),
+          })
+        ),
+        instruction(40, "return", Operands::None),
+      ]
+    );
+    let wide_iinc = &run.disassemble()[6];
+    assert!(wide_iinc.wide);
+    assert_eq!(wide_iinc.category(), OpcodeCategory::Arithmetic);
+    assert!(class_file.methods()[1].disassemble().is_empty());
+  }
+
+  #[test]
+  fn test_wide_branches_and_subroutines() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    #[rustfmt::skip]
+    let code = [
+      0xc8, 0x00, 0x00, 0x00, 0x08,      // 0: goto_w -> 8
+      0x4c,                              // 5: astore_1, the subroutine
+      0xa9, 0x01,                        // 6: ret 1
+      0xc9, 0xff, 0xff, 0xff, 0xfd,      // 8: jsr_w -> 5
+      0xa8, 0xff, 0xf8,                  // 13: jsr -> 5
+      0xc4, 0xa9, 0x01, 0x00,            // 16: wide ret 256
+      0xb1,                              // 20: return
+    ];
+    let code = builder.code_attribute(1, 257, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let run = &class_file.methods()[0];
+
+    let instructions: Vec<_> = run
+      .disassemble()
+      .into_iter()
+      .map(|instruction| (instruction.pc, instruction.mnemonic, instruction.operands))
+      .collect();
+    assert_eq!(
+      instructions,
+      vec![
+        instruction(
+          0,
+          "goto_w",
+          Operands::Branch {
+            offset: 8,
+            target: 8
+          }
+        ),
+        instruction(5, "astore_1", Operands::None),
+        instruction(6, "ret", Operands::Local(1)),
+        instruction(
+          8,
+          "jsr_w",
+          Operands::Branch {
+            offset: -3,
+            target: 5
+          }
+        ),
+        instruction(
+          13,
+          "jsr",
+          Operands::Branch {
+            offset: -8,
+            target: 5
+          }
+        ),
+        instruction(16, "ret", Operands::Local(256)),
+        instruction(20, "return", Operands::None),
+      ]
+    );
+
+    let code = run.code().unwrap().to_string();
+    assert!(
+      code.contains("code: goto_w -> 8 astore_1 ret<1> jsr_w -> 5 jsr -> 5 wide<169 1 0> return ")
+    );
+  }
+
+  #[test]
+  fn test_branch_targets() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // for (int i = 0; i < 10; i++) {}
+    #[rustfmt::skip]
+    let code = [
+      0x03,                // 0: iconst_0
+      0x3b,                // 1: istore_0
+      0x1a,                // 2: iload_0
+      0x10, 10,            // 3: bipush 10
+      0xa2, 0x00, 0x09,    // 5: if_icmpge -> 14, forward
+      0x84, 0x00, 0x01,    // 8: iinc 0 1
+      0xa7, 0xff, 0xf7,    // 11: goto -> 2, backward
+      0xb1,                // 14: return
+    ];
+    let code = builder.code_attribute(2, 1, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let run = &class_file.methods()[0];
+
+    let targets: Vec<_> = run
+      .disassemble()
+      .iter()
+      .filter_map(|instruction| Some((instruction.pc, instruction.branch_target()?)))
+      .collect();
+    assert_eq!(targets, vec![(5, 14), (11, 2)]);
+    assert!(run
+      .code()
+      .unwrap()
+      .to_string()
+      .contains("bipush<10> if_icmpge -> 14 iinc<0 1> goto -> 2 return "));
+  }
+
+  #[test]
+  fn test_array_creation() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let string = builder.class("java/lang/String");
+    let matrix = builder.class("[[I");
+    #[rustfmt::skip]
+    let code = [
+      0x04,                                          // 0: iconst_1
+      0xbc, 10,                                      // 1: newarray int
+      0x57,                                          // 3: pop
+      0x04,                                          // 4: iconst_1
+      0xbd, (string >> 8) as u8, string as u8,       // 5: anewarray
+      0x57,                                          // 8: pop
+      0x04,                                          // 9: iconst_1
+      0x04,                                          // 10: iconst_1
+      0xc5, (matrix >> 8) as u8, matrix as u8, 2,    // 11: multianewarray
+      0x57,                                          // 15: pop
+      0x04,                                          // 16: iconst_1
+      0xbc, 3,                                       // 17: newarray, unassigned atype
+      0xb1,                                          // 19: return
+    ];
+    let code = builder.code_attribute(2, 0, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let run = &class_file.methods()[0];
+    let instructions = run.disassemble();
+    assert_eq!(instructions[1].operands, Operands::ArrayType(10));
+    assert_eq!(array_type_name(10), Some("int"));
+    assert_eq!(array_type_name(4), Some("boolean"));
+    assert_eq!(array_type_name(3), None);
+    assert_eq!(
+      instructions[4].operands,
+      Operands::Constant(Constant::Class("java/lang/String".to_string()))
+    );
+    assert_eq!(
+      instructions[8].operands,
+      Operands::MultiNewArray {
+        class: Constant::Class("[[I".to_string()),
+        dimensions: 2,
+      }
+    );
+
+    let code = run.attributes[0].get_code().unwrap().to_string();
+    assert!(code.contains("iconst_1 newarray int pop "));
+    assert!(code.contains("anewarray java/lang/String pop "));
+    assert!(code.contains("multianewarray [[I 2 pop "));
+    assert!(code.contains("newarray<3> return "));
+  }
+
+  #[test]
+  fn test_ldc_dynamic() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let dynamic = builder.dynamic(0, "_", "Ljava/lang/Class;");
+    let method_type = builder.method_type("(I)V");
+    let wide_dynamic = builder.dynamic(1, "_", "J");
+    #[rustfmt::skip]
+    let code = [
+      0x12, dynamic as u8,                                 // 0: ldc
+      0x13, (method_type >> 8) as u8, method_type as u8,   // 2: ldc_w
+      0x14, (wide_dynamic >> 8) as u8, wide_dynamic as u8, // 5: ldc2_w
+      0xb1,                                                // 8: return
+    ];
+    let code = builder.code_attribute(4, 0, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let operands: Vec<_> = class_file.methods()[0]
+      .disassemble()
+      .into_iter()
+      .map(|instruction| instruction.operands)
+      .collect();
+    assert_eq!(
+      operands,
+      vec![
+        Operands::Constant(Constant::Dynamic {
+          bootstrap_method_attr_index: 0,
+          name: "_".to_string(),
+          descriptor: "Ljava/lang/Class;".to_string(),
+        }),
+        Operands::Constant(Constant::MethodType("(I)V".to_string())),
+        Operands::Constant(Constant::Dynamic {
+          bootstrap_method_attr_index: 1,
+          name: "_".to_string(),
+          descriptor: "J".to_string(),
+        }),
+        Operands::None,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_zero_constant_index() {
+    let mut builder = ClassBuilder::new("a/A", None);
+    // ldc #0, getstatic #0, return
+    let code = builder.code_attribute(1, 0, &[0x12, 0x00, 0xb2, 0x00, 0x00, 0xb1], &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let instructions = class_file.methods()[0].disassemble();
+    assert_eq!(
+      instructions[0].operands,
+      Operands::Constant(Constant::Unknown(0))
+    );
+    assert_eq!(
+      instructions[1].operands,
+      Operands::Constant(Constant::Unknown(0))
+    );
+  }
+
+  #[test]
+  fn test_branches_and_immediates() {
+    let mut builder = ClassBuilder::new("a/A", None);
+    #[rustfmt::skip]
+    let code = [
+      0x10, 0xfe,             // 0: bipush -2
+      0x11, 0x01, 0x00,       // 2: sipush 256
+      0x99, 0xff, 0xfb,       // 5: ifeq -5 -> 0
+      0xab, 0, 0, 0,          // 8: lookupswitch, 3 bytes padding
+      0, 0, 0, 20,            //    default -> 28
+      0, 0, 0, 1,             //    npairs
+      0, 0, 0, 7, 0, 0, 0, 20,//    7 -> 28
+      0xb1,                   // 28: return
+    ];
+    let code = builder.code_attribute(2, 1, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let operands: Vec<_> = class_file.methods()[0]
+      .disassemble()
+      .into_iter()
+      .map(|instruction| instruction.operands)
+      .collect();
+    assert_eq!(
+      operands,
+      vec![
+        Operands::Immediate(-2),
+        Operands::Immediate(256),
+        Operands::Branch {
+          offset: -5,
+          target: 0
+        },
+        Operands::LookupSwitch {
+          default: 28,
+          pairs: vec![(7, 28)]
+        },
+        Operands::None,
+      ]
+    );
+  }
+}
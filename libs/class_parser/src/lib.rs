@@ -1,35 +1,743 @@
-use base::error::Error;
+use base::{
+  error::{Error, ErrorKind},
+  parse_limits::ParseLimits,
+  Parsable,
+};
 use raw_class::ClassFile;
 
 pub mod attribute;
-mod constant_pool;
-mod filed;
+pub mod constant_pool;
+pub mod context;
+pub mod diff;
+mod field;
+pub mod hierarchy;
+pub mod instruction;
 mod method;
-mod opcodes;
+pub mod opcodes;
 pub mod raw_class;
+#[cfg(test)]
+mod test_util;
+pub mod verify;
+
+const CLASS_MAGIC: [u8; 4] = [0xca, 0xfe, 0xba, 0xbe];
+
+/// magic, versions and constant_pool_count, then access_flags, this_class,
+/// super_class and the interfaces, fields, methods and attributes counts.
+const MIN_CLASS_FILE_SIZE: usize = 24;
+
+/// How far into the input a misplaced magic is looked for.
+const SIGNATURE_SCAN_LIMIT: usize = 4096;
+
+/// What `parse_with_options` decodes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+  /// Skip over the instructions of `Code` attributes instead of decoding
+  /// them, for when only the API surface (members, flags, attributes) is
+  /// needed.
+  pub skip_code: bool,
+  /// Keep an attribute whose contents fail to parse as
+  /// `Attribute::Unknown`, bytes and all, instead of failing the whole
+  /// class, so the rest of a damaged or obfuscated class can be inspected.
+  pub relaxed: bool,
+  /// Bounds on declared counts, annotation nesting and code length for
+  /// untrusted input; going over them fails with
+  /// `ErrorKind::LimitExceeded`.
+  pub limits: ParseLimits,
+}
 
 pub fn parse<'a>(bytes: &'a [u8]) -> Result<ClassFile, Error> {
   ClassFile::parse_from_u8(bytes)
 }
 
-static mut CONSTANT_POOL_REF: Vec<constant_pool::ConstantPoolInfo> = vec![];
+/// Like `parse`, decoding only what `options` asks for.
+pub fn parse_with_options(bytes: &[u8], options: ParseOptions) -> Result<ClassFile, Error> {
+  check_header(bytes)?;
+  let (_, class_file) = ClassFile::parse_with_options::<nom::error::Error<_>>(bytes, options)?;
+  class_file.check_references()?;
+  Ok(class_file)
+}
 
-pub fn get_constant_pool_ref() -> &'static Vec<constant_pool::ConstantPoolInfo> {
-  unsafe { &CONSTANT_POOL_REF }
+/// Like `parse`, also returning the bytes after the end of the class file.
+pub fn parse_with_remainder(bytes: &[u8]) -> Result<(ClassFile, &[u8]), Error> {
+  ClassFile::parse_with_remainder(bytes)
 }
 
-pub fn get_str_const<'a>(index: usize) -> &'a str {
-  get_constant_pool_ref()[index].as_utf8().unwrap()
+/// Like `parse_with_remainder`, for a class file starting `offset` bytes into
+/// `bytes`, e.g. after a length prefix or a byte order mark.
+pub fn parse_at_offset(bytes: &[u8], offset: usize) -> Result<(ClassFile, &[u8]), Error> {
+  let len = bytes.len();
+  let bytes = bytes
+    .get(offset..)
+    .ok_or(ErrorKind::OffsetOutOfRange { offset, len })?;
+  parse_with_remainder(bytes)
+}
+
+/// Checks the magic, then that `bytes` can hold the smallest class file.
+fn check_header(bytes: &[u8]) -> Result<(), Error> {
+  check_magic(bytes)?;
+  if bytes.len() < MIN_CLASS_FILE_SIZE {
+    return Err(
+      ErrorKind::TruncatedHeader {
+        expected: MIN_CLASS_FILE_SIZE,
+        actual: bytes.len(),
+      }
+      .into(),
+    );
+  }
+  Ok(())
+}
+
+/// Fails with `ErrorKind::BadMagic` when `bytes` doesn't start with
+/// `0xCAFEBABE`, pointing at the magic if it occurs in the first
+/// `SIGNATURE_SCAN_LIMIT` bytes. Input too short to hold a magic is left to
+/// the size check.
+fn check_magic(bytes: &[u8]) -> Result<(), Error> {
+  let Some(magic) = bytes.get(..4) else {
+    return Ok(());
+  };
+  if magic == CLASS_MAGIC {
+    return Ok(());
+  }
+  let scanned = &bytes[..bytes.len().min(SIGNATURE_SCAN_LIMIT)];
+  Err(
+    ErrorKind::BadMagic {
+      found: u32::from_be_bytes(magic.try_into().unwrap()),
+      expected: u32::from_be_bytes(CLASS_MAGIC),
+      signature_offset: scanned
+        .windows(CLASS_MAGIC.len())
+        .position(|window| window == CLASS_MAGIC),
+    }
+    .into(),
+  )
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::raw_class::ClassFile;
+  use base::{
+    error::{Error, ErrorKind},
+    parse_limits::ParseLimits,
+    Parsable, RenderSource,
+  };
+
+  use crate::{
+    constant_pool::ConstantType, raw_class::ClassFile, test_util::ClassBuilder,
+    verify::Verification, ParseOptions,
+  };
+
+  #[test]
+  fn test_parse_with_remainder() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.method(0x0401, "run", "()V", vec![]);
+    let mut bytes = builder.build();
+    let (class_file, remainder) = crate::parse_with_remainder(&bytes).unwrap();
+    assert!(remainder.is_empty());
+    assert_eq!(class_file.methods().len(), 1);
+
+    bytes.extend_from_slice(b"PK\x03\x04payload");
+    let (class_file, remainder) = crate::parse_with_remainder(&bytes).unwrap();
+    assert_eq!(remainder, b"PK\x03\x04payload");
+    assert_eq!(class_file.methods()[0].name(), "run");
+  }
+
+  #[test]
+  fn test_parse_at_offset() {
+    let builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let mut bytes = vec![0xef, 0xbb, 0xbf];
+    bytes.extend_from_slice(&builder.build());
+
+    let error = crate::parse(&bytes).err().unwrap();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::BadMagic {
+        found: 0xefbbbfca,
+        signature_offset: Some(3),
+        ..
+      }
+    ));
+    assert_eq!(
+      error.to_string(),
+      "bad magic 0xefbbbfca, expected 0xcafebabe (the magic appears at offset 3, try skipping 3 bytes)"
+    );
+
+    let (class_file, remainder) = crate::parse_at_offset(&bytes, 3).unwrap();
+    assert_eq!(class_file.this_class_name(), "a/A");
+    assert!(remainder.is_empty());
+
+    let error = crate::parse_at_offset(&bytes, bytes.len() + 1)
+      .err()
+      .unwrap();
+    assert!(matches!(error.kind(), ErrorKind::OffsetOutOfRange { .. }));
+
+    // No magic anywhere: no hint.
+    let error = crate::parse(b"not a class file").err().unwrap();
+    assert_eq!(
+      error.to_string(),
+      "bad magic 0x6e6f7420, expected 0xcafebabe"
+    );
+  }
+
+  #[test]
+  fn test_class_names() {
+    // resource/Rectangle.java, compiled class files aren't checked in.
+    let mut builder = ClassBuilder::new("Rectangle", Some("java/lang/Object"));
+    builder.field(0x0002, "width", "D", vec![]);
+    builder.field(0x0012, "length", "D", vec![]);
+    builder.method(0x0001, "<init>", "(DD)V", vec![]);
+    builder.method(0x0001, "get_width", "()D", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(class_file.this_class_name(), "Rectangle");
+    assert_eq!(class_file.super_class_name(), Some("java/lang/Object"));
+
+    let builder = ClassBuilder::new("a/b/Square", Some("a/b/Rectangle"));
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(class_file.this_class_name(), "a/b/Square");
+    assert_eq!(class_file.super_class_name(), Some("a/b/Rectangle"));
+  }
+
+  #[test]
+  fn test_versions() {
+    // resource/Rectangle.java compiled by javac 17.
+    let builder = ClassBuilder::new("Rectangle", Some("java/lang/Object")).version(61, 0);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(
+      (class_file.major_version(), class_file.minor_version()),
+      (61, 0)
+    );
+    assert!(class_file
+      .render_file_info()
+      .contains(&"version: 61.0".to_string()));
+
+    let builder = ClassBuilder::new("a/A", Some("java/lang/Object")).version(65, 0xffff);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(class_file.minor_version(), 0xffff);
+  }
+
+  #[test]
+  fn test_content_hash() {
+    let class = |name: &str| {
+      let mut builder = ClassBuilder::new(name, Some("java/lang/Object"));
+      builder.method(0x0001, "run", "()V", vec![]);
+      builder.build()
+    };
+    let hash = ClassFile::content_hash(&class("a/A"));
+    assert_eq!(hash, ClassFile::content_hash(&class("a/A")));
+    assert_ne!(hash, ClassFile::content_hash(&class("a/B")));
+    // The whole file counts, bytes past the class included.
+    let mut appended = class("a/A");
+    appended.push(0);
+    assert_ne!(hash, ClassFile::content_hash(&appended));
+  }
+
+  #[test]
+  fn test_counts() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.string("hi");
+    let value = builder.integer(3);
+    let constant_value = builder.attribute("ConstantValue", &value.to_be_bytes());
+    builder.field(0x0018, "count", "I", vec![constant_value]);
+    for name in ["run", "stop"] {
+      let code = builder.code_attribute(0, 1, &[0xb1], &[], vec![]);
+      builder.method(0x0001, name, "()V", vec![code]);
+    }
+    let source_file = builder.utf8("A.java");
+    let source_file = builder.attribute("SourceFile", &source_file.to_be_bytes());
+    builder.class_attribute(source_file);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let counts: Vec<String> = class_file
+      .counts()
+      .into_iter()
+      .map(|(key, value)| format!("{}: {}", key, value))
+      .collect();
+    assert_eq!(
+      counts,
+      [
+        "classes: 1",
+        "methods: 2",
+        "fields: 1",
+        "strings: 1",
+        "types: 2",
+        "constant_pool: 16",
+        "attributes: 4",
+        "attributes.Code: 2",
+        "attributes.ConstantValue: 1",
+        "attributes.SourceFile: 1",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_constant_pool_sorted() {
+    let mut first = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    first.string("hello");
+    first.integer(7);
+    first.long(-1);
+    first.method_ref("a/B", "run", "()V");
+    let mut second = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    second.method_ref("a/B", "run", "()V");
+    second.long(-1);
+    second.integer(7);
+    second.string("hello");
+    let first = crate::parse(&first.build()).unwrap();
+    let second = crate::parse(&second.build()).unwrap();
+
+    assert_ne!(
+      first.render_constant_pool_resolved(),
+      second.render_constant_pool_resolved()
+    );
+    let sorted = first.constant_pool_sorted();
+    assert_eq!(sorted, second.constant_pool_sorted());
+    assert_eq!(sorted, first.constant_pool_sorted());
+    // The long's placeholder slot is left out.
+    assert!(sorted.len() < first.render_constant_pool_resolved().len());
+    let kinds: Vec<&str> = sorted
+      .iter()
+      .map(|constant| constant.split([':', ' ']).next().unwrap())
+      .collect();
+    let mut grouped = kinds.clone();
+    grouped.dedup();
+    assert_eq!(
+      grouped,
+      [
+        "Utf8",
+        "Integer",
+        "Long",
+        "Class",
+        "String",
+        "Methodref",
+        "NameAndType"
+      ]
+    );
+    assert!(sorted.contains(&"String -> \"hello\"".to_string()));
+  }
+
+  #[test]
+  fn test_invalid_constant_index() {
+    let builder = ClassBuilder::new("java/lang/Object", None);
+    let bytes = builder.build();
+    // super_class 0 is how java/lang/Object says it has no superclass.
+    let class_file = crate::parse(&bytes).unwrap();
+    assert_eq!(class_file.super_class_name(), None);
+
+    // access_flags, this_class, super_class and four empty counts end the file.
+    let mut bytes = bytes;
+    let this_class = bytes.len() - 12;
+    bytes[this_class..this_class + 2].copy_from_slice(&[0, 0]);
+    let error = crate::parse(&bytes).err().unwrap();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::InvalidConstantIndex {
+        index: 0,
+        expected: "Class"
+      }
+    ));
+    assert_eq!(
+      error.to_string(),
+      "invalid constant pool index #0, expected a Class constant"
+    );
+
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.method(0x0401, "run", "()V", vec![]);
+    let mut bytes = builder.build();
+    // The 8 byte method_info comes before the class attributes count.
+    let name_index = bytes.len() - 2 - 8 + 2;
+    bytes[name_index..name_index + 2].copy_from_slice(&[0, 0]);
+    // The `ClassFile` constructors check like the free functions.
+    for error in [
+      crate::parse(&bytes).err().unwrap(),
+      ClassFile::parse_from_u8(&bytes).err().unwrap(),
+      <ClassFile as Parsable>::parse_with_remainder(&bytes)
+        .err()
+        .unwrap(),
+    ] {
+      assert!(matches!(
+        error.kind(),
+        ErrorKind::InvalidConstantIndex {
+          index: 0,
+          expected: "Utf8"
+        }
+      ));
+    }
+
+    // A SourceFile attribute with sourcefile_index 0.
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let source_file = builder.attribute("SourceFile", &[0, 0]);
+    builder.class_attribute(source_file);
+    let error = crate::parse(&builder.build()).err().unwrap();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::InvalidConstantIndex {
+        index: 0,
+        expected: "Utf8"
+      }
+    ));
+  }
+
+  #[test]
+  fn test_skip_code() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let method = builder.method_ref("java/io/PrintStream", "println", "()V");
+    let line_numbers = builder.attribute("LineNumberTable", &[0, 1, 0, 0, 0, 7]);
+    #[rustfmt::skip]
+    let code = [
+      0x2a,                                    // aload_0
+      0xb6, (method >> 8) as u8, method as u8, // invokevirtual
+      0xb1,                                    // return
+    ];
+    let code = builder.code_attribute(1, 1, &code, &[(0, 4, 4, 0)], vec![line_numbers]);
+    builder.method(0x0001, "run", "()V", vec![code]);
+    builder.field(0x0002, "count", "I", vec![]);
+    let bytes = builder.build();
+
+    let options = ParseOptions {
+      skip_code: true,
+      ..Default::default()
+    };
+    let skipped = crate::parse_with_options(&bytes, options).unwrap();
+    let decoded = crate::parse(&bytes).unwrap();
+    assert_eq!(skipped.context().options(), options);
+    assert_eq!(skipped.fields().len(), 1);
+
+    let run = &skipped.methods()[0];
+    assert_eq!(run.name(), "run");
+    assert!(run.validate().is_empty());
+    assert!(run.disassemble().is_empty());
+    assert_eq!(run.opcode_coverage().known(), vec![]);
+    let code = run.attributes[0].get_code().unwrap();
+    assert_eq!(code.code_length(), 5);
+    assert_eq!(code.exception_table().len(), 1);
+    assert_eq!(
+      code.raw_code(),
+      decoded.methods()[0].code().unwrap().raw_code()
+    );
+    assert_eq!(code.raw_code()[0], 0x2a);
+
+    assert_eq!(decoded.methods()[0].disassemble().len(), 3);
+  }
+
+  #[test]
+  fn test_parse_limits() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    for name in ["a", "b", "c"] {
+      let code = builder.code_attribute(1, 1, &[0x03, 0x57, 0xb1], &[], vec![]);
+      builder.method(0x0001, name, "()V", vec![code]);
+    }
+    let bytes = builder.build();
+    // The constant pool is the longest list.
+    let pool_len = crate::parse(&bytes)
+      .unwrap()
+      .context()
+      .constant_pool()
+      .len();
+
+    let parse = |limits: ParseLimits| {
+      let options = ParseOptions {
+        limits,
+        ..Default::default()
+      };
+      crate::parse_with_options(&bytes, options)
+    };
+    let limited = |error: Error| matches!(error.kind(), ErrorKind::LimitExceeded);
+    // Three bytes of code each.
+    let few_items = ParseLimits {
+      max_items: pool_len - 1,
+      ..ParseLimits::DEFAULT
+    };
+    assert!(limited(parse(few_items).err().unwrap()));
+    let short_code = ParseLimits {
+      max_code_len: 2,
+      ..ParseLimits::DEFAULT
+    };
+    assert!(limited(parse(short_code).err().unwrap()));
+    assert!(parse(ParseLimits {
+      max_items: pool_len,
+      max_code_len: 3,
+      ..ParseLimits::DEFAULT
+    })
+    .is_ok());
+
+    // Claims 0xffff annotations but holds none: rejected before looking for
+    // them, even when relaxed.
+    let mut builder = ClassBuilder::new("a/B", Some("java/lang/Object"));
+    let absurd = builder.attribute("RuntimeVisibleAnnotations", &[0xff, 0xff]);
+    builder.class_attribute(absurd);
+    let bytes = builder.build();
+    let options = ParseOptions {
+      relaxed: true,
+      limits: ParseLimits {
+        max_items: 1000,
+        ..ParseLimits::DEFAULT
+      },
+      ..Default::default()
+    };
+    let error = crate::parse_with_options(&bytes, options).err().unwrap();
+    assert_eq!(error.to_string(), "the input exceeds the parse limits");
+    assert!(limited(error));
+  }
+
+  #[test]
+  fn test_relaxed_attributes() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let source_file = builder.utf8("A.java").to_be_bytes();
+    let source_file = builder.attribute("SourceFile", &source_file);
+    builder.class_attribute(source_file);
+    // Claims one annotation but holds none.
+    let corrupt = builder.attribute("RuntimeVisibleAnnotations", &[0, 1]);
+    builder.class_attribute(corrupt);
+    let deprecated = builder.attribute("Deprecated", &[]);
+    builder.class_attribute(deprecated);
+    let bytes = builder.build();
+
+    let error = crate::parse_with_options(&bytes, ParseOptions::default())
+      .err()
+      .unwrap();
+    assert!(matches!(error.kind(), ErrorKind::NomError { .. }));
+
+    let options = ParseOptions {
+      relaxed: true,
+      ..Default::default()
+    };
+    let class_file = crate::parse_with_options(&bytes, options).unwrap();
+    let attributes = class_file.attributes();
+    assert_eq!(attributes.len(), 3);
+    assert_eq!(class_file.source_file(), Some("A.java"));
+    assert_eq!(attributes[0].malformed_bytes(), None);
+    assert_eq!(attributes[1].name(), "RuntimeVisibleAnnotations");
+    assert_eq!(attributes[1].malformed_bytes(), Some(&[0, 1][..]));
+    assert_eq!(attributes[1].to_string(), "Unknown: malformed (2 bytes)");
+    assert_eq!(attributes[2].to_string(), "Deprecated");
+    assert!(class_file.annotations().is_empty());
+
+    let verification = Verification::new(&class_file);
+    assert_eq!(verification.issues().len(), 1);
+    assert_eq!(
+      verification.issues()[0].to_string(),
+      "class attribute RuntimeVisibleAnnotations: contents could not be parsed"
+    );
+  }
+
+  #[test]
+  fn test_parse_constant_pool_only() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.long(1);
+    let greeting = builder.string("hi");
+    builder.method(0x0401, "run", "()V", vec![]);
+    let bytes = builder.build();
+    let class_file = crate::parse(&bytes).unwrap();
+
+    let constant_pool = ClassFile::parse_constant_pool_only(&bytes).unwrap();
+    assert_eq!(
+      constant_pool.len(),
+      class_file.context().constant_pool().len()
+    );
+    let greeting = &constant_pool[greeting as usize - 1];
+    assert!(matches!(greeting.info(), ConstantType::String(_)));
+    assert!(constant_pool
+      .iter()
+      .any(|constant| constant.as_utf8() == Some("hi")));
+
+    // Nothing past the pool is read: access_flags, this_class, super_class,
+    // and the interface, field, method and attribute tables are cut off.
+    let truncated = &bytes[..bytes.len() - 2 - 8 - 2 - 2 - 8];
+    assert!(crate::parse(truncated).is_err());
+    assert_eq!(
+      ClassFile::parse_constant_pool_only(truncated)
+        .unwrap()
+        .len(),
+      constant_pool.len()
+    );
+
+    let error = ClassFile::parse_constant_pool_only(b"not a class file")
+      .err()
+      .unwrap();
+    assert!(matches!(error.kind(), ErrorKind::BadMagic { .. }));
+  }
+
+  #[test]
+  fn test_class_kind() {
+    let mut builder = ClassBuilder::new("a/Color", Some("java/lang/Enum")).access_flags(0x4031);
+    builder.field(0x4019, "RED", "La/Color;", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert!(class_file.is_enum());
+    assert!(!class_file.is_interface());
+    assert!(!class_file.is_annotation());
+    assert_eq!(class_file.render_class_info()[0], "enum a.Color");
+
+    let mut builder = ClassBuilder::new("a/Marker", Some("java/lang/Object")).access_flags(0x2601);
+    builder.interface("java/lang/annotation/Annotation");
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert!(class_file.is_annotation());
+    assert!(class_file.is_interface());
+    assert!(!class_file.is_enum());
+    assert_eq!(class_file.render_class_info()[0], "annotation a.Marker");
+
+    let builder = ClassBuilder::new("a/Plain", Some("java/lang/Object"));
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert!(!class_file.is_interface());
+    assert_eq!(class_file.render_class_info()[0], "class a.Plain");
+  }
+
+  #[test]
+  fn test_referenced_types() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.interface("java/lang/Runnable");
+    builder.method_ref("java/io/PrintStream", "println", "(Ljava/lang/String;)V");
+    builder.field_ref("a/A", "out", "Ljava/io/PrintStream;");
+    builder.class("[La/Elem;");
+    builder.method_type("(Ljava/util/List;)V");
+    builder.field(0x0002, "values", "[[Ljava/util/Map;", vec![]);
+    builder.method(0x0401, "apply", "(I[JLa/A;)La/Result;", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(
+      class_file.referenced_types(),
+      vec![
+        "a/Elem",
+        "a/Result",
+        "java/io/PrintStream",
+        "java/lang/Object",
+        "java/lang/Runnable",
+        "java/lang/String",
+        "java/util/List",
+        "java/util/Map",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_source_file() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let source_file = builder.utf8("A.java").to_be_bytes();
+    let source_file = builder.attribute("SourceFile", &source_file);
+    builder.class_attribute(source_file);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(class_file.source_file(), Some("A.java"));
+
+    let builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(class_file.source_file(), None);
+  }
+
+  #[test]
+  fn test_permitted_subclasses() {
+    let mut builder = ClassBuilder::new("a/Shape", Some("java/lang/Object"))
+      .version(61, 0)
+      .access_flags(0x0421);
+    let circle = builder.class("a/Circle");
+    let square = builder.class("a/Square");
+    let info = [&[0, 2][..], &circle.to_be_bytes(), &square.to_be_bytes()].concat();
+    let permitted_subclasses = builder.attribute("PermittedSubclasses", &info);
+    builder.class_attribute(permitted_subclasses);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(
+      class_file.permitted_subclasses(),
+      vec!["a/Circle", "a/Square"]
+    );
+    let attribute = &class_file.attributes()[0];
+    assert_eq!(attribute.unparsed_length(), 0);
+    assert_eq!(
+      attribute.to_string(),
+      "PermittedSubclasses: {classes: a/Circle, a/Square}"
+    );
+
+    let builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let class_file = crate::parse(&builder.build()).unwrap();
+    assert!(class_file.permitted_subclasses().is_empty());
+  }
+
+  #[test]
+  fn test_find_method() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // iconst_1, ireturn
+    let one = builder.code_attribute(1, 1, &[0x04, 0xac], &[], vec![]);
+    builder.method(0x0001, "one", "()I", vec![one]);
+    // iload_1, ireturn
+    let same = builder.code_attribute(1, 2, &[0x1b, 0xac], &[], vec![]);
+    builder.method(0x0001, "same", "(I)I", vec![same]);
+    // lload_1, l2i, ireturn
+    let narrow = builder.code_attribute(2, 3, &[0x1f, 0x88, 0xac], &[], vec![]);
+    builder.method(0x0001, "same", "(J)I", vec![narrow]);
+    builder.method(0x0401, "run", "()V", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let one = class_file.find_method("one").unwrap();
+    assert_eq!(one.code().unwrap().raw_code(), [0x04, 0xac]);
+    let narrow = class_file.find_method("same(J)I").unwrap();
+    assert_eq!(narrow.code().unwrap().raw_code(), [0x1f, 0x88, 0xac]);
+    assert!(class_file.find_method("run").unwrap().code().is_none());
+
+    let error = class_file.find_method("same").err().unwrap();
+    assert_eq!(
+      error.to_string(),
+      "same matches same(I)I, same(J)I, add the descriptor"
+    );
+    assert!(matches!(
+      class_file.find_method("two").err().unwrap().kind(),
+      ErrorKind::MethodNotFound { query } if query == "two"
+    ));
+  }
+
+  #[test]
+  fn test_instructions() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // iconst_1, ireturn
+    let one = builder.code_attribute(1, 1, &[0x04, 0xac], &[], vec![]);
+    builder.method(0x0001, "one", "()I", vec![one]);
+    builder.method(0x0401, "run", "()V", vec![]);
+    // lload_1, l2i, ireturn
+    let narrow = builder.code_attribute(2, 3, &[0x1f, 0x88, 0xac], &[], vec![]);
+    builder.method(0x0001, "narrow", "(J)I", vec![narrow]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(class_file.instructions().count(), 5);
+    let returns: Vec<(&str, u32)> = class_file
+      .instructions()
+      .filter(|(_, _, instruction)| instruction.mnemonic == "ireturn")
+      .map(|(method, pc, _)| (method.name(), pc))
+      .collect();
+    assert_eq!(returns, [("one", 1), ("narrow", 2)]);
+  }
+
+  #[test]
+  fn test_truncated_header() {
+    let bytes = [0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 0x34, 0, 1];
+    let error = crate::parse(&bytes).err().unwrap();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::TruncatedHeader {
+        expected: 24,
+        actual: 10
+      }
+    ));
+    assert_eq!(
+      error.to_string(),
+      "truncated header: expected at least 24 bytes, got 10"
+    );
+    assert!(matches!(
+      crate::parse_with_remainder(&[]).err().unwrap().kind(),
+      ErrorKind::TruncatedHeader { actual: 0, .. }
+    ));
+  }
 
   #[test]
-  fn test_parse() {
-    let data = String::from("");
-    let res = ClassFile::parse_from_u8(data.as_bytes());
-    assert_ne!(res.is_err(), true)
+  fn test_parse_empty_input() {
+    for bytes in [&[][..], &[0xca, 0xfe, 0xba]] {
+      let actual = bytes.len();
+      for error in [
+        crate::parse(bytes).err().unwrap(),
+        crate::parse_with_options(bytes, ParseOptions::default())
+          .err()
+          .unwrap(),
+        ClassFile::parse_from_u8(bytes).err().unwrap(),
+        ClassFile::parse_constant_pool_only(bytes).err().unwrap(),
+      ] {
+        assert!(
+          matches!(error.kind(), ErrorKind::TruncatedHeader { expected: 24, actual: a } if *a == actual),
+          "{}",
+          error
+        );
+      }
+    }
   }
 }
@@ -1,26 +1,40 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 use nom::{error::ParseError, number::complete::be_u16, sequence::tuple, IResult};
 
 use crate::{
-  attribute::{parse_attributes, AttributeInfo},
-  get_str_const,
+  attribute::{
+    code::CodeAttribute, fmt_nested_attributes, parse_attributes, AttributeInfo, MethodParameters,
+  },
+  context::ResolveContext,
+  instruction::{Constant, Instruction, Operands},
+  opcodes::opcodes_implied::{LDC, LDC2_W, LDC_W},
 };
 
-use base::{access_flag::AccessFlags, Parsable};
+use base::{
+  access_flag::AccessFlags,
+  coverage::OpcodeCoverage,
+  depth::{Depth, DepthDisplay},
+  descriptor::method_signature,
+  error::{Error, Warning},
+};
 
 pub struct MethodInfo {
   access_flags: AccessFlags,
   name_index: u16,
   descriptor_index: u16,
   pub attributes: Vec<AttributeInfo>,
+  context: Arc<ResolveContext>,
 }
 
-impl Parsable for MethodInfo {
-  fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+impl MethodInfo {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> IResult<&'a [u8], Self, E> {
     let (bytes, (access_flags, name_index, descriptor_index)) =
       tuple((be_u16, be_u16, be_u16))(bytes)?;
-    let (bytes, attributes) = parse_attributes(bytes)?;
+    let (bytes, attributes) = parse_attributes(bytes, context)?;
 
     Ok((
       bytes,
@@ -29,30 +43,414 @@ impl Parsable for MethodInfo {
         name_index,
         descriptor_index,
         attributes,
+        context: context.clone(),
       },
     ))
   }
-}
-impl MethodInfo {
+
   pub fn name(&self) -> &str {
-    get_str_const(self.name_index as usize - 1)
+    self.context.get_str_const(self.name_index)
+  }
+
+  pub fn descriptor(&self) -> &str {
+    self.context.get_str_const(self.descriptor_index)
+  }
+
+  /// Fails if the name or descriptor index doesn't name a `Utf8` constant.
+  pub(crate) fn check_references(&self) -> Result<(), Error> {
+    self.context.require_str_const(self.name_index)?;
+    self.context.require_str_const(self.descriptor_index)?;
+    Ok(())
+  }
+
+  pub fn access_flags(&self) -> &AccessFlags {
+    &self.access_flags
+  }
+
+  /// Java source signature, `public static int compute(java.lang.String, int)`;
+  /// see `descriptor::method_signature`.
+  pub fn signature(&self) -> String {
+    method_signature(&self.access_flags, self.name(), self.descriptor())
+  }
+
+  pub fn is_abstract(&self) -> bool {
+    self.access_flags.is_abstract()
+  }
+
+  pub fn has_code(&self) -> bool {
+    self.code().is_some()
+  }
+
+  /// The `Code` attribute; `None` for abstract and native methods.
+  pub fn code(&self) -> Option<&CodeAttribute> {
+    self
+      .attributes
+      .iter()
+      .find_map(|attribute| attribute.get_code())
+  }
+
+  /// Internal names of the exceptions in the `throws` clause, from the
+  /// `Exceptions` attribute; empty without one.
+  pub fn exceptions(&self) -> Vec<&str> {
+    self
+      .attributes
+      .iter()
+      .find_map(|attribute| attribute.get_exceptions())
+      .map(|exceptions| exceptions.class_names())
+      .unwrap_or_default()
+  }
+
+  /// The `Signature` attribute, present when the method's types involve
+  /// generics: `<T:Ljava/lang/Object;>(TT;)TT;`. Not to be confused with
+  /// `signature`, the Java declaration built from the descriptor.
+  pub fn generic_signature(&self) -> Option<&str> {
+    self
+      .attributes
+      .iter()
+      .find_map(|attribute| attribute.get_signature())
+  }
+
+  /// The `MethodParameters` attribute, present when compiled with
+  /// `javac -parameters`.
+  pub fn method_parameters(&self) -> Option<&MethodParameters> {
+    self
+      .attributes
+      .iter()
+      .find_map(|attribute| attribute.get_method_parameters())
+  }
+
+  /// `name` and `descriptor` for warnings, or `#name_index` when either
+  /// doesn't resolve; `check_references` reports that.
+  fn label(&self) -> String {
+    let name = self.context.find_str_const(self.name_index);
+    let descriptor = self.context.find_str_const(self.descriptor_index);
+    match (name, descriptor) {
+      (Some(name), Some(descriptor)) => format!("{}{}", name, descriptor),
+      _ => format!("#{}", self.name_index),
+    }
+  }
+
+  /// Checks that the method has a `Code` attribute exactly when it is
+  /// neither abstract nor native.
+  pub fn validate(&self) -> Vec<Warning> {
+    let method = self.label();
+    let flag = if self.is_abstract() {
+      Some("abstract")
+    } else if self.access_flags.is_native() {
+      Some("native")
+    } else {
+      None
+    };
+    match (flag, self.has_code()) {
+      (Some(flag), true) => vec![Warning::UnexpectedCode { method, flag }],
+      (None, false) => vec![Warning::MissingCode { method }],
+      _ => vec![],
+    }
+  }
+
+  /// A light check of the decoded code: every load, store, `iinc` and `ret`
+  /// stays within `max_locals`, `ldc` and `ldc_w` load a one-slot constant
+  /// and `ldc2_w` a long or double. Empty without code.
+  pub fn validate_code(&self) -> Vec<Warning> {
+    let Some(code) = self.code() else {
+      return vec![];
+    };
+    let method = self.label();
+    let max_locals = code.max_locals();
+    let mut warnings = vec![];
+    for instruction in code.disassemble() {
+      let mut problem = |problem: String| {
+        warnings.push(Warning::InvalidCode {
+          method: method.clone(),
+          pc: instruction.pc,
+          problem,
+        })
+      };
+      if let Some((index, slots)) = instruction.local_slots() {
+        if index as u32 + slots as u32 > max_locals as u32 {
+          problem(format!(
+            "{} uses local {} but max_locals is {}",
+            instruction.mnemonic,
+            index as u32 + slots as u32 - 1,
+            max_locals
+          ));
+        }
+      }
+      let Operands::Constant(constant) = &instruction.operands else {
+        continue;
+      };
+      let wide_constant = match constant {
+        Constant::Long(_) | Constant::Double(_) => true,
+        Constant::Dynamic { descriptor, .. } => descriptor == "J" || descriptor == "D",
+        _ => false,
+      };
+      let loadable = match instruction.opcode {
+        LDC | LDC_W => !wide_constant && constant.is_loadable(),
+        LDC2_W => wide_constant,
+        _ => true,
+      };
+      if !loadable {
+        problem(format!(
+          "{} of an unloadable constant {:?}",
+          instruction.mnemonic, constant
+        ));
+      }
+    }
+    warnings
+  }
+
+  /// Bytecode of the method with constant references resolved; empty for
+  /// abstract and native methods, which have no `Code` attribute.
+  pub fn opcode_coverage(&self) -> OpcodeCoverage {
+    self
+      .attributes
+      .iter()
+      .find_map(|attribute| attribute.get_code())
+      .map(|code| code.opcode_coverage().clone())
+      .unwrap_or_default()
+  }
+
+  pub fn disassemble(&self) -> Vec<Instruction> {
+    self
+      .attributes
+      .iter()
+      .find_map(|attribute| attribute.get_code())
+      .map(|code| code.disassemble())
+      .unwrap_or_default()
   }
 }
 
 impl Display for MethodInfo {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(
-      f,
-      "access_flags: {} name_index: {} descriptor_index: {}",
-      self.access_flags, self.name_index, self.descriptor_index
-    )?;
+    self.fmt_depth(f, Depth::UNLIMITED)
+  }
+}
+
+impl DepthDisplay for MethodInfo {
+  fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: Depth) -> std::fmt::Result {
+    write!(f, "{}", self.signature())?;
     if self.attributes.len() > 0 {
       write!(f, " attributes({}):", self.attributes.len())?;
     }
-    for attribute in &self.attributes {
-      write!(f, " {}", attribute)?;
+    fmt_nested_attributes(f, &self.attributes, depth)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::{
+    depth::{Depth, DepthDisplay},
+    error::Warning,
+  };
+
+  use crate::test_util::ClassBuilder;
+
+  #[test]
+  fn test_signature() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    builder.method(0x0009, "compute", "(Ljava/lang/String;I)I", vec![]);
+    builder.method(
+      0x0081,
+      "format",
+      "(Ljava/lang/String;[Ljava/lang/Object;)V",
+      vec![],
+    );
+    builder.method(0x0401, "values", "()[[J", vec![]);
+    builder.method(0x0002, "<init>", "(Z)V", vec![]);
+    builder.method(0x0008, "<clinit>", "()V", vec![]);
+    builder.method(0x1000, "broken", "(Q)V", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let signatures: Vec<String> = class_file
+      .methods()
+      .iter()
+      .map(|method| method.signature())
+      .collect();
+    assert_eq!(
+      signatures,
+      vec![
+        "public static int compute(java.lang.String, int)",
+        "public void format(java.lang.String, java.lang.Object...)",
+        "public abstract long[][] values()",
+        "private <init>(boolean)",
+        "static {}",
+        "broken(Q)V",
+      ]
+    );
+    assert_eq!(class_file.methods()[0].to_string(), signatures[0]);
+  }
+
+  #[test]
+  fn test_method_attributes() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // <T:Ljava/lang/Object;>(TT;I)TT; throws java.io.IOException, with
+    // parameters `final value` and an unnamed one.
+    let signature = builder
+      .utf8("<T:Ljava/lang/Object;>(TT;I)TT;")
+      .to_be_bytes();
+    let signature = builder.attribute("Signature", &signature);
+    let io = builder.class("java/io/IOException");
+    let exceptions = builder.attribute("Exceptions", &[&[0, 1][..], &io.to_be_bytes()].concat());
+    let value = builder.utf8("value");
+    let parameters = [&[2][..], &value.to_be_bytes(), &[0, 0x10, 0, 0, 0x10, 0x00]].concat();
+    let parameters = builder.attribute("MethodParameters", &parameters);
+    builder.method(
+      0x0401,
+      "apply",
+      "(Ljava/lang/Object;I)Ljava/lang/Object;",
+      vec![signature, exceptions, parameters],
+    );
+    builder.method(0x0401, "run", "()V", vec![]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let apply = &class_file.methods()[0];
+    assert_eq!(
+      apply.generic_signature(),
+      Some("<T:Ljava/lang/Object;>(TT;I)TT;")
+    );
+    assert_eq!(
+      apply.signature(),
+      "public abstract java.lang.Object apply(java.lang.Object, int)"
+    );
+    assert_eq!(apply.exceptions(), vec!["java/io/IOException"]);
+    let parameters = apply.method_parameters().unwrap();
+    assert_eq!(parameters.names(), vec![Some("value"), None]);
+    assert_eq!(parameters.access_flags(), vec![0x0010, 0x1000]);
+    assert_eq!(
+      apply.to_string(),
+      "public abstract java.lang.Object apply(java.lang.Object, int) attributes(3): \
+       Signature: {signature: <T:Ljava/lang/Object;>(TT;I)TT;} \
+       Exceptions: {exceptions: java/io/IOException} \
+       MethodParameters: {parameters: final value, synthetic <unnamed>}"
+    );
+
+    let run = &class_file.methods()[1];
+    assert_eq!(run.generic_signature(), None);
+    assert!(run.exceptions().is_empty());
+    assert!(run.method_parameters().is_none());
+  }
+
+  #[test]
+  fn test_display_depth() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let stack_map_table = builder.attribute("StackMapTable", &[0, 1, 3]);
+    let code = builder.code_attribute(1, 1, &[0xb1], &[], vec![stack_map_table]);
+    // @a/Outer(value=@a/Inner(value={1}))
+    let outer = builder.utf8("La/Outer;");
+    let inner = builder.utf8("La/Inner;");
+    let value = builder.utf8("value");
+    let one = builder.integer(1);
+    let mut info = vec![0, 1];
+    for index in [outer, 1, value] {
+      info.extend_from_slice(&index.to_be_bytes());
+    }
+    info.push(b'@');
+    for index in [inner, 1, value] {
+      info.extend_from_slice(&index.to_be_bytes());
     }
+    info.extend_from_slice(&[b'[', 0, 1, b'I']);
+    info.extend_from_slice(&one.to_be_bytes());
+    let annotations = builder.attribute("RuntimeVisibleAnnotations", &info);
+    builder.method(0x0001, "run", "()V", vec![code, annotations]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+    let run = &class_file.methods()[0];
+    let display = |max_depth| run.display_depth(Depth::new(max_depth)).to_string();
 
-    Ok(())
+    assert_eq!(display(Some(0)), "public void run() attributes(2): ...");
+    let depth_1 = display(Some(1));
+    assert!(depth_1.contains("code_length: 1}\ncode: return \nattributes(1): ..."));
+    assert!(depth_1.ends_with("RuntimeVisibleAnnotations: annotations(1): ..."));
+    let depth_2 = display(Some(2));
+    assert!(depth_2.contains("attributes(1):\n\tStackMapTable: StackMapTable(1)\n\t...\n"));
+    assert!(depth_2.ends_with("annotations(1): @La/Outer;(...) "));
+    assert!(display(Some(3)).ends_with("@La/Outer;(value=@La/Inner;(...)) "));
+    assert!(display(Some(4)).ends_with("@La/Outer;(value=@La/Inner;(value={...})) "));
+
+    let full = display(None);
+    assert_eq!(full, run.to_string());
+    assert!(full.contains("StackMapTable(1)\n\t3: same\n"));
+    assert!(full.ends_with("@La/Outer;(value=@La/Inner;(value={Integer: 1})) "));
+  }
+
+  #[test]
+  fn test_validate_code_presence() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let code = builder.code_attribute(0, 1, &[0xb1], &[], vec![]);
+    builder.method(0x0401, "tampered", "()V", vec![code.clone()]);
+    builder.method(0x0001, "stripped", "(I)V", vec![]);
+    builder.method(0x0401, "run", "()V", vec![]);
+    builder.method(0x0101, "call", "()V", vec![]);
+    builder.method(0x0001, "stop", "()V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let methods = class_file.methods();
+    assert!(methods[0].is_abstract() && methods[0].has_code());
+    assert_eq!(
+      methods[0].validate(),
+      vec![Warning::UnexpectedCode {
+        method: "tampered()V".to_string(),
+        flag: "abstract",
+      }]
+    );
+    assert_eq!(
+      methods[1].validate(),
+      vec![Warning::MissingCode {
+        method: "stripped(I)V".to_string(),
+      }]
+    );
+    assert!(methods[2..]
+      .iter()
+      .all(|method| method.validate().is_empty()));
+
+    let warnings = class_file.validate();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+      warnings[0].to_string(),
+      "method tampered()V is abstract but has a Code attribute"
+    );
+    assert_eq!(
+      warnings[1].to_string(),
+      "method stripped(I)V has no Code attribute"
+    );
+  }
+
+  #[test]
+  fn test_validate_code() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let long = builder.long(7);
+    #[rustfmt::skip]
+    let code = [
+      0x1b,                                // iload_1
+      0x57,                                // pop
+      0x13, (long >> 8) as u8, long as u8, // ldc_w
+      0x58,                                // pop2
+      0x14, (long >> 8) as u8, long as u8, // ldc2_w
+      0x58,                                // pop2
+      0xb1,                                // return
+    ];
+    let code = builder.code_attribute(2, 1, &code, &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let code = builder.code_attribute(2, 2, &[0x1b, 0x57, 0xb1], &[], vec![]);
+    builder.method(0x0001, "stop", "(I)V", vec![code]);
+    let class_file = crate::parse(&builder.build()).unwrap();
+
+    let warnings = class_file.methods()[0].validate_code();
+    let warnings: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+      warnings,
+      vec![
+        "method run()V at pc 0: iload_1 uses local 1 but max_locals is 1".to_string(),
+        "method run()V at pc 2: ldc_w of an unloadable constant Long(7)".to_string(),
+      ]
+    );
+    assert!(class_file.methods()[1].validate_code().is_empty());
+
+    let verification = crate::verify::Verification::with_code_checks(&class_file);
+    assert_eq!(verification.issues().len(), 2);
+    assert_eq!(
+      verification.issues()[0].to_string(),
+      "method run()V pc 0: iload_1 uses local 1 but max_locals is 1"
+    );
+    assert!(crate::verify::Verification::new(&class_file).passed());
   }
 }
@@ -1,14 +1,20 @@
 use std::fmt::Display;
 
-use nom::{error::ParseError, number::complete::be_u8, IResult};
+use nom::{
+  bytes::complete::take,
+  error::ParseError,
+  number::complete::{be_i32, be_u8},
+  IResult,
+};
 
-use base::Parsable;
+use crate::instruction::array_type_name;
 
 /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-6.html#jvms-6.5
 ///
 pub mod opcodes_implied {
   use std::collections::HashMap;
 
+  pub const NOP: u8 = 0x00;
   pub const ACONST_NULL: u8 = 0x01;
   pub const ICONST_M1: u8 = 0x02;
   pub const ICONST_0: u8 = 0x03;
@@ -29,6 +35,23 @@ pub mod opcodes_implied {
   pub const LDC: u8 = 0x12;
   pub const LDC_W: u8 = 0x13;
   pub const LDC2_W: u8 = 0x14;
+  pub const ILOAD: u8 = 0x15;
+  pub const LLOAD: u8 = 0x16;
+  pub const FLOAD: u8 = 0x17;
+  pub const DLOAD: u8 = 0x18;
+  pub const ALOAD: u8 = 0x19;
+  pub const ILOAD_0: u8 = 0x1a;
+  pub const ILOAD_1: u8 = 0x1b;
+  pub const ILOAD_2: u8 = 0x1c;
+  pub const ILOAD_3: u8 = 0x1d;
+  pub const LLOAD_0: u8 = 0x1e;
+  pub const LLOAD_1: u8 = 0x1f;
+  pub const LLOAD_2: u8 = 0x20;
+  pub const LLOAD_3: u8 = 0x21;
+  pub const FLOAD_0: u8 = 0x22;
+  pub const FLOAD_1: u8 = 0x23;
+  pub const FLOAD_2: u8 = 0x24;
+  pub const FLOAD_3: u8 = 0x25;
   pub const DLOAD_0: u8 = 0x26;
   pub const DLOAD_1: u8 = 0x27;
   pub const DLOAD_2: u8 = 0x28;
@@ -37,149 +60,680 @@ pub mod opcodes_implied {
   pub const ALOAD_1: u8 = 0x2b;
   pub const ALOAD_2: u8 = 0x2c;
   pub const ALOAD_3: u8 = 0x2d;
+  pub const IALOAD: u8 = 0x2e;
+  pub const LALOAD: u8 = 0x2f;
+  pub const FALOAD: u8 = 0x30;
+  pub const DALOAD: u8 = 0x31;
   pub const AALOAD: u8 = 0x32;
+  pub const BALOAD: u8 = 0x33;
+  pub const CALOAD: u8 = 0x34;
+  pub const SALOAD: u8 = 0x35;
+  pub const ISTORE: u8 = 0x36;
+  pub const LSTORE: u8 = 0x37;
+  pub const FSTORE: u8 = 0x38;
+  pub const DSTORE: u8 = 0x39;
+  pub const ASTORE: u8 = 0x3a;
+  pub const ISTORE_0: u8 = 0x3b;
+  pub const ISTORE_1: u8 = 0x3c;
+  pub const ISTORE_2: u8 = 0x3d;
+  pub const ISTORE_3: u8 = 0x3e;
+  pub const LSTORE_0: u8 = 0x3f;
+  pub const LSTORE_1: u8 = 0x40;
+  pub const LSTORE_2: u8 = 0x41;
+  pub const LSTORE_3: u8 = 0x42;
+  pub const FSTORE_0: u8 = 0x43;
+  pub const FSTORE_1: u8 = 0x44;
+  pub const FSTORE_2: u8 = 0x45;
+  pub const FSTORE_3: u8 = 0x46;
+  pub const DSTORE_0: u8 = 0x47;
+  pub const DSTORE_1: u8 = 0x48;
+  pub const DSTORE_2: u8 = 0x49;
+  pub const DSTORE_3: u8 = 0x4a;
+  pub const ASTORE_0: u8 = 0x4b;
+  pub const ASTORE_1: u8 = 0x4c;
+  pub const ASTORE_2: u8 = 0x4d;
+  pub const ASTORE_3: u8 = 0x4e;
+  pub const IASTORE: u8 = 0x4f;
+  pub const LASTORE: u8 = 0x50;
+  pub const FASTORE: u8 = 0x51;
+  pub const DASTORE: u8 = 0x52;
   pub const AASTORE: u8 = 0x53;
+  pub const BASTORE: u8 = 0x54;
+  pub const CASTORE: u8 = 0x55;
+  pub const SASTORE: u8 = 0x56;
+  pub const POP: u8 = 0x57;
+  pub const POP2: u8 = 0x58;
+  pub const DUP: u8 = 0x59;
+  pub const DUP_X1: u8 = 0x5a;
+  pub const DUP_X2: u8 = 0x5b;
+  pub const DUP2: u8 = 0x5c;
+  pub const DUP2_X1: u8 = 0x5d;
+  pub const DUP2_X2: u8 = 0x5e;
+  pub const SWAP: u8 = 0x5f;
+  pub const IADD: u8 = 0x60;
+  pub const LADD: u8 = 0x61;
+  pub const FADD: u8 = 0x62;
+  pub const DADD: u8 = 0x63;
+  pub const ISUB: u8 = 0x64;
+  pub const LSUB: u8 = 0x65;
+  pub const FSUB: u8 = 0x66;
+  pub const DSUB: u8 = 0x67;
+  pub const IMUL: u8 = 0x68;
+  pub const LMUL: u8 = 0x69;
+  pub const FMUL: u8 = 0x6a;
+  pub const DMUL: u8 = 0x6b;
+  pub const IDIV: u8 = 0x6c;
+  pub const LDIV: u8 = 0x6d;
+  pub const FDIV: u8 = 0x6e;
+  pub const DDIV: u8 = 0x6f;
+  pub const IREM: u8 = 0x70;
+  pub const LREM: u8 = 0x71;
+  pub const FREM: u8 = 0x72;
+  pub const DREM: u8 = 0x73;
+  pub const INEG: u8 = 0x74;
+  pub const LNEG: u8 = 0x75;
+  pub const FNEG: u8 = 0x76;
+  pub const DNEG: u8 = 0x77;
+  pub const ISHL: u8 = 0x78;
+  pub const LSHL: u8 = 0x79;
+  pub const ISHR: u8 = 0x7a;
+  pub const LSHR: u8 = 0x7b;
+  pub const IUSHR: u8 = 0x7c;
+  pub const LUSHR: u8 = 0x7d;
+  pub const IAND: u8 = 0x7e;
+  pub const LAND: u8 = 0x7f;
+  pub const IOR: u8 = 0x80;
+  pub const LOR: u8 = 0x81;
+  pub const IXOR: u8 = 0x82;
+  pub const LXOR: u8 = 0x83;
+  pub const IINC: u8 = 0x84;
+  pub const I2L: u8 = 0x85;
+  pub const I2F: u8 = 0x86;
+  pub const I2D: u8 = 0x87;
+  pub const L2I: u8 = 0x88;
+  pub const L2F: u8 = 0x89;
+  pub const L2D: u8 = 0x8a;
+  pub const F2I: u8 = 0x8b;
+  pub const F2L: u8 = 0x8c;
+  pub const F2D: u8 = 0x8d;
+  pub const D2I: u8 = 0x8e;
+  pub const D2L: u8 = 0x8f;
+  pub const D2F: u8 = 0x90;
+  pub const I2B: u8 = 0x91;
+  pub const I2C: u8 = 0x92;
+  pub const I2S: u8 = 0x93;
+  pub const LCMP: u8 = 0x94;
+  pub const FCMPL: u8 = 0x95;
+  pub const FCMPG: u8 = 0x96;
+  pub const DCMPL: u8 = 0x97;
+  pub const DCMPG: u8 = 0x98;
+  pub const IFEQ: u8 = 0x99;
+  pub const IFNE: u8 = 0x9a;
+  pub const IFLT: u8 = 0x9b;
+  pub const IFGE: u8 = 0x9c;
+  pub const IFGT: u8 = 0x9d;
+  pub const IFLE: u8 = 0x9e;
+  pub const IF_ICMPEQ: u8 = 0x9f;
+  pub const IF_ICMPNE: u8 = 0xa0;
+  pub const IF_ICMPLT: u8 = 0xa1;
+  pub const IF_ICMPGE: u8 = 0xa2;
+  pub const IF_ICMPGT: u8 = 0xa3;
+  pub const IF_ICMPLE: u8 = 0xa4;
+  pub const IF_ACMPEQ: u8 = 0xa5;
+  pub const IF_ACMPNE: u8 = 0xa6;
+  pub const GOTO: u8 = 0xa7;
+  pub const JSR: u8 = 0xa8;
+  pub const RET: u8 = 0xa9;
+  pub const TABLESWITCH: u8 = 0xaa;
+  pub const LOOKUPSWITCH: u8 = 0xab;
+  pub const IRETURN: u8 = 0xac;
+  pub const LRETURN: u8 = 0xad;
+  pub const FRETURN: u8 = 0xae;
   pub const DRETURN: u8 = 0xaf;
+  pub const ARETURN: u8 = 0xb0;
   pub const RETURN: u8 = 0xb1;
+  pub const GETSTATIC: u8 = 0xb2;
+  pub const PUTSTATIC: u8 = 0xb3;
   pub const GETFIELD: u8 = 0xb4;
   pub const PUTFIELD: u8 = 0xb5;
-  pub const INVOKESPACIAL: u8 = 0xb7;
+  pub const INVOKEVIRTUAL: u8 = 0xb6;
+  pub const INVOKESPECIAL: u8 = 0xb7;
+  pub const INVOKESTATIC: u8 = 0xb8;
+  pub const INVOKEINTERFACE: u8 = 0xb9;
+  pub const INVOKEDYNAMIC: u8 = 0xba;
+  pub const NEW: u8 = 0xbb;
+  pub const NEWARRAY: u8 = 0xbc;
   pub const ANEWARRAY: u8 = 0xbd;
+  pub const ARRAYLENGTH: u8 = 0xbe;
+  pub const ATHROW: u8 = 0xbf;
+  pub const CHECKCAST: u8 = 0xc0;
+  pub const INSTANCEOF: u8 = 0xc1;
+  pub const MONITORENTER: u8 = 0xc2;
+  pub const MONITOREXIT: u8 = 0xc3;
+  pub const WIDE: u8 = 0xc4;
+  pub const MULTIANEWARRAY: u8 = 0xc5;
+  pub const IFNULL: u8 = 0xc6;
+  pub const IFNONNULL: u8 = 0xc7;
+  pub const GOTO_W: u8 = 0xc8;
+  pub const JSR_W: u8 = 0xc9;
+  pub const BREAKPOINT: u8 = 0xca;
+  pub const IMPDEP1: u8 = 0xfe;
+  pub const IMPDEP2: u8 = 0xff;
+
+  /// `(opcode, mnemonic, operand bytes)`. `tableswitch`, `lookupswitch` and
+  /// `wide` have variable-length operands and are listed with 0.
+  pub const OPCODES: &[(u8, &str, u8)] = &[
+    (NOP, "nop", 0),
+    (ACONST_NULL, "aconst_null", 0),
+    (ICONST_M1, "iconst_m1", 0),
+    (ICONST_0, "iconst_0", 0),
+    (ICONST_1, "iconst_1", 0),
+    (ICONST_2, "iconst_2", 0),
+    (ICONST_3, "iconst_3", 0),
+    (ICONST_4, "iconst_4", 0),
+    (ICONST_5, "iconst_5", 0),
+    (LCONST_0, "lconst_0", 0),
+    (LCONST_1, "lconst_1", 0),
+    (FCONST_0, "fconst_0", 0),
+    (FCONST_1, "fconst_1", 0),
+    (FCONST_2, "fconst_2", 0),
+    (DCONST_0, "dconst_0", 0),
+    (DCONST_1, "dconst_1", 0),
+    (BIPUSH, "bipush", 1),
+    (SIPUSH, "sipush", 2),
+    (LDC, "ldc", 1),
+    (LDC_W, "ldc_w", 2),
+    (LDC2_W, "ldc2_w", 2),
+    (ILOAD, "iload", 1),
+    (LLOAD, "lload", 1),
+    (FLOAD, "fload", 1),
+    (DLOAD, "dload", 1),
+    (ALOAD, "aload", 1),
+    (ILOAD_0, "iload_0", 0),
+    (ILOAD_1, "iload_1", 0),
+    (ILOAD_2, "iload_2", 0),
+    (ILOAD_3, "iload_3", 0),
+    (LLOAD_0, "lload_0", 0),
+    (LLOAD_1, "lload_1", 0),
+    (LLOAD_2, "lload_2", 0),
+    (LLOAD_3, "lload_3", 0),
+    (FLOAD_0, "fload_0", 0),
+    (FLOAD_1, "fload_1", 0),
+    (FLOAD_2, "fload_2", 0),
+    (FLOAD_3, "fload_3", 0),
+    (DLOAD_0, "dload_0", 0),
+    (DLOAD_1, "dload_1", 0),
+    (DLOAD_2, "dload_2", 0),
+    (DLOAD_3, "dload_3", 0),
+    (ALOAD_0, "aload_0", 0),
+    (ALOAD_1, "aload_1", 0),
+    (ALOAD_2, "aload_2", 0),
+    (ALOAD_3, "aload_3", 0),
+    (IALOAD, "iaload", 0),
+    (LALOAD, "laload", 0),
+    (FALOAD, "faload", 0),
+    (DALOAD, "daload", 0),
+    (AALOAD, "aaload", 0),
+    (BALOAD, "baload", 0),
+    (CALOAD, "caload", 0),
+    (SALOAD, "saload", 0),
+    (ISTORE, "istore", 1),
+    (LSTORE, "lstore", 1),
+    (FSTORE, "fstore", 1),
+    (DSTORE, "dstore", 1),
+    (ASTORE, "astore", 1),
+    (ISTORE_0, "istore_0", 0),
+    (ISTORE_1, "istore_1", 0),
+    (ISTORE_2, "istore_2", 0),
+    (ISTORE_3, "istore_3", 0),
+    (LSTORE_0, "lstore_0", 0),
+    (LSTORE_1, "lstore_1", 0),
+    (LSTORE_2, "lstore_2", 0),
+    (LSTORE_3, "lstore_3", 0),
+    (FSTORE_0, "fstore_0", 0),
+    (FSTORE_1, "fstore_1", 0),
+    (FSTORE_2, "fstore_2", 0),
+    (FSTORE_3, "fstore_3", 0),
+    (DSTORE_0, "dstore_0", 0),
+    (DSTORE_1, "dstore_1", 0),
+    (DSTORE_2, "dstore_2", 0),
+    (DSTORE_3, "dstore_3", 0),
+    (ASTORE_0, "astore_0", 0),
+    (ASTORE_1, "astore_1", 0),
+    (ASTORE_2, "astore_2", 0),
+    (ASTORE_3, "astore_3", 0),
+    (IASTORE, "iastore", 0),
+    (LASTORE, "lastore", 0),
+    (FASTORE, "fastore", 0),
+    (DASTORE, "dastore", 0),
+    (AASTORE, "aastore", 0),
+    (BASTORE, "bastore", 0),
+    (CASTORE, "castore", 0),
+    (SASTORE, "sastore", 0),
+    (POP, "pop", 0),
+    (POP2, "pop2", 0),
+    (DUP, "dup", 0),
+    (DUP_X1, "dup_x1", 0),
+    (DUP_X2, "dup_x2", 0),
+    (DUP2, "dup2", 0),
+    (DUP2_X1, "dup2_x1", 0),
+    (DUP2_X2, "dup2_x2", 0),
+    (SWAP, "swap", 0),
+    (IADD, "iadd", 0),
+    (LADD, "ladd", 0),
+    (FADD, "fadd", 0),
+    (DADD, "dadd", 0),
+    (ISUB, "isub", 0),
+    (LSUB, "lsub", 0),
+    (FSUB, "fsub", 0),
+    (DSUB, "dsub", 0),
+    (IMUL, "imul", 0),
+    (LMUL, "lmul", 0),
+    (FMUL, "fmul", 0),
+    (DMUL, "dmul", 0),
+    (IDIV, "idiv", 0),
+    (LDIV, "ldiv", 0),
+    (FDIV, "fdiv", 0),
+    (DDIV, "ddiv", 0),
+    (IREM, "irem", 0),
+    (LREM, "lrem", 0),
+    (FREM, "frem", 0),
+    (DREM, "drem", 0),
+    (INEG, "ineg", 0),
+    (LNEG, "lneg", 0),
+    (FNEG, "fneg", 0),
+    (DNEG, "dneg", 0),
+    (ISHL, "ishl", 0),
+    (LSHL, "lshl", 0),
+    (ISHR, "ishr", 0),
+    (LSHR, "lshr", 0),
+    (IUSHR, "iushr", 0),
+    (LUSHR, "lushr", 0),
+    (IAND, "iand", 0),
+    (LAND, "land", 0),
+    (IOR, "ior", 0),
+    (LOR, "lor", 0),
+    (IXOR, "ixor", 0),
+    (LXOR, "lxor", 0),
+    (IINC, "iinc", 2),
+    (I2L, "i2l", 0),
+    (I2F, "i2f", 0),
+    (I2D, "i2d", 0),
+    (L2I, "l2i", 0),
+    (L2F, "l2f", 0),
+    (L2D, "l2d", 0),
+    (F2I, "f2i", 0),
+    (F2L, "f2l", 0),
+    (F2D, "f2d", 0),
+    (D2I, "d2i", 0),
+    (D2L, "d2l", 0),
+    (D2F, "d2f", 0),
+    (I2B, "i2b", 0),
+    (I2C, "i2c", 0),
+    (I2S, "i2s", 0),
+    (LCMP, "lcmp", 0),
+    (FCMPL, "fcmpl", 0),
+    (FCMPG, "fcmpg", 0),
+    (DCMPL, "dcmpl", 0),
+    (DCMPG, "dcmpg", 0),
+    (IFEQ, "ifeq", 2),
+    (IFNE, "ifne", 2),
+    (IFLT, "iflt", 2),
+    (IFGE, "ifge", 2),
+    (IFGT, "ifgt", 2),
+    (IFLE, "ifle", 2),
+    (IF_ICMPEQ, "if_icmpeq", 2),
+    (IF_ICMPNE, "if_icmpne", 2),
+    (IF_ICMPLT, "if_icmplt", 2),
+    (IF_ICMPGE, "if_icmpge", 2),
+    (IF_ICMPGT, "if_icmpgt", 2),
+    (IF_ICMPLE, "if_icmple", 2),
+    (IF_ACMPEQ, "if_acmpeq", 2),
+    (IF_ACMPNE, "if_acmpne", 2),
+    (GOTO, "goto", 2),
+    (JSR, "jsr", 2),
+    (RET, "ret", 1),
+    (TABLESWITCH, "tableswitch", 0),
+    (LOOKUPSWITCH, "lookupswitch", 0),
+    (IRETURN, "ireturn", 0),
+    (LRETURN, "lreturn", 0),
+    (FRETURN, "freturn", 0),
+    (DRETURN, "dreturn", 0),
+    (ARETURN, "areturn", 0),
+    (RETURN, "return", 0),
+    (GETSTATIC, "getstatic", 2),
+    (PUTSTATIC, "putstatic", 2),
+    (GETFIELD, "getfield", 2),
+    (PUTFIELD, "putfield", 2),
+    (INVOKEVIRTUAL, "invokevirtual", 2),
+    (INVOKESPECIAL, "invokespecial", 2),
+    (INVOKESTATIC, "invokestatic", 2),
+    (INVOKEINTERFACE, "invokeinterface", 4),
+    (INVOKEDYNAMIC, "invokedynamic", 4),
+    (NEW, "new", 2),
+    (NEWARRAY, "newarray", 1),
+    (ANEWARRAY, "anewarray", 2),
+    (ARRAYLENGTH, "arraylength", 0),
+    (ATHROW, "athrow", 0),
+    (CHECKCAST, "checkcast", 2),
+    (INSTANCEOF, "instanceof", 2),
+    (MONITORENTER, "monitorenter", 0),
+    (MONITOREXIT, "monitorexit", 0),
+    (WIDE, "wide", 0),
+    (MULTIANEWARRAY, "multianewarray", 3),
+    (IFNULL, "ifnull", 2),
+    (IFNONNULL, "ifnonnull", 2),
+    (GOTO_W, "goto_w", 4),
+    (JSR_W, "jsr_w", 4),
+    (BREAKPOINT, "breakpoint", 0),
+    (IMPDEP1, "impdep1", 0),
+    (IMPDEP2, "impdep2", 0),
+  ];
 
   lazy_static::lazy_static! {
     pub static ref CODE_NAME_MAP: HashMap<u8, &'static str> = {
-      HashMap::from([
-        (AALOAD, "aaload"),
-        (AASTORE, "aastore"),
-        (ACONST_NULL, "aconst_null"),
-        (ICONST_M1, "iconst_m1"),
-        (ICONST_0, "iconst_0"),
-        (ICONST_1, "iconst_1"),
-        (ICONST_2, "iconst_2"),
-        (ICONST_3, "iconst_3"),
-        (ICONST_4, "iconst_4"),
-        (ICONST_5, "iconst_5"),
-        (LCONST_0, "lconst_0"),
-        (LCONST_1, "lconst_1"),
-        (FCONST_0, "fconst_0"),
-        (FCONST_1, "fconst_1"),
-        (FCONST_2, "fconst_2"),
-        (DCONST_0, "dconst_0"),
-        (DCONST_1, "dconst_1"),
-        (BIPUSH, "bipush"),
-        (SIPUSH, "sipush"),
-        (LDC, "ldc"),
-        (LDC_W, "ldc_w"),
-        (LDC2_W, "ldc2_w"),
-        (ALOAD_0, "aload_0"),
-        (ALOAD_1, "aload_1"),
-        (ALOAD_2, "aload_2"),
-        (ALOAD_3, "aload_3"),
-        (DLOAD_0, "dload_0"),
-        (DLOAD_1, "dload_1"),
-        (DLOAD_2, "dload_2"),
-        (DLOAD_3, "dload_3"),
-        (RETURN, "return"),
-        (DRETURN, "dreturn"),
-        (GETFIELD, "getfield"),
-        (PUTFIELD, "putfield"),
-        (INVOKESPACIAL, "invokespecial"),
-        (ANEWARRAY, "anewarray"),
-      ])
+      OPCODES.iter().map(|&(code, name, _)| (code, name)).collect()
+    };
+    pub static ref NAME_CODE_MAP: HashMap<&'static str, u8> = {
+      OPCODES.iter().map(|&(code, name, _)| (name, code)).collect()
     };
     pub static ref CODE_OP_CNT_MAP: HashMap<u8, u8> = {
-      HashMap::from([
-        (AALOAD, 0),
-        (AASTORE, 0),
-        (ACONST_NULL, 0),
-        (ICONST_M1, 0),
-        (ICONST_0, 0),
-        (ICONST_1, 0),
-        (ICONST_2, 0),
-        (ICONST_3, 0),
-        (ICONST_4, 0),
-        (ICONST_5, 0),
-        (LCONST_0, 0),
-        (LCONST_1, 0),
-        (FCONST_0, 0),
-        (FCONST_1, 0),
-        (FCONST_2, 0),
-        (DCONST_0, 0),
-        (DCONST_1, 0),
-        (BIPUSH, 1),
-        (SIPUSH, 2),
-        (LDC, 1),
-        (LDC_W, 2),
-        (LDC2_W, 2),
-        (ALOAD_0, 0),
-        (ALOAD_1, 0),
-        (ALOAD_2, 0),
-        (ALOAD_3, 0),
-        (DLOAD_0, 0),
-        (DLOAD_1, 0),
-        (DLOAD_2, 0),
-        (DLOAD_3, 0),
-        (RETURN, 0),
-        (DRETURN, 0),
-        (GETFIELD, 2),
-        (PUTFIELD, 2),
-        (INVOKESPACIAL, 2),
-        (ANEWARRAY, 2),
-      ])
+      OPCODES
+        .iter()
+        .filter(|&&(code, _, _)| !matches!(code, TABLESWITCH | LOOKUPSWITCH | WIDE))
+        .map(|&(code, _, cnt)| (code, cnt))
+        .collect()
     };
   }
 }
 
+use opcodes_implied::{IINC, LOOKUPSWITCH, TABLESWITCH, WIDE};
+
+/// What an opcode does, after the groups of the JVMS opcode table
+/// (https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-7.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpcodeCategory {
+  /// Pushes a constant: `iconst_0`, `bipush`, `ldc`.
+  Constant,
+  /// Pushes a local variable or array element.
+  Load,
+  /// Pops into a local variable or array element.
+  Store,
+  /// `pop`, `dup` and `swap` forms.
+  Stack,
+  /// Arithmetic, bitwise operations and `iinc`.
+  Arithmetic,
+  /// `i2l` and the other primitive conversions.
+  Conversion,
+  /// `lcmp` and the other comparisons, including every conditional branch.
+  Comparison,
+  /// Unconditional jumps, switches, returns and `athrow`.
+  Control,
+  /// Field access, object and array creation, type checks and monitors.
+  Reference,
+  Invoke,
+  /// `wide`, which only modifies the following instruction.
+  Extended,
+  /// `breakpoint`, `impdep1` and `impdep2`, not found in class files.
+  Reserved,
+  /// An opcode the JVMS doesn't assign.
+  Unknown,
+}
+
+/// The category of `opcode`.
+///
+/// Unlike the JVMS tables, which list them as extended, `ifnull` and
+/// `ifnonnull` count as comparisons, `goto_w` and `jsr_w` as control and
+/// `multianewarray` as a reference instruction, next to their narrow forms.
+pub fn opcode_category(opcode: u8) -> OpcodeCategory {
+  use opcodes_implied::*;
+  match opcode {
+    NOP..=LDC2_W => OpcodeCategory::Constant,
+    ILOAD..=SALOAD => OpcodeCategory::Load,
+    ISTORE..=SASTORE => OpcodeCategory::Store,
+    POP..=SWAP => OpcodeCategory::Stack,
+    IADD..=IINC => OpcodeCategory::Arithmetic,
+    I2L..=I2S => OpcodeCategory::Conversion,
+    LCMP..=IF_ACMPNE | IFNULL | IFNONNULL => OpcodeCategory::Comparison,
+    GOTO..=RETURN | ATHROW | GOTO_W | JSR_W => OpcodeCategory::Control,
+    INVOKEVIRTUAL..=INVOKEDYNAMIC => OpcodeCategory::Invoke,
+    GETSTATIC..=MONITOREXIT | MULTIANEWARRAY => OpcodeCategory::Reference,
+    WIDE => OpcodeCategory::Extended,
+    BREAKPOINT | IMPDEP1 | IMPDEP2 => OpcodeCategory::Reserved,
+    _ => OpcodeCategory::Unknown,
+  }
+}
+
+impl Display for OpcodeCategory {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      OpcodeCategory::Constant => "constant",
+      OpcodeCategory::Load => "load",
+      OpcodeCategory::Store => "store",
+      OpcodeCategory::Stack => "stack",
+      OpcodeCategory::Arithmetic => "arithmetic",
+      OpcodeCategory::Conversion => "conversion",
+      OpcodeCategory::Comparison => "comparison",
+      OpcodeCategory::Control => "control",
+      OpcodeCategory::Reference => "reference",
+      OpcodeCategory::Invoke => "invoke",
+      OpcodeCategory::Extended => "extended",
+      OpcodeCategory::Reserved => "reserved",
+      OpcodeCategory::Unknown => "unknown",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// A single instruction of a `Code` attribute with its raw operand bytes.
+///
+/// Switch operands keep their alignment padding; `wide` keeps the modified
+/// opcode as the first operand byte.
 #[derive(Debug, Clone)]
 pub struct CodeInfo {
   code: u8,
-  index_byte1: Option<u8>,
-  index_byte2: Option<u8>,
+  operands: Vec<u8>,
 }
 
-impl Parsable for CodeInfo {
-  fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
-    let (bytes, code) = be_u8(bytes)?;
-    let (bytes, (code, index_byte1, index_byte2)) =
-      match opcodes_implied::CODE_OP_CNT_MAP.get(&code) {
-        Some(&0) => Ok((bytes, (code, None, None))),
-        Some(&1) => {
-          let (bytes, b1) = be_u8(bytes)?;
-          Ok((bytes, (code, Some(b1), None)))
-        }
-        Some(&2) => {
-          let (bytes, b1) = be_u8(bytes)?;
-          let (bytes, b2) = be_u8(bytes)?;
-          Ok((bytes, (code, Some(b1), Some(b2))))
-        }
-        None => {
-          println!("unknown code: {:2x}", code);
-          Ok((bytes, (code, None, None)))
+impl CodeInfo {
+  /// `pc` is the offset of the opcode in the method's code, needed to skip
+  /// the padding of `tableswitch` and `lookupswitch`.
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    pc: usize,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (operand_bytes, code) = be_u8(bytes)?;
+    let operand_len = match code {
+      TABLESWITCH => {
+        let padding = (4 - (pc + 1) % 4) % 4;
+        let (_, low) = be_i32(&operand_bytes[(padding + 4).min(operand_bytes.len())..])?;
+        let (_, high) = be_i32(&operand_bytes[(padding + 8).min(operand_bytes.len())..])?;
+        let targets = (high as i64 - low as i64 + 1).max(0) as usize;
+        padding + 12 + targets * 4
+      }
+      LOOKUPSWITCH => {
+        let padding = (4 - (pc + 1) % 4) % 4;
+        let (_, npairs) = be_i32(&operand_bytes[(padding + 4).min(operand_bytes.len())..])?;
+        padding + 8 + npairs.max(0) as usize * 8
+      }
+      WIDE => {
+        let (_, modified) = be_u8(operand_bytes)?;
+        if modified == IINC {
+          5
+        } else {
+          3
         }
-        _ => unreachable!(),
-      }?;
+      }
+      _ => opcodes_implied::CODE_OP_CNT_MAP
+        .get(&code)
+        .map_or(0, |&cnt| cnt as usize),
+    };
+    let (bytes, operands) = take(operand_len)(operand_bytes)?;
     Ok((
       bytes,
       Self {
         code,
-        index_byte1,
-        index_byte2,
+        operands: operands.to_vec(),
       },
     ))
   }
+
+  pub fn code(&self) -> u8 {
+    self.code
+  }
+
+  pub fn operands(&self) -> &[u8] {
+    &self.operands
+  }
+
+  /// `None` for opcodes missing from `CODE_NAME_MAP`.
+  pub fn mnemonic(&self) -> Option<&'static str> {
+    opcodes_implied::CODE_NAME_MAP.get(&self.code).copied()
+  }
+
+  /// The category of the opcode; `Extended` for `wide`.
+  pub fn category(&self) -> OpcodeCategory {
+    opcode_category(self.code)
+  }
+
+  /// Encoded size in bytes, opcode included.
+  pub fn encoded_len(&self) -> usize {
+    1 + self.operands.len()
+  }
+}
+
+/// Decodes instructions from a method's code bytes one at a time.
+///
+/// Yields `(pc, CodeInfo)` and stops at the end of the code or at the first
+/// instruction that can't be decoded; `remaining` is then non-empty.
+pub struct InstructionIterator<'a> {
+  code: &'a [u8],
+  remaining: &'a [u8],
+}
+
+impl<'a> InstructionIterator<'a> {
+  pub fn new(code: &'a [u8]) -> Self {
+    Self {
+      code,
+      remaining: code,
+    }
+  }
+
+  /// Bytes not decoded yet.
+  pub fn remaining(&self) -> &'a [u8] {
+    self.remaining
+  }
+}
+
+impl<'a> Iterator for InstructionIterator<'a> {
+  type Item = (u32, CodeInfo);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.remaining.is_empty() {
+      return None;
+    }
+    let pc = self.code.len() - self.remaining.len();
+    let (remaining, code_info) =
+      CodeInfo::parse::<nom::error::Error<_>>(self.remaining, pc).ok()?;
+    self.remaining = remaining;
+    Some((pc as u32, code_info))
+  }
 }
 
 impl Display for CodeInfo {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let code_name = opcodes_implied::CODE_NAME_MAP.get(&self.code).unwrap();
-    if let Some(b1) = self.index_byte1 {
-      if let Some(b2) = self.index_byte2 {
-        write!(f, "{}<{} {}>", code_name, b1, b2)
-      } else {
-        write!(f, "{}<{}>", code_name, b1)
+    match self.mnemonic() {
+      Some(code_name) => write!(f, "{}", code_name)?,
+      None => write!(f, "unknown_{:02x}", self.code)?,
+    }
+    if self.code == opcodes_implied::NEWARRAY {
+      if let Some(name) = self.operands.first().copied().and_then(array_type_name) {
+        return write!(f, " {}", name);
       }
-    } else {
-      write!(f, "{}", code_name)
     }
+    if !self.operands.is_empty() {
+      let operands: Vec<_> = self.operands.iter().map(|b| b.to_string()).collect();
+      write!(f, "<{}>", operands.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{opcode_category, opcodes_implied::*, InstructionIterator, OpcodeCategory};
+
+  #[test]
+  fn test_instruction_iterator() {
+    #[rustfmt::skip]
+    let code = [
+      ILOAD_0,                                // 0
+      LOOKUPSWITCH, 0, 0,                     // 1: 2 bytes padding
+      0, 0, 0, 19,                            //    default -> 20
+      0, 0, 0, 1,                             //    npairs
+      0, 0, 0, 1, 0, 0, 0, 19,                //    1 -> 20
+      WIDE, ILOAD, 0x01, 0x00,                // 20: wide iload 256
+      INVOKEVIRTUAL, 0, 1,                    // 24
+      RETURN,                                 // 27
+    ];
+    let pcs: Vec<_> = InstructionIterator::new(&code)
+      .map(|(pc, code_info)| (pc, code_info.code()))
+      .collect();
+    assert_eq!(
+      pcs,
+      vec![
+        (0, ILOAD_0),
+        (1, LOOKUPSWITCH),
+        (20, WIDE),
+        (24, INVOKEVIRTUAL),
+        (27, RETURN),
+      ]
+    );
+
+    // Callers can stop at the first match without decoding the rest.
+    let mut iterator = InstructionIterator::new(&code);
+    let invoke = iterator.find(|(_, code_info)| code_info.code() == INVOKEVIRTUAL);
+    assert_eq!(invoke.map(|(pc, _)| pc), Some(24));
+    assert_eq!(iterator.remaining(), &[RETURN]);
+  }
+
+  #[test]
+  fn test_opcode_category() {
+    let categories = [
+      (ICONST_M1, OpcodeCategory::Constant),
+      (LDC2_W, OpcodeCategory::Constant),
+      (ALOAD_0, OpcodeCategory::Load),
+      (SALOAD, OpcodeCategory::Load),
+      (ASTORE, OpcodeCategory::Store),
+      (DUP2_X1, OpcodeCategory::Stack),
+      (LXOR, OpcodeCategory::Arithmetic),
+      (IINC, OpcodeCategory::Arithmetic),
+      (I2S, OpcodeCategory::Conversion),
+      (DCMPG, OpcodeCategory::Comparison),
+      (IFNONNULL, OpcodeCategory::Comparison),
+      (LOOKUPSWITCH, OpcodeCategory::Control),
+      (RETURN, OpcodeCategory::Control),
+      (ATHROW, OpcodeCategory::Control),
+      (GOTO_W, OpcodeCategory::Control),
+      (GETFIELD, OpcodeCategory::Reference),
+      (MULTIANEWARRAY, OpcodeCategory::Reference),
+      (INVOKEDYNAMIC, OpcodeCategory::Invoke),
+      (WIDE, OpcodeCategory::Extended),
+      (IMPDEP2, OpcodeCategory::Reserved),
+      (0xcb, OpcodeCategory::Unknown),
+    ];
+    for (opcode, category) in categories {
+      assert_eq!(opcode_category(opcode), category, "opcode 0x{:02x}", opcode);
+    }
+    assert_eq!(OpcodeCategory::Invoke.to_string(), "invoke");
+  }
+
+  #[test]
+  fn test_instruction_iterator_truncated() {
+    let code = [ALOAD_0, GETFIELD, 0];
+    let mut iterator = InstructionIterator::new(&code);
+    assert_eq!(iterator.next().map(|(pc, _)| pc), Some(0));
+    assert!(iterator.next().is_none());
+    assert_eq!(iterator.remaining(), &[GETFIELD, 0]);
   }
 }
@@ -1,13 +1,41 @@
-use std::fmt::Display;
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  fmt::Display,
+  sync::Arc,
+};
 
 use crate::{
-  attribute::{parse_attributes, AttributeInfo, SOURCE_FILE_ATTRIBUTE_NAME},
-  constant_pool::ConstantPoolInfo,
-  filed::FieldInfo,
+  attribute::{
+    annotation::{
+      Annotation, ElementType, RetentionPolicy, DOCUMENTED_DESCRIPTOR, RETENTION_DESCRIPTOR,
+      TARGET_DESCRIPTOR,
+    },
+    parse_attributes, AttributeInfo, PERMITTED_SUBCLASSES_ATTRIBUTE_NAME,
+    SOURCE_FILE_ATTRIBUTE_NAME,
+  },
+  constant_pool::{ConstantPoolInfo, ConstantType},
+  context::ResolveContext,
+  field::FieldInfo,
+  instruction::Instruction,
   method::MethodInfo,
+  ParseOptions,
+};
+use base::{
+  access_flag::{AccessFlag, AccessFlags},
+  coverage::OpcodeCoverage,
+  descriptor::{parse_method_descriptor, referenced_class},
+  error::{Error, ErrorKind, Warning},
+  parse_limits::ParseLimits,
+  Parsable, RenderSource,
 };
-use base::{access_flag::AccessFlags, error::Error, Parsable, RenderSource};
-use nom::{error::ParseError, multi::count, number::complete::*, sequence::tuple, IResult};
+use nom::{
+  bytes::complete::take,
+  error::ParseError,
+  number::complete::*,
+  sequence::{preceded, tuple},
+  IResult,
+};
+use sha2::{Digest, Sha256};
 
 /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.10
 pub struct ClassFile {
@@ -15,7 +43,7 @@ pub struct ClassFile {
   minor_version: u16,
   major_version: u16,
   constant_pool_count: u16,
-  constant_pool: Vec<ConstantPoolInfo>,
+  context: Arc<ResolveContext>,
   access_flags: AccessFlags,
   this_class: u16,
   super_class: u16,
@@ -29,15 +57,17 @@ impl ClassFile {
   fn parse_constant_pool<'a, E: ParseError<&'a [u8]>>(
     bytes: &'a [u8],
     pool_count: u16,
+    limits: &ParseLimits,
   ) -> IResult<&'a [u8], Vec<ConstantPoolInfo>, E> {
-    let mut pool_count = pool_count - 1;
+    let mut pool_count = pool_count.saturating_sub(1);
+    limits.check_items(bytes, pool_count as usize)?;
     let mut m_bytes = bytes;
     let mut constant_pool = Vec::with_capacity(pool_count as usize);
     while pool_count > 0 {
       let (bytes, constant_pool_info) = ConstantPoolInfo::parse(m_bytes)?;
       m_bytes = bytes;
       if constant_pool_info.is_double_size() {
-        pool_count -= 2;
+        pool_count = pool_count.saturating_sub(2);
         constant_pool.push(constant_pool_info);
         constant_pool.push(ConstantPoolInfo::new_empty());
       } else {
@@ -50,37 +80,383 @@ impl ClassFile {
 
   fn parse_fields<'a, E: ParseError<&'a [u8]>>(
     bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
   ) -> IResult<&'a [u8], Vec<FieldInfo>, E> {
     let (bytes, fields) = be_u16(bytes)?;
-    count(FieldInfo::parse, fields as usize)(bytes)
+    let limits = context.options().limits;
+    limits.count(|bytes| FieldInfo::parse(bytes, context), fields as usize)(bytes)
   }
 
   fn parse_methods<'a, E: ParseError<&'a [u8]>>(
     bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
   ) -> IResult<&'a [u8], Vec<MethodInfo>, E> {
     let (bytes, methods) = be_u16(bytes)?;
-    count(MethodInfo::parse, methods as usize)(bytes)
+    let limits = context.options().limits;
+    limits.count(|bytes| MethodInfo::parse(bytes, context), methods as usize)(bytes)
   }
 
+  /// Fails with `ErrorKind::BadMagic` or `ErrorKind::TruncatedHeader` on
+  /// input that can't be a class file, e.g. an empty buffer, and like
+  /// `check_references` on a bad constant pool reference.
   pub fn parse_from_u8<'a>(bytes: &'a [u8]) -> Result<Self, Error> {
+    Self::parse_with_remainder(bytes).map(|(class_file, _)| class_file)
+  }
+
+  /// Parses without `check_references`, for the verifier, which reports bad
+  /// references as issues instead of failing on the first one.
+  pub(crate) fn parse_unchecked(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+    crate::check_header(bytes)?;
     Self::parse::<nom::error::Error<_>>(bytes)
-      .map(|(_, class)| class)
+      .map(|(remainder, class_file)| (class_file, remainder))
       .map_err(|e| Error::from(e))
   }
 
-  fn source_file_name(&self) -> String {
-    let source_file = self
+  /// SHA-256 of the whole class file `bytes`, for telling classes apart
+  /// across artifacts. Byte-identical files hash the same; anything else,
+  /// even a reordered constant pool, doesn't.
+  pub fn content_hash(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+  }
+
+  /// The context every item of this class resolves its constant pool indices through.
+  pub fn context(&self) -> &Arc<ResolveContext> {
+    &self.context
+  }
+
+  /// The name of the source file this class was compiled from, as recorded
+  /// by its `SourceFile` attribute.
+  pub fn source_file(&self) -> Option<&str> {
+    self
       .attributes
       .iter()
       .filter(|attr| attr.type_filter(SOURCE_FILE_ATTRIBUTE_NAME))
-      .map(|attr| attr.get_sourcefile())
-      .next();
-    if let Some(source_file) = source_file {
-      if let Some(file_name) = source_file {
-        return file_name.to_string();
+      .find_map(|attr| attr.get_sourcefile())
+  }
+
+  /// Internal names of the subclasses a sealed class or interface permits,
+  /// from its `PermittedSubclasses` attribute; empty if it isn't sealed.
+  pub fn permitted_subclasses(&self) -> Vec<&str> {
+    self
+      .attributes
+      .iter()
+      .filter(|attr| attr.type_filter(PERMITTED_SUBCLASSES_ATTRIBUTE_NAME))
+      .find_map(|attr| attr.get_permitted_subclasses())
+      .map(|permitted_subclasses| permitted_subclasses.class_names())
+      .unwrap_or_default()
+  }
+
+  /// Internal name of the class, `a/b/C`, resolved through its `Class`
+  /// constant.
+  pub fn this_class_name(&self) -> &str {
+    self.context.get_class_name(self.this_class)
+  }
+
+  /// Fails with `ErrorKind::InvalidConstantIndex` if a mandatory reference of
+  /// the class, its members or their attributes is 0 or names the wrong kind
+  /// of constant, so the accessors that assume them valid can't panic.
+  /// `super_class` may be 0.
+  pub(crate) fn check_references(&self) -> Result<(), Error> {
+    self.context.require_class_name(self.this_class)?;
+    self.context.optional_class_name(self.super_class)?;
+    for interface in &self.interfaces {
+      self.context.require_class_name(*interface)?;
+    }
+    for field in &self.fields {
+      field.check_references()?;
+    }
+    for method in &self.methods {
+      method.check_references()?;
+    }
+    let member_attributes = self
+      .fields
+      .iter()
+      .flat_map(|field| field.attributes())
+      .chain(self.methods.iter().flat_map(|method| &method.attributes));
+    for attribute in self.attributes.iter().chain(member_attributes) {
+      attribute.check_references()?;
+    }
+    Ok(())
+  }
+
+  /// Internal name of the superclass; `None` for `java/lang/Object` and
+  /// `module-info`, whose `super_class` is 0.
+  pub fn super_class_name(&self) -> Option<&str> {
+    self.context.find_class_name(self.super_class)
+  }
+
+  /// The class file format version: 44 plus the Java release since 1.2,
+  /// e.g. 52 for Java 8 and 61 for Java 17.
+  pub fn major_version(&self) -> u16 {
+    self.major_version
+  }
+
+  /// 0, or 65535 for a class using preview features of its release.
+  pub fn minor_version(&self) -> u16 {
+    self.minor_version
+  }
+
+  pub fn access_flags(&self) -> &AccessFlags {
+    &self.access_flags
+  }
+
+  /// Interfaces include annotation types.
+  pub fn is_interface(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Interface)
+  }
+
+  pub fn is_enum(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Enum)
+  }
+
+  /// Whether this is an annotation type, an `@interface`.
+  pub fn is_annotation(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Annotation)
+  }
+
+  pub fn magic(&self) -> u32 {
+    self.magic
+  }
+
+  pub fn attributes(&self) -> &[AttributeInfo] {
+    &self.attributes
+  }
+
+  /// `this_class`, `super_class` and the interfaces as the raw constant pool
+  /// indices, for checks that must not assume them valid.
+  pub(crate) fn class_indices(&self) -> (u16, u16, &[u16]) {
+    (self.this_class, self.super_class, &self.interfaces)
+  }
+
+  pub fn fields(&self) -> &[FieldInfo] {
+    &self.fields
+  }
+
+  pub fn methods(&self) -> &[MethodInfo] {
+    &self.methods
+  }
+
+  /// The method named `query`, or with overloads its name and descriptor,
+  /// `compute(I)I`.
+  pub fn find_method(&self, query: &str) -> Result<&MethodInfo, Error> {
+    let full_name = |method: &MethodInfo| format!("{}{}", method.name(), method.descriptor());
+    let matches: Vec<&MethodInfo> = self
+      .methods
+      .iter()
+      .filter(|method| method.name() == query || full_name(method) == query)
+      .collect();
+    match matches[..] {
+      [] => Err(ErrorKind::MethodNotFound {
+        query: query.to_string(),
+      })?,
+      [method] => Ok(method),
+      _ => Err(ErrorKind::AmbiguousMethod {
+        query: query.to_string(),
+        candidates: matches.into_iter().map(full_name).collect(),
+      })?,
+    }
+  }
+
+  /// Class annotations from both `RuntimeVisibleAnnotations` and
+  /// `RuntimeInvisibleAnnotations`.
+  pub fn annotations(&self) -> Vec<&Annotation> {
+    self
+      .attributes
+      .iter()
+      .filter_map(AttributeInfo::get_annotations)
+      .flat_map(|annotations| annotations.annotations())
+      .collect()
+  }
+
+  fn find_annotation(&self, descriptor: &str) -> Option<&Annotation> {
+    self
+      .annotations()
+      .into_iter()
+      .find(|annotation| annotation.type_descriptor() == Some(descriptor))
+  }
+
+  /// The policy of an annotation type's `@Retention`. Without one, the
+  /// annotation is retained as `CLASS`.
+  pub fn retention(&self) -> Option<RetentionPolicy> {
+    let retention = self.find_annotation(RETENTION_DESCRIPTOR)?;
+    RetentionPolicy::from_name(retention.enum_constants("value").first()?)
+  }
+
+  /// The element types of an annotation type's `@Target`, skipping names
+  /// this parser doesn't know.
+  pub fn targets(&self) -> Option<Vec<ElementType>> {
+    let target = self.find_annotation(TARGET_DESCRIPTOR)?;
+    Some(
+      target
+        .enum_constants("value")
+        .into_iter()
+        .filter_map(ElementType::from_name)
+        .collect(),
+    )
+  }
+
+  pub fn is_documented(&self) -> bool {
+    self.find_annotation(DOCUMENTED_DESCRIPTOR).is_some()
+  }
+
+  /// The instructions of every method in one pass, in method order, each
+  /// with the method it belongs to and its pc in that method's code.
+  pub fn instructions(&self) -> impl Iterator<Item = (&MethodInfo, u32, Instruction)> {
+    self.methods.iter().flat_map(|method| {
+      method
+        .disassemble()
+        .into_iter()
+        .map(move |instruction| (method, instruction.pc, instruction))
+    })
+  }
+
+  /// Opcodes of every method, tallied while their code was decoded.
+  pub fn opcode_coverage(&self) -> OpcodeCoverage {
+    let mut coverage = OpcodeCoverage::default();
+    for method in &self.methods {
+      coverage.merge(&method.opcode_coverage());
+    }
+    coverage
+  }
+
+  /// How many times each mnemonic occurs across the code of every method.
+  pub fn opcode_histogram(&self) -> HashMap<&'static str, usize> {
+    self.opcode_coverage().histogram()
+  }
+
+  /// Tallies for a quick inventory, in output order: the class itself,
+  /// members, `String` and `Class` constants, constant pool entries (the
+  /// second slot of a long or double included), then attributes of the
+  /// class, its fields and methods and their `Code` in total and by name.
+  pub fn counts(&self) -> Vec<(String, usize)> {
+    let constant_pool = self.context.constant_pool();
+    let tally = |is_kind: fn(&ConstantType) -> bool| {
+      constant_pool
+        .iter()
+        .filter(|constant| is_kind(constant.info()))
+        .count()
+    };
+    let mut attributes: Vec<&AttributeInfo> = self.attributes.iter().collect();
+    for field in &self.fields {
+      attributes.extend(field.attributes());
+    }
+    for method in &self.methods {
+      attributes.extend(&method.attributes);
+      if let Some(code) = method.code() {
+        attributes.extend(code.attributes());
+      }
+    }
+    let mut by_name: BTreeMap<&str, usize> = BTreeMap::new();
+    for attribute in &attributes {
+      *by_name.entry(attribute.name()).or_default() += 1;
+    }
+
+    let mut counts = vec![
+      ("classes".to_string(), 1),
+      ("methods".to_string(), self.methods.len()),
+      ("fields".to_string(), self.fields.len()),
+      (
+        "strings".to_string(),
+        tally(|constant| matches!(constant, ConstantType::String(_))),
+      ),
+      (
+        "types".to_string(),
+        tally(|constant| matches!(constant, ConstantType::Class(_))),
+      ),
+      ("constant_pool".to_string(), constant_pool.len()),
+      ("attributes".to_string(), attributes.len()),
+    ];
+    counts.extend(
+      by_name
+        .into_iter()
+        .map(|(name, count)| (format!("attributes.{}", name), count)),
+    );
+    counts
+  }
+
+  /// Internal names of every class this class refers to, sorted: `Class`
+  /// constants, and the types in its own field and method descriptors and in
+  /// the descriptors of the members and method types it references. Array
+  /// types count as their element class; primitives and the class itself are
+  /// left out.
+  pub fn referenced_types(&self) -> Vec<String> {
+    let mut descriptors: Vec<&str> = vec![];
+    let mut types = BTreeSet::new();
+    for constant in self.context.constant_pool() {
+      match constant.info() {
+        ConstantType::Class(name_index) => {
+          let Some(name) = self.context.find_str_const(*name_index) else {
+            continue;
+          };
+          if name.starts_with('[') {
+            descriptors.push(name);
+          } else {
+            types.insert(name);
+          }
+        }
+        ConstantType::NameAndType(_, descriptor_index)
+        | ConstantType::MethodType(descriptor_index) => {
+          descriptors.extend(self.context.find_str_const(*descriptor_index));
+        }
+        _ => {}
+      }
+    }
+    descriptors.extend(self.fields.iter().map(FieldInfo::descriptor));
+    descriptors.extend(self.methods.iter().map(MethodInfo::descriptor));
+
+    for descriptor in descriptors {
+      match parse_method_descriptor(descriptor) {
+        Some((parameters, return_type)) => types.extend(
+          parameters
+            .into_iter()
+            .chain([return_type])
+            .filter_map(referenced_class),
+        ),
+        None => types.extend(referenced_class(descriptor)),
       }
     }
-    return "Unknown".to_string();
+    types.remove(self.this_class_name());
+    types.into_iter().map(str::to_string).collect()
+  }
+
+  /// Inconsistencies that don't prevent parsing, see `MethodInfo::validate`.
+  pub fn validate(&self) -> Vec<Warning> {
+    self.methods.iter().flat_map(MethodInfo::validate).collect()
+  }
+
+  /// The constants as `render_constant_pool_resolved` shows them, without
+  /// their indices and sorted by tag, then text. Pools holding the same
+  /// constants in a different order render the same, so builds can be
+  /// compared without compiler-dependent noise.
+  pub fn constant_pool_sorted(&self) -> Vec<String> {
+    let mut constants: Vec<(u8, String)> = (1..=self.context.constant_pool().len() as u16)
+      .filter_map(|index| {
+        let constant = self.context.find_constant(index)?;
+        if matches!(constant.info(), ConstantType::Empty) {
+          return None;
+        }
+        Some((constant.tag(), self.context.render_constant(index)))
+      })
+      .collect();
+    constants.sort();
+    constants
+      .into_iter()
+      .map(|(_, constant)| constant)
+      .collect()
+  }
+
+  /// Like `render_constant_pool`, but with class, member and string
+  /// references followed to the names they point at.
+  pub fn render_constant_pool_resolved(&self) -> Vec<String> {
+    (1..=self.context.constant_pool().len() as u16)
+      .map(|index| format!("#{}: {}", index, self.context.render_constant(index)))
+      .collect()
+  }
+
+  /// Like `render_fields`, but each field as declared in source, see
+  /// `FieldInfo::declaration`.
+  pub fn render_fields_verbose(&self) -> Vec<String> {
+    self.fields.iter().map(FieldInfo::declaration).collect()
   }
 
   pub fn render_methods_verbose(&self) -> Vec<&MethodInfo> {
@@ -90,6 +466,43 @@ impl ClassFile {
 
 impl Parsable for ClassFile {
   fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+    Self::parse_with_options(bytes, ParseOptions::default())
+  }
+
+  fn parse_from_u8<'a>(bytes: &'a [u8]) -> Result<Self, Error> {
+    ClassFile::parse_from_u8(bytes)
+  }
+
+  /// Checks the references like `parse_from_u8`, so no accessor of the
+  /// returned class can panic on a bad index.
+  fn parse_with_remainder(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+    let (class_file, remainder) = Self::parse_unchecked(bytes)?;
+    class_file.check_references()?;
+    Ok((class_file, remainder))
+  }
+}
+
+impl ClassFile {
+  /// Parses only the header and constant pool of the class file in `bytes`,
+  /// for tools that need nothing else, e.g. to extract its strings. Like
+  /// `ResolveContext::constant_pool`, entry `i` has index `i + 1` and wide
+  /// constants are followed by an `Empty` placeholder.
+  pub fn parse_constant_pool_only(bytes: &[u8]) -> Result<Vec<ConstantPoolInfo>, Error> {
+    crate::check_header(bytes)?;
+    let (bytes, constant_pool_count) = preceded(take(8usize), be_u16)(bytes)
+      .map_err(|e: nom::Err<nom::error::Error<_>>| Error::from(e))?;
+    let (_, constant_pool) = Self::parse_constant_pool::<nom::error::Error<_>>(
+      bytes,
+      constant_pool_count,
+      &ParseLimits::DEFAULT,
+    )?;
+    Ok(constant_pool)
+  }
+
+  pub(crate) fn parse_with_options<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    options: ParseOptions,
+  ) -> IResult<&'a [u8], Self, E> {
     let (bytes, (magic, minor_version, major_version, constant_pool_count)) =
       tuple((be_u32, be_u16, be_u16, be_u16))(bytes)?;
     if magic != 0xCAFEBABE {
@@ -99,15 +512,16 @@ impl Parsable for ClassFile {
         nom::error::ErrorKind::Tag,
       )));
     }
-    let (bytes, constant_pool) = Self::parse_constant_pool(bytes, constant_pool_count)?;
-    unsafe { crate::CONSTANT_POOL_REF = constant_pool.clone() };
+    let limits = options.limits;
+    let (bytes, constant_pool) = Self::parse_constant_pool(bytes, constant_pool_count, &limits)?;
+    let context = Arc::new(ResolveContext::with_options(constant_pool, options));
     let (bytes, (access_flags, this_class, super_class, interfaces_count)) =
       tuple((be_u16, be_u16, be_u16, be_u16))(bytes)?;
-    let (bytes, interfaces) = nom::multi::count(be_u16, interfaces_count as usize)(bytes)?;
-    let (bytes, fields) = Self::parse_fields(bytes)?;
-    let (bytes, methods) = Self::parse_methods(bytes)?;
+    let (bytes, interfaces) = limits.count(be_u16, interfaces_count as usize)(bytes)?;
+    let (bytes, fields) = Self::parse_fields(bytes, &context)?;
+    let (bytes, methods) = Self::parse_methods(bytes, &context)?;
 
-    let (bytes, attributes) = parse_attributes(bytes)?;
+    let (bytes, attributes) = parse_attributes(bytes, &context)?;
     log::info!("pass");
 
     Ok((
@@ -117,7 +531,7 @@ impl Parsable for ClassFile {
         minor_version,
         major_version,
         constant_pool_count,
-        constant_pool,
+        context,
         access_flags: AccessFlags::new_class_flag(access_flags),
         this_class,
         super_class,
@@ -141,7 +555,7 @@ impl Display for ClassFile {
       self.access_flags,
       self.constant_pool_count
     )?;
-    for (_, info) in self.constant_pool.iter().enumerate() {
+    for (_, info) in self.context.constant_pool().iter().enumerate() {
       write!(f, "\t{}\n", info)?;
     }
     write!(
@@ -174,21 +588,37 @@ impl RenderSource for ClassFile {
     vec![
       format!("magic: 0x{:08x}", self.magic),
       format!("version: {}.{}", self.major_version, self.minor_version),
-      format!("source file: {}", self.source_file_name()),
+      format!("source file: {}", self.source_file().unwrap_or("Unknown")),
     ]
   }
 
   fn render_class_info(&self) -> Vec<String> {
-    vec![
+    let mut result = vec![
+      format!(
+        "{} {}",
+        self.access_flags.class_kind(),
+        self.this_class_name().replace('/', ".")
+      ),
       format!("this class: {}", self.this_class),
       format!("super class: {}", self.super_class),
       format!("access_flags: {}", self.access_flags),
-    ]
+    ];
+    if let Some(retention) = self.retention() {
+      result.push(format!("retention: {}", retention));
+    }
+    if let Some(targets) = self.targets() {
+      let targets = targets.iter().map(ElementType::name).collect::<Vec<_>>();
+      result.push(format!("targets: {}", targets.join(", ")));
+    }
+    if self.is_documented() {
+      result.push("documented".to_string());
+    }
+    result
   }
 
   fn render_constant_pool(&self) -> Vec<String> {
     let mut result = vec![];
-    for (i, info) in self.constant_pool.iter().enumerate() {
+    for (i, info) in self.context.constant_pool().iter().enumerate() {
       result.push(format!("#{}: {}", i + 1, info));
     }
     result
@@ -198,7 +628,7 @@ impl RenderSource for ClassFile {
     self
       .interfaces
       .iter()
-      .map(|interface| crate::get_str_const(*interface as usize - 1).to_string())
+      .map(|interface| self.context.get_class_name(*interface).to_string())
       .collect()
   }
 
@@ -214,7 +644,7 @@ impl RenderSource for ClassFile {
     self
       .methods
       .iter()
-      .map(|method| method.name().to_string())
+      .map(|method| method.signature())
       .collect::<Vec<String>>()
   }
 
@@ -0,0 +1,278 @@
+//! Helpers for assembling small class files in unit tests.
+#![allow(dead_code)]
+
+pub struct ClassBuilder {
+  minor_version: u16,
+  major_version: u16,
+  /// Encoded entries; `None` marks the unusable slot after a Long/Double.
+  constant_pool: Vec<Option<Vec<u8>>>,
+  access_flags: u16,
+  this_class: u16,
+  super_class: u16,
+  interfaces: Vec<u16>,
+  fields: Vec<Vec<u8>>,
+  methods: Vec<Vec<u8>>,
+  attributes: Vec<Vec<u8>>,
+}
+
+impl ClassBuilder {
+  pub fn new(this_class: &str, super_class: Option<&str>) -> Self {
+    let mut builder = Self {
+      minor_version: 0,
+      major_version: 52,
+      constant_pool: vec![],
+      access_flags: 0x0021,
+      this_class: 0,
+      super_class: 0,
+      interfaces: vec![],
+      fields: vec![],
+      methods: vec![],
+      attributes: vec![],
+    };
+    builder.this_class = builder.class(this_class);
+    if let Some(super_class) = super_class {
+      builder.super_class = builder.class(super_class);
+    }
+    builder
+  }
+
+  pub fn version(mut self, major_version: u16, minor_version: u16) -> Self {
+    self.major_version = major_version;
+    self.minor_version = minor_version;
+    self
+  }
+
+  pub fn access_flags(mut self, access_flags: u16) -> Self {
+    self.access_flags = access_flags;
+    self
+  }
+
+  /// Appends an encoded entry without de-duplication and returns its index.
+  pub fn raw_constant(&mut self, entry: Vec<u8>) -> u16 {
+    let wide = entry[0] == 5 || entry[0] == 6;
+    self.constant_pool.push(Some(entry));
+    let index = self.constant_pool.len() as u16;
+    if wide {
+      self.constant_pool.push(None);
+    }
+    index
+  }
+
+  fn constant(&mut self, entry: Vec<u8>) -> u16 {
+    if let Some(pos) = self
+      .constant_pool
+      .iter()
+      .position(|e| e.as_ref() == Some(&entry))
+    {
+      return pos as u16 + 1;
+    }
+    self.raw_constant(entry)
+  }
+
+  pub fn utf8(&mut self, value: &str) -> u16 {
+    let mut entry = vec![1];
+    entry.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    entry.extend_from_slice(value.as_bytes());
+    self.constant(entry)
+  }
+
+  pub fn integer(&mut self, value: i32) -> u16 {
+    let mut entry = vec![3];
+    entry.extend_from_slice(&value.to_be_bytes());
+    self.constant(entry)
+  }
+
+  pub fn float(&mut self, value: f32) -> u16 {
+    let mut entry = vec![4];
+    entry.extend_from_slice(&value.to_bits().to_be_bytes());
+    self.constant(entry)
+  }
+
+  pub fn long(&mut self, value: i64) -> u16 {
+    let mut entry = vec![5];
+    entry.extend_from_slice(&value.to_be_bytes());
+    self.constant(entry)
+  }
+
+  pub fn double(&mut self, value: f64) -> u16 {
+    let mut entry = vec![6];
+    entry.extend_from_slice(&value.to_bits().to_be_bytes());
+    self.constant(entry)
+  }
+
+  pub fn class(&mut self, name: &str) -> u16 {
+    let name_index = self.utf8(name);
+    self.constant(Self::entry_u16(7, &[name_index]))
+  }
+
+  pub fn string(&mut self, value: &str) -> u16 {
+    let string_index = self.utf8(value);
+    self.constant(Self::entry_u16(8, &[string_index]))
+  }
+
+  pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+    let name_index = self.utf8(name);
+    let descriptor_index = self.utf8(descriptor);
+    self.constant(Self::entry_u16(12, &[name_index, descriptor_index]))
+  }
+
+  pub fn field_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+    self.member_ref(9, class, name, descriptor)
+  }
+
+  pub fn method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+    self.member_ref(10, class, name, descriptor)
+  }
+
+  pub fn interface_method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+    self.member_ref(11, class, name, descriptor)
+  }
+
+  fn member_ref(&mut self, tag: u8, class: &str, name: &str, descriptor: &str) -> u16 {
+    let class_index = self.class(class);
+    let name_and_type_index = self.name_and_type(name, descriptor);
+    self.constant(Self::entry_u16(tag, &[class_index, name_and_type_index]))
+  }
+
+  pub fn method_handle(&mut self, reference_kind: u8, reference_index: u16) -> u16 {
+    let mut entry = vec![15, reference_kind];
+    entry.extend_from_slice(&reference_index.to_be_bytes());
+    self.constant(entry)
+  }
+
+  pub fn method_type(&mut self, descriptor: &str) -> u16 {
+    let descriptor_index = self.utf8(descriptor);
+    self.constant(Self::entry_u16(16, &[descriptor_index]))
+  }
+
+  pub fn invoke_dynamic(&mut self, bootstrap_method: u16, name: &str, descriptor: &str) -> u16 {
+    let name_and_type_index = self.name_and_type(name, descriptor);
+    self.constant(Self::entry_u16(
+      18,
+      &[bootstrap_method, name_and_type_index],
+    ))
+  }
+
+  pub fn dynamic(&mut self, bootstrap_method: u16, name: &str, descriptor: &str) -> u16 {
+    let name_and_type_index = self.name_and_type(name, descriptor);
+    self.constant(Self::entry_u16(
+      17,
+      &[bootstrap_method, name_and_type_index],
+    ))
+  }
+
+  fn entry_u16(tag: u8, values: &[u16]) -> Vec<u8> {
+    let mut entry = vec![tag];
+    for value in values {
+      entry.extend_from_slice(&value.to_be_bytes());
+    }
+    entry
+  }
+
+  pub fn interface(&mut self, name: &str) {
+    let index = self.class(name);
+    self.interfaces.push(index);
+  }
+
+  /// Encodes an attribute, interning its name.
+  pub fn attribute(&mut self, name: &str, info: &[u8]) -> Vec<u8> {
+    let mut attribute = self.utf8(name).to_be_bytes().to_vec();
+    attribute.extend_from_slice(&(info.len() as u32).to_be_bytes());
+    attribute.extend_from_slice(info);
+    attribute
+  }
+
+  /// Encodes a `Code` attribute; `exception_table` entries are
+  /// `(start_pc, end_pc, handler_pc, catch_type)`.
+  pub fn code_attribute(
+    &mut self,
+    max_stack: u16,
+    max_locals: u16,
+    code: &[u8],
+    exception_table: &[(u16, u16, u16, u16)],
+    attributes: Vec<Vec<u8>>,
+  ) -> Vec<u8> {
+    let mut info = vec![];
+    info.extend_from_slice(&max_stack.to_be_bytes());
+    info.extend_from_slice(&max_locals.to_be_bytes());
+    info.extend_from_slice(&(code.len() as u32).to_be_bytes());
+    info.extend_from_slice(code);
+    info.extend_from_slice(&(exception_table.len() as u16).to_be_bytes());
+    for (start_pc, end_pc, handler_pc, catch_type) in exception_table {
+      for value in [start_pc, end_pc, handler_pc, catch_type] {
+        info.extend_from_slice(&value.to_be_bytes());
+      }
+    }
+    Self::extend_attributes(&mut info, &attributes);
+    self.attribute("Code", &info)
+  }
+
+  pub fn field(
+    &mut self,
+    access_flags: u16,
+    name: &str,
+    descriptor: &str,
+    attributes: Vec<Vec<u8>>,
+  ) {
+    let member = self.member(access_flags, name, descriptor, &attributes);
+    self.fields.push(member);
+  }
+
+  pub fn method(
+    &mut self,
+    access_flags: u16,
+    name: &str,
+    descriptor: &str,
+    attributes: Vec<Vec<u8>>,
+  ) {
+    let member = self.member(access_flags, name, descriptor, &attributes);
+    self.methods.push(member);
+  }
+
+  fn member(
+    &mut self,
+    access_flags: u16,
+    name: &str,
+    descriptor: &str,
+    attributes: &[Vec<u8>],
+  ) -> Vec<u8> {
+    let mut member = access_flags.to_be_bytes().to_vec();
+    member.extend_from_slice(&self.utf8(name).to_be_bytes());
+    member.extend_from_slice(&self.utf8(descriptor).to_be_bytes());
+    Self::extend_attributes(&mut member, attributes);
+    member
+  }
+
+  pub fn class_attribute(&mut self, attribute: Vec<u8>) {
+    self.attributes.push(attribute);
+  }
+
+  fn extend_attributes(bytes: &mut Vec<u8>, attributes: &[Vec<u8>]) {
+    bytes.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+    for attribute in attributes {
+      bytes.extend_from_slice(attribute);
+    }
+  }
+
+  pub fn build(&self) -> Vec<u8> {
+    let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe];
+    bytes.extend_from_slice(&self.minor_version.to_be_bytes());
+    bytes.extend_from_slice(&self.major_version.to_be_bytes());
+    bytes.extend_from_slice(&(self.constant_pool.len() as u16 + 1).to_be_bytes());
+    for entry in self.constant_pool.iter().flatten() {
+      bytes.extend_from_slice(entry);
+    }
+    for value in [self.access_flags, self.this_class, self.super_class] {
+      bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    bytes.extend_from_slice(&(self.interfaces.len() as u16).to_be_bytes());
+    for interface in &self.interfaces {
+      bytes.extend_from_slice(&interface.to_be_bytes());
+    }
+    for members in [&self.fields, &self.methods] {
+      Self::extend_attributes(&mut bytes, members);
+    }
+    Self::extend_attributes(&mut bytes, &self.attributes);
+    bytes
+  }
+}
@@ -0,0 +1,509 @@
+use std::fmt::Display;
+
+use base::{
+  access_flag::{AccessFlag, AccessFlags},
+  error::Warning,
+};
+
+use crate::{
+  attribute::AttributeInfo, constant_pool::ConstantType, context::ResolveContext,
+  raw_class::ClassFile, CLASS_MAGIC,
+};
+
+/// JDK 1.0.2, the oldest class file version.
+const MIN_MAJOR_VERSION: u16 = 45;
+/// Java 25.
+const MAX_MAJOR_VERSION: u16 = 69;
+/// Java 12, from which a non-zero minor version must be `PREVIEW_MINOR_VERSION`.
+const PREVIEW_MAJOR_VERSION: u16 = 56;
+const PREVIEW_MINOR_VERSION: u16 = 0xffff;
+
+/// One failed check: the structure at fault, e.g. `constant_pool #3` or
+/// `method run()V`, and what is wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+  pub location: String,
+  pub message: String,
+}
+
+/// Structural checks of a class file beyond what parsing needs: version
+/// range, constant pool references, attribute lengths and access flags.
+///
+/// Meant for CI over generated bytecode, so it reports every issue rather
+/// than stopping at the first.
+#[derive(Default)]
+pub struct Verification {
+  issues: Vec<Issue>,
+}
+
+impl Verification {
+  pub fn new(class_file: &ClassFile) -> Self {
+    let mut verification = Self::default();
+    verification.check_header(class_file);
+    verification.check_constant_pool(class_file.context());
+    verification.check_class(class_file);
+    verification.check_fields(class_file);
+    verification.check_methods(class_file);
+    verification
+  }
+
+  /// Like `new`, also checking the locals and constants each method's code
+  /// uses, see `MethodInfo::validate_code`.
+  pub fn with_code_checks(class_file: &ClassFile) -> Self {
+    let mut verification = Self::new(class_file);
+    for (index, method) in class_file.methods().iter().enumerate() {
+      // Labeled like `check_methods`, which reports a bad name or descriptor.
+      let location = match method.check_references() {
+        Ok(()) => format!("method {}{}", method.name(), method.descriptor()),
+        Err(_) => format!("method #{}", index),
+      };
+      for warning in method.validate_code() {
+        if let Warning::InvalidCode { pc, problem, .. } = warning {
+          verification.push(format!("{} pc {}", location, pc), problem);
+        }
+      }
+    }
+    verification
+  }
+
+  /// Parses `bytes` and checks the result. Input that doesn't parse yields
+  /// a single issue with the parse error.
+  pub fn of_bytes(bytes: &[u8]) -> Self {
+    Self::of_bytes_with(bytes, Self::new)
+  }
+
+  /// Like `of_bytes`, with `with_code_checks`.
+  pub fn of_bytes_with_code_checks(bytes: &[u8]) -> Self {
+    Self::of_bytes_with(bytes, Self::with_code_checks)
+  }
+
+  fn of_bytes_with(bytes: &[u8], verify: fn(&ClassFile) -> Self) -> Self {
+    let parsed = ClassFile::parse_unchecked(bytes);
+    match parsed {
+      Ok((class_file, remainder)) => {
+        let mut verification = verify(&class_file);
+        if !remainder.is_empty() {
+          verification.push(
+            "file",
+            format!(
+              "{} trailing bytes after the end of the class",
+              remainder.len()
+            ),
+          );
+        }
+        verification
+      }
+      Err(error) => {
+        let mut verification = Self::default();
+        verification.push("file", error.to_string());
+        verification
+      }
+    }
+  }
+
+  pub fn passed(&self) -> bool {
+    self.issues.is_empty()
+  }
+
+  pub fn issues(&self) -> &[Issue] {
+    &self.issues
+  }
+
+  fn push(&mut self, location: impl Into<String>, message: impl Into<String>) {
+    self.issues.push(Issue {
+      location: location.into(),
+      message: message.into(),
+    });
+  }
+
+  fn check_header(&mut self, class_file: &ClassFile) {
+    if class_file.magic() != u32::from_be_bytes(CLASS_MAGIC) {
+      self.push(
+        "magic",
+        format!("0x{:08x} is not 0xcafebabe", class_file.magic()),
+      );
+    }
+    let (major, minor) = (class_file.major_version(), class_file.minor_version());
+    if !(MIN_MAJOR_VERSION..=MAX_MAJOR_VERSION).contains(&major) {
+      self.push(
+        "version",
+        format!(
+          "major version {} is outside {}..={}",
+          major, MIN_MAJOR_VERSION, MAX_MAJOR_VERSION
+        ),
+      );
+    } else if major >= PREVIEW_MAJOR_VERSION && minor != 0 && minor != PREVIEW_MINOR_VERSION {
+      self.push(
+        "version",
+        format!("minor version {} is neither 0 nor 65535 (preview)", minor),
+      );
+    }
+  }
+
+  /// Checks that every index held by a constant names a constant of the
+  /// kind the spec requires.
+  fn check_constant_pool(&mut self, context: &ResolveContext) {
+    let is_utf8 = |info: &ConstantType| matches!(info, ConstantType::Utf8(_));
+    let is_class = |info: &ConstantType| matches!(info, ConstantType::Class(_));
+    let is_name_and_type = |info: &ConstantType| matches!(info, ConstantType::NameAndType(..));
+    for (slot, constant) in context.constant_pool().iter().enumerate() {
+      let location = format!("constant_pool #{}", slot + 1);
+      let mut expect = |index: u16, kind: &str, matches: &dyn Fn(&ConstantType) -> bool| {
+        if !context
+          .find_constant(index)
+          .is_some_and(|constant| matches(constant.info()))
+        {
+          self.push(&location, format!("#{} is not a {} constant", index, kind));
+        }
+      };
+      match constant.info() {
+        ConstantType::Class(name) | ConstantType::String(name) | ConstantType::MethodType(name) => {
+          expect(*name, "Utf8", &is_utf8)
+        }
+        ConstantType::Fieldref(class, name_and_type)
+        | ConstantType::Methodref(class, name_and_type)
+        | ConstantType::InterfaceMethodref(class, name_and_type) => {
+          expect(*class, "Class", &is_class);
+          expect(*name_and_type, "NameAndType", &is_name_and_type);
+        }
+        ConstantType::NameAndType(name, descriptor) => {
+          expect(*name, "Utf8", &is_utf8);
+          expect(*descriptor, "Utf8", &is_utf8);
+        }
+        ConstantType::Dynamic(_, name_and_type) | ConstantType::InvokeDynamic(_, name_and_type) => {
+          expect(*name_and_type, "NameAndType", &is_name_and_type)
+        }
+        ConstantType::MethodHandle(reference_kind, reference) => match reference_kind {
+          1..=4 => expect(*reference, "Fieldref", &|info| {
+            matches!(info, ConstantType::Fieldref(..))
+          }),
+          5 | 8 => expect(*reference, "Methodref", &|info| {
+            matches!(info, ConstantType::Methodref(..))
+          }),
+          6 | 7 | 9 => expect(*reference, "Methodref or InterfaceMethodref", &|info| {
+            matches!(
+              info,
+              ConstantType::Methodref(..) | ConstantType::InterfaceMethodref(..)
+            )
+          }),
+          _ => self.push(
+            &location,
+            format!("reference kind {} is not 1..=9", reference_kind),
+          ),
+        },
+        _ => {}
+      }
+    }
+  }
+
+  fn check_class(&mut self, class_file: &ClassFile) {
+    let context = class_file.context();
+    let (this_class, super_class, interfaces) = class_file.class_indices();
+    if let Err(error) = context.require_class_name(this_class) {
+      self.push("this_class", error.to_string());
+    }
+    if let Err(error) = context.optional_class_name(super_class) {
+      self.push("super_class", error.to_string());
+    }
+    for (index, interface) in interfaces.iter().enumerate() {
+      if let Err(error) = context.require_class_name(*interface) {
+        self.push(format!("interfaces[{}]", index), error.to_string());
+      }
+    }
+
+    let flags = class_file.access_flags();
+    if flags.contains(AccessFlag::Interface) {
+      if !flags.is_abstract() {
+        self.push("class", "interface is not abstract");
+      }
+      self.check_exclusive(
+        "class",
+        flags,
+        AccessFlag::Interface,
+        &[AccessFlag::Final, AccessFlag::Super, AccessFlag::Enum],
+      );
+    } else if flags.contains(AccessFlag::Annotation) {
+      self.push("class", "annotation type is not an interface");
+    }
+    self.check_exclusive("class", flags, AccessFlag::Final, &[AccessFlag::Abstract]);
+    self.check_attributes("class", class_file.attributes());
+  }
+
+  fn check_fields(&mut self, class_file: &ClassFile) {
+    let is_interface = class_file.access_flags().contains(AccessFlag::Interface);
+    for (index, field) in class_file.fields().iter().enumerate() {
+      let references = field.check_references();
+      let location = match &references {
+        Ok(()) => format!("field {}:{}", field.name(), field.descriptor()),
+        Err(_) => format!("field #{}", index),
+      };
+      if let Err(error) = references {
+        self.push(&location, error.to_string());
+      }
+      let flags = field.access_flags();
+      self.check_visibility(&location, flags);
+      self.check_exclusive(&location, flags, AccessFlag::Final, &[AccessFlag::Volatile]);
+      let interface_field = [AccessFlag::Public, AccessFlag::Static, AccessFlag::Final];
+      if is_interface && !interface_field.iter().all(|flag| flags.contains(*flag)) {
+        self.push(&location, "interface field is not public static final");
+      }
+      self.check_attributes(&location, field.attributes());
+    }
+  }
+
+  fn check_methods(&mut self, class_file: &ClassFile) {
+    for (index, method) in class_file.methods().iter().enumerate() {
+      let references = method.check_references();
+      let location = match &references {
+        Ok(()) => format!("method {}{}", method.name(), method.descriptor()),
+        Err(_) => format!("method #{}", index),
+      };
+      let flags = method.access_flags();
+      self.check_visibility(&location, flags);
+      self.check_exclusive(
+        &location,
+        flags,
+        AccessFlag::Abstract,
+        &[
+          AccessFlag::Private,
+          AccessFlag::Static,
+          AccessFlag::Final,
+          AccessFlag::Synchronized,
+          AccessFlag::Native,
+          AccessFlag::Strict,
+        ],
+      );
+      match references {
+        // `validate` names the method, so only with valid references.
+        Ok(()) => {
+          for warning in method.validate() {
+            self.push(&location, warning.to_string());
+          }
+        }
+        Err(error) => self.push(&location, error.to_string()),
+      }
+      self.check_attributes(&location, &method.attributes);
+    }
+  }
+
+  /// At most one of public, private and protected.
+  fn check_visibility(&mut self, location: &str, flags: &AccessFlags) {
+    let visibility = [
+      AccessFlag::Public,
+      AccessFlag::Private,
+      AccessFlag::Protected,
+    ];
+    if visibility
+      .iter()
+      .filter(|flag| flags.contains(**flag))
+      .count()
+      > 1
+    {
+      self.push(
+        location,
+        format!(
+          "more than one of public, private and protected in {}",
+          flags
+        ),
+      );
+    }
+  }
+
+  /// `flag` together with any of `excluded`.
+  fn check_exclusive(
+    &mut self,
+    location: &str,
+    flags: &AccessFlags,
+    flag: AccessFlag,
+    excluded: &[AccessFlag],
+  ) {
+    if !flags.contains(flag) {
+      return;
+    }
+    for other in excluded.iter().filter(|other| flags.contains(**other)) {
+      self.push(location, format!("{} together with {}", flag, other));
+    }
+  }
+
+  /// `attribute_length` against what the contents of each attribute take,
+  /// and the constant pool indices they hold, including the attributes
+  /// nested in a `Code` attribute.
+  fn check_attributes(&mut self, location: &str, attributes: &[AttributeInfo]) {
+    for attribute in attributes {
+      let attribute_location = format!("{} attribute {}", location, attribute.name());
+      if attribute.malformed_bytes().is_some() {
+        self.push(
+          &attribute_location,
+          "contents could not be parsed".to_string(),
+        );
+      }
+      if let Err(error) = attribute.check_references() {
+        self.push(&attribute_location, error.to_string());
+      }
+      if attribute.unparsed_length() > 0 {
+        self.push(
+          &attribute_location,
+          format!(
+            "attribute_length is {} but the contents take {} bytes",
+            attribute.attribute_length(),
+            attribute.attribute_length() as usize - attribute.unparsed_length()
+          ),
+        );
+      }
+      if let Some(code) = attribute.get_code() {
+        self.check_attributes(&attribute_location, code.attributes());
+      }
+    }
+  }
+}
+
+impl Display for Issue {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.location, self.message)
+  }
+}
+
+impl Display for Verification {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.passed() {
+      return writeln!(f, "PASS");
+    }
+    writeln!(f, "FAIL: {} issue(s)", self.issues.len())?;
+    for issue in &self.issues {
+      writeln!(f, "  {}", issue)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Verification;
+  use crate::test_util::ClassBuilder;
+
+  #[test]
+  fn test_verification() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object")).access_flags(0x0421);
+    builder.field(0x0002, "count", "I", vec![]);
+    builder.method(0x0401, "run", "()V", vec![]);
+    let verification = Verification::of_bytes(&builder.build());
+    assert!(verification.passed());
+    assert_eq!(verification.to_string(), "PASS\n");
+
+    // public final super, for a newer JVM than there is.
+    let mut builder = ClassBuilder::new("a/B", Some("java/lang/Object"))
+      .version(70, 0)
+      .access_flags(0x0031);
+    let number = builder.integer(1);
+    let class_of_number = builder.raw_constant(vec![7, (number >> 8) as u8, number as u8]);
+    builder.field(0x0003, "both", "I", vec![]);
+    builder.method(0x0411, "run", "()V", vec![]);
+    // A SourceFile attribute one byte longer than its index.
+    let mut info = builder.utf8("B.java").to_be_bytes().to_vec();
+    info.push(0);
+    let source_file = builder.attribute("SourceFile", &info);
+    builder.class_attribute(source_file);
+    let verification = Verification::of_bytes(&builder.build());
+
+    assert!(!verification.passed());
+    let issues: Vec<String> = verification
+      .issues()
+      .iter()
+      .map(ToString::to_string)
+      .collect();
+    assert_eq!(
+      issues,
+      vec![
+        "version: major version 70 is outside 45..=69".to_string(),
+        format!(
+          "constant_pool #{}: #{} is not a Utf8 constant",
+          class_of_number, number
+        ),
+        "class attribute SourceFile: attribute_length is 3 but the contents take 2 bytes"
+          .to_string(),
+        "field both:I: more than one of public, private and protected in 0x0003 (ACC_PUBLIC,ACC_PRIVATE)"
+          .to_string(),
+        "method run()V: ACC_ABSTRACT together with ACC_FINAL".to_string(),
+      ]
+    );
+    assert!(verification
+      .to_string()
+      .starts_with("FAIL: 5 issue(s)\n  version: "));
+
+    let verification = Verification::of_bytes(b"not a class file");
+    assert_eq!(
+      verification.to_string(),
+      "FAIL: 1 issue(s)\n  file: bad magic 0x6e6f7420, expected 0xcafebabe\n"
+    );
+  }
+
+  #[test]
+  fn test_attribute_references() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    let name = builder.utf8("name");
+    // ConstantValue naming a Utf8, Signature and Exceptions past the pool.
+    let constant_value = builder.attribute("ConstantValue", &name.to_be_bytes());
+    builder.field(0x0019, "MAX", "I", vec![constant_value]);
+    let signature = builder.attribute("Signature", &[0, 200]);
+    let exceptions = builder.attribute("Exceptions", &[0, 1, 0, 0]);
+    builder.method(0x0401, "run", "()V", vec![signature, exceptions]);
+    // An annotation whose type_index is 0.
+    let annotations = builder.attribute("RuntimeVisibleAnnotations", &[0, 1, 0, 0, 0, 0]);
+    builder.class_attribute(annotations);
+    let bytes = builder.build();
+    assert!(crate::parse(&bytes).is_err());
+
+    let verification = Verification::of_bytes(&bytes);
+    let issues: Vec<String> = verification
+      .issues()
+      .iter()
+      .map(ToString::to_string)
+      .collect();
+    assert_eq!(
+      issues,
+      vec![
+        "class attribute RuntimeVisibleAnnotations: invalid constant pool index #0, expected a Utf8 constant"
+          .to_string(),
+        format!(
+          "field MAX:I attribute ConstantValue: invalid constant pool index #{}, expected a numeric or String constant",
+          name
+        ),
+        "method run()V attribute Signature: invalid constant pool index #200, expected a Utf8 constant"
+          .to_string(),
+        "method run()V attribute Exceptions: invalid constant pool index #0, expected a Class constant"
+          .to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_code_checks_with_invalid_name() {
+    let mut builder = ClassBuilder::new("a/A", Some("java/lang/Object"));
+    // iload_1 with max_locals 1.
+    let code = builder.code_attribute(1, 1, &[0x1b, 0x57, 0xb1], &[], vec![]);
+    builder.method(0x0009, "run", "()V", vec![code]);
+    let name = builder.utf8("run");
+    let mut bytes = builder.build();
+    let mut method = vec![0x00, 0x09];
+    method.extend_from_slice(&name.to_be_bytes());
+    let name_index = bytes
+      .windows(method.len())
+      .position(|window| window == method)
+      .unwrap()
+      + 2;
+    bytes[name_index..name_index + 2].copy_from_slice(&48390u16.to_be_bytes());
+
+    let issues: Vec<String> = Verification::of_bytes_with_code_checks(&bytes)
+      .issues()
+      .iter()
+      .map(ToString::to_string)
+      .collect();
+    assert_eq!(
+      issues,
+      vec![
+        "method #0: invalid constant pool index #48390, expected a Utf8 constant".to_string(),
+        "method #0 pc 0: iload_1 uses local 1 but max_locals is 1".to_string(),
+      ]
+    );
+  }
+}
@@ -1,8 +1,12 @@
 use std::cell::Cell;
 
-use base::RenderSource;
+use base::{
+  depth::{Depth, DepthDisplay},
+  RenderSource,
+};
 use class_parser::{
-  attribute::{AttributeInfo, CODE_ATTRIBUTE_NAME},
+  attribute::{code::CodeAttribute, AttributeInfo, CODE_ATTRIBUTE_NAME},
+  opcodes::{opcode_category, opcodes_implied::NAME_CODE_MAP, InstructionIterator, OpcodeCategory},
   raw_class::ClassFile,
 };
 use crossterm::event::{KeyCode, KeyEvent};
@@ -24,14 +28,17 @@ pub struct App<'a> {
   pub titles: Vec<&'a str>,
   pub index: usize,
 
-  pub class_file: &'a dyn RenderSource,
+  pub class_file: &'a ClassFile,
   list: SelectableList<'a, String>,
   constant_pool_state: Cell<ParagraphState>,
 }
 
 impl<'a> App<'a> {
-  pub fn new(class_file: &'a ClassFile) -> App<'a> {
-    let method_list: Vec<(&str, String)> = class_file
+  /// `max_depth` bounds how deeply the method details expand nested
+  /// attributes; `None` expands everything.
+  pub fn new(class_file: &'a ClassFile, max_depth: Option<usize>) -> App<'a> {
+    let depth = Depth::new(max_depth);
+    let method_list: Vec<(String, String)> = class_file
       .render_methods_verbose()
       .into_iter()
       .map(|method| {
@@ -41,9 +48,17 @@ impl<'a> App<'a> {
           .filter(|attr| attr.type_filter(CODE_ATTRIBUTE_NAME))
           .collect();
         if code.len() > 0 {
-          return (method.name(), format!("{} (code)", method.to_string()));
+          let disassembly = method.code().map(disassembly).unwrap_or_default();
+          return (
+            method.signature(),
+            format!(
+              "{} (code)\ndisassembly:{}",
+              method.display_depth(depth),
+              disassembly
+            ),
+          );
         }
-        return (method.name(), method.to_string());
+        (method.signature(), method.display_depth(depth).to_string())
       })
       .collect();
     App {
@@ -59,7 +74,7 @@ impl<'a> App<'a> {
       ],
       index: 0,
       class_file,
-      list: SelectableList::new(method_list, "method"),
+      list: SelectableList::new(method_list, "method").with_line_style(instruction_style),
       constant_pool_state: Cell::new(ParagraphState::default()),
     }
   }
@@ -133,10 +148,10 @@ impl<'a> App<'a> {
       0 => self.class_file.render_file_info(),
       1 => self.class_file.render_class_info(),
       2 => self.class_file.render_interfaces(),
-      3 => self.class_file.render_fields(),
+      3 => self.class_file.render_fields_verbose(),
       4 => self.class_file.render_methods(),
       5 => self.class_file.render_attributes(),
-      6 => self.class_file.render_constant_pool(),
+      6 => self.class_file.render_constant_pool_resolved(),
       _ => unreachable!(),
     };
     strings.into_iter().map(|s| Spans::from(s)).collect()
@@ -175,3 +190,43 @@ impl<'a> App<'a> {
 
   pub fn on_tick(&mut self) {}
 }
+
+/// One `pc: instruction` line per instruction, each starting with a newline;
+/// branches show their absolute target, `ifeq -> 42`.
+fn disassembly(code: &CodeAttribute) -> String {
+  InstructionIterator::new(code.raw_code())
+    .zip(code.disassemble())
+    .map(
+      |((pc, code_info), instruction)| match instruction.branch_target() {
+        Some(target) => format!("\n{}: {} -> {}", pc, instruction.mnemonic, target),
+        None => format!("\n{}: {}", pc, code_info),
+      },
+    )
+    .collect()
+}
+
+/// Colors a `disassembly` line by the category of its opcode.
+fn instruction_style(line: &str) -> Style {
+  let opcode = line.split_once(": ").and_then(|(pc, instruction)| {
+    pc.parse::<u32>().ok()?;
+    let mnemonic = instruction.split(['<', ' ']).next()?;
+    NAME_CODE_MAP.get(mnemonic).copied()
+  });
+  let Some(opcode) = opcode else {
+    return Style::default();
+  };
+  let color = match opcode_category(opcode) {
+    OpcodeCategory::Constant => Color::Magenta,
+    OpcodeCategory::Load => Color::Blue,
+    OpcodeCategory::Store => Color::LightBlue,
+    OpcodeCategory::Stack => Color::DarkGray,
+    OpcodeCategory::Arithmetic => Color::Green,
+    OpcodeCategory::Conversion => Color::LightGreen,
+    OpcodeCategory::Comparison => Color::LightRed,
+    OpcodeCategory::Control => Color::Red,
+    OpcodeCategory::Reference => Color::LightMagenta,
+    OpcodeCategory::Invoke => Color::Cyan,
+    OpcodeCategory::Extended | OpcodeCategory::Reserved | OpcodeCategory::Unknown => Color::Black,
+  };
+  Style::default().fg(color)
+}
@@ -19,11 +19,18 @@ mod reflow;
 mod stateful_paragraph;
 mod stateful_select_list;
 
-pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, io::Error> {
+/// Mouse capture is off unless `mouse_capture` is set, so the terminal's own
+/// text selection keeps working; pass the same value to `restore_terminal`.
+pub fn setup_terminal(
+  mouse_capture: bool,
+) -> Result<Terminal<CrosstermBackend<Stdout>>, io::Error> {
   // setup terminal
   enable_raw_mode()?;
   let mut stdout = io::stdout();
-  execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+  execute!(stdout, EnterAlternateScreen)?;
+  if mouse_capture {
+    execute!(stdout, EnableMouseCapture)?;
+  }
   let backend = CrosstermBackend::new(stdout);
   let terminal = Terminal::new(backend)?;
   Ok(terminal)
@@ -31,14 +38,14 @@ pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, io::Error>
 
 pub fn restore_terminal<B: Backend + std::io::Write>(
   mut terminal: Terminal<B>,
+  mouse_capture: bool,
 ) -> Result<(), io::Error> {
   // restore terminal
   disable_raw_mode()?;
-  execute!(
-    terminal.backend_mut(),
-    LeaveAlternateScreen,
-    DisableMouseCapture
-  )?;
+  if mouse_capture {
+    execute!(terminal.backend_mut(), DisableMouseCapture)?;
+  }
+  execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
   terminal.show_cursor()?;
   Ok(())
 }
@@ -77,18 +77,28 @@ impl<T> StatefulList<T> {
 /// Check the drawing logic for items on how to specify the highlighting style for selected items.
 #[derive(Clone)]
 pub struct SelectableList<'a, T: Display> {
-  pub items: StatefulList<(&'a str, T)>,
+  pub items: StatefulList<(String, T)>,
   title: &'a str,
+  /// Style of each line of the selected item's content.
+  line_style: fn(&str) -> Style,
 }
 
 impl<'a, T: Display> SelectableList<'a, T> {
-  pub fn new(items: Vec<(&'a str, T)>, title: &'a str) -> SelectableList<'a, T> {
+  pub fn new(items: Vec<(String, T)>, title: &'a str) -> SelectableList<'a, T> {
     SelectableList {
       items: StatefulList::with_items(items),
       title,
+      line_style: |_| Style::default(),
     }
   }
 
+  /// Styles the content lines with `line_style`; a line wrapped to fit the
+  /// pane is styled piece by piece.
+  pub fn with_line_style(mut self, line_style: fn(&str) -> Style) -> Self {
+    self.line_style = line_style;
+    self
+  }
+
   pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, r: Rect) {
     let chunks = Layout::default()
       .direction(Direction::Horizontal)
@@ -107,7 +117,10 @@ impl<'a, T: Display> SelectableList<'a, T> {
       .zip(0..)
       .map(|(e, idx)| {
         let idx_str = format!("# {}. ", idx);
-        let mut lines = vec![Spans::from(Span::raw(idx_str)), Spans::from(Span::raw(e.0))];
+        let mut lines = vec![
+          Spans::from(Span::raw(idx_str)),
+          Spans::from(Span::raw(e.0.as_str())),
+        ];
         if selected_idx == idx && self.items.is_toggled() {
           // TODO add more information for the selected item.
           lines.push(Spans::from(Span::raw(format!(" "))));
@@ -126,7 +139,7 @@ impl<'a, T: Display> SelectableList<'a, T> {
     let (item, content) = &self.items.items[selected_idx];
     let text: Text = content.to_string().into();
     let items: ListItem = {
-      let mut lines = vec![Spans::from(Span::styled(*item, style.clone()))];
+      let mut lines = vec![Spans::from(Span::styled(item.as_str(), style))];
       let mut styled = text.lines.iter().flat_map(|spans| {
         spans
           .0
@@ -148,7 +161,8 @@ impl<'a, T: Display> SelectableList<'a, T> {
             acc = format!("{}{}", acc, grapheme.symbol);
             acc
           });
-        lines.push(Spans::from(Span::raw(str)));
+        let style = (self.line_style)(&str);
+        lines.push(Spans::from(Span::styled(str, style)));
       }
       ListItem::new(lines).style(Style::default().fg(Color::Black).bg(Color::White))
     };
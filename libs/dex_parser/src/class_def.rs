@@ -1,6 +1,15 @@
-use std::fmt::Display;
+use std::{collections::BTreeSet, fmt::Display, ops::Range, sync::Arc};
 
-use base::{access_flag::AccessFlags, error::Error, Parsable};
+use base::{
+  access_flag::{AccessFlag, AccessFlags},
+  cfg::{ControlFlowGraph, Flow, FlowInfo, Handler},
+  descriptor::{descriptor_to_source, field_signature, method_signature, referenced_class},
+  error::{Error, Warning},
+  indent::{Indent, IndentDisplay},
+  literal::LiteralValue,
+  parse_limits::ParseLimits,
+  Parsable,
+};
 use nom::{
   error::ParseError,
   multi::count,
@@ -8,13 +17,20 @@ use nom::{
   sequence::tuple,
   IResult, Slice,
 };
+use sha2::{Digest, Sha256};
 
 use crate::{
-  get_field_id, get_method_id, get_type_id, get_type_id_ref,
-  leb128::parse_uleb128_nom,
-  raw_dex::{FieldIdItem, MethodIdItem, TypeIdItem, TypeList},
+  context::ResolveContext,
+  encoded_value::EncodedValue,
+  hiddenapi::HiddenApiFlag,
+  instruction::{IndexKind, Instruction, InstructionIterator},
+  leb128::{parse_sleb128_nom, parse_uleb128_nom},
+  opcodes::Format,
+  raw_dex::{FieldIdItem, MethodIdItem, ProtoIdItem, TypeIdItem, TypeList},
 };
 
+const OBJECT_DESCRIPTOR: &str = "Ljava/lang/Object;";
+
 pub struct ClassDefItem {
   class_idx: u32,
   class: TypeIdItem,
@@ -32,6 +48,8 @@ pub struct ClassDefItem {
 }
 
 pub struct ClassDataItem {
+  /// Encoded size in bytes.
+  byte_len: usize,
   static_fields: Vec<EncodedField>,
   instance_fields: Vec<EncodedField>,
   direct_methods: Vec<EncodedMethod>,
@@ -39,8 +57,12 @@ pub struct ClassDataItem {
 }
 
 impl ClassDataItem {
-  pub fn parse_from_u8<'a>(bytes: &'a [u8], origin_bytes: &'a [u8]) -> Result<Self, Error> {
-    Self::parse(bytes, origin_bytes)
+  pub fn parse_from_u8<'a>(
+    bytes: &'a [u8],
+    origin_bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> Result<Self, Error> {
+    Self::parse(bytes, origin_bytes, context)
       .map(|(_, class_data_item)| class_data_item)
       .map_err(|e| e.into())
   }
@@ -48,7 +70,9 @@ impl ClassDataItem {
   pub fn parse<'a, E: ParseError<&'a [u8]>>(
     bytes: &'a [u8],
     origin_bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
   ) -> IResult<&'a [u8], Self, E> {
+    let start = bytes;
     let (
       bytes,
       (static_fields_size, instance_fields_size, direct_methods_size, virtual_methods_size),
@@ -58,13 +82,22 @@ impl ClassDataItem {
       parse_uleb128_nom,
       parse_uleb128_nom,
     ))(bytes)?;
+    let limits = context.limits();
+    for size in [
+      static_fields_size,
+      instance_fields_size,
+      direct_methods_size,
+      virtual_methods_size,
+    ] {
+      limits.check_items(bytes, size as usize)?;
+    }
     let mut m_bytes = bytes;
     let mut cur_offset = 0;
     let mut static_fields = vec![];
     for _ in 0..static_fields_size {
       let (bytes, mut field) = EncodedField::parse(m_bytes)?;
       cur_offset = field.field_idx_diff + cur_offset;
-      field.field = get_field_id(cur_offset as usize);
+      field.field = context.get_field_id(cur_offset as usize);
       static_fields.push(field);
       m_bytes = bytes;
     }
@@ -73,7 +106,7 @@ impl ClassDataItem {
     for _ in 0..instance_fields_size {
       let (bytes, mut field) = EncodedField::parse(m_bytes)?;
       cur_offset = field.field_idx_diff + cur_offset;
-      field.field = get_field_id(cur_offset as usize);
+      field.field = context.get_field_id(cur_offset as usize);
       instance_fields.push(field);
       m_bytes = bytes;
     }
@@ -82,11 +115,11 @@ impl ClassDataItem {
     for _ in 0..direct_methods_size {
       let (bytes, mut method) = EncodedMethod::parse(m_bytes)?;
       cur_offset = method.method_idx_diff + cur_offset;
-      method.method = get_method_id(cur_offset as usize);
+      method.method = context.get_method_id(cur_offset as usize);
 
       let code_item = if method.code_off != 0 {
         let offset_bytes = origin_bytes.slice(method.code_off as usize..);
-        let (_, code_item) = CodeItem::parse(offset_bytes)?;
+        let (_, code_item) = CodeItem::parse_with_limits(offset_bytes, limits)?;
         Some(code_item)
       } else {
         None
@@ -100,11 +133,11 @@ impl ClassDataItem {
     for _ in 0..virtual_methods_size {
       let (bytes, mut method) = EncodedMethod::parse(m_bytes)?;
       cur_offset = method.method_idx_diff + cur_offset;
-      method.method = get_method_id(cur_offset as usize);
+      method.method = context.get_method_id(cur_offset as usize);
 
       let code_item = if method.code_off != 0 {
         let offset_bytes = origin_bytes.slice(method.code_off as usize..);
-        let (_, code_item) = CodeItem::parse(offset_bytes)?;
+        let (_, code_item) = CodeItem::parse_with_limits(offset_bytes, limits)?;
         Some(code_item)
       } else {
         None
@@ -114,6 +147,7 @@ impl ClassDataItem {
       m_bytes = bytes;
     }
     let ret = Self {
+      byte_len: start.len() - m_bytes.len(),
       static_fields,
       instance_fields,
       direct_methods,
@@ -121,12 +155,121 @@ impl ClassDataItem {
     };
     Ok((m_bytes, ret))
   }
+
+  /// Reads the `hiddenapi_class_data_item` flags of this class, one uleb128
+  /// per member in class data order.
+  pub fn parse_hidden_api_flags<'a, E: ParseError<&'a [u8]>>(
+    &mut self,
+    bytes: &'a [u8],
+  ) -> IResult<&'a [u8], (), E> {
+    let mut m_bytes = bytes;
+    let fields = self
+      .static_fields
+      .iter_mut()
+      .chain(self.instance_fields.iter_mut())
+      .map(|field| &mut field.hidden_api_flag);
+    let methods = self
+      .direct_methods
+      .iter_mut()
+      .chain(self.virtual_methods.iter_mut())
+      .map(|method| &mut method.hidden_api_flag);
+    for hidden_api_flag in fields.chain(methods) {
+      let (bytes, flag) = parse_uleb128_nom(m_bytes)?;
+      *hidden_api_flag = Some(HiddenApiFlag(flag));
+      m_bytes = bytes;
+    }
+    Ok((m_bytes, ()))
+  }
+
+  /// Gives the static fields the values of the class's `static_values`, in
+  /// order. Fields past the end of the array keep no value: they start out
+  /// as 0 or `null`.
+  pub fn set_static_values(&mut self, values: &[EncodedValue], context: &ResolveContext) {
+    for (field, value) in self.static_fields.iter_mut().zip(values) {
+      field.constant_value = value.to_literal(context);
+    }
+  }
+
+  pub fn static_fields(&self) -> &[EncodedField] {
+    &self.static_fields
+  }
+
+  pub fn instance_fields(&self) -> &[EncodedField] {
+    &self.instance_fields
+  }
+
+  pub fn direct_methods(&self) -> &[EncodedMethod] {
+    &self.direct_methods
+  }
+
+  pub fn virtual_methods(&self) -> &[EncodedMethod] {
+    &self.virtual_methods
+  }
+
+  pub fn static_field_count(&self) -> usize {
+    self.static_fields.len()
+  }
+
+  pub fn instance_field_count(&self) -> usize {
+    self.instance_fields.len()
+  }
+
+  pub fn direct_method_count(&self) -> usize {
+    self.direct_methods.len()
+  }
+
+  pub fn virtual_method_count(&self) -> usize {
+    self.virtual_methods.len()
+  }
+
+  /// Direct methods followed by virtual methods.
+  pub fn methods(&self) -> impl Iterator<Item = &EncodedMethod> {
+    self
+      .direct_methods
+      .iter()
+      .chain(self.virtual_methods.iter())
+  }
 }
 
 pub struct EncodedField {
   field_idx_diff: u32,
   access_flags: AccessFlags,
   field: FieldIdItem,
+  hidden_api_flag: Option<HiddenApiFlag>,
+  constant_value: Option<LiteralValue>,
+}
+
+impl EncodedField {
+  pub fn name(&self) -> &str {
+    self.field.name()
+  }
+
+  pub fn descriptor(&self) -> &str {
+    self.field.descriptor()
+  }
+
+  pub fn access_flags(&self) -> &AccessFlags {
+    &self.access_flags
+  }
+
+  /// Java source declaration, `private static final int MAX`.
+  pub fn signature(&self) -> String {
+    field_signature(&self.access_flags, self.name(), self.descriptor())
+  }
+
+  /// Initial value of a static field from the class's `static_values`.
+  pub fn constant_value(&self) -> Option<&LiteralValue> {
+    self.constant_value.as_ref()
+  }
+
+  /// `None` when the dex has no hiddenapi data for this field.
+  pub fn hidden_api_flag(&self) -> Option<HiddenApiFlag> {
+    self.hidden_api_flag
+  }
+
+  pub fn is_synthetic(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Synthetic)
+  }
 }
 
 impl Parsable for EncodedField {
@@ -144,19 +287,35 @@ impl Parsable for EncodedField {
         field_idx_diff,
         access_flags: AccessFlags::new_field_flag(access_flags as u16),
         field: FieldIdItem::default(),
+        hidden_api_flag: None,
+        constant_value: None,
       },
     ))
   }
 }
 impl Display for EncodedField {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_indent(f, Indent::TABS)
+  }
+}
+
+impl IndentDisplay for EncodedField {
+  fn fmt_indent(&self, f: &mut std::fmt::Formatter<'_>, indent: Indent) -> std::fmt::Result {
+    let t = indent.level(1);
     write!(
       f,
-      "\tname\t: {}\n\ttype\t: {}\n\taccess\t: {}",
+      "{t}name{t}: {}\n{t}type{t}: {}\n{t}access{t}: {}",
       self.field.name(),
       self.field.descriptor(),
       self.access_flags
-    )
+    )?;
+    if self.is_synthetic() {
+      write!(f, "\n{t}markers{t}: synthetic")?;
+    }
+    if let Some(hidden_api_flag) = self.hidden_api_flag {
+      write!(f, "\n{t}hiddenapi{t}: {}", hidden_api_flag)?;
+    }
+    Ok(())
   }
 }
 
@@ -166,6 +325,143 @@ pub struct EncodedMethod {
   code_off: u32,
   method: MethodIdItem,
   code_item: Option<CodeItem>,
+  hidden_api_flag: Option<HiddenApiFlag>,
+}
+
+impl EncodedMethod {
+  pub fn name(&self) -> &str {
+    self.method.name()
+  }
+
+  /// Descriptor of the parameters and return type, `(I)V`.
+  pub fn descriptor(&self) -> String {
+    self.method.proto().descriptor()
+  }
+
+  pub fn proto(&self) -> &ProtoIdItem {
+    self.method.proto()
+  }
+
+  pub fn method_id(&self) -> &MethodIdItem {
+    &self.method
+  }
+
+  /// Descriptor of the class declaring the method.
+  pub fn class_descriptor(&self) -> &str {
+    self.method.class_descriptor()
+  }
+
+  pub fn access_flags(&self) -> &AccessFlags {
+    &self.access_flags
+  }
+
+  /// Java source signature, `public static int compute(java.lang.String, int)`.
+  pub fn signature(&self) -> String {
+    method_signature(&self.access_flags, self.name(), &self.descriptor())
+  }
+
+  /// `None` when the dex has no hiddenapi data for this method.
+  pub fn hidden_api_flag(&self) -> Option<HiddenApiFlag> {
+    self.hidden_api_flag
+  }
+
+  /// The method's bytecode, registers and try blocks; `None` for abstract
+  /// and native methods.
+  pub fn code(&self) -> Option<&CodeItem> {
+    self.code_item.as_ref()
+  }
+
+  /// Dex has no `Synthetic` or `Deprecated` attributes; synthetic members are
+  /// marked by `ACC_SYNTHETIC` alone.
+  pub fn is_synthetic(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Synthetic)
+  }
+
+  /// Descriptors of the types its try blocks catch, each once in the order
+  /// first seen; catch-all handlers add none.
+  pub fn catch_types(&self) -> Vec<&str> {
+    let Some(code_item) = &self.code_item else {
+      return vec![];
+    };
+    let context = self.method.context();
+    let mut catch_types = vec![];
+    let handlers = code_item
+      .tries
+      .iter()
+      .filter_map(|try_item| code_item.handler(try_item));
+    for handler in handlers {
+      for (type_idx, _) in &handler.handlers {
+        if let Some(descriptor) = context.find_type_descriptor(*type_idx as usize) {
+          if !catch_types.contains(&descriptor) {
+            catch_types.push(descriptor);
+          }
+        }
+      }
+    }
+    catch_types
+  }
+
+  /// A bridge method javac generates for generics or covariant returns.
+  pub fn is_bridge(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Bridge)
+  }
+
+  /// `synthetic`, `bridge`, or both, for the dump.
+  fn markers(&self) -> Vec<&'static str> {
+    [
+      (self.is_synthetic(), "synthetic"),
+      (self.is_bridge(), "bridge"),
+    ]
+    .into_iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, marker)| marker)
+    .collect()
+  }
+
+  /// Parameter shorty, without the leading return type.
+  fn parameter_shorty(&self) -> &str {
+    self.method.param_type().get(1..).unwrap_or_default()
+  }
+
+  /// See [`CodeItem::parameter_registers`].
+  pub fn parameter_registers(&self) -> Option<(Option<u16>, Vec<u16>)> {
+    let code_item = self.code_item.as_ref()?;
+    Some(code_item.parameter_registers(self.access_flags.is_static(), self.parameter_shorty()))
+  }
+
+  /// Registers taken by the parameters, not counting `this`: two for each
+  /// `long` and `double`, one for anything else.
+  fn parameter_words(&self) -> u16 {
+    self
+      .parameter_shorty()
+      .chars()
+      .map(|ty| if ty == 'J' || ty == 'D' { 2 } else { 1 })
+      .sum()
+  }
+
+  /// The `ins_size` the signature calls for: the parameter words, plus one
+  /// for `this` in instance methods.
+  pub fn expected_ins_size(&self) -> u16 {
+    let this = if self.access_flags.is_static() { 0 } else { 1 };
+    this + self.parameter_words()
+  }
+
+  /// Checks that the ins fit in the registers and match the signature.
+  pub fn validate(&self) -> Vec<Warning> {
+    let Some(code_item) = &self.code_item else {
+      return vec![];
+    };
+    let expected_ins = self.expected_ins_size();
+    if code_item.ins_size <= code_item.registers_size && code_item.ins_size == expected_ins {
+      return vec![];
+    }
+    vec![Warning::RegisterCounts {
+      method: format!("{}({})", self.name(), self.method.param_type()),
+      registers: code_item.registers_size,
+      ins: code_item.ins_size,
+      expected_ins,
+    }]
+  }
 }
 
 impl Parsable for EncodedMethod {
@@ -185,6 +481,7 @@ impl Parsable for EncodedMethod {
         code_off,
         method: MethodIdItem::default(),
         code_item: None,
+        hidden_api_flag: None,
       },
     ))
   }
@@ -192,29 +489,91 @@ impl Parsable for EncodedMethod {
 
 impl Display for EncodedMethod {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_indent(f, Indent::TABS)
+  }
+}
+
+impl IndentDisplay for EncodedMethod {
+  fn fmt_indent(&self, f: &mut std::fmt::Formatter<'_>, indent: Indent) -> std::fmt::Result {
+    let (t, t2, t3) = (indent.level(1), indent.level(2), indent.level(3));
     writeln!(
       f,
-      "\t\tname\t: {}\n\t\ttype\t: ({}){}\n\t\taccess\t: {}",
+      "{t2}name{t}: {}\n{t2}type{t}: ({}){}\n{t2}access{t}: {}",
       self.method.name(),
       self.method.param_type(),
       self.method.return_type(),
       self.access_flags
     )?;
+    let markers = self.markers();
+    if !markers.is_empty() {
+      writeln!(f, "{t2}markers{t}: {}", markers.join(", "))?;
+    }
+    if let Some(hidden_api_flag) = self.hidden_api_flag {
+      writeln!(f, "{t2}hiddenapi{t}: {}", hidden_api_flag)?;
+    }
     if let Some(code_item) = &self.code_item {
-      writeln!(f, "\n\t\tcode\t-")?;
-      writeln!(f, "\t\tregisterss\t: {}", code_item.registers_size)?;
-      writeln!(f, "\t\tins\t: {}", code_item.ins_size)?;
-      writeln!(f, "\t\touts\t: {}", code_item.outs_size)?;
+      writeln!(f, "\n{t2}code{t}-")?;
+      writeln!(f, "{t2}registers{t}: {}", code_item.registers_size)?;
+      if self.access_flags.is_static() {
+        writeln!(f, "{t2}ins{t}: {}", code_item.ins_size)?;
+      } else {
+        writeln!(
+          f,
+          "{t2}ins{t}: {} (this + {} parameter registers)",
+          code_item.ins_size,
+          self.parameter_words()
+        )?;
+      }
+      writeln!(f, "{t2}outs{t}: {}", code_item.outs_size)?;
       writeln!(
         f,
-        "\t\tinsns size\t: {} 16-bit code units",
+        "{t2}insns size{t}: {} 16-bit code units",
         code_item.insns_size
       )?;
+      let (this, parameters) =
+        code_item.parameter_registers(self.access_flags.is_static(), self.parameter_shorty());
+      let mut names: Vec<String> = this
+        .map(|register| format!("{} (this)", code_item.register_name(register)))
+        .into_iter()
+        .collect();
+      names.extend(
+        parameters
+          .iter()
+          .map(|register| code_item.register_name(*register)),
+      );
+      writeln!(f, "{t2}params{t}: {}", names.join(", "))?;
+      // The first unit of each instruction is labeled with its mnemonic,
+      // argument registers and category, and that of a payload with its
+      // contents too.
+      let mut instructions = code_item.instructions().peekable();
       for (i, ins) in code_item.insns.iter().enumerate() {
-        writeln!(f, "\t\t\t{:04x}:\t{:04x}", i, ins)?;
+        write!(f, "{t3}{:04x}:{t}{:04x}", i, ins)?;
+        if let Some(instruction) = instructions.next_if(|instruction| instruction.pc as usize == i)
+        {
+          match instruction.mnemonic {
+            Some(mnemonic) => write!(f, "{t}{}", mnemonic)?,
+            None => write!(f, "{t}unknown_{:02x}", instruction.opcode)?,
+          }
+          if let Some(registers) = instruction.argument_registers() {
+            let names: Vec<String> = registers
+              .iter()
+              .map(|register| code_item.register_name(*register))
+              .collect();
+            let range = matches!(instruction.format, Some(Format::F3rc | Format::F4rcc));
+            match names.as_slice() {
+              [first, .., last] if range => write!(f, " {{{} .. {}}}", first, last)?,
+              _ => write!(f, " {{{}}}", names.join(", "))?,
+            }
+          }
+          write!(f, " ({})", instruction.category())?;
+          if let Some(payload) = instruction.payload() {
+            write!(f, " {}", payload)?;
+          }
+        }
+        writeln!(f)?;
       }
     } else {
-      writeln!(f, "\n\t\tcode\t: (none)")?;
+      writeln!(f, "\n{t2}code{t}: (none)")?;
     }
     Ok(())
   }
@@ -228,8 +587,246 @@ pub struct CodeItem {
   debug_info_off: u32,
   insns_size: u32,
   insns: Vec<u16>,
-  // tries: Option<Vec<TryItem>>,
-  // handlers: Option<EncodedCatchHandlerList>,
+  tries: Vec<TryItem>,
+  handlers: Vec<EncodedCatchHandler>,
+}
+
+/// A range of code covered by the catch handler at `handler_off`.
+pub struct TryItem {
+  start_addr: u32,
+  insn_count: u16,
+  handler_off: u16,
+}
+
+/// The catch clauses of a try: each caught type with the address of its
+/// handler, then the catch-all handler if there is one.
+pub struct EncodedCatchHandler {
+  /// Byte offset in the `encoded_catch_handler_list`, what `handler_off` holds.
+  offset: u16,
+  handlers: Vec<(u32, u32)>,
+  catch_all_addr: Option<u32>,
+}
+
+impl Parsable for TryItem {
+  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+  ) -> nom::IResult<&'a [u8], Self, E>
+  where
+    Self: Sized,
+  {
+    let (bytes, (start_addr, insn_count, handler_off)) = tuple((le_u32, le_u16, le_u16))(bytes)?;
+    Ok((
+      bytes,
+      Self {
+        start_addr,
+        insn_count,
+        handler_off,
+      },
+    ))
+  }
+}
+
+impl TryItem {
+  /// First code unit covered.
+  pub fn start_addr(&self) -> u32 {
+    self.start_addr
+  }
+
+  /// Number of code units covered.
+  pub fn insn_count(&self) -> u16 {
+    self.insn_count
+  }
+}
+
+impl EncodedCatchHandler {
+  /// Parses the handler at `offset` bytes into the list.
+  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    offset: u16,
+    limits: &ParseLimits,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, size) = parse_sleb128_nom(bytes)?;
+    let (bytes, handlers) = limits.count(
+      tuple((parse_uleb128_nom, parse_uleb128_nom)),
+      size.unsigned_abs() as usize,
+    )(bytes)?;
+    let (bytes, catch_all_addr) = if size <= 0 {
+      let (bytes, catch_all_addr) = parse_uleb128_nom(bytes)?;
+      (bytes, Some(catch_all_addr))
+    } else {
+      (bytes, None)
+    };
+    Ok((
+      bytes,
+      Self {
+        offset,
+        handlers,
+        catch_all_addr,
+      },
+    ))
+  }
+
+  /// `(type_idx, addr)` of each caught type, in the order they are tried.
+  pub fn handlers(&self) -> &[(u32, u32)] {
+    &self.handlers
+  }
+
+  pub fn catch_all_addr(&self) -> Option<u32> {
+    self.catch_all_addr
+  }
+}
+
+impl CodeItem {
+  pub fn registers_size(&self) -> u16 {
+    self.registers_size
+  }
+
+  pub fn ins_size(&self) -> u16 {
+    self.ins_size
+  }
+
+  pub fn outs_size(&self) -> u16 {
+    self.outs_size
+  }
+
+  pub fn tries_size(&self) -> u16 {
+    self.tries_size
+  }
+
+  /// Offset of the method's `debug_info_item`, 0 if it has none.
+  pub fn debug_info_off(&self) -> u32 {
+    self.debug_info_off
+  }
+
+  /// Length of the bytecode in 16-bit code units.
+  pub fn insns_size(&self) -> u32 {
+    self.insns_size
+  }
+
+  /// The undecoded bytecode.
+  pub fn raw_insns(&self) -> &[u16] {
+    &self.insns
+  }
+
+  pub fn instructions(&self) -> InstructionIterator<'_> {
+    InstructionIterator::new(&self.insns)
+  }
+
+  /// The instruction starting at code unit `pc`; `None` if `pc` is inside
+  /// another instruction or payload, or past the end of the insns.
+  pub fn instruction_at(&self, pc: u32) -> Option<Instruction<'_>> {
+    self
+      .instructions()
+      .take_while(|instruction| instruction.pc <= pc)
+      .find(|instruction| instruction.pc == pc)
+  }
+
+  pub fn tries(&self) -> &[TryItem] {
+    &self.tries
+  }
+
+  /// Every catch handler, in the order of the `encoded_catch_handler_list`.
+  pub fn handlers(&self) -> &[EncodedCatchHandler] {
+    &self.handlers
+  }
+
+  /// The catch handler `try_item` points to, `None` if its `handler_off`
+  /// is not the start of one.
+  pub fn handler(&self, try_item: &TryItem) -> Option<&EncodedCatchHandler> {
+    self
+      .handlers
+      .iter()
+      .find(|handler| handler.offset == try_item.handler_off)
+  }
+
+  /// Splits the bytecode into basic blocks, with edges for fallthrough,
+  /// `goto`, `if-*`, the switches and each try's catch handlers. A switch
+  /// with no matching case falls through, so that is its default target.
+  pub fn cfg(&self) -> ControlFlowGraph {
+    let instructions: Vec<FlowInfo> = self
+      .instructions()
+      .map(|instruction| {
+        let next = instruction.pc + instruction.units.len() as u32;
+        let flow = match instruction.opcode {
+          0x0e..=0x11 | 0x27 => Flow::Exit,
+          0x28..=0x2a => instruction
+            .branch_target()
+            .map_or(Flow::Continue, Flow::Goto),
+          0x32..=0x3d => instruction
+            .branch_target()
+            .map_or(Flow::Continue, Flow::Branch),
+          0x2b | 0x2c => match instruction.switch_targets(&self.insns) {
+            Some(targets) => Flow::Switch([next].into_iter().chain(targets).collect()),
+            None => Flow::Continue,
+          },
+          _ => Flow::Continue,
+        };
+        FlowInfo {
+          pc: instruction.pc,
+          len: instruction.units.len() as u32,
+          flow,
+        }
+      })
+      .collect();
+    let mut handlers = vec![];
+    for try_item in &self.tries {
+      let Some(handler) = self.handler(try_item) else {
+        continue;
+      };
+      let start = try_item.start_addr;
+      let end = start + try_item.insn_count as u32;
+      let addrs = handler.handlers.iter().map(|(_, addr)| *addr);
+      for addr in addrs.chain(handler.catch_all_addr) {
+        handlers.push(Handler {
+          start,
+          end,
+          handler: addr,
+        });
+      }
+    }
+    ControlFlowGraph::new(&instructions, &handlers)
+  }
+
+  /// The ins of a method are passed in its last `ins_size` registers:
+  /// `this` first for instance methods, then each parameter, a wide (`J`/`D`)
+  /// one taking two registers. Returns the register of `this` and of each
+  /// parameter in `parameter_shorty`.
+  pub fn parameter_registers(
+    &self,
+    is_static: bool,
+    parameter_shorty: &str,
+  ) -> (Option<u16>, Vec<u16>) {
+    let mut register = self.first_parameter_register();
+    let this = if is_static {
+      None
+    } else {
+      register += 1;
+      Some(register - 1)
+    };
+    let parameters = parameter_shorty
+      .chars()
+      .map(|ty| {
+        let current = register;
+        register += if ty == 'J' || ty == 'D' { 2 } else { 1 };
+        current
+      })
+      .collect();
+    (this, parameters)
+  }
+
+  /// baksmali style name: `pN` for the ins, `vN` for the locals.
+  pub fn register_name(&self, register: u16) -> String {
+    let first_parameter = self.first_parameter_register();
+    if register >= first_parameter {
+      format!("p{}", register - first_parameter)
+    } else {
+      format!("v{}", register)
+    }
+  }
+
+  fn first_parameter_register(&self) -> u16 {
+    self.registers_size.saturating_sub(self.ins_size)
+  }
 }
 
 impl Parsable for CodeItem {
@@ -239,15 +836,41 @@ impl Parsable for CodeItem {
   where
     Self: Sized,
   {
+    Self::parse_with_limits(bytes, &ParseLimits::DEFAULT)
+  }
+}
+
+impl CodeItem {
+  /// Fails with `ErrorKind::TooLarge` on more than `limits.max_code_len`
+  /// code units, or more than `limits.max_items` tries or handlers.
+  pub fn parse_with_limits<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    limits: &ParseLimits,
+  ) -> nom::IResult<&'a [u8], Self, E> {
     let (bytes, (registers_size, ins_size, outs_size, tries_size, debug_info_off, insns_size)) =
       tuple((le_u16, le_u16, le_u16, le_u16, le_u32, le_u32))(bytes)?;
+    limits.check_code_len(bytes, insns_size as usize)?;
     let (bytes, insns) = count(le_u16, insns_size as usize)(bytes)?;
     let mut m_bytes = bytes;
     if insns_size % 2 == 1 && tries_size > 0 {
       let (bytes, _) = le_u16(bytes)?;
       m_bytes = bytes;
     }
-    // TODO parse instructions
+    let (bytes, tries) = count(TryItem::parse, tries_size as usize)(m_bytes)?;
+    m_bytes = bytes;
+    let mut handlers = vec![];
+    if tries_size > 0 {
+      let list = m_bytes;
+      let (bytes, size) = parse_uleb128_nom(list)?;
+      limits.check_items(bytes, size as usize)?;
+      m_bytes = bytes;
+      for _ in 0..size {
+        let offset = (list.len() - m_bytes.len()) as u16;
+        let (bytes, handler) = EncodedCatchHandler::parse(m_bytes, offset, limits)?;
+        handlers.push(handler);
+        m_bytes = bytes;
+      }
+    }
     Ok((
       m_bytes,
       Self {
@@ -258,11 +881,20 @@ impl Parsable for CodeItem {
         debug_info_off,
         insns_size,
         insns,
+        tries,
+        handlers,
       },
     ))
   }
 }
 
+/// Source name of the class `descriptor` refers to, through any array
+/// dimensions; `None` for primitives and primitive arrays.
+pub(crate) fn referenced_class_name(descriptor: &str) -> Option<String> {
+  referenced_class(descriptor)?;
+  Some(descriptor_to_source(descriptor.trim_start_matches('[')))
+}
+
 impl ClassDefItem {
   pub fn new(
     class_idx: u32,
@@ -295,50 +927,255 @@ impl ClassDefItem {
   }
 }
 
-impl Display for ClassDefItem {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    writeln!(f, "\tClass descriptor\t: {}", self.class.descriptor())?;
-    writeln!(f, "\tAccess flags\t: {}", self.access_flags)?;
-    let super_class = if let Some(super_class) = &self.superclass {
-      super_class.descriptor()
-    } else {
-      "Ljava/lang/Object;"
+impl ClassDefItem {
+  pub fn descriptor(&self) -> &str {
+    self.class.descriptor()
+  }
+
+  /// Interfaces include annotation types.
+  pub fn is_interface(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Interface)
+  }
+
+  pub fn is_enum(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Enum)
+  }
+
+  /// Whether this is an annotation type, an `@interface`.
+  pub fn is_annotation(&self) -> bool {
+    self.access_flags.contains(AccessFlag::Annotation)
+  }
+
+  /// Whether this is `java.lang.Object`, the only class without a superclass.
+  pub fn is_root_class(&self) -> bool {
+    self.descriptor() == OBJECT_DESCRIPTOR
+  }
+
+  /// Descriptor of the superclass, `None` for `java.lang.Object` (and for a
+  /// malformed class missing its `superclass_idx`; see `is_root_class`).
+  pub fn superclass_descriptor(&self) -> Option<&str> {
+    self.superclass.as_ref().map(TypeIdItem::descriptor)
+  }
+
+  /// Internal names of the directly implemented interfaces in declaration
+  /// order, `java/lang/Runnable`, as the class file's `render_interfaces`
+  /// gives them. A descriptor that doesn't name a class is kept as is.
+  pub fn interfaces(&self) -> Vec<&str> {
+    let Some(interfaces) = &self.interfaces else {
+      return vec![];
+    };
+    interfaces
+      .list
+      .iter()
+      .map(|interface| {
+        let descriptor = interface.descriptor();
+        referenced_class(descriptor).unwrap_or(descriptor)
+      })
+      .collect()
+  }
+
+  /// Name of the source file this class was compiled from, `None` without a
+  /// `source_file_idx` or when it is outside the string ids.
+  pub fn source_file(&self) -> Option<&str> {
+    let string_id = self
+      .class
+      .context()
+      .string_ids()
+      .get(self.source_file_idx? as usize)?;
+    Some(string_id.string_data.as_str())
+  }
+
+  pub fn class_data_item(&self) -> Option<&ClassDataItem> {
+    self.class_data_item.as_ref()
+  }
+
+  /// Where the `class_data_item` lies in the dex file, `None` for a class
+  /// without fields or methods.
+  pub fn class_data_range(&self) -> Option<Range<usize>> {
+    let start = self.class_data_off as usize;
+    Some(start..start + self.class_data_item.as_ref()?.byte_len)
+  }
+
+  /// SHA-256 of the class's `class_data_item` in `dex_bytes`, the file it
+  /// was parsed from; of no bytes for a class without one. The class data
+  /// holds member indices and code offsets, so the same class only hashes
+  /// the same in dex files laid out alike.
+  pub fn content_hash(&self, dex_bytes: &[u8]) -> [u8; 32] {
+    let class_data = self
+      .class_data_range()
+      .and_then(|range| dex_bytes.get(range))
+      .unwrap_or_default();
+    Sha256::digest(class_data).into()
+  }
+
+  /// Source names of the classes this class refers to, sorted: its
+  /// supertypes, the types in its field and method signatures, and the
+  /// types, fields (owner and type) and methods (owner and signature) its
+  /// code references. Array types count as their element class;
+  /// primitives and the class itself are left out.
+  pub fn referenced_types(&self) -> Vec<String> {
+    let context = self.class.context();
+    let mut descriptors: Vec<String> = self
+      .direct_supertypes()
+      .into_iter()
+      .map(str::to_string)
+      .collect();
+    let proto_types = |proto: &ProtoIdItem| -> Vec<String> {
+      let mut types: Vec<String> = proto
+        .parameter_types()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+      types.push(proto.return_type().to_string());
+      types
     };
-    writeln!(f, "\tSuperclass\t: {}", super_class)?;
-    writeln!(f, "\tInterfaces\t-")?;
-    if let Some(interfaces) = &self.interfaces {
-      for (interface, idx) in interfaces.list.iter().zip(0..) {
-        writeln!(f, "\t\t#{}\t: {}", idx, interface.descriptor())?;
-      }
-    }
-    writeln!(f, "\tStatic fields\t-")?;
     if let Some(class_data_item) = &self.class_data_item {
-      for (field, idx) in class_data_item.static_fields.iter().zip(0..) {
-        writeln!(f, "\t\t#{}\t: (in {})", idx, self.class.descriptor())?;
-        writeln!(f, "{}", field)?;
+      for field in class_data_item
+        .static_fields
+        .iter()
+        .chain(&class_data_item.instance_fields)
+      {
+        descriptors.push(field.field.descriptor().to_string());
       }
-    }
-    writeln!(f, "\tInstance fields\t-")?;
-    if let Some(class_data_item) = &self.class_data_item {
-      for (field, idx) in class_data_item.instance_fields.iter().zip(0..) {
-        writeln!(f, "\t\t#{}\t: (in {})", idx, self.class.descriptor())?;
-        writeln!(f, "{}", field)?;
+      for method in class_data_item.methods() {
+        descriptors.extend(proto_types(method.method.proto()));
+        let Some(code_item) = method.code() else {
+          continue;
+        };
+        for (kind, index) in code_item
+          .instructions()
+          .filter_map(|instruction| instruction.reference())
+        {
+          let index = index as usize;
+          match kind {
+            IndexKind::Type if index < context.type_ids_size() => {
+              descriptors.push(context.get_type_id(index).descriptor().to_string());
+            }
+            IndexKind::Field if index < context.field_ids_size() => {
+              let field = context.get_field_id(index);
+              descriptors.push(field.class_descriptor().to_string());
+              descriptors.push(field.descriptor().to_string());
+            }
+            IndexKind::Method if index < context.method_ids_size() => {
+              let method = context.get_method_id(index);
+              descriptors.push(method.class_descriptor().to_string());
+              descriptors.extend(proto_types(method.proto()));
+            }
+            IndexKind::Proto if index < context.proto_ids_size() => {
+              descriptors.extend(proto_types(&context.get_proto_id(index)));
+            }
+            _ => {}
+          }
+        }
       }
     }
-    writeln!(f, "\tDirect methods\t-")?;
-    if let Some(class_data_item) = &self.class_data_item {
-      for (method, idx) in class_data_item.direct_methods.iter().zip(0..) {
-        writeln!(f, "\t\t#{}\t: (in {})", idx, self.class.descriptor())?;
-        writeln!(f, "{}", method)?;
-      }
+    let this = descriptor_to_source(self.descriptor());
+    let types: BTreeSet<String> = descriptors
+      .iter()
+      .filter_map(|descriptor| referenced_class_name(descriptor))
+      .filter(|name| *name != this)
+      .collect();
+    types.into_iter().collect()
+  }
+
+  /// Superclass followed by the directly implemented interfaces.
+  pub(crate) fn direct_supertypes(&self) -> Vec<&str> {
+    let mut result = vec![];
+    if let Some(superclass) = &self.superclass {
+      result.push(superclass.descriptor());
     }
-    writeln!(f, "\tVirtual methods\t-")?;
-    if let Some(class_data_item) = &self.class_data_item {
-      for (method, idx) in class_data_item.virtual_methods.iter().zip(0..) {
-        writeln!(f, "\t\t#{}\t: (in {})", idx, self.class.descriptor())?;
-        writeln!(f, "{}", method)?;
+    if let Some(interfaces) = &self.interfaces {
+      result.extend(
+        interfaces
+          .list
+          .iter()
+          .map(|interface| interface.descriptor()),
+      );
+    }
+    result
+  }
+}
+
+/// A `title (N)` section listing `members`, or `title: (none)` for a class
+/// without class data.
+fn fmt_members<T: IndentDisplay>(
+  f: &mut std::fmt::Formatter<'_>,
+  indent: Indent,
+  title: &str,
+  members: Option<&[T]>,
+  descriptor: &str,
+) -> std::fmt::Result {
+  let (t, t2) = (indent.level(1), indent.level(2));
+  let Some(members) = members else {
+    return writeln!(f, "{t}{}{t}: (none)", title);
+  };
+  writeln!(f, "{t}{} ({}){t}-", title, members.len())?;
+  for (member, idx) in members.iter().zip(0..) {
+    writeln!(f, "{t2}#{}{t}: (in {})", idx, descriptor)?;
+    writeln!(f, "{}", member.display_indent(indent))?;
+  }
+  Ok(())
+}
+
+impl Display for ClassDefItem {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_indent(f, Indent::TABS)
+  }
+}
+
+impl IndentDisplay for ClassDefItem {
+  fn fmt_indent(&self, f: &mut std::fmt::Formatter<'_>, indent: Indent) -> std::fmt::Result {
+    let (t, t2) = (indent.level(1), indent.level(2));
+    writeln!(
+      f,
+      "{t}{} {}",
+      self.access_flags.class_kind(),
+      descriptor_to_source(self.descriptor())
+    )?;
+    writeln!(f, "{t}Class descriptor{t}: {}", self.class.descriptor())?;
+    writeln!(f, "{t}Access flags{t}: {}", self.access_flags)?;
+    let super_class = match self.superclass_descriptor() {
+      Some(super_class) => super_class,
+      None if self.is_root_class() => "(none, root class)",
+      None => "(missing)",
+    };
+    writeln!(f, "{t}Superclass{t}: {}", super_class)?;
+    writeln!(f, "{t}Interfaces{t}-")?;
+    if let Some(interfaces) = &self.interfaces {
+      for (interface, idx) in interfaces.list.iter().zip(0..) {
+        writeln!(f, "{t2}#{}{t}: {}", idx, interface.descriptor())?;
       }
     }
+    let class_data_item = self.class_data_item.as_ref();
+    let descriptor = self.class.descriptor();
+    fmt_members(
+      f,
+      indent,
+      "Static fields",
+      class_data_item.map(ClassDataItem::static_fields),
+      descriptor,
+    )?;
+    fmt_members(
+      f,
+      indent,
+      "Instance fields",
+      class_data_item.map(ClassDataItem::instance_fields),
+      descriptor,
+    )?;
+    fmt_members(
+      f,
+      indent,
+      "Direct methods",
+      class_data_item.map(ClassDataItem::direct_methods),
+      descriptor,
+    )?;
+    fmt_members(
+      f,
+      indent,
+      "Virtual methods",
+      class_data_item.map(ClassDataItem::virtual_methods),
+      descriptor,
+    )?;
     writeln!(
       f,
       "annotations_off: {}, static_values_off: {}",
@@ -347,3 +1184,785 @@ impl Display for ClassDefItem {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use base::{
+    access_flag::AccessFlag,
+    error::{ErrorKind, Warning},
+    indent::{Indent, IndentDisplay},
+    literal::LiteralValue,
+  };
+
+  use crate::test_util::{without_string_id_warnings, ClassDef, DexBuilder, Method};
+
+  #[test]
+  fn test_code() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let run = builder.method("La;", "run", "V", &[]);
+    let stop = builder.method("La;", "stop", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .virtual_method(Method::new(run, 0x0001).code(2, 1, 0, &[0x0012, 0x000e]))
+        .virtual_method(Method::new(stop, 0x0401)),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let methods = dex_file.class_defs()[0]
+      .class_data_item()
+      .unwrap()
+      .virtual_methods();
+    let code_item = methods[0].code().unwrap();
+    assert_eq!(code_item.insns_size(), 2);
+    assert_eq!(code_item.raw_insns(), &[0x0012, 0x000e]);
+    assert_eq!(code_item.registers_size(), 2);
+    assert_eq!(code_item.ins_size(), 1);
+    assert_eq!(code_item.outs_size(), 0);
+    assert_eq!(code_item.tries_size(), 0);
+    assert!(code_item.handlers().is_empty());
+    assert_eq!(code_item.debug_info_off(), 0);
+    assert!(methods[1].access_flags().contains(AccessFlag::Abstract));
+    assert!(methods[1].code().is_none());
+  }
+
+  #[test]
+  fn test_parameter_registers() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let add = builder.method("La;", "add", "I", &["I", "I"]);
+    let sum = builder.method("La;", "sum", "J", &["J", "I"]);
+    builder.class(
+      ClassDef::new(a)
+        .direct_method(Method::new(sum, 0x0009).code(4, 3, 0, &[0x000e]))
+        .virtual_method(Method::new(add, 0x0001).code(5, 3, 0, &[0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+
+    let add = &class_data.virtual_methods()[0];
+    assert_eq!(add.parameter_registers(), Some((Some(2), vec![3, 4])));
+    let code_item = add.code().unwrap();
+    let names: Vec<String> = (0..5).map(|r| code_item.register_name(r)).collect();
+    assert_eq!(names, vec!["v0", "v1", "p0", "p1", "p2"]);
+    assert!(add.to_string().contains("params\t: p0 (this), p1, p2"));
+
+    let sum = &class_data.direct_methods()[0];
+    assert_eq!(sum.parameter_registers(), Some((None, vec![1, 3])));
+    assert!(sum.to_string().contains("params\t: p0, p2"));
+
+    // run(I) with v0 and the ins v1 (this) and v2: invoke-static {v0, p1},
+    // invoke-virtual/range {p0 .. p1}, return-void.
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let run = builder.method("La;", "run", "V", &["I"]);
+    let code = [0x2071, 0x0000, 0x0020, 0x0274, 0x0000, 0x0001, 0x000e];
+    builder.class(ClassDef::new(a).virtual_method(Method::new(run, 0x0001).code(3, 2, 2, &code)));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let run = &dex_file.class_defs()[0]
+      .class_data_item()
+      .unwrap()
+      .virtual_methods()[0];
+    let code_item = run.code().unwrap();
+    let invokes: Vec<Vec<u16>> = code_item
+      .instructions()
+      .filter_map(|instruction| instruction.argument_registers())
+      .collect();
+    assert_eq!(invokes, vec![vec![0, 2], vec![1, 2]]);
+    let dump = run.to_string();
+    assert!(dump.contains("invoke-static {v0, p1} ("));
+    assert!(dump.contains("invoke-virtual/range {p0 .. p1} ("));
+  }
+
+  #[test]
+  fn test_superclass_descriptor() {
+    let mut builder = DexBuilder::default();
+    let object = builder.type_id("Ljava/lang/Object;");
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    builder.class(ClassDef::new(object));
+    builder.class(ClassDef::new(a).superclass(object));
+    builder.class(ClassDef::new(b));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let [object, a, b] = dex_file.class_defs() else {
+      panic!("expected three classes");
+    };
+
+    assert!(object.is_root_class());
+    assert_eq!(object.superclass_descriptor(), None);
+    assert!(object
+      .to_string()
+      .contains("Superclass\t: (none, root class)\n"));
+
+    assert!(!a.is_root_class());
+    assert_eq!(a.superclass_descriptor(), Some("Ljava/lang/Object;"));
+    assert!(a.to_string().contains("Superclass\t: Ljava/lang/Object;\n"));
+
+    // No superclass_idx on anything but Object is malformed, not "extends Object".
+    assert!(!b.is_root_class());
+    assert_eq!(b.superclass_descriptor(), None);
+    assert!(b.to_string().contains("Superclass\t: (missing)\n"));
+  }
+
+  #[test]
+  fn test_source_file() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    let source_file = builder.string("A.java");
+    builder.class(ClassDef::new(a).source_file(source_file));
+    builder.class(ClassDef::new(b));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let [a, b] = dex_file.class_defs() else {
+      panic!("expected two classes");
+    };
+
+    assert_eq!(a.source_file(), Some("A.java"));
+    assert_eq!(b.source_file(), None);
+  }
+
+  #[test]
+  fn test_content_hash() {
+    let build = || {
+      let mut builder = DexBuilder::default();
+      let a = builder.type_id("La;");
+      let b = builder.type_id("Lb;");
+      let run = builder.method("La;", "run", "V", &[]);
+      builder.class(ClassDef::new(a).virtual_method(Method::new(run, 0x0401)));
+      builder.class(ClassDef::new(b));
+      builder.build()
+    };
+    let bytes = build();
+    let dex_file = crate::parse(&bytes).unwrap();
+    let [a, b] = dex_file.class_defs() else {
+      panic!("expected two classes");
+    };
+
+    // Four sizes, then the method's index, uleb128 flags and code offset.
+    let range = a.class_data_range().unwrap();
+    assert_eq!(range.len(), 8);
+    assert_eq!(&bytes[range][4..], [0x00, 0x81, 0x08, 0x00]);
+    let hash = a.content_hash(&bytes);
+    let rebuilt = build();
+    let same = crate::parse(&rebuilt).unwrap();
+    assert_eq!(hash, same.class_defs()[0].content_hash(&rebuilt));
+
+    assert_eq!(b.class_data_range(), None);
+    assert_eq!(
+      base::hash::to_hex(&b.content_hash(&bytes)),
+      "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_ne!(hash, b.content_hash(&bytes));
+  }
+
+  #[test]
+  fn test_interfaces() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La/Task;");
+    let b = builder.type_id("Lb;");
+    let runnable = builder.type_id("Ljava/lang/Runnable;");
+    let closeable = builder.type_id("Ljava/io/Closeable;");
+    builder.class(ClassDef::new(a).interface(runnable).interface(closeable));
+    builder.class(ClassDef::new(b));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let [a, b] = dex_file.class_defs() else {
+      panic!("expected two classes");
+    };
+
+    assert_eq!(a.interfaces(), ["java/lang/Runnable", "java/io/Closeable"]);
+    assert!(b.interfaces().is_empty());
+  }
+
+  #[test]
+  fn test_catch_types() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let io = builder.type_id("Ljava/io/IOException;") as u32;
+    let state = builder.type_id("Ljava/lang/IllegalStateException;") as u32;
+    let run = builder.method("La;", "run", "V", &[]);
+    let stop = builder.method("La;", "stop", "V", &[]);
+    // nop, return-void, return-void, return-void, nop
+    let insns = [0x0000, 0x000e, 0x000e, 0x000e, 0x0000];
+    builder.class(
+      ClassDef::new(a)
+        .virtual_method(
+          Method::new(run, 0x0001)
+            .code(1, 1, 0, &insns)
+            .try_block(0, 1, &[(io, 1), (state, 2)], None)
+            .try_block(1, 1, &[(io, 2)], Some(3)),
+        )
+        .virtual_method(Method::new(stop, 0x0001).code(1, 1, 0, &[0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+    let [run, stop] = class_data.virtual_methods() else {
+      panic!("expected two methods");
+    };
+
+    assert_eq!(
+      run.catch_types(),
+      vec!["Ljava/io/IOException;", "Ljava/lang/IllegalStateException;"]
+    );
+    let code_item = run.code().unwrap();
+    let tries = code_item.tries();
+    assert_eq!(tries.len(), 2);
+    assert_eq!((tries[1].start_addr(), tries[1].insn_count()), (1, 1));
+    let handler = code_item.handler(&tries[1]).unwrap();
+    assert_eq!(handler.handlers(), &[(io, 2)]);
+    assert_eq!(handler.catch_all_addr(), Some(3));
+    assert_eq!(code_item.handler(&tries[0]).unwrap().catch_all_addr(), None);
+
+    assert!(stop.catch_types().is_empty());
+    assert!(stop.code().unwrap().tries().is_empty());
+  }
+
+  #[test]
+  fn test_cfg() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let choose = builder.method("La;", "choose", "I", &["Z"]);
+    // 0: if-eqz v0, 4; 2: const/4 v0, 1; 3: goto 5; 4: const/4 v0, 2;
+    // 5: return v0
+    let insns = [0x0038, 0x0004, 0x1012, 0x0228, 0x2012, 0x000f];
+    builder.class(
+      ClassDef::new(a).direct_method(Method::new(choose, 0x0009).code(1, 1, 0, &insns).try_block(
+        0,
+        2,
+        &[],
+        Some(5),
+      )),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+    let code_item = class_data.direct_methods()[0].code().unwrap();
+
+    let cfg = code_item.cfg();
+    assert_eq!(cfg.len(), 4);
+    assert_eq!(
+      cfg.to_string(),
+      "[0, 2) -> fallthrough 2, branch 4, exception 5\n\
+       [2, 4) -> branch 5\n\
+       [4, 5) -> fallthrough 5\n\
+       [5, 6)\n"
+    );
+    assert_eq!(cfg.block(2).unwrap().instructions, [2, 3]);
+    assert_eq!(cfg.predecessors(5), [0, 2, 4]);
+  }
+
+  #[test]
+  fn test_instruction_at() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let run = builder.method("La;", "run", "V", &[]);
+    // 0: const/4 v0, 0; 1: const-wide/16 v0, 7; 3: if-eqz v0, 0; 5: return-void
+    let insns = [0x0012, 0x0016, 0x0007, 0x0038, 0xfffd, 0x000e];
+    builder.class(ClassDef::new(a).direct_method(Method::new(run, 0x0009).code(2, 0, 0, &insns)));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+    let code_item = class_data.direct_methods()[0].code().unwrap();
+
+    let instruction = code_item.instruction_at(3).unwrap();
+    assert_eq!(instruction.mnemonic, Some("if-eqz"));
+    assert_eq!(instruction.units, &[0x0038, 0xfffd]);
+    assert_eq!(instruction.branch_target(), Some(0));
+    assert_eq!(
+      code_item.instruction_at(5).unwrap().mnemonic,
+      Some("return-void")
+    );
+    // The literal of const-wide/16 and past the end.
+    assert_eq!(code_item.instruction_at(2), None);
+    assert_eq!(code_item.instruction_at(6), None);
+  }
+
+  #[test]
+  fn test_class_kind() {
+    let mut builder = DexBuilder::default();
+    let color = builder.type_id("La/Color;");
+    let marker = builder.type_id("La/Marker;");
+    let plain = builder.type_id("La/Plain;");
+    builder.class(ClassDef::new(color).access_flags(0x4011));
+    builder.class(ClassDef::new(marker).access_flags(0x2601));
+    builder.class(ClassDef::new(plain));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let [color, marker, plain] = dex_file.class_defs() else {
+      panic!("expected three classes");
+    };
+
+    assert!(color.is_enum());
+    assert!(!color.is_interface());
+    assert!(color.to_string().starts_with("\tenum a.Color\n"));
+    assert!(marker.is_annotation());
+    assert!(marker.is_interface());
+    assert!(!marker.is_enum());
+    assert!(marker.to_string().starts_with("\tannotation a.Marker\n"));
+    assert!(!plain.is_interface() && !plain.is_enum() && !plain.is_annotation());
+    assert!(plain.to_string().starts_with("\tclass a.Plain\n"));
+  }
+
+  #[test]
+  fn test_all_methods() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    let init = builder.method("La;", "<init>", "V", &[]);
+    let run = builder.method("La;", "run", "Ljava/lang/String;", &["I", "[J"]);
+    let stop = builder.method("Lb;", "stop", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .direct_method(Method::new(init, 0x10001).code(1, 1, 0, &[0x000e]))
+        .virtual_method(Method::new(run, 0x0401)),
+    );
+    builder.class(ClassDef::new(b).virtual_method(Method::new(stop, 0x0401)));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let methods: Vec<String> = dex_file.all_methods().map(|m| m.to_string()).collect();
+    assert_eq!(
+      methods,
+      vec![
+        "La;-><init>: () -> void",
+        "La;->run: (int, long[]) -> java.lang.String",
+        "Lb;->stop: () -> void",
+      ]
+    );
+
+    let resource = concat!(env!("CARGO_MANIFEST_DIR"), "/../../resource/classes.dex");
+    let resource = crate::parse(&std::fs::read(resource).unwrap()).unwrap();
+    let per_class: usize = resource
+      .class_defs()
+      .iter()
+      .filter_map(|class| class.class_data_item())
+      .map(|data| data.direct_method_count() + data.virtual_method_count())
+      .sum();
+    assert!(per_class > 0);
+    assert_eq!(resource.all_methods().count(), per_class);
+  }
+
+  #[test]
+  fn test_field_accessors() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let count = builder.field("La;", "I", "count");
+    let name = builder.field("La;", "Ljava/lang/String;", "name");
+    let unused = builder.field("La;", "J", "unused");
+    let increment = builder.method("La;", "increment", "V", &[]);
+    let get_name = builder.method("La;", "getName", "Ljava/lang/String;", &[]);
+    let reset = builder.method("La;", "reset", "V", &[]);
+    let stop = builder.method("La;", "stop", "V", &[]);
+    #[rustfmt::skip]
+    let increment_insns = [
+      0x0060, count as u16,  // sget v0, La;->count:I
+      0x01d8, 0x0100,        // add-int/lit8 v0, v0, 1
+      0x0067, count as u16,  // sput v0, La;->count:I
+      0x0060, count as u16,  // sget v0, La;->count:I
+      0x000e,                // return-void
+    ];
+    #[rustfmt::skip]
+    let get_name_insns = [
+      0x1054, name as u16,   // iget-object v0, v1, La;->name:Ljava/lang/String;
+      0x0011,                // return-object v0
+    ];
+    builder.class(
+      ClassDef::new(a)
+        .static_field(count, 0x0008)
+        .instance_field(name, 0x0002)
+        .instance_field(unused, 0x0002)
+        .direct_method(Method::new(increment, 0x0008).code(1, 0, 0, &increment_insns))
+        .direct_method(Method::new(reset, 0x0008).code(
+          1,
+          0,
+          0,
+          &[0x0012, 0x0067, count as u16, 0x000e],
+        ))
+        .virtual_method(Method::new(get_name, 0x0001).code(2, 1, 0, &get_name_insns))
+        .virtual_method(Method::new(stop, 0x0401)),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let names = |field_idx: u32| -> Vec<&str> {
+      dex_file
+        .field_accessors(field_idx)
+        .iter()
+        .map(|method| method.name())
+        .collect()
+    };
+    // Read and written: each method once.
+    assert_eq!(names(count), ["increment", "reset"]);
+    assert_eq!(names(name), ["getName"]);
+    assert!(names(unused).is_empty());
+    assert!(names(100).is_empty());
+    assert_eq!(
+      dex_file.field_accessors(name)[0].proto().descriptor(),
+      "()Ljava/lang/String;"
+    );
+  }
+
+  #[test]
+  fn test_find_method() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    let run = builder.method("La;", "run", "V", &[]);
+    let run_with = builder.method("La;", "run", "V", &["I", "Ljava/lang/String;"]);
+    let stop = builder.method("La;", "stop", "V", &[]);
+    let start = builder.method("Lb;", "start", "J", &[]);
+    let stop_b = builder.method("Lb;", "stop", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .virtual_method(Method::new(run, 0x0001).code(1, 1, 0, &[0x000e]))
+        .virtual_method(Method::new(run_with, 0x0001).code(3, 3, 0, &[0x0012, 0x000e]))
+        .virtual_method(Method::new(stop, 0x0401)),
+    );
+    builder.class(
+      ClassDef::new(b)
+        .virtual_method(Method::new(start, 0x0001).code(2, 1, 0, &[0x0016, 0x0010]))
+        .virtual_method(Method::new(stop_b, 0x0001).code(1, 1, 0, &[0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(dex_file.methods().count(), 5);
+
+    let start = dex_file.find_method("start").unwrap();
+    assert_eq!(start.descriptor(), "()J");
+    assert_eq!(start.class_descriptor(), "Lb;");
+    let run_with = dex_file.find_method("run(ILjava/lang/String;)V").unwrap();
+    assert_eq!(run_with.code().unwrap().raw_insns(), [0x0012, 0x000e]);
+    let stop = dex_file.find_method("La;->stop()V").unwrap();
+    assert!(stop.code().is_none());
+
+    let error = dex_file.find_method("run").err().unwrap();
+    assert_eq!(
+      error.to_string(),
+      "run matches La;->run()V, La;->run(ILjava/lang/String;)V, add the descriptor"
+    );
+    assert!(matches!(
+      dex_file.find_method("stop()V").err().unwrap().kind(),
+      ErrorKind::AmbiguousMethod { candidates, .. } if candidates.len() == 2
+    ));
+    assert!(matches!(
+      dex_file.find_method("pause").err().unwrap().kind(),
+      ErrorKind::MethodNotFound { query } if query == "pause"
+    ));
+  }
+
+  #[test]
+  fn test_display_indent() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let count = builder.field("La;", "I", "count");
+    let run = builder.method("La;", "run", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .instance_field(count, 0x0002)
+        .virtual_method(Method::new(run, 0x0001).code(1, 1, 0, &[0x0013, 0x0005, 0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_def = &dex_file.class_defs()[0];
+
+    assert_eq!(
+      class_def.display_indent(Indent::TABS).to_string(),
+      class_def.to_string()
+    );
+    let rendered = class_def.display_indent(Indent::spaces(2)).to_string();
+    assert!(!rendered.contains('\t'));
+    assert!(rendered.starts_with("  class a\n  Class descriptor  : La;\n"));
+    assert!(rendered.contains("\n  Instance fields (1)  -\n    #0  : (in La;)\n  name  : count\n"));
+    assert!(rendered.contains("\n    name  : run\n"));
+    // const/16 v0, 5; return-void
+    assert!(rendered.contains(
+      "\n      0000:  0013  const/16 (const)\n      \
+       0001:  0005\n      \
+       0002:  000e  return-void (return)\n"
+    ));
+    assert!(dex_file
+      .display_indent(Indent::spaces(4))
+      .to_string()
+      .contains("Class #0: \n    class a\n"));
+  }
+
+  #[test]
+  fn test_member_counts() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    let max = builder.field("La;", "I", "MAX");
+    let count = builder.field("La;", "I", "count");
+    let name = builder.field("La;", "Ljava/lang/String;", "name");
+    let init = builder.method("La;", "<init>", "V", &[]);
+    let run = builder.method("La;", "run", "V", &[]);
+    let stop = builder.method("La;", "stop", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .static_field(max, 0x0019)
+        .instance_field(count, 0x0002)
+        .instance_field(name, 0x0002)
+        .direct_method(Method::new(init, 0x10001).code(1, 1, 0, &[0x000e]))
+        .virtual_method(Method::new(run, 0x0401))
+        .virtual_method(Method::new(stop, 0x0401)),
+    );
+    builder.class(ClassDef::new(b));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let [a, b] = dex_file.class_defs() else {
+      panic!("expected two classes");
+    };
+
+    let class_data = a.class_data_item().unwrap();
+    assert_eq!(class_data.static_field_count(), 1);
+    assert_eq!(class_data.instance_field_count(), 2);
+    assert_eq!(class_data.direct_method_count(), 1);
+    assert_eq!(class_data.virtual_method_count(), 2);
+    let rendered = a.to_string();
+    assert!(rendered.contains("\tStatic fields (1)\t-\n"));
+    assert!(rendered.contains("\tInstance fields (2)\t-\n"));
+    assert!(rendered.contains("\tDirect methods (1)\t-\n"));
+    assert!(rendered.contains("\tVirtual methods (2)\t-\n"));
+
+    assert!(b.class_data_item().is_none());
+    let rendered = b.to_string();
+    assert!(rendered.contains("\tStatic fields\t: (none)\n"));
+    assert!(rendered.contains("\tVirtual methods\t: (none)\n"));
+
+    let counts: Vec<String> = dex_file
+      .counts()
+      .into_iter()
+      .map(|(key, value)| format!("{}: {}", key, value))
+      .collect();
+    assert_eq!(
+      counts,
+      [
+        "classes: 2",
+        "methods: 3",
+        "fields: 3",
+        "strings: 11",
+        "types: 5",
+        "protos: 3",
+        "field_ids: 3",
+        "method_ids: 3",
+        "call_sites: 0",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_referenced_types() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let object = builder.type_id("Ljava/lang/Object;");
+    let runnable = builder.type_id("Ljava/lang/Runnable;");
+    let b = builder.type_id("Lb;");
+    let ints = builder.type_id("[I");
+    builder.type_id("Lunused;");
+    let list = builder.field("La;", "Ljava/util/List;", "list");
+    let out = builder.field("Lc;", "Ljava/io/PrintStream;", "out");
+    let println = builder.method(
+      "Ljava/io/PrintStream;",
+      "println",
+      "V",
+      &["[Ljava/lang/String;"],
+    );
+    let run = builder.method("La;", "run", "V", &[]);
+    #[rustfmt::skip]
+    let insns = [
+      0x0022, b,                   // new-instance v0, Lb;
+      0x001c, ints,                // const-class v0, [I
+      0x0062, out as u16,          // sget-object v0, Lc;->out
+      0x106e, println as u16, 0x0, // invoke-virtual {v0}, PrintStream.println
+      0x000e,                      // return-void
+    ];
+    builder.class(
+      ClassDef::new(a)
+        .superclass(object)
+        .interface(runnable)
+        .instance_field(list, 0x0002)
+        .virtual_method(Method::new(run, 0x0001).code(1, 1, 1, &insns)),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(
+      dex_file.class_defs()[0].referenced_types(),
+      vec![
+        "b",
+        "c",
+        "java.io.PrintStream",
+        "java.lang.Object",
+        "java.lang.Runnable",
+        "java.lang.String",
+        "java.util.List"
+      ]
+    );
+    let types = dex_file.referenced_types();
+    assert!(types.contains(&"unused".to_string()));
+    assert!(!types.contains(&"a".to_string()));
+  }
+
+  #[test]
+  fn test_synthetic_markers() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let compare = builder.method("La;", "compareTo", "I", &["Ljava/lang/Object;"]);
+    let run = builder.method("La;", "run", "V", &[]);
+    let this = builder.field("La;", "Lb;", "this$0");
+    let count = builder.field("La;", "I", "count");
+    builder.class(
+      ClassDef::new(a)
+        .instance_field(this, 0x1010)
+        .instance_field(count, 0x0002)
+        // public bridge synthetic
+        .virtual_method(Method::new(compare, 0x1041).code(3, 2, 2, &[0x000e]))
+        .virtual_method(Method::new(run, 0x0001).code(1, 1, 0, &[0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+
+    let compare = &class_data.virtual_methods()[0];
+    assert!(compare.is_synthetic());
+    assert!(compare.is_bridge());
+    assert!(compare
+      .to_string()
+      .contains("markers\t: synthetic, bridge\n"));
+    let run = &class_data.virtual_methods()[1];
+    assert!(!run.is_synthetic() && !run.is_bridge());
+    assert!(!run.to_string().contains("markers"));
+
+    let fields = class_data.instance_fields();
+    assert!(fields[0].is_synthetic());
+    assert!(fields[0].to_string().ends_with("markers\t: synthetic"));
+    assert!(!fields[1].is_synthetic());
+  }
+
+  #[test]
+  fn test_register_counts() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let add = builder.method("La;", "add", "I", &["I", "J"]);
+    let sum = builder.method("La;", "sum", "J", &["J", "I"]);
+    let run = builder.method("La;", "run", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .direct_method(Method::new(sum, 0x0009).code(2, 3, 0, &[0x000e]))
+        .virtual_method(Method::new(add, 0x0001).code(5, 4, 0, &[0x000e]))
+        .virtual_method(Method::new(run, 0x0001).code(2, 2, 0, &[0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+
+    let add = &class_data.virtual_methods()[0];
+    assert_eq!(add.expected_ins_size(), 4);
+    assert!(add.validate().is_empty());
+    let text = add.to_string();
+    assert!(text.contains("registers\t: 5\n"));
+    assert!(text.contains("ins\t: 4 (this + 3 parameter registers)"));
+
+    // More ins than registers.
+    let sum = &class_data.direct_methods()[0];
+    assert!(sum.to_string().contains("ins\t: 3\n"));
+    assert_eq!(
+      sum.validate(),
+      vec![Warning::RegisterCounts {
+        method: "sum(JJI)".to_string(),
+        registers: 2,
+        ins: 3,
+        expected_ins: 3,
+      }]
+    );
+    // Ins that don't match the signature.
+    let run = &class_data.virtual_methods()[1];
+    assert_eq!(
+      run.validate()[0].to_string(),
+      "method run(V) has 2 registers and 2 ins, its signature needs 1 ins"
+    );
+    assert_eq!(without_string_id_warnings(dex_file.validate()).len(), 2);
+  }
+
+  #[test]
+  fn test_opcode_coverage() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let run = builder.method("La;", "run", "V", &[]);
+    let init = builder.method("La;", "<init>", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .direct_method(Method::new(init, 0x0001).code(1, 1, 0, &[0x000e]))
+        .virtual_method(Method::new(run, 0x0001).code(2, 1, 0, &[0x0012, 0x003e, 0x000e])),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+    let names: Vec<_> = class_data.methods().map(|method| method.name()).collect();
+    assert_eq!(names, vec!["<init>", "run"]);
+
+    let coverage = dex_file.opcode_coverage();
+    assert_eq!(
+      coverage.known(),
+      vec![(0x0e, "return-void", 2), (0x12, "const/4", 1)]
+    );
+    assert_eq!(coverage.unknown(), vec![(0x3e, 1)]);
+    assert_eq!(
+      dex_file.opcode_histogram(),
+      HashMap::from([("return-void", 2), ("const/4", 1)])
+    );
+
+    let resource = concat!(env!("CARGO_MANIFEST_DIR"), "/../../resource/classes.dex");
+    let resource = crate::parse(&std::fs::read(resource).unwrap()).unwrap();
+    let coverage = resource.opcode_coverage();
+    assert!(coverage.count(0x0e) > 0);
+    assert!(coverage.count(0x70) > 0);
+  }
+
+  #[test]
+  fn test_static_values() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let max = builder.field("La;", "I", "MAX");
+    let enabled = builder.field("La;", "Z", "ENABLED");
+    let name = builder.field("La;", "Ljava/lang/String;", "NAME");
+    let count = builder.field("La;", "J", "count");
+    let hello = builder.string("hello") as u8;
+    // MAX = 10, ENABLED = true, NAME = "hello"; count has no entry.
+    let values = [0x03, 0x04, 0x0a, 0x3f, 0x17, hello];
+    builder.class(
+      ClassDef::new(a)
+        .static_field(max, 0x0019)
+        .static_field(enabled, 0x0019)
+        .static_field(name, 0x0019)
+        .static_field(count, 0x0009)
+        .static_values(&values),
+    );
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let fields = dex_file.class_defs()[0]
+      .class_data_item()
+      .unwrap()
+      .static_fields();
+    let values: Vec<_> = fields.iter().map(|field| field.constant_value()).collect();
+    assert_eq!(
+      values,
+      vec![
+        Some(&LiteralValue::Int(10)),
+        Some(&LiteralValue::Boolean(true)),
+        Some(&LiteralValue::String("hello".to_string())),
+        None,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_malformed_static_values() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let max = builder.field("La;", "I", "MAX");
+    // A value of type 0x05, which doesn't exist.
+    builder.class(
+      ClassDef::new(a)
+        .static_field(max, 0x0019)
+        .static_values(&[0x01, 0x05]),
+    );
+    let mut bytes = builder.build();
+    assert!(crate::parse(&bytes).is_err());
+
+    // static_values_off, the last field of the class_def_item, past the end.
+    let class_defs_off = u32::from_le_bytes(bytes[0x64..0x68].try_into().unwrap()) as usize;
+    let static_values_off = class_defs_off + 28;
+    bytes[static_values_off..static_values_off + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(crate::parse(&bytes).is_err());
+  }
+}
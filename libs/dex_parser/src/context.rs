@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use base::parse_limits::ParseLimits;
+
+use crate::raw_dex::{FieldIdItem, MethodIdItem, ProtoIdItem, StringIdItem, TypeIdItem, TypeList};
+
+/// Owns the string/type/proto/field/method id tables of a parsed dex file.
+///
+/// The tables are kept as the raw indices read from the id sections; the
+/// `get_*` lookups build the resolved items on demand. `DexFile` and every
+/// item parsed from it keep an `Arc` to the same context, so several dex
+/// files can be parsed and inspected side by side.
+#[derive(Default)]
+pub struct ResolveContext {
+  string_ids: Vec<StringIdItem>,
+  /// `descriptor_idx` of each type id.
+  type_ids: Vec<u32>,
+  /// `(shorty_idx, return_type_idx, parameters_off, parameter type indices)`.
+  proto_ids: Vec<(u32, u32, u32, Option<Vec<u16>>)>,
+  /// `(class_idx, type_idx, name_idx)`.
+  field_ids: Vec<(u16, u16, u32)>,
+  /// `(class_idx, proto_idx, name_idx)`.
+  method_ids: Vec<(u16, u16, u32)>,
+  /// What the items parsed after the id tables, class data and code, are
+  /// checked against.
+  limits: ParseLimits,
+}
+
+impl ResolveContext {
+  pub fn new(
+    string_ids: Vec<StringIdItem>,
+    type_ids: Vec<u32>,
+    proto_ids: Vec<(u32, u32, u32, Option<Vec<u16>>)>,
+    field_ids: Vec<(u16, u16, u32)>,
+    method_ids: Vec<(u16, u16, u32)>,
+  ) -> Self {
+    Self {
+      string_ids,
+      type_ids,
+      proto_ids,
+      field_ids,
+      method_ids,
+      limits: ParseLimits::DEFAULT,
+    }
+  }
+
+  pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+    self.limits = limits;
+    self
+  }
+
+  pub fn limits(&self) -> &ParseLimits {
+    &self.limits
+  }
+
+  pub fn string_ids(&self) -> &[StringIdItem] {
+    &self.string_ids
+  }
+
+  pub fn get_str_const(&self, index: usize) -> &str {
+    self.string_ids[index].string_data.as_str()
+  }
+
+  /// Descriptor of type id `index`, `None` when it is out of range.
+  pub fn find_type_descriptor(&self, index: usize) -> Option<&str> {
+    let descriptor_idx = *self.type_ids.get(index)?;
+    let string_id = self.string_ids.get(descriptor_idx as usize)?;
+    Some(string_id.string_data.as_str())
+  }
+
+  /// `(class descriptor, name, type descriptor)` of field id `index`,
+  /// `None` when it or an id it points at is out of range.
+  pub fn find_field(&self, index: usize) -> Option<(&str, &str, &str)> {
+    let (class_idx, type_idx, name_idx) = *self.field_ids.get(index)?;
+    Some((
+      self.find_type_descriptor(class_idx as usize)?,
+      self.string_ids.get(name_idx as usize)?.string_data.as_str(),
+      self.find_type_descriptor(type_idx as usize)?,
+    ))
+  }
+
+  pub fn type_ids_size(&self) -> usize {
+    self.type_ids.len()
+  }
+
+  pub fn proto_ids_size(&self) -> usize {
+    self.proto_ids.len()
+  }
+
+  pub fn field_ids_size(&self) -> usize {
+    self.field_ids.len()
+  }
+
+  pub fn method_ids_size(&self) -> usize {
+    self.method_ids.len()
+  }
+
+  pub fn get_type_id(self: &Arc<Self>, index: usize) -> TypeIdItem {
+    TypeIdItem::new(self.type_ids[index], self)
+  }
+
+  pub fn get_proto_id(self: &Arc<Self>, index: usize) -> ProtoIdItem {
+    let (shorty_idx, return_type_idx, parameters_off, parameters) = &self.proto_ids[index];
+    let parameters_type_list = parameters
+      .as_ref()
+      .map(|parameters| TypeList::new(parameters, self));
+    ProtoIdItem::new(
+      *shorty_idx,
+      *return_type_idx,
+      self.get_type_id(*return_type_idx as usize),
+      *parameters_off,
+      parameters_type_list,
+      self,
+    )
+  }
+
+  pub fn get_field_id(self: &Arc<Self>, index: usize) -> FieldIdItem {
+    let (class_idx, type_idx, name_idx) = self.field_ids[index];
+    FieldIdItem::new(
+      class_idx,
+      self.get_type_id(class_idx as usize),
+      type_idx,
+      self.get_type_id(type_idx as usize),
+      name_idx,
+      self,
+    )
+  }
+
+  pub fn get_method_id(self: &Arc<Self>, index: usize) -> MethodIdItem {
+    let (class_idx, proto_idx, name_idx) = self.method_ids[index];
+    MethodIdItem::new(
+      class_idx,
+      self.get_type_id(class_idx as usize),
+      proto_idx,
+      self.get_proto_id(proto_idx as usize),
+      name_idx,
+      self,
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::test_util::DexBuilder;
+
+  #[test]
+  fn test_contexts_are_isolated() {
+    let mut first = DexBuilder::default();
+    let first_method = first.method("La;", "run", "V", &[]);
+    let first = crate::parse(&first.build()).unwrap();
+
+    let mut second = DexBuilder::default();
+    second.string("padding");
+    let second_method = second.method("Lb;", "stop", "V", &["I"]);
+    let second = crate::parse(&second.build()).unwrap();
+
+    let run = first.context().get_method_id(first_method as usize);
+    let stop = second.context().get_method_id(second_method as usize);
+    assert_eq!(run.name(), "run");
+    assert_eq!(stop.name(), "stop");
+    assert_eq!(run.param_type(), "V");
+    assert_eq!(stop.param_type(), "VI");
+  }
+
+  #[test]
+  fn test_proto_signature() {
+    let mut builder = DexBuilder::default();
+    let run = builder.proto("V", &[]);
+    let concat = builder.proto("Ljava/lang/String;", &["I", "[Ljava/lang/String;"]);
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let run = dex_file.context().get_proto_id(run as usize);
+    assert_eq!(run.signature(), "() -> void");
+    assert_eq!(run.parameters_off(), 0);
+    let concat = dex_file.context().get_proto_id(concat as usize);
+    assert_eq!(
+      concat.signature(),
+      "(int, java.lang.String[]) -> java.lang.String"
+    );
+    assert_ne!(concat.parameters_off(), 0);
+    assert_eq!(
+      concat.to_string(),
+      "shorty: LIL, signature: (int, java.lang.String[]) -> java.lang.String\n"
+    );
+  }
+}
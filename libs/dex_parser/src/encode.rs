@@ -0,0 +1,91 @@
+//! Encoders for the data formats the parser reads.
+
+/// Encodes `value` as dex MUTF-8 string data, without the uleb128 utf16 size
+/// prefix and the terminating NUL.
+///
+/// MUTF-8 differs from UTF-8 in two places: NUL is written as the two-byte
+/// form `C0 80` so that the encoded string never contains a zero byte, and
+/// characters outside the BMP are written as their UTF-16 surrogate pair,
+/// three bytes per surrogate, instead of a single four-byte sequence.
+pub fn encode_mutf8(value: &str) -> Vec<u8> {
+  let mut bytes = Vec::with_capacity(value.len());
+  for unit in value.encode_utf16() {
+    match unit {
+      0x01..=0x7f => bytes.push(unit as u8),
+      0x00 | 0x80..=0x7ff => {
+        bytes.push(0xc0 | (unit >> 6) as u8);
+        bytes.push(0x80 | (unit & 0x3f) as u8);
+      }
+      _ => {
+        bytes.push(0xe0 | (unit >> 12) as u8);
+        bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+        bytes.push(0x80 | (unit & 0x3f) as u8);
+      }
+    }
+  }
+  bytes
+}
+
+#[cfg(test)]
+mod tests {
+  use base::mutf8::decode_mutf8;
+
+  use super::encode_mutf8;
+  use crate::leb128::parse_uleb128;
+
+  fn round_trip(value: &str) -> Vec<u8> {
+    let bytes = encode_mutf8(value);
+    let mut terminated = bytes.clone();
+    terminated.push(0);
+    let (rest, decoded) = decode_mutf8::<nom::error::Error<_>>(&terminated).unwrap();
+    assert_eq!(decoded, value);
+    assert_eq!(rest, &[0]);
+    bytes
+  }
+
+  #[test]
+  fn test_encode_mutf8() {
+    assert_eq!(round_trip("Ljava/lang/Object;"), b"Ljava/lang/Object;");
+    assert_eq!(round_trip("a\0b"), [b'a', 0xc0, 0x80, b'b']);
+    assert_eq!(round_trip("\u{e9}"), [0xc3, 0xa9]);
+    assert_eq!(round_trip("\u{4e2d}"), [0xe4, 0xb8, 0xad]);
+    // U+1F600 is the surrogate pair D83D DE00, not the UTF-8 F0 9F 98 80.
+    assert_eq!(
+      round_trip("\u{1f600}"),
+      [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]
+    );
+  }
+
+  #[test]
+  fn test_parse_mutf8_strings() {
+    let mut builder = crate::test_util::DexBuilder::default();
+    let value = "caf\u{e9}\0\u{1f600}";
+    let index = builder.string(value);
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    assert_eq!(dex_file.context().get_str_const(index as usize), value);
+  }
+
+  #[test]
+  fn test_string_table_round_trip() {
+    let bytes = std::fs::read(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/../../resource/classes.dex"
+    ))
+    .unwrap();
+    let dex_file = crate::parse(&bytes).unwrap();
+    let string_ids = dex_file.context().string_ids();
+    assert!(!string_ids.is_empty());
+    for string_id in string_ids {
+      let offset = string_id.string_data_off() as usize;
+      let (utf16_size, prefix) = parse_uleb128(&bytes[offset..]);
+      let data = &bytes[offset + prefix..];
+      let original = &data[..data.iter().position(|&b| b == 0).unwrap()];
+      let encoded = encode_mutf8(&string_id.string_data);
+      assert_eq!(encoded, original, "{:?}", string_id.string_data);
+      assert_eq!(
+        string_id.string_data.encode_utf16().count(),
+        utf16_size as usize
+      );
+    }
+  }
+}
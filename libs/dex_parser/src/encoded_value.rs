@@ -0,0 +1,452 @@
+//! `encoded_value`, `encoded_array` and `encoded_annotation`, used by static
+//! values, annotations and call sites.
+//!
+//! https://source.android.com/docs/core/runtime/dex-format#encoding
+
+use std::{fmt::Display, sync::Arc};
+
+use base::{literal::LiteralValue, parse_limits::ParseLimits};
+use nom::{
+  bytes::complete::take,
+  error::{ErrorKind, ParseError},
+  number::complete::le_u8,
+  IResult,
+};
+
+use crate::{context::ResolveContext, leb128::parse_uleb128_nom};
+
+const VALUE_BYTE: u8 = 0x00;
+const VALUE_SHORT: u8 = 0x02;
+const VALUE_CHAR: u8 = 0x03;
+const VALUE_INT: u8 = 0x04;
+const VALUE_LONG: u8 = 0x06;
+const VALUE_FLOAT: u8 = 0x10;
+const VALUE_DOUBLE: u8 = 0x11;
+const VALUE_METHOD_TYPE: u8 = 0x15;
+const VALUE_METHOD_HANDLE: u8 = 0x16;
+const VALUE_STRING: u8 = 0x17;
+const VALUE_TYPE: u8 = 0x18;
+const VALUE_FIELD: u8 = 0x19;
+const VALUE_METHOD: u8 = 0x1a;
+const VALUE_ENUM: u8 = 0x1b;
+const VALUE_ARRAY: u8 = 0x1c;
+const VALUE_ANNOTATION: u8 = 0x1d;
+const VALUE_NULL: u8 = 0x1e;
+const VALUE_BOOLEAN: u8 = 0x1f;
+
+/// A decoded `encoded_value`. Index variants hold the raw index into the
+/// corresponding id list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodedValue {
+  Byte(i8),
+  Short(i16),
+  Char(u16),
+  Int(i32),
+  Long(i64),
+  Float(f32),
+  Double(f64),
+  MethodType(u32),
+  MethodHandle(u32),
+  String(u32),
+  Type(u32),
+  Field(u32),
+  Method(u32),
+  Enum(u32),
+  Array(Vec<EncodedValue>),
+  Annotation(EncodedAnnotation),
+  Null,
+  Boolean(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedAnnotation {
+  pub type_idx: u32,
+  /// `(name_idx, value)` pairs.
+  pub elements: Vec<(u32, EncodedValue)>,
+}
+
+/// Reads `size` little-endian bytes, at most 8.
+fn parse_sized<'a, E: ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+  size: usize,
+  max_size: usize,
+) -> IResult<&'a [u8], u64, E> {
+  if size > max_size {
+    return Err(nom::Err::Error(E::from_error_kind(
+      bytes,
+      ErrorKind::Verify,
+    )));
+  }
+  let (bytes, value) = take(size)(bytes)?;
+  let value = value
+    .iter()
+    .rev()
+    .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+  Ok((bytes, value))
+}
+
+fn sign_extend(value: u64, size: usize) -> i64 {
+  let shift = 64 - size * 8;
+  ((value << shift) as i64) >> shift
+}
+
+impl EncodedValue {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+    Self::parse_with_limits(bytes, &ParseLimits::DEFAULT)
+  }
+
+  /// Fails with `ErrorKind::TooLarge` on arrays and annotations nested
+  /// deeper than `limits.max_depth`, or holding more than `limits.max_items`
+  /// values.
+  pub fn parse_with_limits<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    limits: &ParseLimits,
+  ) -> IResult<&'a [u8], Self, E> {
+    Self::parse_nested(bytes, limits, 0)
+  }
+
+  fn parse_nested<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    limits: &ParseLimits,
+    nesting: usize,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, header) = le_u8(bytes)?;
+    let value_type = header & 0x1f;
+    let value_arg = (header >> 5) as usize;
+    let size = value_arg + 1;
+    let index = |bytes| parse_sized(bytes, size, 4).map(|(bytes, v)| (bytes, v as u32));
+    match value_type {
+      VALUE_BYTE => {
+        let (bytes, value) = parse_sized(bytes, size, 1)?;
+        Ok((bytes, EncodedValue::Byte(value as i8)))
+      }
+      VALUE_SHORT => {
+        let (bytes, value) = parse_sized(bytes, size, 2)?;
+        Ok((bytes, EncodedValue::Short(sign_extend(value, size) as i16)))
+      }
+      VALUE_CHAR => {
+        let (bytes, value) = parse_sized(bytes, size, 2)?;
+        Ok((bytes, EncodedValue::Char(value as u16)))
+      }
+      VALUE_INT => {
+        let (bytes, value) = parse_sized(bytes, size, 4)?;
+        Ok((bytes, EncodedValue::Int(sign_extend(value, size) as i32)))
+      }
+      VALUE_LONG => {
+        let (bytes, value) = parse_sized(bytes, size, 8)?;
+        Ok((bytes, EncodedValue::Long(sign_extend(value, size))))
+      }
+      // Floating point values are zero-extended to the right: the bytes
+      // given are the high-order ones.
+      VALUE_FLOAT => {
+        let (bytes, value) = parse_sized(bytes, size, 4)?;
+        let bits = (value as u32) << ((4 - size) * 8);
+        Ok((bytes, EncodedValue::Float(f32::from_bits(bits))))
+      }
+      VALUE_DOUBLE => {
+        let (bytes, value) = parse_sized(bytes, size, 8)?;
+        let bits = value << ((8 - size) * 8);
+        Ok((bytes, EncodedValue::Double(f64::from_bits(bits))))
+      }
+      VALUE_METHOD_TYPE => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::MethodType(v))),
+      VALUE_METHOD_HANDLE => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::MethodHandle(v))),
+      VALUE_STRING => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::String(v))),
+      VALUE_TYPE => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::Type(v))),
+      VALUE_FIELD => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::Field(v))),
+      VALUE_METHOD => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::Method(v))),
+      VALUE_ENUM => index(bytes).map(|(bytes, v)| (bytes, EncodedValue::Enum(v))),
+      VALUE_ARRAY => {
+        limits.check_depth(bytes, nesting)?;
+        let (bytes, values) = parse_encoded_array_nested(bytes, limits, nesting + 1)?;
+        Ok((bytes, EncodedValue::Array(values)))
+      }
+      VALUE_ANNOTATION => {
+        limits.check_depth(bytes, nesting)?;
+        let (bytes, annotation) = EncodedAnnotation::parse_nested(bytes, limits, nesting + 1)?;
+        Ok((bytes, EncodedValue::Annotation(annotation)))
+      }
+      VALUE_NULL => Ok((bytes, EncodedValue::Null)),
+      VALUE_BOOLEAN => Ok((bytes, EncodedValue::Boolean(value_arg != 0))),
+      _ => Err(nom::Err::Error(E::from_error_kind(bytes, ErrorKind::Tag))),
+    }
+  }
+
+  /// The value as a field initializer; `None` for values a field can't be
+  /// initialized with, such as types and method handles.
+  pub fn to_literal(&self, context: &ResolveContext) -> Option<LiteralValue> {
+    let literal = match self {
+      EncodedValue::Byte(value) => LiteralValue::Byte(*value),
+      EncodedValue::Short(value) => LiteralValue::Short(*value),
+      EncodedValue::Char(value) => LiteralValue::Char(*value),
+      EncodedValue::Int(value) => LiteralValue::Int(*value),
+      EncodedValue::Long(value) => LiteralValue::Long(*value),
+      EncodedValue::Float(value) => LiteralValue::Float(*value),
+      EncodedValue::Double(value) => LiteralValue::Double(*value),
+      EncodedValue::String(index) => {
+        let string_id = context.string_ids().get(*index as usize)?;
+        LiteralValue::String(string_id.string_data.clone())
+      }
+      EncodedValue::Boolean(value) => LiteralValue::Boolean(*value),
+      EncodedValue::Null => LiteralValue::Null,
+      _ => return None,
+    };
+    Some(literal)
+  }
+
+  /// Shows the value with the ids it holds resolved against `context`.
+  pub fn display<'a>(&'a self, context: &'a Arc<ResolveContext>) -> EncodedValueDisplay<'a> {
+    EncodedValueDisplay {
+      value: self,
+      context,
+    }
+  }
+}
+
+/// An [`EncodedValue`] as Java-like source: literals in Java syntax, types
+/// and method types as descriptors, fields as `Lcom/Foo;.count:I`, enum
+/// constants as `Lcom/Foo$Color;.RED`, arrays in braces and annotations as
+/// `@Lcom/Foo;(name=value)`. Ids that can't be resolved are shown raw.
+pub struct EncodedValueDisplay<'a> {
+  value: &'a EncodedValue,
+  context: &'a Arc<ResolveContext>,
+}
+
+impl Display for EncodedValueDisplay<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let context = self.context;
+    if let Some(literal) = self.value.to_literal(context) {
+      return write!(f, "{}", literal);
+    }
+    match self.value {
+      EncodedValue::MethodType(idx) if (*idx as usize) < context.proto_ids_size() => {
+        write!(f, "{}", context.get_proto_id(*idx as usize).descriptor())
+      }
+      EncodedValue::MethodHandle(idx) => write!(f, "method handle @{}", idx),
+      EncodedValue::Type(idx) => match context.find_type_descriptor(*idx as usize) {
+        Some(descriptor) => write!(f, "{}", descriptor),
+        None => write!(f, "{:?}", self.value),
+      },
+      EncodedValue::Field(idx) => match context.find_field(*idx as usize) {
+        Some((class, name, descriptor)) => write!(f, "{}.{}:{}", class, name, descriptor),
+        None => write!(f, "{:?}", self.value),
+      },
+      EncodedValue::Enum(idx) => match context.find_field(*idx as usize) {
+        Some((class, name, _)) => write!(f, "{}.{}", class, name),
+        None => write!(f, "{:?}", self.value),
+      },
+      EncodedValue::Array(values) => {
+        let values: Vec<String> = values
+          .iter()
+          .map(|value| value.display(context).to_string())
+          .collect();
+        write!(f, "{{{}}}", values.join(", "))
+      }
+      EncodedValue::Annotation(annotation) => write!(f, "{}", annotation.display(context)),
+      _ => write!(f, "{:?}", self.value),
+    }
+  }
+}
+
+/// An `encoded_array`: a uleb128 size followed by that many values.
+pub fn parse_encoded_array<'a, E: ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+) -> IResult<&'a [u8], Vec<EncodedValue>, E> {
+  parse_encoded_array_with_limits(bytes, &ParseLimits::DEFAULT)
+}
+
+/// Like `parse_encoded_array`, see `EncodedValue::parse_with_limits`.
+pub fn parse_encoded_array_with_limits<'a, E: ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+  limits: &ParseLimits,
+) -> IResult<&'a [u8], Vec<EncodedValue>, E> {
+  parse_encoded_array_nested(bytes, limits, 0)
+}
+
+fn parse_encoded_array_nested<'a, E: ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+  limits: &ParseLimits,
+  nesting: usize,
+) -> IResult<&'a [u8], Vec<EncodedValue>, E> {
+  let (bytes, size) = parse_uleb128_nom(bytes)?;
+  limits.count(
+    |bytes| EncodedValue::parse_nested(bytes, limits, nesting),
+    size as usize,
+  )(bytes)
+}
+
+impl EncodedAnnotation {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&'a [u8], Self, E> {
+    Self::parse_nested(bytes, &ParseLimits::DEFAULT, 0)
+  }
+
+  fn parse_nested<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    limits: &ParseLimits,
+    nesting: usize,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, type_idx) = parse_uleb128_nom(bytes)?;
+    let (bytes, size) = parse_uleb128_nom(bytes)?;
+    let (bytes, elements) = limits.count(
+      |bytes| {
+        let (bytes, name_idx) = parse_uleb128_nom(bytes)?;
+        let (bytes, value) = EncodedValue::parse_nested(bytes, limits, nesting)?;
+        Ok((bytes, (name_idx, value)))
+      },
+      size as usize,
+    )(bytes)?;
+    Ok((bytes, Self { type_idx, elements }))
+  }
+
+  /// `@Lcom/Foo;(name=value, ...)`, see [`EncodedValue::display`].
+  pub fn display<'a>(&'a self, context: &'a Arc<ResolveContext>) -> EncodedAnnotationDisplay<'a> {
+    EncodedAnnotationDisplay {
+      annotation: self,
+      context,
+    }
+  }
+}
+
+pub struct EncodedAnnotationDisplay<'a> {
+  annotation: &'a EncodedAnnotation,
+  context: &'a Arc<ResolveContext>,
+}
+
+impl Display for EncodedAnnotationDisplay<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let context = self.context;
+    let type_idx = self.annotation.type_idx;
+    match context.find_type_descriptor(type_idx as usize) {
+      Some(descriptor) => write!(f, "@{}", descriptor)?,
+      None => write!(f, "@type@{}", type_idx)?,
+    }
+    let elements: Vec<String> = self
+      .annotation
+      .elements
+      .iter()
+      .map(|(name_idx, value)| {
+        let name = match context.string_ids().get(*name_idx as usize) {
+          Some(string_id) => string_id.string_data.clone(),
+          None => format!("string@{}", name_idx),
+        };
+        format!("{}={}", name, value.display(context))
+      })
+      .collect();
+    write!(f, "({})", elements.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse_encoded_array, EncodedAnnotation, EncodedValue};
+  use crate::test_util::DexBuilder;
+
+  fn parse(bytes: &[u8]) -> EncodedValue {
+    let (rest, value) = EncodedValue::parse::<nom::error::Error<_>>(bytes).unwrap();
+    assert!(rest.is_empty());
+    value
+  }
+
+  #[test]
+  fn test_numbers() {
+    assert_eq!(parse(&[0x00, 0xff]), EncodedValue::Byte(-1));
+    assert_eq!(parse(&[0x02, 0x80]), EncodedValue::Short(-128));
+    assert_eq!(parse(&[0x23, 0xff, 0xff]), EncodedValue::Char(0xffff));
+    assert_eq!(parse(&[0x04, 0x0a]), EncodedValue::Int(10));
+    assert_eq!(parse(&[0x24, 0x00, 0x80]), EncodedValue::Int(-32768));
+    assert_eq!(
+      parse(&[0x64, 0x78, 0x56, 0x34, 0x12]),
+      EncodedValue::Int(0x12345678)
+    );
+    assert_eq!(parse(&[0x06, 0xfd]), EncodedValue::Long(-3));
+    // 0.5f is 0x3f000000: only the high byte is stored.
+    assert_eq!(parse(&[0x10, 0x3f]), EncodedValue::Float(0.5));
+    // 2.0 is 0x4000000000000000.
+    assert_eq!(parse(&[0x11, 0x40]), EncodedValue::Double(2.0));
+  }
+
+  #[test]
+  fn test_other_values() {
+    assert_eq!(parse(&[0x17, 0x05]), EncodedValue::String(5));
+    assert_eq!(parse(&[0x3b, 0x01, 0x01]), EncodedValue::Enum(0x0101));
+    assert_eq!(parse(&[0x1e]), EncodedValue::Null);
+    assert_eq!(parse(&[0x3f]), EncodedValue::Boolean(true));
+    assert_eq!(parse(&[0x1f]), EncodedValue::Boolean(false));
+    assert_eq!(
+      parse(&[0x1c, 0x02, 0x04, 0x01, 0x1e]),
+      EncodedValue::Array(vec![EncodedValue::Int(1), EncodedValue::Null])
+    );
+    assert_eq!(
+      parse(&[0x1d, 0x03, 0x01, 0x07, 0x3f]),
+      EncodedValue::Annotation(EncodedAnnotation {
+        type_idx: 3,
+        elements: vec![(7, EncodedValue::Boolean(true))],
+      })
+    );
+
+    let (rest, values) = parse_encoded_array::<nom::error::Error<_>>(&[0x01, 0x1e, 0xaa]).unwrap();
+    assert_eq!((values, rest), (vec![EncodedValue::Null], &[0xaa][..]));
+
+    // An int can't take 5 bytes, and 0x05 is not a value type.
+    assert!(EncodedValue::parse::<nom::error::Error<_>>(&[0x84, 0, 0, 0, 0, 0]).is_err());
+    assert!(EncodedValue::parse::<nom::error::Error<_>>(&[0x05, 0]).is_err());
+  }
+
+  #[test]
+  fn test_nesting_limit() {
+    use base::parse_limits::ParseLimits;
+
+    // [[[null]]]
+    let bytes = [0x1c, 0x01, 0x1c, 0x01, 0x1c, 0x01, 0x1e];
+    let limits = |max_depth| ParseLimits {
+      max_depth,
+      ..ParseLimits::DEFAULT
+    };
+    assert!(EncodedValue::parse_with_limits::<()>(&bytes, &limits(3)).is_ok());
+    assert_eq!(
+      EncodedValue::parse_with_limits::<()>(&bytes, &limits(2)),
+      Err(nom::Err::Failure(()))
+    );
+    // Far deeper than the default limit, short of overflowing the stack.
+    let mut bytes = [0x1c, 0x01].repeat(1000);
+    bytes.push(0x1e);
+    assert!(parse_encoded_array::<()>(&bytes).is_err());
+  }
+
+  #[test]
+  fn test_enum_annotation_element() {
+    let mut builder = DexBuilder::default();
+    let red = builder.field("Lcom/Foo$Color;", "Lcom/Foo$Color;", "RED") as u8;
+    let count = builder.field("Lcom/Foo;", "I", "count") as u8;
+    let annotation_type = builder.type_id("Lcom/Foo$Paint;") as u8;
+    let color = builder.string("color") as u8;
+    let fields = builder.string("fields") as u8;
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let context = dex_file.context();
+
+    // @Lcom/Foo$Paint;(color=RED, fields={count, RED})
+    #[rustfmt::skip]
+    let value = parse(&[
+      0x1d, annotation_type, 2,
+      color, 0x1b, red,
+      fields, 0x1c, 2, 0x19, count, 0x1b, red,
+    ]);
+    let EncodedValue::Annotation(annotation) = &value else {
+      panic!("expected an annotation");
+    };
+    assert_eq!(annotation.elements[0].1, EncodedValue::Enum(red as u32));
+    assert_eq!(
+      annotation.elements[0].1.display(context).to_string(),
+      "Lcom/Foo$Color;.RED"
+    );
+    assert_eq!(
+      value.display(context).to_string(),
+      "@Lcom/Foo$Paint;(color=Lcom/Foo$Color;.RED, \
+       fields={Lcom/Foo;.count:I, Lcom/Foo$Color;.RED})"
+    );
+    // A field id past the end stays raw.
+    assert_eq!(
+      EncodedValue::Enum(100).display(context).to_string(),
+      "Enum(100)"
+    );
+  }
+}
@@ -0,0 +1,180 @@
+use std::fmt::Display;
+
+use nom::{error::ParseError, multi::count, number::complete::le_u32, IResult};
+
+/// https://source.android.com/docs/core/runtime/dex-format#hiddenapi-class-data-item
+///
+/// Per class def, the offset (relative to the start of this item) of the
+/// uleb128 flags of its members, or 0 when the class has no flags.
+pub struct HiddenApiClassData {
+  size: u32,
+  offsets: Vec<u32>,
+}
+
+impl HiddenApiClassData {
+  pub fn parse<'a, E: ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    class_defs_size: usize,
+  ) -> IResult<&'a [u8], Self, E> {
+    let (bytes, size) = le_u32(bytes)?;
+    let (bytes, offsets) = count(le_u32, class_defs_size)(bytes)?;
+    Ok((bytes, Self { size, offsets }))
+  }
+
+  /// Flags of the members of class def `class_def_idx`, in class data order:
+  /// static fields, instance fields, direct methods, virtual methods. Cut
+  /// off at the end of the item, so flags running past it fail to parse.
+  pub fn class_flags<'a>(&self, section: &'a [u8], class_def_idx: usize) -> Option<&'a [u8]> {
+    let section = section.get(..self.size as usize).unwrap_or(section);
+    match self.offsets.get(class_def_idx) {
+      Some(0) | None => None,
+      Some(offset) => Some(section.get(*offset as usize..).unwrap_or_default()),
+    }
+  }
+}
+
+/// Restriction list of a field or method as encoded by `hiddenapi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenApiRestriction {
+  Whitelist,
+  Greylist,
+  Blacklist,
+  GreylistMaxO,
+  GreylistMaxP,
+  GreylistMaxQ,
+  GreylistMaxR,
+  GreylistMaxS,
+}
+
+const RESTRICTION_MASK: u32 = 0x7;
+const CORE_PLATFORM_API: u32 = 0x8;
+const TEST_API: u32 = 0x10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HiddenApiFlag(pub u32);
+
+impl HiddenApiFlag {
+  pub fn restriction(&self) -> HiddenApiRestriction {
+    match self.0 & RESTRICTION_MASK {
+      0 => HiddenApiRestriction::Whitelist,
+      1 => HiddenApiRestriction::Greylist,
+      2 => HiddenApiRestriction::Blacklist,
+      3 => HiddenApiRestriction::GreylistMaxO,
+      4 => HiddenApiRestriction::GreylistMaxP,
+      5 => HiddenApiRestriction::GreylistMaxQ,
+      6 => HiddenApiRestriction::GreylistMaxR,
+      _ => HiddenApiRestriction::GreylistMaxS,
+    }
+  }
+
+  pub fn is_core_platform_api(&self) -> bool {
+    self.0 & CORE_PLATFORM_API != 0
+  }
+
+  pub fn is_test_api(&self) -> bool {
+    self.0 & TEST_API != 0
+  }
+}
+
+impl Display for HiddenApiRestriction {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      HiddenApiRestriction::Whitelist => "whitelist",
+      HiddenApiRestriction::Greylist => "greylist",
+      HiddenApiRestriction::Blacklist => "blacklist",
+      HiddenApiRestriction::GreylistMaxO => "greylist-max-o",
+      HiddenApiRestriction::GreylistMaxP => "greylist-max-p",
+      HiddenApiRestriction::GreylistMaxQ => "greylist-max-q",
+      HiddenApiRestriction::GreylistMaxR => "greylist-max-r",
+      HiddenApiRestriction::GreylistMaxS => "greylist-max-s",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl Display for HiddenApiFlag {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.restriction())?;
+    if self.is_core_platform_api() {
+      write!(f, ", core-platform-api")?;
+    }
+    if self.is_test_api() {
+      write!(f, ", test-api")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::error::ErrorKind;
+
+  use super::{HiddenApiFlag, HiddenApiRestriction};
+  use crate::test_util::{ClassDef, DexBuilder, Method};
+
+  #[test]
+  fn test_parse_hidden_api_flags() {
+    let mut builder = DexBuilder::default();
+    let object = builder.type_id("Ljava/lang/Object;");
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    let field = builder.field("La;", "I", "count");
+    let direct = builder.method("La;", "<init>", "V", &[]);
+    let virtual_method = builder.method("La;", "run", "V", &[]);
+    let b_method = builder.method("Lb;", "stop", "V", &[]);
+    builder.class(
+      ClassDef::new(a)
+        .superclass(object)
+        .instance_field(field, 0x0002)
+        .direct_method(Method::new(direct, 0x10001))
+        .virtual_method(Method::new(virtual_method, 0x0001))
+        .hidden_api_flags(&[2, 0, 0x1 | 0x8]),
+    );
+    builder.class(ClassDef::new(b).virtual_method(Method::new(b_method, 0x0001)));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let class_data = dex_file.class_defs()[0].class_data_item().unwrap();
+    let field = &class_data.instance_fields()[0];
+    assert_eq!(field.hidden_api_flag(), Some(HiddenApiFlag(2)));
+    assert!(field.to_string().contains("hiddenapi\t: blacklist"));
+    assert_eq!(
+      class_data.direct_methods()[0]
+        .hidden_api_flag()
+        .unwrap()
+        .restriction(),
+      HiddenApiRestriction::Whitelist
+    );
+    let run = &class_data.virtual_methods()[0];
+    assert_eq!(run.name(), "run");
+    assert!(run.hidden_api_flag().unwrap().is_core_platform_api());
+    assert!(run
+      .to_string()
+      .contains("hiddenapi\t: greylist, core-platform-api"));
+
+    let class_data = dex_file.class_defs()[1].class_data_item().unwrap();
+    assert_eq!(class_data.virtual_methods()[0].hidden_api_flag(), None);
+  }
+
+  #[test]
+  fn test_truncated_hidden_api_flags() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let field = builder.field("La;", "I", "count");
+    let run = builder.method("La;", "run", "V", &[]);
+    // Two members but a single flag.
+    builder.class(
+      ClassDef::new(a)
+        .instance_field(field, 0x0002)
+        .virtual_method(Method::new(run, 0x0001))
+        .hidden_api_flags(&[2]),
+    );
+    let error = crate::parse(&builder.build()).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::NomError { .. }));
+  }
+
+  #[test]
+  fn test_hidden_api_flag_display() {
+    assert_eq!(HiddenApiFlag(6).to_string(), "greylist-max-r");
+    assert_eq!(HiddenApiFlag(0x10).to_string(), "whitelist, test-api");
+  }
+}
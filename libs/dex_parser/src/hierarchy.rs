@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use base::{
+  descriptor::descriptor_to_source,
+  inherit::{InheritedMembers, Member},
+};
+
+use crate::{class_def::ClassDefItem, raw_dex::DexFile};
+
+/// Superclass/interface graph of the classes defined in a dex file.
+///
+/// Types that are only referenced as a superclass or interface (framework
+/// classes, other dex files of a multidex app) are still recorded by
+/// descriptor, they just have no supertypes of their own.
+#[derive(Default)]
+pub struct TypeHierarchy<'a> {
+  supertypes: HashMap<&'a str, Vec<&'a str>>,
+  subtypes: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> TypeHierarchy<'a> {
+  pub fn new(dex_file: &'a DexFile) -> Self {
+    let mut hierarchy = Self::default();
+    for class_def in dex_file.class_defs() {
+      let class = class_def.descriptor();
+      hierarchy.subtypes.entry(class).or_default();
+      hierarchy.supertypes.entry(class).or_default();
+      for super_type in class_def.direct_supertypes() {
+        hierarchy.supertypes.entry(super_type).or_default();
+        hierarchy
+          .subtypes
+          .entry(super_type)
+          .or_default()
+          .push(class);
+        hierarchy
+          .supertypes
+          .get_mut(class)
+          .unwrap()
+          .push(super_type);
+      }
+    }
+    hierarchy
+  }
+
+  pub fn contains(&self, class_descriptor: &str) -> bool {
+    self.supertypes.contains_key(class_descriptor)
+  }
+
+  /// Superclass followed by the directly implemented interfaces.
+  pub fn direct_supertypes(&self, class_descriptor: &str) -> &[&'a str] {
+    self
+      .supertypes
+      .get(class_descriptor)
+      .map_or(&[], |types| types.as_slice())
+  }
+
+  pub fn direct_subtypes(&self, class_descriptor: &str) -> &[&'a str] {
+    self
+      .subtypes
+      .get(class_descriptor)
+      .map_or(&[], |types| types.as_slice())
+  }
+
+  /// All transitive supertypes, nearest first.
+  pub fn supertypes(&self, class_descriptor: &str) -> Vec<&'a str> {
+    Self::walk(&self.supertypes, class_descriptor)
+  }
+
+  /// All transitive subtypes, nearest first.
+  pub fn subtypes(&self, class_descriptor: &str) -> Vec<&'a str> {
+    Self::walk(&self.subtypes, class_descriptor)
+  }
+
+  fn walk(edges: &HashMap<&'a str, Vec<&'a str>>, start: &str) -> Vec<&'a str> {
+    let mut result = vec![];
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<&str> = VecDeque::from([start]);
+    visited.insert(start);
+    while let Some(current) = queue.pop_front() {
+      for next in edges.get(current).into_iter().flatten() {
+        if visited.insert(next) {
+          result.push(*next);
+          queue.push_back(next);
+        }
+      }
+    }
+    result
+  }
+}
+
+/// The class `class_descriptor` with the members it inherits from the
+/// superclasses defined in any of `dex_files`, e.g. the `classes*.dex` of a
+/// multidex app. `None` if none of them defines the class.
+pub fn inherited_members(
+  dex_files: &[DexFile],
+  class_descriptor: &str,
+) -> Option<InheritedMembers> {
+  let find = |descriptor: &str| {
+    dex_files
+      .iter()
+      .flat_map(DexFile::class_defs)
+      .find(|class_def| class_def.descriptor() == descriptor)
+  };
+  let class_def = find(class_descriptor)?;
+  let (fields, methods) = members(class_def);
+  let mut inherited =
+    InheritedMembers::new(&descriptor_to_source(class_descriptor), fields, methods);
+  let mut visited = HashSet::from([class_descriptor]);
+  let mut current = class_def;
+  while let Some(superclass) = current.superclass_descriptor() {
+    if !visited.insert(superclass) {
+      break;
+    }
+    let Some(superclass_def) = find(superclass) else {
+      inherited.set_missing_superclass(&descriptor_to_source(superclass));
+      break;
+    };
+    let (fields, methods) = members(superclass_def);
+    inherited.add_superclass(&descriptor_to_source(superclass), fields, methods);
+    current = superclass_def;
+  }
+  Some(inherited)
+}
+
+/// Fields and methods of a class; none without class data.
+fn members(class_def: &ClassDefItem) -> (Vec<Member<'_>>, Vec<Member<'_>>) {
+  let Some(class_data) = class_def.class_data_item() else {
+    return (vec![], vec![]);
+  };
+  let fields = class_data
+    .static_fields()
+    .iter()
+    .chain(class_data.instance_fields())
+    .map(|field| Member {
+      name: field.name(),
+      descriptor: field.descriptor().to_string(),
+      access_flags: field.access_flags(),
+      signature: field.signature(),
+    })
+    .collect();
+  let methods = class_data
+    .methods()
+    .map(|method| Member {
+      name: method.name(),
+      descriptor: method.descriptor(),
+      access_flags: method.access_flags(),
+      signature: method.signature(),
+    })
+    .collect();
+  (fields, methods)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::test_util::{ClassDef, DexBuilder, Method};
+
+  #[test]
+  fn test_three_class_hierarchy() {
+    let mut builder = DexBuilder::default();
+    let object = builder.type_id("Ljava/lang/Object;");
+    let runnable = builder.type_id("Ljava/lang/Runnable;");
+    let a = builder.type_id("Lcom/example/A;");
+    let b = builder.type_id("Lcom/example/B;");
+    let c = builder.type_id("Lcom/example/C;");
+    builder.class(ClassDef::new(a).superclass(object));
+    builder.class(ClassDef::new(b).superclass(a).interface(runnable));
+    builder.class(ClassDef::new(c).superclass(b));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(
+      dex_file.supertypes("Lcom/example/C;"),
+      vec![
+        "Lcom/example/B;",
+        "Lcom/example/A;",
+        "Ljava/lang/Runnable;",
+        "Ljava/lang/Object;",
+      ]
+    );
+    assert_eq!(
+      dex_file.subtypes("Lcom/example/A;"),
+      vec!["Lcom/example/B;", "Lcom/example/C;"]
+    );
+    assert_eq!(
+      dex_file.subtypes("Ljava/lang/Runnable;"),
+      vec!["Lcom/example/B;", "Lcom/example/C;"]
+    );
+
+    let hierarchy = dex_file.type_hierarchy();
+    assert!(hierarchy.contains("Ljava/lang/Object;"));
+    assert!(hierarchy.supertypes("Ljava/lang/Object;").is_empty());
+    assert!(hierarchy.supertypes("Lcom/example/Missing;").is_empty());
+  }
+
+  #[test]
+  fn test_inherited_members_across_files() {
+    let mut first = DexBuilder::default();
+    let object = first.type_id("Ljava/lang/Object;");
+    let base = first.type_id("La/Base;");
+    let id = first.field("La/Base;", "J", "id");
+    let secret = first.field("La/Base;", "I", "secret");
+    let init = first.method("La/Base;", "<init>", "V", &[]);
+    let run = first.method("La/Base;", "run", "V", &[]);
+    let describe = first.method("La/Base;", "describe", "Ljava/lang/String;", &[]);
+    first.class(
+      ClassDef::new(base)
+        .superclass(object)
+        .instance_field(id, 0x0004)
+        .instance_field(secret, 0x0002)
+        .direct_method(Method::new(init, 0x10001).code(1, 1, 0, &[0x000e]))
+        .virtual_method(Method::new(run, 0x0401))
+        .virtual_method(Method::new(describe, 0x0401)),
+    );
+
+    let mut second = DexBuilder::default();
+    let base = second.type_id("La/Base;");
+    let derived = second.type_id("La/Derived;");
+    let count = second.field("La/Derived;", "I", "count");
+    let run = second.method("La/Derived;", "run", "V", &[]);
+    second.class(
+      ClassDef::new(derived)
+        .superclass(base)
+        .instance_field(count, 0x0002)
+        .virtual_method(Method::new(run, 0x0001).code(1, 1, 0, &[0x000e])),
+    );
+    let dex_files = [
+      crate::parse(&first.build()).unwrap(),
+      crate::parse(&second.build()).unwrap(),
+    ];
+
+    let inherited = super::inherited_members(&dex_files, "La/Derived;").unwrap();
+    assert_eq!(
+      inherited.to_string(),
+      "class a.Derived\n\
+       superclasses: a.Base (java.lang.Object is not among the inputs)\n\
+       fields:\n\
+       \tprivate int count\n\
+       \tprotected long id\t(from a.Base)\n\
+       methods:\n\
+       \tpublic void run()\n\
+       \tpublic abstract java.lang.String describe()\t(from a.Base)\n"
+    );
+    assert!(super::inherited_members(&dex_files[1..], "La/Derived;")
+      .unwrap()
+      .superclasses()
+      .is_empty());
+    assert!(super::inherited_members(&dex_files, "La/Missing;").is_none());
+  }
+}
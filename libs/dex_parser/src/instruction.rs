@@ -0,0 +1,367 @@
+use std::fmt::Display;
+
+use base::coverage::OpcodeCoverage;
+
+use crate::{
+  opcodes::{opcode_category, opcode_info, Format, OpcodeCategory},
+  payload::{payload_mnemonic, payload_units, Payload},
+};
+
+/// A Dalvik instruction with its undecoded code units. Payloads are walked
+/// as one instruction each, with opcode `nop`, their own mnemonic and no
+/// format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction<'a> {
+  /// Offset in 16-bit code units from the start of the method's insns.
+  pub pc: u32,
+  pub opcode: u8,
+  /// `None` for an unused opcode, which is taken to be one unit long.
+  pub mnemonic: Option<&'static str>,
+  pub format: Option<Format>,
+  pub units: &'a [u16],
+}
+
+impl Display for Instruction<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.mnemonic {
+      Some(mnemonic) => write!(f, "{}", mnemonic)?,
+      None => write!(f, "unknown_{:02x}", self.opcode)?,
+    }
+    for unit in self.units {
+      write!(f, " {:04x}", unit)?;
+    }
+    Ok(())
+  }
+}
+
+/// What the index operand of an instruction refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+  String,
+  Type,
+  Field,
+  Method,
+  CallSite,
+  MethodHandle,
+  Proto,
+}
+
+fn index_kind(opcode: u8) -> Option<IndexKind> {
+  let kind = match opcode {
+    0x1a | 0x1b => IndexKind::String,
+    0x1c | 0x1f | 0x20 | 0x22..=0x25 => IndexKind::Type,
+    0x52..=0x6d => IndexKind::Field,
+    0x6e..=0x72 | 0x74..=0x78 | 0xfa | 0xfb => IndexKind::Method,
+    0xfc | 0xfd => IndexKind::CallSite,
+    0xfe => IndexKind::MethodHandle,
+    0xff => IndexKind::Proto,
+    _ => return None,
+  };
+  Some(kind)
+}
+
+impl Instruction<'_> {
+  pub fn category(&self) -> OpcodeCategory {
+    if self.is_payload() {
+      return OpcodeCategory::Payload;
+    }
+    opcode_category(self.opcode)
+  }
+
+  pub fn is_payload(&self) -> bool {
+    self.format.is_none() && payload_mnemonic(self.units[0]).is_some()
+  }
+
+  /// The decoded table, if this is a payload.
+  pub fn payload(&self) -> Option<Payload> {
+    if !self.is_payload() {
+      return None;
+    }
+    Payload::parse(self.units)
+  }
+
+  /// The id list entry the instruction refers to, if any. For
+  /// `invoke-polymorphic` this is the method; its proto is in `units[3]`.
+  pub fn reference(&self) -> Option<(IndexKind, u32)> {
+    let kind = index_kind(self.opcode)?;
+    let index = match self.format? {
+      Format::F31c => self.wide_operand(),
+      _ => self.units[1] as u32,
+    };
+    Some((kind, index))
+  }
+
+  /// The argument registers of an `invoke-*`, `filled-new-array` or
+  /// `invoke-custom`, in order: up to five listed for the 35c and 45cc
+  /// formats, a contiguous range for 3rc and 4rcc.
+  pub fn argument_registers(&self) -> Option<Vec<u16>> {
+    match self.format? {
+      Format::F35c | Format::F45cc => {
+        let count = (self.units[0] >> 12) as usize;
+        let listed = self.units[2];
+        let registers = [
+          listed & 0xf,
+          (listed >> 4) & 0xf,
+          (listed >> 8) & 0xf,
+          listed >> 12,
+          (self.units[0] >> 8) & 0xf,
+        ];
+        Some(registers[..count.min(registers.len())].to_vec())
+      }
+      Format::F3rc | Format::F4rcc => {
+        let count = self.units[0] >> 8;
+        let first = self.units[2];
+        Some((0..count).map(|i| first.wrapping_add(i)).collect())
+      }
+      _ => None,
+    }
+  }
+
+  /// Where a `goto` or `if-*` jumps to, as a pc. The `31t` instructions
+  /// point at a payload instead, see [`Instruction::switch_targets`].
+  pub fn branch_target(&self) -> Option<u32> {
+    let offset = match self.format? {
+      Format::F10t => (self.units[0] >> 8) as i8 as i32,
+      Format::F20t | Format::F21t | Format::F22t => self.units[1] as i16 as i32,
+      Format::F30t => self.wide_operand() as i32,
+      _ => return None,
+    };
+    Some(self.pc.wrapping_add_signed(offset))
+  }
+
+  /// The payload a `packed-switch`, `sparse-switch` or `fill-array-data`
+  /// points at in `insns`. `None` if it is missing, truncated or of the
+  /// wrong kind.
+  pub fn referenced_payload(&self, insns: &[u16]) -> Option<Payload> {
+    if self.format? != Format::F31t {
+      return None;
+    }
+    let pc = self.pc.wrapping_add_signed(self.wide_operand() as i32) as usize;
+    let payload = Payload::parse(insns.get(pc..)?)?;
+    let matches = matches!(
+      (self.opcode, &payload),
+      (0x2b, Payload::PackedSwitch { .. })
+        | (0x2c, Payload::SparseSwitch { .. })
+        | (0x26, Payload::FillArrayData { .. })
+    );
+    matches.then_some(payload)
+  }
+
+  /// `(key, pc)` of each case of a `packed-switch` or `sparse-switch`, read
+  /// from its payload in `insns`.
+  pub fn switch_cases(&self, insns: &[u16]) -> Option<Vec<(i32, u32)>> {
+    let payload = self.referenced_payload(insns)?;
+    if let Payload::FillArrayData { .. } = payload {
+      return None;
+    }
+    let cases = payload
+      .cases()
+      .into_iter()
+      .map(|(key, target)| (key, self.pc.wrapping_add_signed(target)))
+      .collect();
+    Some(cases)
+  }
+
+  /// The pcs a `packed-switch` or `sparse-switch` jumps to, read from its
+  /// payload in `insns`. `None` if the payload is missing or truncated.
+  pub fn switch_targets(&self, insns: &[u16]) -> Option<Vec<u32>> {
+    let cases = self.switch_cases(insns)?;
+    Some(cases.into_iter().map(|(_, target)| target).collect())
+  }
+
+  /// The 32-bit operand in `units[1..3]`, low unit first.
+  fn wide_operand(&self) -> u32 {
+    self.units[1] as u32 | (self.units[2] as u32) << 16
+  }
+}
+
+/// Walks an insns array one instruction at a time. Stops at an instruction
+/// that runs past the end of the array.
+pub struct InstructionIterator<'a> {
+  insns: &'a [u16],
+  pc: usize,
+}
+
+impl<'a> InstructionIterator<'a> {
+  pub fn new(insns: &'a [u16]) -> Self {
+    Self { insns, pc: 0 }
+  }
+
+  /// Code units not decoded yet.
+  pub fn remaining(&self) -> &'a [u16] {
+    &self.insns[self.pc..]
+  }
+
+  /// Decodes the rest of the insns, counting every opcode in `coverage`.
+  /// Payloads aren't counted.
+  pub fn record(self, coverage: &mut OpcodeCoverage) {
+    for instruction in self.filter(|instruction| !instruction.is_payload()) {
+      coverage.record(instruction.opcode, instruction.mnemonic);
+    }
+  }
+}
+
+impl<'a> Iterator for InstructionIterator<'a> {
+  type Item = Instruction<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let unit = *self.insns.get(self.pc)?;
+    let opcode = (unit & 0xff) as u8;
+    let (mnemonic, format, len) = match payload_mnemonic(unit) {
+      Some(mnemonic) => (Some(mnemonic), None, payload_units(self.remaining())?),
+      None => {
+        let info = opcode_info(opcode);
+        let len = info.map(|(_, format)| format.units()).unwrap_or(1);
+        (
+          info.map(|(mnemonic, _)| mnemonic),
+          info.map(|(_, format)| format),
+          len,
+        )
+      }
+    };
+    let units = self.insns.get(self.pc..self.pc + len)?;
+    let instruction = Instruction {
+      pc: self.pc as u32,
+      opcode,
+      mnemonic,
+      format,
+      units,
+    };
+    self.pc += len;
+    Some(instruction)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::coverage::OpcodeCoverage;
+
+  use super::{IndexKind, InstructionIterator};
+  use crate::opcodes::{opcode_category, opcode_info, Format, OpcodeCategory, OPCODES};
+
+  #[test]
+  fn test_opcode_table() {
+    assert_eq!(OPCODES.len(), 256 - 32);
+    assert!(OPCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(opcode_info(0x0e), Some(("return-void", Format::F10x)));
+    assert_eq!(opcode_info(0x6e), Some(("invoke-virtual", Format::F35c)));
+    assert_eq!(opcode_info(0x18), Some(("const-wide", Format::F51l)));
+    assert_eq!(opcode_info(0xff), Some(("const-method-type", Format::F21c)));
+    assert_eq!(opcode_info(0x3e), None);
+    assert_eq!(opcode_info(0xe3), None);
+  }
+
+  #[test]
+  fn test_opcode_category() {
+    let categories = [
+      ("invoke-virtual", OpcodeCategory::Invoke),
+      ("invoke-custom/range", OpcodeCategory::Invoke),
+      ("const/4", OpcodeCategory::Const),
+      ("const-string", OpcodeCategory::Const),
+      ("const-method-type", OpcodeCategory::Const),
+      ("if-eqz", OpcodeCategory::Branch),
+      ("packed-switch", OpcodeCategory::Branch),
+      ("cmp-long", OpcodeCategory::Compare),
+      ("move-result-object", OpcodeCategory::Move),
+      ("return-void", OpcodeCategory::Return),
+      ("monitor-exit", OpcodeCategory::Monitor),
+      ("new-instance", OpcodeCategory::Object),
+      ("aput-object", OpcodeCategory::Array),
+      ("fill-array-data", OpcodeCategory::Array),
+      ("throw", OpcodeCategory::Throw),
+      ("iget-wide", OpcodeCategory::Instance),
+      ("sput-short", OpcodeCategory::Static),
+      ("neg-int", OpcodeCategory::Arithmetic),
+      ("int-to-char", OpcodeCategory::Conversion),
+      ("rsub-int/lit8", OpcodeCategory::Arithmetic),
+    ];
+    for (mnemonic, category) in categories {
+      let (opcode, _, _) = OPCODES.iter().find(|(_, m, _)| *m == mnemonic).unwrap();
+      assert_eq!(opcode_category(*opcode), category, "{}", mnemonic);
+    }
+    assert!(OPCODES
+      .iter()
+      .all(|(opcode, _, _)| opcode_category(*opcode) != OpcodeCategory::Unknown));
+    assert_eq!(opcode_category(0x3e), OpcodeCategory::Unknown);
+    let invoke = InstructionIterator::new(&[0x0071, 0x0001, 0x0000])
+      .next()
+      .unwrap();
+    assert_eq!(invoke.category().to_string(), "invoke");
+  }
+
+  #[test]
+  fn test_branch_targets() {
+    // 0: goto -1, which wraps; 1: if-nez v0, 0; 3: goto/32 1;
+    // 6: packed-switch v0, 12; 9: sparse-switch v0, 20; 12: packed payload
+    // with targets 0, 3; 20: sparse payload with keys 5, 7 to 1, 9
+    #[rustfmt::skip]
+    let insns = [
+      0xff28,
+      0x0039, 0xffff,
+      0x002a, 0xfffe, 0xffff,
+      0x002b, 0x0006, 0x0000,
+      0x002c, 0x000b, 0x0000,
+      0x0100, 0x0002, 0x0000, 0x0000, 0xfffa, 0xffff, 0xfffd, 0xffff,
+      0x0200, 0x0002, 0x0005, 0x0000, 0x0007, 0x0000, 0xfff8, 0xffff, 0x0000, 0x0000,
+    ];
+    let instructions: Vec<_> = InstructionIterator::new(&insns).take(5).collect();
+    let targets: Vec<_> = instructions.iter().map(|i| i.branch_target()).collect();
+    assert_eq!(targets, vec![Some(u32::MAX), Some(0), Some(1), None, None]);
+    assert_eq!(instructions[3].switch_targets(&insns), Some(vec![0, 3]));
+    assert_eq!(instructions[4].switch_targets(&insns), Some(vec![1, 9]));
+    assert_eq!(instructions[0].switch_targets(&insns), None);
+    // The payload is cut short.
+    assert_eq!(instructions[4].switch_targets(&insns[..28]), None);
+  }
+
+  #[test]
+  fn test_instructions() {
+    // const/4 v0, 0; const-string v1, #2; unused 0x3e; invoke-static {}, #1;
+    // return-void
+    let insns = [
+      0x0012, 0x011a, 0x0002, 0x003e, 0x0071, 0x0001, 0x0000, 0x000e,
+    ];
+    let instructions: Vec<_> = InstructionIterator::new(&insns).collect();
+    let pcs: Vec<_> = instructions.iter().map(|i| (i.pc, i.mnemonic)).collect();
+    assert_eq!(
+      pcs,
+      vec![
+        (0, Some("const/4")),
+        (1, Some("const-string")),
+        (3, None),
+        (4, Some("invoke-static")),
+        (7, Some("return-void")),
+      ]
+    );
+    assert_eq!(instructions[1].units, &[0x011a, 0x0002]);
+    assert_eq!(instructions[2].to_string(), "unknown_3e 003e");
+
+    // A truncated const-wide ends the walk.
+    let mut iterator = InstructionIterator::new(&[0x000e, 0x0018, 0x0000]);
+    assert_eq!(iterator.next().unwrap().mnemonic, Some("return-void"));
+    assert!(iterator.next().is_none());
+    assert_eq!(iterator.remaining(), &[0x0018, 0x0000]);
+
+    let references: Vec<_> = instructions.iter().map(|i| i.reference()).collect();
+    assert_eq!(
+      references,
+      vec![
+        None,
+        Some((IndexKind::String, 2)),
+        None,
+        Some((IndexKind::Method, 1)),
+        None
+      ]
+    );
+    // const-string/jumbo v0, #0x00010002
+    let jumbo = InstructionIterator::new(&[0x001b, 0x0002, 0x0001])
+      .next()
+      .unwrap();
+    assert_eq!(jumbo.reference(), Some((IndexKind::String, 0x10002)));
+
+    let mut coverage = OpcodeCoverage::default();
+    InstructionIterator::new(&insns).record(&mut coverage);
+    assert_eq!(coverage.count(0x12), 1);
+    assert_eq!(coverage.unknown(), vec![(0x3e, 1)]);
+  }
+}
@@ -1,10 +1,14 @@
-use nom::{error::ParseError, IResult};
+use nom::{
+  error::{ErrorKind, ParseError},
+  IResult,
+};
 
 pub fn parse_uleb128<'a>(bytes: &'a [u8]) -> (u32, usize) {
   let mut result = 0;
   let mut shift = 0;
   let mut i = 1;
-  for byte in bytes {
+  // A u32 takes at most five bytes; a longer run is malformed.
+  for byte in bytes.iter().take(5) {
     result |= ((byte & 0x7f) as u32) << shift;
     if byte & 0x80 == 0 {
       break;
@@ -17,5 +21,21 @@ pub fn parse_uleb128<'a>(bytes: &'a [u8]) -> (u32, usize) {
 
 pub fn parse_uleb128_nom<'a, E: ParseError<&'a [u8]>>(bytes: &'a [u8]) -> IResult<&[u8], u32, E> {
   let (result, i) = parse_uleb128(bytes);
+  if i > bytes.len() {
+    return Err(nom::Err::Error(E::from_error_kind(bytes, ErrorKind::Eof)));
+  }
   Ok((bytes.split_at(i).1, result))
 }
+
+/// The signed variant: the sign bit is bit 6 of the last byte.
+pub fn parse_sleb128_nom<'a, E: ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+) -> IResult<&'a [u8], i32, E> {
+  let (rest, result) = parse_uleb128_nom(bytes)?;
+  let i = bytes.len() - rest.len();
+  let shift = 7 * i;
+  if shift < 32 && bytes[i - 1] & 0x40 != 0 {
+    return Ok((rest, (result | (!0 << shift)) as i32));
+  }
+  Ok((rest, result as i32))
+}
@@ -1,50 +1,551 @@
-use base::{error::Error, Parsable};
-use raw_dex::{DexFile, FieldIdItem, MethodIdItem, StringIdItem, TypeIdItem};
+use std::panic::{AssertUnwindSafe, UnwindSafe};
 
-mod class_def;
+use base::{
+  error::{Error, ErrorKind},
+  parse_limits::ParseLimits,
+  Parsable,
+};
+use raw_dex::{DexFile, DEX_HEADER_SIZE};
+pub use raw_dex::{FullyQualifiedMethod, ParseEvent};
+
+pub mod class_def;
+pub mod context;
+pub mod encode;
+pub mod encoded_value;
+pub mod hiddenapi;
+pub mod hierarchy;
+pub mod instruction;
 mod leb128;
+pub mod limits;
 mod map_list;
+pub mod opcodes;
+pub mod payload;
 mod raw_dex;
+#[cfg(test)]
+mod test_util;
 mod type_test;
-mod utf;
 
+/// Magic of the CompactDex files ART writes into vdex/oat files.
+const CDEX_MAGIC: &[u8] = b"cdex";
+
+/// Parses a standard dex file (magic `dex\n0NN\0`, any version).
+///
+/// CompactDex (`cdex001\0`) is recognized and rejected with
+/// `ErrorKind::UnsupportedFormat`: its header layout and shared data section
+/// differ from standard dex.
 pub fn parse<'a>(bytes: &'a [u8]) -> Result<DexFile, Error> {
+  check_header(bytes)?;
   DexFile::parse_from_u8(bytes)
 }
 
-static mut STRING_DATA_REF: Vec<StringIdItem> = vec![];
-static mut TYPE_ID_REF: Vec<TypeIdItem> = vec![];
-static mut METHOD_ID_REF: Vec<MethodIdItem> = vec![];
-static mut FIELD_ID_REF: Vec<FieldIdItem> = vec![];
+/// Like `parse`, failing with `ErrorKind::LimitExceeded` instead of
+/// allocating for a declared size, or descending into nested values, beyond
+/// `limits`. `parse` applies `ParseLimits::DEFAULT`.
+pub fn parse_with_limits(bytes: &[u8], limits: ParseLimits) -> Result<DexFile, Error> {
+  check_header(bytes)?;
+  DexFile::parse_with_limits::<nom::error::Error<_>, _>(bytes, limits, |_| {})
+    .map(|(_, dex_file)| dex_file)
+    .map_err(|e| e.into())
+}
+
+/// Like `parse`, turning a panic on malformed input into
+/// `ErrorKind::Panicked` instead of unwinding into the caller.
+///
+/// A temporary safety net for untrusted input: the indices within the id
+/// tables are checked while parsing, but some lookups into them, e.g. from
+/// class data, still index or unwrap without checking, and those are being
+/// turned into errors one by one. The panic hook still runs, so the message
+/// is printed to stderr as usual.
+pub fn parse_catching(bytes: &[u8]) -> Result<DexFile, Error> {
+  catch_panic(|| parse(bytes))
+}
+
+/// `parse_with_visitor` with the panic handling of `parse_catching`. A panic
+/// in `visitor` is reported the same way.
+pub fn parse_with_visitor_catching<V: FnMut(ParseEvent)>(
+  bytes: &[u8],
+  visitor: V,
+) -> Result<DexFile, Error> {
+  // Events already delivered stay delivered; the caller decides what to make
+  // of them when an error comes back.
+  catch_panic(AssertUnwindSafe(|| parse_with_visitor(bytes, visitor)))
+}
 
-pub fn get_string_data_ref() -> &'static Vec<StringIdItem> {
-  unsafe { &STRING_DATA_REF }
+fn catch_panic<F: FnOnce() -> Result<DexFile, Error> + UnwindSafe>(
+  parse: F,
+) -> Result<DexFile, Error> {
+  match std::panic::catch_unwind(parse) {
+    Ok(result) => result,
+    Err(payload) => {
+      let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+          Some(message) => message.clone(),
+          None => "unknown panic payload".to_string(),
+        },
+      };
+      Err(ErrorKind::Panicked { message }.into())
+    }
+  }
 }
 
-pub fn get_str_const<'a>(index: usize) -> &'a str {
-  get_string_data_ref()[index].string_data.as_str()
+/// Like `parse`, calling `visitor` with the header, each string and each
+/// class as they are parsed, e.g. to stream output for a huge file.
+pub fn parse_with_visitor<V: FnMut(ParseEvent)>(
+  bytes: &[u8],
+  visitor: V,
+) -> Result<DexFile, Error> {
+  check_header(bytes)?;
+  DexFile::parse_with_visitor::<nom::error::Error<_>, _>(bytes, visitor)
+    .map(|(_, dex_file)| dex_file)
+    .map_err(|e| e.into())
 }
 
-pub fn get_type_id_ref() -> &'static Vec<TypeIdItem> {
-  unsafe { &TYPE_ID_REF }
+/// Like `parse`, also returning the bytes past the `file_size` given in the
+/// header.
+pub fn parse_with_remainder(bytes: &[u8]) -> Result<(DexFile, &[u8]), Error> {
+  check_header(bytes)?;
+  DexFile::parse_with_remainder(bytes)
 }
 
-pub fn get_type_id(index: usize) -> TypeIdItem {
-  get_type_id_ref()[index].clone()
+/// Like `parse_with_remainder`, for a dex file starting `offset` bytes into
+/// `bytes`, e.g. one embedded in a vdex or oat container. The offsets in a
+/// dex are relative to its own start, so it is parsed in place from there.
+pub fn parse_at_offset(bytes: &[u8], offset: usize) -> Result<(DexFile, &[u8]), Error> {
+  let len = bytes.len();
+  let bytes = bytes
+    .get(offset..)
+    .ok_or(ErrorKind::OffsetOutOfRange { offset, len })?;
+  parse_with_remainder(bytes)
 }
 
-pub fn get_method_id_ref() -> &'static Vec<MethodIdItem> {
-  unsafe { &METHOD_ID_REF }
+/// Rejects CompactDex, and input too short for a `header_item`, before the
+/// parser gets to fail on it with a bare nom error.
+fn check_header(bytes: &[u8]) -> Result<(), Error> {
+  if bytes.starts_with(CDEX_MAGIC) {
+    return Err(ErrorKind::UnsupportedFormat("CompactDex".to_string()).into());
+  }
+  if bytes.len() < DEX_HEADER_SIZE {
+    return Err(
+      ErrorKind::TruncatedHeader {
+        expected: DEX_HEADER_SIZE,
+        actual: bytes.len(),
+      }
+      .into(),
+    );
+  }
+  Ok(())
 }
 
-pub fn get_method_id(index: usize) -> MethodIdItem {
-  get_method_id_ref()[index].clone()
+/// Parses the dex file at `path` through a read-only memory map instead of
+/// reading it into memory first. The parsed `DexFile` owns all of its data,
+/// so the mapping is released before this returns.
+#[cfg(feature = "mmap")]
+pub fn parse_mmap<P: AsRef<std::path::Path>>(path: P) -> Result<DexFile, Error> {
+  let file = std::fs::File::open(path)?;
+  // Safety: the map is only read during parsing; nothing borrowed from it
+  // outlives this call. Truncating the file while it is being parsed is
+  // undefined behaviour, as with any mmap-based reader.
+  let mmap = unsafe { memmap2::Mmap::map(&file)? };
+  parse(&mmap)
 }
 
-pub fn get_field_id_ref() -> &'static Vec<FieldIdItem> {
-  unsafe { &FIELD_ID_REF }
+/// `parse_mmap` with the panic handling of `parse_catching`.
+#[cfg(feature = "mmap")]
+pub fn parse_mmap_catching<P: AsRef<std::path::Path>>(path: P) -> Result<DexFile, Error> {
+  let path = path.as_ref();
+  catch_panic(|| parse_mmap(path))
 }
 
-pub fn get_field_id(index: usize) -> FieldIdItem {
-  get_field_id_ref()[index].clone()
+#[cfg(test)]
+mod tests {
+  use base::error::ErrorKind;
+
+  #[test]
+  fn test_compact_dex_is_unsupported() {
+    let mut bytes = b"cdex001\0".to_vec();
+    bytes.resize(0x70, 0);
+    let error = crate::parse(&bytes).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::UnsupportedFormat(format) if format == "CompactDex"));
+    assert_eq!(error.to_string(), "unsupported format: CompactDex");
+  }
+
+  #[test]
+  fn test_truncated_header() {
+    let error = crate::parse(b"dex\n035\0\0\0").err().unwrap();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::TruncatedHeader {
+        expected: 0x70,
+        actual: 10
+      }
+    ));
+    assert_eq!(
+      error.to_string(),
+      "truncated header: expected at least 112 bytes, got 10"
+    );
+    assert!(matches!(
+      crate::parse_with_remainder(&[]).err().unwrap().kind(),
+      ErrorKind::TruncatedHeader { actual: 0, .. }
+    ));
+  }
+
+  #[test]
+  fn test_parse_empty_input() {
+    use crate::raw_dex::DexFile;
+    use base::Parsable;
+
+    for bytes in [&b""[..], b"dex"] {
+      for error in [
+        crate::parse(bytes).err().unwrap(),
+        crate::parse_with_visitor(bytes, |_| {}).err().unwrap(),
+        crate::parse_with_remainder(bytes).err().unwrap(),
+      ] {
+        assert!(matches!(
+          error.kind(),
+          ErrorKind::TruncatedHeader { expected: 0x70, actual } if *actual == bytes.len()
+        ));
+      }
+      // Without the size check the header parser fails, it doesn't panic.
+      assert!(DexFile::parse_from_u8(bytes).is_err());
+    }
+  }
+
+  #[test]
+  fn test_parse_with_remainder() {
+    let mut builder = crate::test_util::DexBuilder::default();
+    builder.method("La;", "run", "V", &[]);
+    let mut bytes = builder.build();
+    let (_, remainder) = crate::parse_with_remainder(&bytes).unwrap();
+    assert!(remainder.is_empty());
+
+    bytes.extend_from_slice(&[0xca, 0xfe, 0xba, 0xbe]);
+    let (dex_file, remainder) = crate::parse_with_remainder(&bytes).unwrap();
+    assert_eq!(remainder, &[0xca, 0xfe, 0xba, 0xbe]);
+    assert_eq!(dex_file.method_ref_count(), 1);
+  }
+
+  #[test]
+  fn test_parse_at_offset() {
+    use crate::test_util::{ClassDef, DexBuilder};
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    builder.class(ClassDef::new(a));
+    let dex = builder.build();
+    // A container header before the dex and another section after it.
+    let mut bytes = vec![0xff; 0x30];
+    bytes.extend_from_slice(&dex);
+    bytes.extend_from_slice(b"vdex");
+
+    assert!(crate::parse(&bytes).is_err());
+    let (dex_file, remainder) = crate::parse_at_offset(&bytes, 0x30).unwrap();
+    assert_eq!(remainder, b"vdex");
+    assert_eq!(dex_file.class_defs()[0].descriptor(), "La;");
+    assert!(dex_file.validate().is_empty());
+
+    let error = crate::parse_at_offset(&bytes, bytes.len() + 1)
+      .err()
+      .unwrap();
+    assert!(matches!(
+      error.kind(),
+      ErrorKind::OffsetOutOfRange { offset, len } if *offset == bytes.len() + 1 && *len == bytes.len()
+    ));
+    assert!(matches!(
+      crate::parse_at_offset(&bytes, bytes.len() - 4)
+        .err()
+        .unwrap()
+        .kind(),
+      ErrorKind::TruncatedHeader { actual: 4, .. }
+    ));
+  }
+
+  #[test]
+  fn test_parse_with_limits() {
+    use base::parse_limits::ParseLimits;
+
+    use crate::test_util::{ClassDef, DexBuilder, Method};
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let run = builder.method("La;", "run", "V", &[]);
+    builder.class(
+      ClassDef::new(a).direct_method(Method::new(run, 0x0009).code(1, 0, 0, &[0x0000, 0x000e])),
+    );
+    let mut bytes = builder.build();
+    assert!(crate::parse_with_limits(&bytes, ParseLimits::DEFAULT).is_ok());
+    let short_code = ParseLimits {
+      max_code_len: 1,
+      ..ParseLimits::DEFAULT
+    };
+    let error = crate::parse_with_limits(&bytes, short_code).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::LimitExceeded));
+
+    // string_ids_size = 0xffffffff: rejected before reading a single id, not
+    // after trying to allocate for four billion of them.
+    bytes[0x38..0x3c].copy_from_slice(&u32::MAX.to_le_bytes());
+    let error = crate::parse(&bytes).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::LimitExceeded));
+    assert!(crate::parse_catching(&bytes).is_err());
+
+    // A static value of [[[null]]], one level deeper than allowed.
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let values = builder.field("La;", "[[[Ljava/lang/Object;", "VALUES");
+    builder.class(
+      ClassDef::new(a)
+        .static_field(values, 0x0019)
+        .static_values(&[0x01, 0x1c, 0x01, 0x1c, 0x01, 0x1c, 0x01, 0x1e]),
+    );
+    let bytes = builder.build();
+    let shallow = ParseLimits {
+      max_depth: 2,
+      ..ParseLimits::DEFAULT
+    };
+    assert!(crate::parse_with_limits(&bytes, ParseLimits::DEFAULT).is_ok());
+    let error = crate::parse_with_limits(&bytes, shallow).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::LimitExceeded));
+  }
+
+  #[test]
+  fn test_parse_catching() {
+    use crate::test_util::{ClassDef, DexBuilder};
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let count = builder.field("La;", "I", "count");
+    builder.class(ClassDef::new(a).static_field(count, 0x0008));
+    let mut bytes = builder.build();
+    assert!(crate::parse_catching(&bytes).is_ok());
+
+    // Point the static field at field_ids[127], which doesn't exist.
+    let class_defs_off = u32::from_le_bytes(bytes[0x64..0x68].try_into().unwrap()) as usize;
+    let class_data_off = class_defs_off + 24;
+    let class_data_off = u32::from_le_bytes(
+      bytes[class_data_off..class_data_off + 4]
+        .try_into()
+        .unwrap(),
+    ) as usize;
+    // After the four member counts.
+    bytes[class_data_off + 4] = 0x7f;
+
+    let error = crate::parse_catching(&bytes).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::Panicked { .. }));
+    assert!(error.to_string().starts_with("parser panicked: "));
+    let mut headers = 0;
+    let error = crate::parse_with_visitor_catching(&bytes, |event| {
+      if let crate::ParseEvent::HeaderParsed(_) = event {
+        headers += 1;
+      }
+    })
+    .err()
+    .unwrap();
+    assert!(matches!(error.kind(), ErrorKind::Panicked { .. }));
+    assert_eq!(headers, 1);
+    // Errors the parser reports itself pass through unchanged.
+    assert!(matches!(
+      crate::parse_catching(&bytes[..4]).err().unwrap().kind(),
+      ErrorKind::TruncatedHeader { actual: 4, .. }
+    ));
+  }
+
+  #[test]
+  fn test_parse_with_visitor() {
+    use crate::{
+      test_util::{ClassDef, DexBuilder},
+      ParseEvent,
+    };
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let b = builder.type_id("Lb;");
+    builder.class(ClassDef::new(a));
+    builder.class(ClassDef::new(b));
+    let bytes = builder.build();
+
+    let mut events = vec![];
+    let dex_file = crate::parse_with_visitor(&bytes, |event| {
+      events.push(match event {
+        ParseEvent::HeaderParsed(header) => format!("header {}", header.class_defs_size),
+        ParseEvent::StringParsed(index, string_id) => {
+          format!("string {} {}", index, string_id.string_data)
+        }
+        ParseEvent::ClassParsed(index, class_def) => {
+          format!("class {} {}", index, class_def.descriptor())
+        }
+      })
+    })
+    .unwrap();
+    assert_eq!(
+      events,
+      vec![
+        "header 2",
+        "string 0 La;",
+        "string 1 Lb;",
+        "class 0 La;",
+        "class 1 Lb;"
+      ]
+    );
+    assert_eq!(dex_file.class_defs().len(), 2);
+  }
+
+  #[test]
+  fn test_header_display() {
+    use crate::test_util::{ClassDef, DexBuilder};
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    builder.class(ClassDef::new(a));
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let header = dex_file.header();
+
+    let lines: Vec<String> = header.to_string().lines().map(String::from).collect();
+    assert_eq!(lines.len(), 23);
+    assert_eq!(lines[0], "magic:           0x6465780a");
+    assert_eq!(lines[4], "header_size:     0x70");
+    assert_eq!(lines[19], "class_defs_size: 0x1");
+    assert_eq!(
+      lines[20],
+      format!("class_defs_off:  0x{:x}", header.class_defs_off)
+    );
+    assert!(header.to_string().is_ascii());
+
+    let compact = format!("{:#}", header);
+    assert_eq!(compact.lines().count(), 1);
+    assert!(compact.starts_with("magic: 0x6465780a, checksum: 0x"));
+    assert!(compact.contains(", header_size: 0x70, endian_tag: 0x12345678, "));
+  }
+
+  #[test]
+  fn test_type_list_index_out_of_range() {
+    use crate::test_util::{ClassDef, DexBuilder};
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    builder.class(ClassDef::new(a).interface(7));
+    let error = crate::parse(&builder.build()).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::NomError { .. }));
+  }
+
+  #[test]
+  fn test_id_index_out_of_range() {
+    use crate::test_util::{ClassDef, DexBuilder};
+
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let count = builder.field("La;", "I", "count");
+    builder.method("La;", "run", "V", &[]);
+    builder.class(ClassDef::new(a).static_field(count, 0x0008));
+    let bytes = builder.build();
+    assert!(crate::parse(&bytes).is_ok());
+
+    let offset = |header_off: usize| {
+      u32::from_le_bytes(bytes[header_off..header_off + 4].try_into().unwrap()) as usize
+    };
+    // field_ids[0].name_idx, method_ids[0].proto_idx and
+    // class_defs[0].superclass_idx, each pointed past its table.
+    for (position, corrupt) in [
+      (offset(0x54) + 4, &[0x56, 0x34, 0xec, 0x00][..]),
+      (offset(0x5c) + 2, &[0x7f, 0x00]),
+      (offset(0x64) + 8, &[0x7f, 0x00, 0x00, 0x00]),
+    ] {
+      let mut bytes = bytes.clone();
+      bytes[position..position + corrupt.len()].copy_from_slice(corrupt);
+      let error = crate::parse(&bytes).err().unwrap();
+      assert!(matches!(error.kind(), ErrorKind::NomError { .. }));
+    }
+  }
+
+  #[test]
+  fn test_method_handles() {
+    use crate::test_util::DexBuilder;
+
+    let mut builder = DexBuilder::default();
+    let count = builder.field("La;", "I", "count");
+    let run = builder.method("La;", "run", "V", &["I"]);
+    builder.method_handle(0x03, count as u16);
+    builder.method_handle(0x04, run as u16);
+    builder.method_handle(0x06, 99);
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    let [getter, invoker, dangling] = dex_file.method_handles() else {
+      panic!("expected three method handles");
+    };
+    assert_eq!(getter.method_handle_type(), 0x03);
+    assert!(getter.is_field_accessor());
+    assert_eq!(getter.to_string(), "REF_getField La;->count:I");
+    assert_eq!(invoker.field_or_method_id(), run as u16);
+    assert_eq!(invoker.to_string(), "REF_invokeStatic La;->run(I)V");
+    assert_eq!(dangling.to_string(), "REF_newInvokeSpecial method@99");
+    assert!(dex_file
+      .to_string()
+      .contains("\nmethod_handles: \nMethod handle #0: REF_getField La;->count:I\n"));
+  }
+
+  #[test]
+  fn test_call_site() {
+    use crate::{
+      encoded_value::EncodedValue,
+      test_util::{without_string_id_warnings, DexBuilder},
+    };
+
+    let mut builder = DexBuilder::default();
+    let name = builder.string("apply") as u8;
+    let call_type = builder.proto("Ljava/util/function/Function;", &["I"]) as u8;
+    let erased_type = builder.proto("Ljava/lang/Object;", &["Ljava/lang/Object;"]) as u8;
+    #[rustfmt::skip]
+    let call_site = [
+      5,                // size
+      0x16, 0,          // method handle @0
+      0x17, name,       // "apply"
+      0x15, call_type,  // (I)Ljava/util/function/Function;
+      0x15, erased_type,
+      0x04, 42,         // int 42
+    ];
+    builder.call_site(&call_site);
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    assert!(without_string_id_warnings(dex_file.validate()).is_empty());
+
+    let [call_site_id] = dex_file.call_site_ids() else {
+      panic!("expected one call site");
+    };
+    assert_eq!(call_site_id.bootstrap_method_handle(), 0);
+    assert_eq!(call_site_id.method_name(), "apply");
+    assert_eq!(
+      call_site_id.method_type().descriptor(),
+      "(I)Ljava/util/function/Function;"
+    );
+    assert_eq!(
+      call_site_id.extra_arguments(),
+      [
+        EncodedValue::MethodType(erased_type as u32),
+        EncodedValue::Int(42)
+      ]
+    );
+    assert_eq!(
+      call_site_id.to_string(),
+      "apply(I)Ljava/util/function/Function; via method handle @0 \
+       [(Ljava/lang/Object;)Ljava/lang/Object;, 42]"
+    );
+    assert!(dex_file
+      .to_string()
+      .contains("\ncall_site_ids: \nCall site #0: apply(I)"));
+
+    // The name and method type are swapped.
+    let mut builder = DexBuilder::default();
+    let label = builder.string("label") as u8;
+    builder.call_site(&[3, 0x16, 0, 0x15, 0, 0x17, label]);
+    let error = crate::parse(&builder.build()).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::NomError { .. }));
+  }
+
+  #[cfg(feature = "mmap")]
+  #[test]
+  fn test_parse_mmap() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../resource/classes.dex");
+    let mapped = crate::parse_mmap(path).unwrap();
+    let read = crate::parse(&std::fs::read(path).unwrap()).unwrap();
+    assert_eq!(mapped.class_defs().len(), read.class_defs().len());
+    assert_eq!(mapped.to_string(), read.to_string());
+  }
 }
@@ -0,0 +1,158 @@
+use std::fmt::Display;
+
+use base::error::Warning;
+
+use crate::raw_dex::DexFile;
+
+/// Method and field ids are indexed by 16 bits in dex instructions, so a
+/// single dex file can reference at most this many of each.
+pub const DEX_REFERENCE_LIMIT: usize = 65536;
+
+/// Counts at or above this are reported as approaching the limit.
+const WARNING_THRESHOLD: usize = DEX_REFERENCE_LIMIT * 9 / 10;
+
+/// Method and field reference counts of the dex files of an app.
+///
+/// The limit applies to each dex file separately; the totals only tell how
+/// much a multidex app would need in a single file.
+pub struct DexLimits {
+  /// `(method refs, field refs)` per dex file.
+  counts: Vec<(usize, usize)>,
+}
+
+impl DexLimits {
+  pub fn new<'a>(dex_files: impl IntoIterator<Item = &'a DexFile>) -> Self {
+    Self::from_counts(
+      dex_files
+        .into_iter()
+        .map(|dex_file| (dex_file.method_ref_count(), dex_file.field_ref_count()))
+        .collect(),
+    )
+  }
+
+  fn from_counts(counts: Vec<(usize, usize)>) -> Self {
+    Self { counts }
+  }
+
+  pub fn method_ref_count(&self) -> usize {
+    self.counts.iter().map(|(methods, _)| methods).sum()
+  }
+
+  pub fn field_ref_count(&self) -> usize {
+    self.counts.iter().map(|(_, fields)| fields).sum()
+  }
+
+  /// Dex files whose method or field references are close to or over
+  /// `DEX_REFERENCE_LIMIT`.
+  pub fn warnings(&self) -> Vec<Warning> {
+    let mut warnings = vec![];
+    for (file, &(methods, fields)) in self.counts.iter().enumerate() {
+      for (kind, count) in [("method", methods), ("field", fields)] {
+        if count >= WARNING_THRESHOLD {
+          warnings.push(Warning::ReferenceLimit {
+            file,
+            kind,
+            count,
+            limit: DEX_REFERENCE_LIMIT,
+          });
+        }
+      }
+    }
+    warnings
+  }
+}
+
+impl Display for DexLimits {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (file, (methods, fields)) in self.counts.iter().enumerate() {
+      writeln!(
+        f,
+        "dex file {}\tmethods: {}\tfields: {}",
+        file, methods, fields
+      )?;
+    }
+    if self.counts.len() > 1 {
+      writeln!(
+        f,
+        "total\tmethods: {}\tfields: {}",
+        self.method_ref_count(),
+        self.field_ref_count()
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base::error::Warning;
+
+  use super::{DexLimits, DEX_REFERENCE_LIMIT};
+  use crate::test_util::DexBuilder;
+
+  #[test]
+  fn test_counts_match_header() {
+    let mut builder = DexBuilder::default();
+    builder.field("La;", "I", "count");
+    builder.field("La;", "J", "total");
+    builder.method("La;", "run", "V", &[]);
+    builder.method("La;", "stop", "V", &[]);
+    builder.method("Lb;", "run", "V", &["I"]);
+    let dex_file = crate::parse(&builder.build()).unwrap();
+
+    assert_eq!(dex_file.method_ref_count(), 3);
+    assert_eq!(dex_file.field_ref_count(), 2);
+    let header = dex_file.header();
+    assert_eq!(dex_file.method_ref_count(), header.method_ids_size as usize);
+    assert_eq!(dex_file.field_ref_count(), header.field_ids_size as usize);
+
+    let resource = concat!(env!("CARGO_MANIFEST_DIR"), "/../../resource/classes.dex");
+    let resource = crate::parse(&std::fs::read(resource).unwrap()).unwrap();
+    assert_eq!(
+      resource.method_ref_count(),
+      resource.header().method_ids_size as usize
+    );
+    assert_eq!(
+      resource.field_ref_count(),
+      resource.header().field_ids_size as usize
+    );
+
+    let limits = DexLimits::new([&dex_file, &resource]);
+    assert_eq!(limits.method_ref_count(), 3 + resource.method_ref_count());
+    assert!(limits.warnings().is_empty());
+    assert!(limits
+      .to_string()
+      .starts_with("dex file 0\tmethods: 3\tfields: 2\n"));
+    assert!(limits.to_string().contains("total\tmethods: "));
+  }
+
+  #[test]
+  fn test_limit_warnings() {
+    let limits = DexLimits::from_counts(vec![(60000, 100), (1000, DEX_REFERENCE_LIMIT + 1)]);
+    assert_eq!(
+      limits.warnings(),
+      vec![
+        Warning::ReferenceLimit {
+          file: 0,
+          kind: "method",
+          count: 60000,
+          limit: DEX_REFERENCE_LIMIT,
+        },
+        Warning::ReferenceLimit {
+          file: 1,
+          kind: "field",
+          count: DEX_REFERENCE_LIMIT + 1,
+          limit: DEX_REFERENCE_LIMIT,
+        },
+      ]
+    );
+    assert_eq!(
+      limits.warnings()[0].to_string(),
+      "dex file 0: 60000 of 65536 method references used"
+    );
+    // 58982 is 90% of the limit.
+    assert!(DexLimits::from_counts(vec![(58981, 0)])
+      .warnings()
+      .is_empty());
+  }
+}
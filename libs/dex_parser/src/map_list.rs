@@ -1,4 +1,6 @@
-use base::Parsable;
+use std::fmt::Display;
+
+use base::{error::Warning, Parsable};
 use nom::{
   error::ParseError,
   number::complete::{le_u16, le_u32},
@@ -6,6 +8,63 @@ use nom::{
   IResult,
 };
 
+use crate::raw_dex::{DexHeader, DEX_HEADER_SIZE};
+
+pub const TYPE_HEADER_ITEM: u16 = 0x0000;
+pub const TYPE_STRING_ID_ITEM: u16 = 0x0001;
+pub const TYPE_TYPE_ID_ITEM: u16 = 0x0002;
+pub const TYPE_PROTO_ID_ITEM: u16 = 0x0003;
+pub const TYPE_FIELD_ID_ITEM: u16 = 0x0004;
+pub const TYPE_METHOD_ID_ITEM: u16 = 0x0005;
+pub const TYPE_CLASS_DEF_ITEM: u16 = 0x0006;
+pub const TYPE_CALL_SITE_ID_ITEM: u16 = 0x0007;
+pub const TYPE_METHOD_HANDLE_ITEM: u16 = 0x0008;
+pub const TYPE_MAP_LIST: u16 = 0x1000;
+pub const TYPE_HIDDENAPI_CLASS_DATA_ITEM: u16 = 0xf000;
+
+/// Size of the map_list header and of each map_item.
+const MAP_LIST_HEADER_SIZE: u32 = 4;
+const MAP_ITEM_SIZE: u32 = 12;
+
+/// Name of a map item type as in the dex format documentation, `unknown`
+/// for a type it doesn't list. Types the crate doesn't parse, such as
+/// `annotation_set_ref_list`, still have their name.
+pub fn section_type_name(code: u16) -> &'static str {
+  match code {
+    TYPE_HEADER_ITEM => "header_item",
+    TYPE_STRING_ID_ITEM => "string_id_item",
+    TYPE_TYPE_ID_ITEM => "type_id_item",
+    TYPE_PROTO_ID_ITEM => "proto_id_item",
+    TYPE_FIELD_ID_ITEM => "field_id_item",
+    TYPE_METHOD_ID_ITEM => "method_id_item",
+    TYPE_CLASS_DEF_ITEM => "class_def_item",
+    TYPE_CALL_SITE_ID_ITEM => "call_site_id_item",
+    TYPE_METHOD_HANDLE_ITEM => "method_handle_item",
+    TYPE_MAP_LIST => "map_list",
+    0x1001 => "type_list",
+    0x1002 => "annotation_set_ref_list",
+    0x1003 => "annotation_set_item",
+    0x2000 => "class_data_item",
+    0x2001 => "code_item",
+    0x2002 => "string_data_item",
+    0x2003 => "debug_info_item",
+    0x2004 => "annotation_item",
+    0x2005 => "encoded_array_item",
+    0x2006 => "annotations_directory_item",
+    TYPE_HIDDENAPI_CLASS_DATA_ITEM => "hiddenapi_class_data_item",
+    _ => "unknown",
+  }
+}
+
+/// [`section_type_name`], with the code of an unknown type: `unknown(0x1234)`.
+fn type_name(map_item_type: u16) -> String {
+  match section_type_name(map_item_type) {
+    "unknown" => format!("unknown(0x{:04x})", map_item_type),
+    name => name.to_string(),
+  }
+}
+
+#[derive(Default)]
 pub struct MapList {
   size: u32,
   map_item: Vec<MapItem>,
@@ -17,6 +76,177 @@ pub struct MapItem {
   offset: u32,
 }
 
+impl MapList {
+  pub fn item(&self, map_item_type: u16) -> Option<&MapItem> {
+    self
+      .map_item
+      .iter()
+      .find(|item| item.map_item_type == map_item_type)
+  }
+
+  /// In file order, unknown types included.
+  pub fn items(&self) -> &[MapItem] {
+    &self.map_item
+  }
+
+  /// Checks that the items are sorted by offset without overlapping, lie
+  /// within `file_size`, and that the id sections and the map itself match
+  /// the sizes and offsets in `header`.
+  pub fn validate(&self, header: &DexHeader) -> Vec<Warning> {
+    let mut warnings = vec![];
+    let mut warn = |map_item_type: u16, problem: String| {
+      warnings.push(Warning::InvalidMap {
+        item: type_name(map_item_type),
+        problem,
+      })
+    };
+    let mut previous: Option<&MapItem> = None;
+    for item in &self.map_item {
+      if let Some(previous) = previous {
+        if item.offset <= previous.offset {
+          warn(
+            item.map_item_type,
+            format!(
+              "offset 0x{:x} is not after the previous item's 0x{:x}",
+              item.offset, previous.offset
+            ),
+          );
+        } else if let Some(end) = previous.end() {
+          if (item.offset as u64) < end {
+            warn(
+              item.map_item_type,
+              format!(
+                "offset 0x{:x} overlaps {}, which ends at 0x{:x}",
+                item.offset,
+                type_name(previous.map_item_type),
+                end
+              ),
+            );
+          }
+        }
+      }
+      let end = item.end().unwrap_or(item.offset as u64 + 1);
+      if item.size > 0 && end > header.file_size as u64 {
+        warn(
+          item.map_item_type,
+          format!(
+            "0x{:x}..0x{:x} is past the end of the file (0x{:x} bytes)",
+            item.offset, end, header.file_size
+          ),
+        );
+      }
+      previous = Some(item);
+    }
+
+    let sections = [
+      (
+        TYPE_STRING_ID_ITEM,
+        header.string_ids_size,
+        header.string_ids_off,
+      ),
+      (TYPE_TYPE_ID_ITEM, header.type_ids_size, header.type_ids_off),
+      (
+        TYPE_PROTO_ID_ITEM,
+        header.proto_ids_size,
+        header.proto_ids_off,
+      ),
+      (
+        TYPE_FIELD_ID_ITEM,
+        header.field_ids_size,
+        header.field_ids_off,
+      ),
+      (
+        TYPE_METHOD_ID_ITEM,
+        header.method_ids_size,
+        header.method_ids_off,
+      ),
+      (
+        TYPE_CLASS_DEF_ITEM,
+        header.class_defs_size,
+        header.class_defs_off,
+      ),
+      (TYPE_MAP_LIST, 1, header.map_off),
+    ];
+    for (map_item_type, size, offset) in sections {
+      match self.item(map_item_type) {
+        Some(item) if item.size != size => warn(
+          map_item_type,
+          format!("size {} but the header says {}", item.size, size),
+        ),
+        Some(item) if item.offset != offset => warn(
+          map_item_type,
+          format!(
+            "offset 0x{:x} but the header says 0x{:x}",
+            item.offset, offset
+          ),
+        ),
+        Some(_) => {}
+        None if size > 0 => warn(
+          map_item_type,
+          format!("missing, the header says {} at 0x{:x}", size, offset),
+        ),
+        None => {}
+      }
+    }
+    warnings
+  }
+}
+
+impl MapItem {
+  pub fn map_item_type(&self) -> u16 {
+    self.map_item_type
+  }
+
+  pub fn offset(&self) -> u32 {
+    self.offset
+  }
+
+  /// Number of items of this type.
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+
+  /// End offset of the item, for the types whose size is fixed by the
+  /// format; `None` for variable-sized data items.
+  fn end(&self) -> Option<u64> {
+    let length = match self.map_item_type {
+      TYPE_HEADER_ITEM => DEX_HEADER_SIZE as u32,
+      TYPE_STRING_ID_ITEM | TYPE_TYPE_ID_ITEM | TYPE_CALL_SITE_ID_ITEM => 4,
+      TYPE_PROTO_ID_ITEM => 12,
+      TYPE_FIELD_ID_ITEM | TYPE_METHOD_ID_ITEM | TYPE_METHOD_HANDLE_ITEM => 8,
+      TYPE_CLASS_DEF_ITEM => 32,
+      TYPE_MAP_LIST => {
+        return Some(self.offset as u64 + (MAP_LIST_HEADER_SIZE + MAP_ITEM_SIZE * self.size) as u64)
+      }
+      _ => return None,
+    };
+    Some(self.offset as u64 + length as u64 * self.size as u64)
+  }
+}
+
+/// `string_id_item: 3 at 0x70`.
+impl Display for MapItem {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}: {} at 0x{:x}",
+      type_name(self.map_item_type),
+      self.size,
+      self.offset
+    )
+  }
+}
+
+/// One item per line.
+impl Display for MapList {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for item in &self.map_item {
+      writeln!(f, "{}", item)?;
+    }
+    Ok(())
+  }
+}
+
 impl Parsable for MapList {
   fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
     bytes: &'a [u8],
@@ -44,3 +274,174 @@ impl Parsable for MapItem {
     ))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use base::{error::Warning, Parsable};
+
+  use super::{section_type_name, MapList};
+  use crate::test_util::{ClassDef, DexBuilder, Method};
+
+  #[test]
+  fn test_unmodeled_section_types() {
+    #[rustfmt::skip]
+    let bytes = [
+      3, 0, 0, 0,
+      0x00, 0x00, 0, 0, 1, 0, 0, 0, 0x00, 0x00, 0, 0, // header_item
+      0x02, 0x10, 0, 0, 2, 0, 0, 0, 0x00, 0x01, 0, 0, // annotation_set_ref_list
+      0xcd, 0xab, 0, 0, 1, 0, 0, 0, 0x40, 0x01, 0, 0, // not in the format
+    ];
+    let (_, map_list) = MapList::parse::<nom::error::Error<_>>(&bytes).unwrap();
+    let types: Vec<u16> = map_list
+      .items()
+      .iter()
+      .map(|item| item.map_item_type())
+      .collect();
+    assert_eq!(types, [0x0000, 0x1002, 0xabcd]);
+    assert_eq!(
+      map_list.to_string(),
+      "header_item: 1 at 0x0\n\
+       annotation_set_ref_list: 2 at 0x100\n\
+       unknown(0xabcd): 1 at 0x140\n"
+    );
+    assert_eq!(section_type_name(0x2006), "annotations_directory_item");
+    assert_eq!(section_type_name(0xabcd), "unknown");
+
+    // A dex whose map lists an unknown type still parses, and keeps it.
+    let mut builder = DexBuilder::default();
+    builder.method("La;", "run", "V", &[]);
+    let mut bytes = builder.build();
+    let map_off = u32::from_le_bytes(bytes[0x34..0x38].try_into().unwrap()) as usize;
+    // The first item after header_item.
+    let item = map_off + 4 + 12;
+    let original = u16::from_le_bytes(bytes[item..item + 2].try_into().unwrap());
+    bytes[item..item + 2].copy_from_slice(&0xabcdu16.to_le_bytes());
+    let dex_file = crate::parse(&bytes).unwrap();
+    assert_eq!(dex_file.map_list().items()[1].map_item_type(), 0xabcd);
+    assert!(dex_file.map_list().item(original).is_none());
+    assert!(dex_file
+      .map_list()
+      .to_string()
+      .lines()
+      .nth(1)
+      .unwrap()
+      .starts_with("unknown(0xabcd): "));
+  }
+
+  #[test]
+  fn test_validate() {
+    let mut builder = DexBuilder::default();
+    builder.method("La;", "run", "V", &[]);
+    let mut bytes = builder.build();
+    assert!(crate::parse(&bytes).unwrap().validate().is_empty());
+
+    // The map's string_id_item entry, right after header_item, claims one
+    // string more than the header.
+    let map_off = u32::from_le_bytes(bytes[0x34..0x38].try_into().unwrap()) as usize;
+    let string_ids_size = map_off + 4 + 12 + 4;
+    let size = u32::from_le_bytes(
+      bytes[string_ids_size..string_ids_size + 4]
+        .try_into()
+        .unwrap(),
+    );
+    bytes[string_ids_size..string_ids_size + 4].copy_from_slice(&(size + 1).to_le_bytes());
+
+    let warnings = crate::parse(&bytes).unwrap().validate();
+    assert_eq!(
+      warnings,
+      vec![
+        Warning::InvalidMap {
+          item: "type_id_item".to_string(),
+          problem: "offset 0x7c overlaps string_id_item, which ends at 0x80".to_string(),
+        },
+        Warning::InvalidMap {
+          item: "string_id_item".to_string(),
+          problem: format!("size {} but the header says {}", size + 1, size),
+        },
+      ]
+    );
+    assert_eq!(
+      warnings[1].to_string(),
+      format!(
+        "map item string_id_item: size {} but the header says {}",
+        size + 1,
+        size
+      )
+    );
+  }
+
+  #[test]
+  fn test_string_ids() {
+    let mut builder = DexBuilder::default();
+    for string in ["a", "c", "b", "d"] {
+      builder.string(string);
+    }
+    let mut bytes = builder.build();
+    let warnings = crate::parse(&bytes).unwrap().validate();
+    assert_eq!(
+      warnings,
+      vec![Warning::InvalidStringId {
+        index: 2,
+        problem: "\"b\" sorts before the previous string \"c\"".to_string(),
+      }]
+    );
+    assert_eq!(
+      warnings[0].to_string(),
+      "string_ids[2]: \"b\" sorts before the previous string \"c\""
+    );
+
+    // Point "d" at the data of "b", and "a" into the header, where the magic
+    // reads as "ex\n035".
+    let string_ids_off = u32::from_le_bytes(bytes[0x3c..0x40].try_into().unwrap()) as usize;
+    let string_id = |index: usize| string_ids_off + index * 4;
+    let b = bytes[string_id(2)..string_id(2) + 4].to_vec();
+    bytes[string_id(3)..string_id(3) + 4].copy_from_slice(&b);
+    bytes[string_id(0)..string_id(0) + 4].copy_from_slice(&0u32.to_le_bytes());
+    let problems: Vec<_> = crate::parse(&bytes)
+      .unwrap()
+      .validate()
+      .iter()
+      .map(ToString::to_string)
+      .collect();
+    let data_off = u32::from_le_bytes(bytes[0x6c..0x70].try_into().unwrap());
+    let data_size = u32::from_le_bytes(bytes[0x68..0x6c].try_into().unwrap());
+    assert_eq!(
+      problems,
+      vec![
+        format!(
+          "string_ids[0]: string_data_off 0x0 is outside the data section 0x{:x}..0x{:x}",
+          data_off,
+          data_off + data_size
+        ),
+        "string_ids[1]: \"c\" sorts before the previous string \"ex\\n035\"".to_string(),
+        "string_ids[2]: \"b\" sorts before the previous string \"c\"".to_string(),
+        "string_ids[3]: \"b\" duplicates string_ids[2]".to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_header_size() {
+    let mut builder = DexBuilder::default();
+    let a = builder.type_id("La;");
+    let run = builder.method("La;", "run", "V", &[]);
+    builder.class(ClassDef::new(a).virtual_method(Method::new(run, 0x0401)));
+    builder.header_size(0x78);
+    // The id sections follow the 8 bytes of padding after the fixed fields.
+    let dex_file = crate::parse(&builder.build()).unwrap();
+    let run = dex_file.methods().next().unwrap();
+    assert_eq!((run.class_descriptor(), run.name()), ("La;", "run"));
+    let warnings = dex_file.validate();
+    assert_eq!(
+      warnings,
+      vec![Warning::HeaderSize {
+        header_size: 0x78,
+        expected: 0x70,
+      }]
+    );
+    assert_eq!(
+      warnings[0].to_string(),
+      "header_size is 0x78, expected 0x70; the file may be a compact dex or corrupt"
+    );
+  }
+}
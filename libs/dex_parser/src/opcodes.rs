@@ -0,0 +1,389 @@
+//! The Dalvik opcode table.
+//!
+//! https://source.android.com/docs/core/runtime/dalvik-bytecode
+
+use std::fmt::Display;
+
+/// Instruction formats, named as in the Dalvik spec: the first digit is the
+/// size in 16-bit code units.
+///
+/// https://source.android.com/docs/core/runtime/instruction-formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+  F10t,
+  F10x,
+  F11n,
+  F11x,
+  F12x,
+  F20t,
+  F21c,
+  F21h,
+  F21s,
+  F21t,
+  F22b,
+  F22c,
+  F22s,
+  F22t,
+  F22x,
+  F23x,
+  F30t,
+  F31c,
+  F31i,
+  F31t,
+  F32x,
+  F35c,
+  F3rc,
+  F45cc,
+  F4rcc,
+  F51l,
+}
+
+impl Format {
+  /// Size of an instruction of this format in 16-bit code units.
+  pub fn units(&self) -> usize {
+    match self {
+      Format::F10x | Format::F10t | Format::F11n | Format::F11x | Format::F12x => 1,
+      Format::F20t
+      | Format::F21c
+      | Format::F21h
+      | Format::F21s
+      | Format::F21t
+      | Format::F22b
+      | Format::F22c
+      | Format::F22s
+      | Format::F22t
+      | Format::F22x
+      | Format::F23x => 2,
+      Format::F30t
+      | Format::F31c
+      | Format::F31i
+      | Format::F31t
+      | Format::F32x
+      | Format::F35c
+      | Format::F3rc => 3,
+      Format::F45cc | Format::F4rcc => 4,
+      Format::F51l => 5,
+    }
+  }
+}
+
+/// `(opcode, mnemonic, format)` of every assigned opcode, sorted by opcode.
+/// The unused ranges (`0x3e..=0x43`, `0x73`, `0x79..=0x7a`, `0xe3..=0xf9`)
+/// are left out.
+pub const OPCODES: &[(u8, &str, Format)] = &[
+  (0x00, "nop", Format::F10x),
+  (0x01, "move", Format::F12x),
+  (0x02, "move/from16", Format::F22x),
+  (0x03, "move/16", Format::F32x),
+  (0x04, "move-wide", Format::F12x),
+  (0x05, "move-wide/from16", Format::F22x),
+  (0x06, "move-wide/16", Format::F32x),
+  (0x07, "move-object", Format::F12x),
+  (0x08, "move-object/from16", Format::F22x),
+  (0x09, "move-object/16", Format::F32x),
+  (0x0a, "move-result", Format::F11x),
+  (0x0b, "move-result-wide", Format::F11x),
+  (0x0c, "move-result-object", Format::F11x),
+  (0x0d, "move-exception", Format::F11x),
+  (0x0e, "return-void", Format::F10x),
+  (0x0f, "return", Format::F11x),
+  (0x10, "return-wide", Format::F11x),
+  (0x11, "return-object", Format::F11x),
+  (0x12, "const/4", Format::F11n),
+  (0x13, "const/16", Format::F21s),
+  (0x14, "const", Format::F31i),
+  (0x15, "const/high16", Format::F21h),
+  (0x16, "const-wide/16", Format::F21s),
+  (0x17, "const-wide/32", Format::F31i),
+  (0x18, "const-wide", Format::F51l),
+  (0x19, "const-wide/high16", Format::F21h),
+  (0x1a, "const-string", Format::F21c),
+  (0x1b, "const-string/jumbo", Format::F31c),
+  (0x1c, "const-class", Format::F21c),
+  (0x1d, "monitor-enter", Format::F11x),
+  (0x1e, "monitor-exit", Format::F11x),
+  (0x1f, "check-cast", Format::F21c),
+  (0x20, "instance-of", Format::F22c),
+  (0x21, "array-length", Format::F12x),
+  (0x22, "new-instance", Format::F21c),
+  (0x23, "new-array", Format::F22c),
+  (0x24, "filled-new-array", Format::F35c),
+  (0x25, "filled-new-array/range", Format::F3rc),
+  (0x26, "fill-array-data", Format::F31t),
+  (0x27, "throw", Format::F11x),
+  (0x28, "goto", Format::F10t),
+  (0x29, "goto/16", Format::F20t),
+  (0x2a, "goto/32", Format::F30t),
+  (0x2b, "packed-switch", Format::F31t),
+  (0x2c, "sparse-switch", Format::F31t),
+  (0x2d, "cmpl-float", Format::F23x),
+  (0x2e, "cmpg-float", Format::F23x),
+  (0x2f, "cmpl-double", Format::F23x),
+  (0x30, "cmpg-double", Format::F23x),
+  (0x31, "cmp-long", Format::F23x),
+  (0x32, "if-eq", Format::F22t),
+  (0x33, "if-ne", Format::F22t),
+  (0x34, "if-lt", Format::F22t),
+  (0x35, "if-ge", Format::F22t),
+  (0x36, "if-gt", Format::F22t),
+  (0x37, "if-le", Format::F22t),
+  (0x38, "if-eqz", Format::F21t),
+  (0x39, "if-nez", Format::F21t),
+  (0x3a, "if-ltz", Format::F21t),
+  (0x3b, "if-gez", Format::F21t),
+  (0x3c, "if-gtz", Format::F21t),
+  (0x3d, "if-lez", Format::F21t),
+  (0x44, "aget", Format::F23x),
+  (0x45, "aget-wide", Format::F23x),
+  (0x46, "aget-object", Format::F23x),
+  (0x47, "aget-boolean", Format::F23x),
+  (0x48, "aget-byte", Format::F23x),
+  (0x49, "aget-char", Format::F23x),
+  (0x4a, "aget-short", Format::F23x),
+  (0x4b, "aput", Format::F23x),
+  (0x4c, "aput-wide", Format::F23x),
+  (0x4d, "aput-object", Format::F23x),
+  (0x4e, "aput-boolean", Format::F23x),
+  (0x4f, "aput-byte", Format::F23x),
+  (0x50, "aput-char", Format::F23x),
+  (0x51, "aput-short", Format::F23x),
+  (0x52, "iget", Format::F22c),
+  (0x53, "iget-wide", Format::F22c),
+  (0x54, "iget-object", Format::F22c),
+  (0x55, "iget-boolean", Format::F22c),
+  (0x56, "iget-byte", Format::F22c),
+  (0x57, "iget-char", Format::F22c),
+  (0x58, "iget-short", Format::F22c),
+  (0x59, "iput", Format::F22c),
+  (0x5a, "iput-wide", Format::F22c),
+  (0x5b, "iput-object", Format::F22c),
+  (0x5c, "iput-boolean", Format::F22c),
+  (0x5d, "iput-byte", Format::F22c),
+  (0x5e, "iput-char", Format::F22c),
+  (0x5f, "iput-short", Format::F22c),
+  (0x60, "sget", Format::F21c),
+  (0x61, "sget-wide", Format::F21c),
+  (0x62, "sget-object", Format::F21c),
+  (0x63, "sget-boolean", Format::F21c),
+  (0x64, "sget-byte", Format::F21c),
+  (0x65, "sget-char", Format::F21c),
+  (0x66, "sget-short", Format::F21c),
+  (0x67, "sput", Format::F21c),
+  (0x68, "sput-wide", Format::F21c),
+  (0x69, "sput-object", Format::F21c),
+  (0x6a, "sput-boolean", Format::F21c),
+  (0x6b, "sput-byte", Format::F21c),
+  (0x6c, "sput-char", Format::F21c),
+  (0x6d, "sput-short", Format::F21c),
+  (0x6e, "invoke-virtual", Format::F35c),
+  (0x6f, "invoke-super", Format::F35c),
+  (0x70, "invoke-direct", Format::F35c),
+  (0x71, "invoke-static", Format::F35c),
+  (0x72, "invoke-interface", Format::F35c),
+  (0x74, "invoke-virtual/range", Format::F3rc),
+  (0x75, "invoke-super/range", Format::F3rc),
+  (0x76, "invoke-direct/range", Format::F3rc),
+  (0x77, "invoke-static/range", Format::F3rc),
+  (0x78, "invoke-interface/range", Format::F3rc),
+  (0x7b, "neg-int", Format::F12x),
+  (0x7c, "not-int", Format::F12x),
+  (0x7d, "neg-long", Format::F12x),
+  (0x7e, "not-long", Format::F12x),
+  (0x7f, "neg-float", Format::F12x),
+  (0x80, "neg-double", Format::F12x),
+  (0x81, "int-to-long", Format::F12x),
+  (0x82, "int-to-float", Format::F12x),
+  (0x83, "int-to-double", Format::F12x),
+  (0x84, "long-to-int", Format::F12x),
+  (0x85, "long-to-float", Format::F12x),
+  (0x86, "long-to-double", Format::F12x),
+  (0x87, "float-to-int", Format::F12x),
+  (0x88, "float-to-long", Format::F12x),
+  (0x89, "float-to-double", Format::F12x),
+  (0x8a, "double-to-int", Format::F12x),
+  (0x8b, "double-to-long", Format::F12x),
+  (0x8c, "double-to-float", Format::F12x),
+  (0x8d, "int-to-byte", Format::F12x),
+  (0x8e, "int-to-char", Format::F12x),
+  (0x8f, "int-to-short", Format::F12x),
+  (0x90, "add-int", Format::F23x),
+  (0x91, "sub-int", Format::F23x),
+  (0x92, "mul-int", Format::F23x),
+  (0x93, "div-int", Format::F23x),
+  (0x94, "rem-int", Format::F23x),
+  (0x95, "and-int", Format::F23x),
+  (0x96, "or-int", Format::F23x),
+  (0x97, "xor-int", Format::F23x),
+  (0x98, "shl-int", Format::F23x),
+  (0x99, "shr-int", Format::F23x),
+  (0x9a, "ushr-int", Format::F23x),
+  (0x9b, "add-long", Format::F23x),
+  (0x9c, "sub-long", Format::F23x),
+  (0x9d, "mul-long", Format::F23x),
+  (0x9e, "div-long", Format::F23x),
+  (0x9f, "rem-long", Format::F23x),
+  (0xa0, "and-long", Format::F23x),
+  (0xa1, "or-long", Format::F23x),
+  (0xa2, "xor-long", Format::F23x),
+  (0xa3, "shl-long", Format::F23x),
+  (0xa4, "shr-long", Format::F23x),
+  (0xa5, "ushr-long", Format::F23x),
+  (0xa6, "add-float", Format::F23x),
+  (0xa7, "sub-float", Format::F23x),
+  (0xa8, "mul-float", Format::F23x),
+  (0xa9, "div-float", Format::F23x),
+  (0xaa, "rem-float", Format::F23x),
+  (0xab, "add-double", Format::F23x),
+  (0xac, "sub-double", Format::F23x),
+  (0xad, "mul-double", Format::F23x),
+  (0xae, "div-double", Format::F23x),
+  (0xaf, "rem-double", Format::F23x),
+  (0xb0, "add-int/2addr", Format::F12x),
+  (0xb1, "sub-int/2addr", Format::F12x),
+  (0xb2, "mul-int/2addr", Format::F12x),
+  (0xb3, "div-int/2addr", Format::F12x),
+  (0xb4, "rem-int/2addr", Format::F12x),
+  (0xb5, "and-int/2addr", Format::F12x),
+  (0xb6, "or-int/2addr", Format::F12x),
+  (0xb7, "xor-int/2addr", Format::F12x),
+  (0xb8, "shl-int/2addr", Format::F12x),
+  (0xb9, "shr-int/2addr", Format::F12x),
+  (0xba, "ushr-int/2addr", Format::F12x),
+  (0xbb, "add-long/2addr", Format::F12x),
+  (0xbc, "sub-long/2addr", Format::F12x),
+  (0xbd, "mul-long/2addr", Format::F12x),
+  (0xbe, "div-long/2addr", Format::F12x),
+  (0xbf, "rem-long/2addr", Format::F12x),
+  (0xc0, "and-long/2addr", Format::F12x),
+  (0xc1, "or-long/2addr", Format::F12x),
+  (0xc2, "xor-long/2addr", Format::F12x),
+  (0xc3, "shl-long/2addr", Format::F12x),
+  (0xc4, "shr-long/2addr", Format::F12x),
+  (0xc5, "ushr-long/2addr", Format::F12x),
+  (0xc6, "add-float/2addr", Format::F12x),
+  (0xc7, "sub-float/2addr", Format::F12x),
+  (0xc8, "mul-float/2addr", Format::F12x),
+  (0xc9, "div-float/2addr", Format::F12x),
+  (0xca, "rem-float/2addr", Format::F12x),
+  (0xcb, "add-double/2addr", Format::F12x),
+  (0xcc, "sub-double/2addr", Format::F12x),
+  (0xcd, "mul-double/2addr", Format::F12x),
+  (0xce, "div-double/2addr", Format::F12x),
+  (0xcf, "rem-double/2addr", Format::F12x),
+  (0xd0, "add-int/lit16", Format::F22s),
+  (0xd1, "rsub-int", Format::F22s),
+  (0xd2, "mul-int/lit16", Format::F22s),
+  (0xd3, "div-int/lit16", Format::F22s),
+  (0xd4, "rem-int/lit16", Format::F22s),
+  (0xd5, "and-int/lit16", Format::F22s),
+  (0xd6, "or-int/lit16", Format::F22s),
+  (0xd7, "xor-int/lit16", Format::F22s),
+  (0xd8, "add-int/lit8", Format::F22b),
+  (0xd9, "rsub-int/lit8", Format::F22b),
+  (0xda, "mul-int/lit8", Format::F22b),
+  (0xdb, "div-int/lit8", Format::F22b),
+  (0xdc, "rem-int/lit8", Format::F22b),
+  (0xdd, "and-int/lit8", Format::F22b),
+  (0xde, "or-int/lit8", Format::F22b),
+  (0xdf, "xor-int/lit8", Format::F22b),
+  (0xe0, "shl-int/lit8", Format::F22b),
+  (0xe1, "shr-int/lit8", Format::F22b),
+  (0xe2, "ushr-int/lit8", Format::F22b),
+  (0xfa, "invoke-polymorphic", Format::F45cc),
+  (0xfb, "invoke-polymorphic/range", Format::F4rcc),
+  (0xfc, "invoke-custom", Format::F35c),
+  (0xfd, "invoke-custom/range", Format::F3rc),
+  (0xfe, "const-method-handle", Format::F21c),
+  (0xff, "const-method-type", Format::F21c),
+];
+
+/// What a Dalvik opcode does, after the groups of the Dalvik bytecode table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpcodeCategory {
+  Nop,
+  /// `move` forms, `move-result` and `move-exception`.
+  Move,
+  Return,
+  /// `const` forms, including `const-string`, `const-class`,
+  /// `const-method-handle` and `const-method-type`.
+  Const,
+  Monitor,
+  /// `check-cast`, `instance-of` and `new-instance`.
+  Object,
+  /// Array creation, filling, length and `aget`/`aput`.
+  Array,
+  Throw,
+  /// `goto`, switches and `if-*`.
+  Branch,
+  /// `cmpl-float` and the other comparisons yielding an int.
+  Compare,
+  /// `iget`/`iput`.
+  Instance,
+  /// `sget`/`sput`.
+  Static,
+  /// `invoke-*`, including `invoke-polymorphic` and `invoke-custom`.
+  Invoke,
+  /// `int-to-long` and the other primitive conversions.
+  Conversion,
+  /// Unary, binary and literal arithmetic and bitwise operations.
+  Arithmetic,
+  /// A switch or array data table, which is data rather than code.
+  Payload,
+  /// An unused opcode.
+  Unknown,
+}
+
+/// The category of `opcode`.
+pub fn opcode_category(opcode: u8) -> OpcodeCategory {
+  match opcode {
+    0x00 => OpcodeCategory::Nop,
+    0x01..=0x0d => OpcodeCategory::Move,
+    0x0e..=0x11 => OpcodeCategory::Return,
+    0x12..=0x1c | 0xfe | 0xff => OpcodeCategory::Const,
+    0x1d | 0x1e => OpcodeCategory::Monitor,
+    0x1f | 0x20 | 0x22 => OpcodeCategory::Object,
+    0x21 | 0x23..=0x26 | 0x44..=0x51 => OpcodeCategory::Array,
+    0x27 => OpcodeCategory::Throw,
+    0x28..=0x2c | 0x32..=0x3d => OpcodeCategory::Branch,
+    0x2d..=0x31 => OpcodeCategory::Compare,
+    0x52..=0x5f => OpcodeCategory::Instance,
+    0x60..=0x6d => OpcodeCategory::Static,
+    0x6e..=0x72 | 0x74..=0x78 | 0xfa..=0xfd => OpcodeCategory::Invoke,
+    0x81..=0x8f => OpcodeCategory::Conversion,
+    0x7b..=0x80 | 0x90..=0xe2 => OpcodeCategory::Arithmetic,
+    _ => OpcodeCategory::Unknown,
+  }
+}
+
+impl Display for OpcodeCategory {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      OpcodeCategory::Nop => "nop",
+      OpcodeCategory::Move => "move",
+      OpcodeCategory::Return => "return",
+      OpcodeCategory::Const => "const",
+      OpcodeCategory::Monitor => "monitor",
+      OpcodeCategory::Object => "object",
+      OpcodeCategory::Array => "array",
+      OpcodeCategory::Throw => "throw",
+      OpcodeCategory::Branch => "branch",
+      OpcodeCategory::Compare => "compare",
+      OpcodeCategory::Instance => "instance",
+      OpcodeCategory::Static => "static",
+      OpcodeCategory::Invoke => "invoke",
+      OpcodeCategory::Conversion => "conversion",
+      OpcodeCategory::Arithmetic => "arithmetic",
+      OpcodeCategory::Payload => "payload",
+      OpcodeCategory::Unknown => "unknown",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// Mnemonic and format of `opcode`, `None` for unused opcodes.
+pub fn opcode_info(opcode: u8) -> Option<(&'static str, Format)> {
+  OPCODES
+    .binary_search_by_key(&opcode, |(code, _, _)| *code)
+    .ok()
+    .map(|index| (OPCODES[index].1, OPCODES[index].2))
+}
@@ -0,0 +1,282 @@
+//! The data tables `packed-switch`, `sparse-switch` and `fill-array-data`
+//! point at. They are stored inline in the insns, usually after the code,
+//! and start with an ident unit whose low byte reads as `nop`.
+//!
+//! https://source.android.com/docs/core/runtime/dalvik-bytecode#packed-switch
+
+use std::fmt::Display;
+
+pub const PACKED_SWITCH_IDENT: u16 = 0x0100;
+pub const SPARSE_SWITCH_IDENT: u16 = 0x0200;
+pub const FILL_ARRAY_DATA_IDENT: u16 = 0x0300;
+
+/// A decoded payload. Switch targets are relative to the switch
+/// instruction, in code units. Array data is `size` elements of
+/// `element_width` bytes, little-endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+  PackedSwitch {
+    first_key: i32,
+    targets: Vec<i32>,
+  },
+  SparseSwitch {
+    keys: Vec<i32>,
+    targets: Vec<i32>,
+  },
+  FillArrayData {
+    element_width: u16,
+    size: u32,
+    data: Vec<u8>,
+  },
+}
+
+/// Size in code units of the payload starting at `units[0]`, `None` if
+/// there is no payload there or its header is truncated. The payload may
+/// still run past the end of `units`.
+pub fn payload_units(units: &[u16]) -> Option<usize> {
+  let ident = *units.first()?;
+  let len = match ident {
+    PACKED_SWITCH_IDENT => 4 + *units.get(1)? as usize * 2,
+    SPARSE_SWITCH_IDENT => 2 + *units.get(1)? as usize * 4,
+    FILL_ARRAY_DATA_IDENT => {
+      let element_width = *units.get(1)? as usize;
+      let size = read_u32(units.get(2..4)?) as usize;
+      4 + (element_width * size).div_ceil(2)
+    }
+    _ => return None,
+  };
+  Some(len)
+}
+
+/// Mnemonic of the payload `ident` starts, `None` if it doesn't start one.
+pub fn payload_mnemonic(ident: u16) -> Option<&'static str> {
+  match ident {
+    PACKED_SWITCH_IDENT => Some("packed-switch-payload"),
+    SPARSE_SWITCH_IDENT => Some("sparse-switch-payload"),
+    FILL_ARRAY_DATA_IDENT => Some("fill-array-data-payload"),
+    _ => None,
+  }
+}
+
+fn read_u32(units: &[u16]) -> u32 {
+  units[0] as u32 | (units[1] as u32) << 16
+}
+
+fn read_i32s(units: &[u16]) -> Vec<i32> {
+  units.chunks(2).map(|pair| read_u32(pair) as i32).collect()
+}
+
+impl Payload {
+  /// Decodes the payload starting at `units[0]`, `None` if there is none or
+  /// it is truncated.
+  pub fn parse(units: &[u16]) -> Option<Self> {
+    let units = units.get(..payload_units(units)?)?;
+    let payload = match units[0] {
+      PACKED_SWITCH_IDENT => Payload::PackedSwitch {
+        first_key: read_u32(&units[2..4]) as i32,
+        targets: read_i32s(&units[4..]),
+      },
+      SPARSE_SWITCH_IDENT => {
+        let size = units[1] as usize;
+        Payload::SparseSwitch {
+          keys: read_i32s(&units[2..2 + size * 2]),
+          targets: read_i32s(&units[2 + size * 2..]),
+        }
+      }
+      _ => {
+        let element_width = units[1];
+        let size = read_u32(&units[2..4]);
+        let mut data: Vec<u8> = units[4..]
+          .iter()
+          .flat_map(|unit| unit.to_le_bytes())
+          .collect();
+        // An odd number of bytes is padded to a whole unit.
+        data.truncate(element_width as usize * size as usize);
+        Payload::FillArrayData {
+          element_width,
+          size,
+          data,
+        }
+      }
+    };
+    Some(payload)
+  }
+
+  pub fn mnemonic(&self) -> &'static str {
+    let ident = match self {
+      Payload::PackedSwitch { .. } => PACKED_SWITCH_IDENT,
+      Payload::SparseSwitch { .. } => SPARSE_SWITCH_IDENT,
+      Payload::FillArrayData { .. } => FILL_ARRAY_DATA_IDENT,
+    };
+    payload_mnemonic(ident).unwrap()
+  }
+
+  /// `(key, relative target)` of each case of a switch, empty for array
+  /// data. Packed keys run up from `first_key`.
+  pub fn cases(&self) -> Vec<(i32, i32)> {
+    match self {
+      Payload::PackedSwitch { first_key, targets } => targets
+        .iter()
+        .enumerate()
+        .map(|(i, target)| (first_key.wrapping_add(i as i32), *target))
+        .collect(),
+      Payload::SparseSwitch { keys, targets } => {
+        keys.iter().copied().zip(targets.iter().copied()).collect()
+      }
+      Payload::FillArrayData { .. } => vec![],
+    }
+  }
+
+  /// The elements of array data, sign-extended. `None` for switches and for
+  /// a width other than 1, 2, 4 or 8 bytes.
+  pub fn elements(&self) -> Option<Vec<i64>> {
+    let Payload::FillArrayData {
+      element_width,
+      data,
+      ..
+    } = self
+    else {
+      return None;
+    };
+    let element_width = *element_width as usize;
+    if ![1, 2, 4, 8].contains(&element_width) {
+      return None;
+    }
+    let elements = data
+      .chunks(element_width)
+      .map(|bytes| {
+        let mut buf = [0u8; 8];
+        buf[..element_width].copy_from_slice(bytes);
+        let shift = 64 - 8 * element_width as u32;
+        (i64::from_le_bytes(buf) << shift) >> shift
+      })
+      .collect();
+    Some(elements)
+  }
+}
+
+impl Display for Payload {
+  /// The cases as `key: +target`, or the array elements as
+  /// `{1, 2, 3} (4-byte)`.
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Payload::FillArrayData {
+        element_width,
+        size,
+        ..
+      } => match self.elements() {
+        Some(elements) => {
+          let elements: Vec<String> = elements.iter().map(i64::to_string).collect();
+          write!(f, "{{{}}} ({}-byte)", elements.join(", "), element_width)
+        }
+        None => write!(f, "{} elements of {} bytes", size, element_width),
+      },
+      _ => {
+        let cases: Vec<String> = self
+          .cases()
+          .iter()
+          .map(|(key, target)| format!("{}: {:+}", key, target))
+          .collect();
+        write!(f, "{}", cases.join(", "))
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Payload;
+  use crate::{instruction::InstructionIterator, opcodes::OpcodeCategory};
+
+  #[test]
+  fn test_packed_switch() {
+    // 0: packed-switch v0, 4; 3: return-void; 4: packed-switch-payload with
+    // keys 10, 11, 12 to 3, 0, 3
+    #[rustfmt::skip]
+    let insns = [
+      0x002b, 0x0004, 0x0000,
+      0x000e,
+      0x0100, 0x0003, 0x000a, 0x0000, 0x0003, 0x0000, 0x0000, 0x0000, 0x0003, 0x0000,
+    ];
+    let instructions: Vec<_> = InstructionIterator::new(&insns).collect();
+    let walked: Vec<_> = instructions
+      .iter()
+      .map(|i| (i.pc, i.mnemonic.unwrap()))
+      .collect();
+    assert_eq!(
+      walked,
+      vec![
+        (0, "packed-switch"),
+        (3, "return-void"),
+        (4, "packed-switch-payload")
+      ]
+    );
+
+    let payload = instructions[2].payload().unwrap();
+    assert_eq!(
+      payload,
+      Payload::PackedSwitch {
+        first_key: 10,
+        targets: vec![3, 0, 3]
+      }
+    );
+    assert_eq!(payload.mnemonic(), "packed-switch-payload");
+    assert_eq!(payload.to_string(), "10: +3, 11: +0, 12: +3");
+    assert_eq!(instructions[2].category(), OpcodeCategory::Payload);
+    assert_eq!(instructions[0].payload(), None);
+    assert_eq!(instructions[0].referenced_payload(&insns), Some(payload));
+    assert_eq!(
+      instructions[0].switch_cases(&insns),
+      Some(vec![(10, 3), (11, 0), (12, 3)])
+    );
+
+    // A payload cut short ends the walk, and can't be read.
+    let truncated = &insns[..13];
+    assert_eq!(InstructionIterator::new(truncated).count(), 2);
+    assert_eq!(Payload::parse(&truncated[4..]), None);
+    assert_eq!(instructions[0].switch_cases(truncated), None);
+  }
+
+  #[test]
+  fn test_fill_array_data() {
+    // 0: fill-array-data v0, 4; 3: return-void; 4: int[] {1, -2, 0x12345678}
+    #[rustfmt::skip]
+    let insns = [
+      0x0026, 0x0004, 0x0000,
+      0x000e,
+      0x0300, 0x0004, 0x0003, 0x0000,
+      0x0001, 0x0000, 0xfffe, 0xffff, 0x5678, 0x1234,
+    ];
+    let instructions: Vec<_> = InstructionIterator::new(&insns).collect();
+    assert_eq!(instructions.len(), 3);
+    assert_eq!(instructions[2].mnemonic, Some("fill-array-data-payload"));
+    let payload = instructions[0].referenced_payload(&insns).unwrap();
+    assert_eq!(payload.elements(), Some(vec![1, -2, 0x12345678]));
+    assert_eq!(payload.to_string(), "{1, -2, 305419896} (4-byte)");
+    assert_eq!(instructions[0].switch_cases(&insns), None);
+
+    // byte[] {-1, 2, 3}, padded to a whole unit
+    let bytes = Payload::parse(&[0x0300, 0x0001, 0x0003, 0x0000, 0x02ff, 0x0003]).unwrap();
+    assert_eq!(bytes.elements(), Some(vec![-1, 2, 3]));
+    assert_eq!(
+      InstructionIterator::new(&[0x0300, 0x0001, 0x0003, 0x0000, 0x02ff, 0x0003, 0x000e]).count(),
+      2
+    );
+    // An odd width is only described.
+    let odd = Payload::parse(&[0x0300, 0x0003, 0x0001, 0x0000, 0x0001, 0x0000]).unwrap();
+    assert_eq!(odd.elements(), None);
+    assert_eq!(odd.to_string(), "1 elements of 3 bytes");
+  }
+
+  #[test]
+  fn test_sparse_switch() {
+    // keys -1, 5 to -3, +6
+    let units = [
+      0x0200, 0x0002, 0xffff, 0xffff, 0x0005, 0x0000, 0xfffd, 0xffff, 0x0006, 0x0000,
+    ];
+    let payload = Payload::parse(&units).unwrap();
+    assert_eq!(payload.cases(), vec![(-1, -3), (5, 6)]);
+    assert_eq!(payload.to_string(), "-1: -3, 5: +6");
+    assert_eq!(Payload::parse(&[0x0000, 0x0002]), None);
+  }
+}
@@ -1,7 +1,27 @@
-use std::{cmp::min, fmt::Display};
+use std::{
+  cmp::{min, Ordering},
+  collections::{BTreeSet, HashMap},
+  fmt::Display,
+  sync::Arc,
+};
 
-use base::{access_flag::AccessFlags, Parsable};
+use base::{
+  access_flag::AccessFlags,
+  coverage::OpcodeCoverage,
+  descriptor::descriptor_to_source,
+  error::{Error, ErrorKind, Warning},
+  indent::{Indent, IndentDisplay},
+  method_handle::{
+    is_field_kind, method_handle_kind_name, REF_GET_FIELD, REF_GET_STATIC, REF_INVOKE_INTERFACE,
+    REF_INVOKE_SPECIAL, REF_INVOKE_STATIC, REF_INVOKE_VIRTUAL, REF_NEW_INVOKE_SPECIAL,
+    REF_PUT_FIELD, REF_PUT_STATIC,
+  },
+  mutf8::decode_mutf8,
+  parse_limits::ParseLimits,
+  Parsable,
+};
 use nom::{
+  bytes::complete::take,
   multi::count,
   number::complete::{be_u32, be_u8, le_u16, le_u32},
   sequence::tuple,
@@ -9,24 +29,52 @@ use nom::{
 };
 
 use crate::{
-  class_def::{ClassDataItem, ClassDefItem},
-  get_str_const, get_type_id_ref,
+  class_def::{referenced_class_name, ClassDataItem, ClassDefItem, EncodedMethod},
+  context::ResolveContext,
+  encoded_value::{parse_encoded_array_with_limits, EncodedValue},
+  hiddenapi::HiddenApiClassData,
+  hierarchy::TypeHierarchy,
+  instruction::IndexKind,
   leb128::parse_uleb128,
+  map_list::{
+    MapList, TYPE_CALL_SITE_ID_ITEM, TYPE_HIDDENAPI_CLASS_DATA_ITEM, TYPE_METHOD_HANDLE_ITEM,
+  },
 };
 
 #[derive(Default)]
 pub struct DexFile {
   dex_header: DexHeader,
-  string_ids: Vec<StringIdItem>,
+  context: Arc<ResolveContext>,
   type_ids: Vec<TypeIdItem>,
   proto_ids: Vec<ProtoIdItem>,
   field_ids: Vec<FieldIdItem>,
   method_ids: Vec<MethodIdItem>,
   class_defs: Vec<ClassDefItem>,
+  map_list: MapList,
   call_site_ids: Vec<CallSiteIdItem>,
   method_handles: Vec<MethodHandleItem>,
 }
 
+/// A method together with the class declaring it, see `DexFile::all_methods`.
+pub struct FullyQualifiedMethod<'a> {
+  /// `La/b/C;`
+  pub class_descriptor: &'a str,
+  pub name: &'a str,
+  /// The resolved proto, `(int, java.lang.String) -> void`.
+  pub signature: String,
+  pub method: &'a EncodedMethod,
+}
+
+impl Display for FullyQualifiedMethod<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}->{}: {}",
+      self.class_descriptor, self.name, self.signature
+    )
+  }
+}
+
 #[derive(Clone)]
 pub struct StringIdItem {
   string_data_off: u32,
@@ -34,9 +82,16 @@ pub struct StringIdItem {
   pub string_data: String,
 }
 
+impl StringIdItem {
+  pub fn string_data_off(&self) -> u32 {
+    self.string_data_off
+  }
+}
+
 #[derive(Clone, Default)]
 pub struct TypeIdItem {
   pub descriptor_idx: u32,
+  context: Arc<ResolveContext>,
 }
 
 #[derive(Clone, Default)]
@@ -46,16 +101,74 @@ pub struct ProtoIdItem {
   return_type: TypeIdItem,
   parameters_off: u32,
   parameters_type_list: Option<TypeList>,
+  context: Arc<ResolveContext>,
 }
 
 impl ProtoIdItem {
+  pub(crate) fn new(
+    shorty_idx: u32,
+    return_type_idx: u32,
+    return_type: TypeIdItem,
+    parameters_off: u32,
+    parameters_type_list: Option<TypeList>,
+    context: &Arc<ResolveContext>,
+  ) -> Self {
+    Self {
+      shorty_idx,
+      return_type_idx,
+      return_type,
+      parameters_off,
+      parameters_type_list,
+      context: context.clone(),
+    }
+  }
+
   pub fn shorty(&self) -> &str {
-    get_str_const(self.shorty_idx as usize)
+    self.context.get_str_const(self.shorty_idx as usize)
+  }
+
+  /// Offset of the parameter `type_list`, 0 without parameters.
+  pub fn parameters_off(&self) -> u32 {
+    self.parameters_off
   }
 
   pub fn return_type(&self) -> &str {
     self.return_type.descriptor()
   }
+
+  /// Descriptors of the parameter types.
+  pub fn parameter_types(&self) -> Vec<&str> {
+    self
+      .parameters_type_list
+      .iter()
+      .flat_map(|parameters| parameters.list.iter())
+      .map(TypeIdItem::descriptor)
+      .collect()
+  }
+
+  /// Parameter and return types as Java source names,
+  /// `(int, java.lang.String) -> void`.
+  pub fn signature(&self) -> String {
+    let parameters: Vec<String> = self
+      .parameter_types()
+      .into_iter()
+      .map(descriptor_to_source)
+      .collect();
+    format!(
+      "({}) -> {}",
+      parameters.join(", "),
+      descriptor_to_source(self.return_type())
+    )
+  }
+
+  /// Method descriptor as in a class file, `(ILjava/lang/String;)V`.
+  pub fn descriptor(&self) -> String {
+    format!(
+      "({}){}",
+      self.parameter_types().concat(),
+      self.return_type()
+    )
+  }
 }
 #[derive(Clone, Default)]
 pub struct FieldIdItem {
@@ -64,21 +177,56 @@ pub struct FieldIdItem {
   type_idx: u16,
   type_item: TypeIdItem,
   name_idx: u32,
+  context: Arc<ResolveContext>,
 }
 
 impl FieldIdItem {
+  pub(crate) fn new(
+    class_idx: u16,
+    class: TypeIdItem,
+    type_idx: u16,
+    type_item: TypeIdItem,
+    name_idx: u32,
+    context: &Arc<ResolveContext>,
+  ) -> Self {
+    Self {
+      class_idx,
+      class,
+      type_idx,
+      type_item,
+      name_idx,
+      context: context.clone(),
+    }
+  }
+
   pub fn name(&self) -> &str {
-    get_str_const(self.name_idx as usize)
+    self.context.get_str_const(self.name_idx as usize)
   }
 
   pub fn descriptor(&self) -> &str {
     self.type_item.descriptor()
   }
+
+  /// Descriptor of the class declaring the field.
+  pub fn class_descriptor(&self) -> &str {
+    self.class.descriptor()
+  }
 }
 
 impl TypeIdItem {
+  pub(crate) fn new(descriptor_idx: u32, context: &Arc<ResolveContext>) -> Self {
+    Self {
+      descriptor_idx,
+      context: context.clone(),
+    }
+  }
+
   pub fn descriptor(&self) -> &str {
-    get_str_const(self.descriptor_idx as usize)
+    self.context.get_str_const(self.descriptor_idx as usize)
+  }
+
+  pub(crate) fn context(&self) -> &Arc<ResolveContext> {
+    &self.context
   }
 }
 
@@ -89,11 +237,30 @@ pub struct MethodIdItem {
   proto_idx: u16,
   proto: ProtoIdItem,
   name_idx: u32,
+  context: Arc<ResolveContext>,
 }
 
 impl MethodIdItem {
+  pub(crate) fn new(
+    class_idx: u16,
+    class: TypeIdItem,
+    proto_idx: u16,
+    proto: ProtoIdItem,
+    name_idx: u32,
+    context: &Arc<ResolveContext>,
+  ) -> Self {
+    Self {
+      class_idx,
+      class,
+      proto_idx,
+      proto,
+      name_idx,
+      context: context.clone(),
+    }
+  }
+
   pub fn name(&self) -> &str {
-    get_str_const(self.name_idx as usize)
+    self.context.get_str_const(self.name_idx as usize)
   }
 
   pub fn param_type(&self) -> &str {
@@ -102,14 +269,37 @@ impl MethodIdItem {
   pub fn return_type(&self) -> &str {
     self.proto.return_type()
   }
+
+  /// Descriptor of the class declaring the method.
+  pub fn class_descriptor(&self) -> &str {
+    self.class.descriptor()
+  }
+
+  pub fn proto(&self) -> &ProtoIdItem {
+    &self.proto
+  }
+
+  pub(crate) fn context(&self) -> &Arc<ResolveContext> {
+    &self.context
+  }
 }
 
+/// A `call_site_id_item` with the `call_site_item` it points to: what an
+/// `invoke-custom` passes to its bootstrap method.
 pub struct CallSiteIdItem {
   call_site_off: u32,
+  bootstrap_method_handle: u32,
+  method_name_idx: u32,
+  method_type: ProtoIdItem,
+  extra_arguments: Vec<EncodedValue>,
+  context: Arc<ResolveContext>,
 }
+/// A `method_handle_item`: a field accessor or method invoker, as a
+/// bootstrap method or argument of a call site.
 pub struct MethodHandleItem {
   method_handle_type: u16,
   field_or_method_id: u16,
+  context: Arc<ResolveContext>,
 }
 
 #[derive(Default, Clone)]
@@ -118,6 +308,57 @@ pub struct TypeList {
   pub list: Vec<TypeIdItem>,
 }
 
+impl TypeList {
+  pub(crate) fn new(type_indices: &[u16], context: &Arc<ResolveContext>) -> Self {
+    Self {
+      size: type_indices.len() as u32,
+      list: type_indices
+        .iter()
+        .map(|type_idx| context.get_type_id(*type_idx as usize))
+        .collect(),
+    }
+  }
+
+  /// Fails with `ErrorKind::Verify` on a type index past `type_ids`, as
+  /// found in corrupt or fuzzed files.
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (rest, type_indices) =
+      Self::parse_checked_indices(bytes, context.type_ids_size(), context.limits())?;
+    Ok((rest, Self::new(&type_indices, context)))
+  }
+
+  /// Like `parse_indices`, failing on an index not below `type_ids_size`.
+  fn parse_checked_indices<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    type_ids_size: usize,
+    limits: &ParseLimits,
+  ) -> nom::IResult<&'a [u8], Vec<u16>, E> {
+    let (rest, type_indices) = Self::parse_indices(bytes, limits)?;
+    if type_indices
+      .iter()
+      .any(|type_idx| *type_idx as usize >= type_ids_size)
+    {
+      return Err(nom::Err::Error(E::from_error_kind(
+        bytes,
+        nom::error::ErrorKind::Verify,
+      )));
+    }
+    Ok((rest, type_indices))
+  }
+
+  /// Reads a `type_list` without resolving the type indices.
+  fn parse_indices<'a, E: nom::error::ParseError<&'a [u8]>>(
+    bytes: &'a [u8],
+    limits: &ParseLimits,
+  ) -> nom::IResult<&'a [u8], Vec<u16>, E> {
+    let (bytes, size) = le_u32(bytes)?;
+    limits.count(le_u16, size as usize)(bytes)
+  }
+}
+
 #[derive(Default)]
 pub struct DexHeader {
   pub magic: u64,
@@ -145,9 +386,37 @@ pub struct DexHeader {
   pub data_off: u32,
 }
 
+/// Size of the fixed `header_item`.
+pub(crate) const DEX_HEADER_SIZE: usize = 0x70;
 const DEX_MAGIC: u32 = 0x6465780a;
 const NO_INDEX: u32 = 0xffffffff;
 
+/// Fails with `ErrorKind::Verify` unless `index` is below `size`, the length
+/// of the id table it points into.
+fn check_index<'a, E: nom::error::ParseError<&'a [u8]>>(
+  bytes: &'a [u8],
+  index: u32,
+  size: u32,
+) -> Result<(), nom::Err<E>> {
+  if index >= size {
+    return Err(nom::Err::Error(E::from_error_kind(
+      bytes,
+      nom::error::ErrorKind::Verify,
+    )));
+  }
+  Ok(())
+}
+
+pub const METHOD_HANDLE_TYPE_STATIC_PUT: u16 = 0x00;
+pub const METHOD_HANDLE_TYPE_STATIC_GET: u16 = 0x01;
+pub const METHOD_HANDLE_TYPE_INSTANCE_PUT: u16 = 0x02;
+pub const METHOD_HANDLE_TYPE_INSTANCE_GET: u16 = 0x03;
+pub const METHOD_HANDLE_TYPE_INVOKE_STATIC: u16 = 0x04;
+pub const METHOD_HANDLE_TYPE_INVOKE_INSTANCE: u16 = 0x05;
+pub const METHOD_HANDLE_TYPE_INVOKE_CONSTRUCTOR: u16 = 0x06;
+pub const METHOD_HANDLE_TYPE_INVOKE_DIRECT: u16 = 0x07;
+pub const METHOD_HANDLE_TYPE_INVOKE_INTERFACE: u16 = 0x08;
+
 impl Parsable for DexHeader {
   fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
     bytes: &'a [u8],
@@ -213,115 +482,191 @@ impl Parsable for DexHeader {
   }
 }
 
+/// Progress reported by `DexFile::parse_with_visitor`, in file order.
+pub enum ParseEvent<'a> {
+  /// The `header_item`, before any section is read.
+  HeaderParsed(&'a DexHeader),
+  /// A `string_ids` entry and its decoded data, by index.
+  StringParsed(usize, &'a StringIdItem),
+  /// A class definition with its class data, by `class_defs` index.
+  ClassParsed(usize, &'a ClassDefItem),
+}
+
 impl Parsable for DexFile {
   fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
     bytes: &'a [u8],
   ) -> nom::IResult<&'a [u8], Self, E>
   where
     Self: Sized,
+  {
+    Self::parse_with_visitor(bytes, |_| {})
+  }
+}
+
+impl DexFile {
+  /// Like `parse`, handing each item to `visitor` as soon as it is parsed,
+  /// so output for a large file can start before the last class is read.
+  pub fn parse_with_visitor<'a, E, V>(
+    bytes: &'a [u8],
+    visitor: V,
+  ) -> nom::IResult<&'a [u8], Self, E>
+  where
+    E: nom::error::ParseError<&'a [u8]>,
+    V: FnMut(ParseEvent),
+  {
+    Self::parse_with_limits(bytes, ParseLimits::DEFAULT, visitor)
+  }
+
+  /// Like `parse_with_visitor`, failing with `ErrorKind::TooLarge` as soon
+  /// as a declared size, code length or value nesting goes over `limits`.
+  pub fn parse_with_limits<'a, E, V>(
+    bytes: &'a [u8],
+    limits: ParseLimits,
+    mut visitor: V,
+  ) -> nom::IResult<&'a [u8], Self, E>
+  where
+    E: nom::error::ParseError<&'a [u8]>,
+    V: FnMut(ParseEvent),
   {
     let origin_bytes = bytes;
     let (bytes, dex_header) = DexHeader::parse(bytes)?;
+    visitor(ParseEvent::HeaderParsed(&dex_header));
+    // The id sections start after `header_size` bytes; a header shorter than
+    // the fields just read can't be honored and is only warned about.
+    let extra_header = (dex_header.header_size as usize).saturating_sub(DEX_HEADER_SIZE);
+    let (bytes, _) = take(extra_header)(bytes)?;
+    let map_list = if dex_header.map_off == 0 {
+      MapList::default()
+    } else {
+      let (_, map_list) = MapList::parse(origin_bytes.slice(dex_header.map_off as usize..))?;
+      map_list
+    };
+    let hidden_api_class_data = match map_list.item(TYPE_HIDDENAPI_CLASS_DATA_ITEM) {
+      Some(map_item) => {
+        let section = origin_bytes.slice(map_item.offset() as usize..);
+        let (_, hidden_api_class_data) =
+          HiddenApiClassData::parse(section, dex_header.class_defs_size as usize)?;
+        Some((section, hidden_api_class_data))
+      }
+      None => None,
+    };
 
-    let (bytes, string_ids) = count(le_u32, dex_header.string_ids_size as usize)(bytes)?;
+    let (bytes, string_ids) = limits.count(le_u32, dex_header.string_ids_size as usize)(bytes)?;
     let mut string_id_items = Vec::with_capacity(dex_header.string_ids_size as usize);
-    for string_data_off in &string_ids {
+    for (index, string_data_off) in string_ids.iter().enumerate() {
       let string_data_off = *string_data_off;
       let (string_data_len, data_offset) = parse_uleb128(&origin_bytes[string_data_off as usize..]);
       let offset_byte = origin_bytes.slice(string_data_off as usize + data_offset..);
-      let (_, string_data) = count(be_u8, string_data_len as usize)(offset_byte)?;
-      // Test for utf16
-      // let (_, utf16_str) = parse_utf16_str(offset_byte)?;
-      // let utf16_str = String::from_utf16(utf16_str.as_slice()).unwrap();
-      // let utf8_str = unsafe { String::from_utf8_unchecked(string_data.to_vec()) };
-      // println!("string: {} {}", utf16_str, utf8_str,);
-
-      string_id_items.push(StringIdItem {
+      let (_, string_data) = decode_mutf8(offset_byte)?;
+
+      let string_id_item = StringIdItem {
         string_data_off,
         string_utf16_size: string_data_len,
-        string_data: unsafe { String::from_utf8_unchecked(string_data.to_vec()) },
-      });
+        string_data,
+      };
+      visitor(ParseEvent::StringParsed(index, &string_id_item));
+      string_id_items.push(string_id_item);
+    }
+    // The id tables are resolved lazily by unchecked lookups, so every index
+    // in them is checked against the table it points into up front.
+    let strings = dex_header.string_ids_size;
+    let types = dex_header.type_ids_size;
+    let (bytes, type_ids) = limits.count(le_u32, types as usize)(bytes)?;
+    for descriptor_idx in &type_ids {
+      check_index(origin_bytes, *descriptor_idx, strings)?;
     }
-    let (bytes, type_ids) = count(TypeIdItem::parse, dex_header.type_ids_size as usize)(bytes)?;
-
-    unsafe { crate::STRING_DATA_REF = string_id_items.clone() }
-    unsafe { crate::TYPE_ID_REF = type_ids.clone() }
 
-    let (bytes, proto_ids) = count(
+    let (bytes, proto_ids) = limits.count(
       tuple((le_u32, le_u32, le_u32)),
       dex_header.proto_ids_size as usize,
     )(bytes)?;
-    let proto_ids: Vec<ProtoIdItem> = proto_ids
+    let proto_ids = proto_ids
       .into_iter()
       .map(|(shorty_idx, return_type_idx, parameters_off)| {
+        check_index(origin_bytes, shorty_idx, strings)?;
+        check_index(origin_bytes, return_type_idx, types)?;
         let parameters = if parameters_off == 0 {
           None
         } else {
           let offset_byte = origin_bytes.slice(parameters_off as usize..);
-          let type_list = TypeList::parse_from_u8(offset_byte).unwrap();
-          Some(type_list)
+          let (_, type_indices) = TypeList::parse_checked_indices(
+            offset_byte,
+            dex_header.type_ids_size as usize,
+            &limits,
+          )?;
+          Some(type_indices)
         };
-        ProtoIdItem {
-          shorty_idx,
-          return_type_idx,
-          return_type: type_ids[return_type_idx as usize].clone(),
-          parameters_off,
-          parameters_type_list: parameters,
-        }
+        Ok((shorty_idx, return_type_idx, parameters_off, parameters))
       })
-      .collect();
-    let (bytes, field_ids) = count(
+      .collect::<Result<_, nom::Err<E>>>()?;
+    let (bytes, field_ids) = limits.count(
       tuple((le_u16, le_u16, le_u32)),
       dex_header.field_ids_size as usize,
     )(bytes)?;
-    let field_ids: Vec<FieldIdItem> = field_ids
-      .into_iter()
-      .map(|(class_idx, type_idx, name_idx)| FieldIdItem {
-        class_idx,
-        class: type_ids[class_idx as usize].clone(),
-        type_idx,
-        type_item: type_ids[type_idx as usize].clone(),
-        name_idx,
-      })
-      .collect();
+    for (class_idx, type_idx, name_idx) in &field_ids {
+      check_index(origin_bytes, *class_idx as u32, types)?;
+      check_index(origin_bytes, *type_idx as u32, types)?;
+      check_index(origin_bytes, *name_idx, strings)?;
+    }
 
-    let (bytes, method_ids) = count(
+    let (bytes, method_ids) = limits.count(
       tuple((le_u16, le_u16, le_u32)),
       dex_header.method_ids_size as usize,
     )(bytes)?;
-    let method_ids: Vec<MethodIdItem> = method_ids
-      .into_iter()
-      .map(|(class_idx, proto_idx, name_idx)| MethodIdItem {
-        class_idx,
-        class: type_ids[class_idx as usize].clone(),
-        proto_idx,
-        proto: proto_ids[proto_idx as usize].clone(),
-        name_idx,
-      })
-      .collect();
-    let (bytes, class_defs) = count(
+    for (class_idx, proto_idx, name_idx) in &method_ids {
+      check_index(origin_bytes, *class_idx as u32, types)?;
+      check_index(origin_bytes, *proto_idx as u32, dex_header.proto_ids_size)?;
+      check_index(origin_bytes, *name_idx, strings)?;
+    }
+    let (_, class_defs) = limits.count(
       tuple((
         le_u32, le_u32, le_u32, le_u32, le_u32, le_u32, le_u32, le_u32,
       )),
       dex_header.class_defs_size as usize,
     )(bytes)?;
+    for (class_idx, _, superclass_idx, _, source_file_idx, ..) in &class_defs {
+      check_index(origin_bytes, *class_idx, types)?;
+      if *superclass_idx != NO_INDEX {
+        check_index(origin_bytes, *superclass_idx, types)?;
+      }
+      if *source_file_idx != NO_INDEX {
+        check_index(origin_bytes, *source_file_idx, strings)?;
+      }
+    }
 
-    // unsafe { crate::PROTO_ID_REF = proto_ids.clone() }
-    unsafe { crate::FIELD_ID_REF = field_ids.clone() }
-    unsafe { crate::METHOD_ID_REF = method_ids.clone() }
+    let context = Arc::new(
+      ResolveContext::new(string_id_items, type_ids, proto_ids, field_ids, method_ids)
+        .with_limits(limits),
+    );
+    let type_ids: Vec<TypeIdItem> = (0..context.type_ids_size())
+      .map(|idx| context.get_type_id(idx))
+      .collect();
+    let proto_ids: Vec<ProtoIdItem> = (0..context.proto_ids_size())
+      .map(|idx| context.get_proto_id(idx))
+      .collect();
+    let field_ids: Vec<FieldIdItem> = (0..context.field_ids_size())
+      .map(|idx| context.get_field_id(idx))
+      .collect();
+    let method_ids: Vec<MethodIdItem> = (0..context.method_ids_size())
+      .map(|idx| context.get_method_id(idx))
+      .collect();
 
     let class_defs = class_defs
       .into_iter()
+      .enumerate()
       .map(
         |(
-          class_idx,
-          access_flags,
-          superclass_idx,
-          interfaces_off,
-          source_file_idx,
-          annotations_off,
-          class_data_off,
-          static_values_off,
+          class_def_idx,
+          (
+            class_idx,
+            access_flags,
+            superclass_idx,
+            interfaces_off,
+            source_file_idx,
+            annotations_off,
+            class_data_off,
+            static_values_off,
+          ),
         )| {
           let superclass = if superclass_idx == NO_INDEX {
             None
@@ -337,7 +682,7 @@ impl Parsable for DexFile {
             None
           } else {
             let offset_byte = origin_bytes.slice(interfaces_off as usize..);
-            let type_list = TypeList::parse_from_u8(offset_byte).unwrap();
+            let (_, type_list) = TypeList::parse(offset_byte, &context)?;
             Some(type_list)
           };
 
@@ -345,10 +690,26 @@ impl Parsable for DexFile {
             None
           } else {
             let offset_byte = origin_bytes.slice(class_data_off as usize..);
-            let class_data_item = ClassDataItem::parse_from_u8(offset_byte, origin_bytes).unwrap();
+            let (_, mut class_data_item) =
+              ClassDataItem::parse(offset_byte, origin_bytes, &context)?;
+            if let Some((section, hidden_api_class_data)) = &hidden_api_class_data {
+              if let Some(flags) = hidden_api_class_data.class_flags(section, class_def_idx) {
+                class_data_item.parse_hidden_api_flags(flags)?;
+              }
+            }
+            if static_values_off != 0 {
+              let offset_byte =
+                origin_bytes
+                  .get(static_values_off as usize..)
+                  .ok_or_else(|| {
+                    nom::Err::Error(E::from_error_kind(bytes, nom::error::ErrorKind::Eof))
+                  })?;
+              let (_, static_values) = parse_encoded_array_with_limits(offset_byte, &limits)?;
+              class_data_item.set_static_values(&static_values, &context);
+            }
             Some(class_data_item)
           };
-          ClassDefItem::new(
+          Ok(ClassDefItem::new(
             class_idx,
             type_ids[class_idx as usize].clone(),
             AccessFlags::new_class_flag(access_flags as u16),
@@ -361,96 +722,480 @@ impl Parsable for DexFile {
             class_data_off,
             static_values_off,
             class_data_item,
-          )
+          ))
         },
       )
-      .collect();
-    // let (bytes, call_site_ids) = count(
-    //   CallSiteIdItem::parse,
-    //   dex_header.call_site_ids_size as usize,
-    // )(bytes)?;
+      .enumerate()
+      .map(|(index, class_def)| {
+        let class_def = class_def?;
+        visitor(ParseEvent::ClassParsed(index, &class_def));
+        Ok(class_def)
+      })
+      .collect::<Result<_, nom::Err<E>>>()?;
+    // The header has no call_site_ids fields; only the map locates them.
+    let call_site_ids = match map_list.item(TYPE_CALL_SITE_ID_ITEM) {
+      Some(map_item) => {
+        let section = origin_bytes.slice(map_item.offset() as usize..);
+        let parse_call_site_id = |bytes| CallSiteIdItem::parse(bytes, origin_bytes, &context);
+        let (_, call_site_ids) =
+          limits.count(parse_call_site_id, map_item.size() as usize)(section)?;
+        call_site_ids
+      }
+      None => vec![],
+    };
+    let method_handles = match map_list.item(TYPE_METHOD_HANDLE_ITEM) {
+      Some(map_item) => {
+        let section = origin_bytes.slice(map_item.offset() as usize..);
+        let parse_method_handle = |bytes| MethodHandleItem::parse(bytes, &context);
+        let (_, method_handles) =
+          limits.count(parse_method_handle, map_item.size() as usize)(section)?;
+        method_handles
+      }
+      None => vec![],
+    };
     log::info!("pass");
+    // Sections are located by offset, so what follows the last one read is
+    // not the remainder; anything past `file_size` is.
+    let remainder = origin_bytes.slice(min(dex_header.file_size as usize, origin_bytes.len())..);
     Ok((
-      bytes,
+      remainder,
       Self {
         dex_header,
-        string_ids: string_id_items,
+        context,
         type_ids,
         proto_ids,
         field_ids,
         method_ids,
         class_defs,
+        map_list,
+        call_site_ids,
+        method_handles,
         ..Default::default()
       },
     ))
   }
 }
 
-impl Parsable for CallSiteIdItem {
-  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+impl DexFile {
+  /// The context every item of this dex resolves its indices through.
+  pub fn context(&self) -> &Arc<ResolveContext> {
+    &self.context
+  }
+
+  pub fn header(&self) -> &DexHeader {
+    &self.dex_header
+  }
+
+  pub fn class_defs(&self) -> &[ClassDefItem] {
+    &self.class_defs
+  }
+
+  /// Empty when the header has no `map_off`.
+  pub fn map_list(&self) -> &MapList {
+    &self.map_list
+  }
+
+  /// Call sites of `invoke-custom` instructions, by `call_site_ids` index.
+  pub fn call_site_ids(&self) -> &[CallSiteIdItem] {
+    &self.call_site_ids
+  }
+
+  /// Method handles of call sites and `const-method-handle`, by
+  /// `method_handles` index.
+  pub fn method_handles(&self) -> &[MethodHandleItem] {
+    &self.method_handles
+  }
+
+  /// Number of `method_ids`, the count behind the 64K method limit.
+  pub fn method_ref_count(&self) -> usize {
+    self.method_ids.len()
+  }
+
+  pub fn field_ref_count(&self) -> usize {
+    self.field_ids.len()
+  }
+
+  /// Tallies for a quick inventory, in output order: defined classes,
+  /// methods and fields, then the sizes of the id tables.
+  pub fn counts(&self) -> Vec<(String, usize)> {
+    let fields = self
+      .class_defs
+      .iter()
+      .filter_map(ClassDefItem::class_data_item)
+      .map(|class_data| class_data.static_field_count() + class_data.instance_field_count())
+      .sum();
+    vec![
+      ("classes".to_string(), self.class_defs.len()),
+      ("methods".to_string(), self.methods().count()),
+      ("fields".to_string(), fields),
+      ("strings".to_string(), self.context.string_ids().len()),
+      ("types".to_string(), self.context.type_ids_size()),
+      ("protos".to_string(), self.context.proto_ids_size()),
+      ("field_ids".to_string(), self.field_ref_count()),
+      ("method_ids".to_string(), self.method_ref_count()),
+      ("call_sites".to_string(), self.call_site_ids.len()),
+    ]
+  }
+
+  pub fn type_hierarchy(&self) -> TypeHierarchy<'_> {
+    TypeHierarchy::new(self)
+  }
+
+  /// Transitive superclasses and interfaces of `class_descriptor`, nearest first.
+  pub fn supertypes(&self, class_descriptor: &str) -> Vec<&str> {
+    self.type_hierarchy().supertypes(class_descriptor)
+  }
+
+  /// Transitive subclasses and implementors of `class_descriptor` within this dex.
+  pub fn subtypes(&self, class_descriptor: &str) -> Vec<&str> {
+    self.type_hierarchy().subtypes(class_descriptor)
+  }
+
+  /// Source names of the classes referenced by this dex file but defined
+  /// outside it, e.g. the framework and library classes an app uses. Covers
+  /// every `type_ids` entry and everything the classes' code refers to.
+  pub fn referenced_types(&self) -> Vec<String> {
+    let defined: BTreeSet<String> = self
+      .class_defs
+      .iter()
+      .map(|class_def| descriptor_to_source(class_def.descriptor()))
+      .collect();
+    let mut types: BTreeSet<String> = (0..self.context.type_ids_size())
+      .filter_map(|index| referenced_class_name(self.context.get_type_id(index).descriptor()))
+      .collect();
+    for class_def in &self.class_defs {
+      types.extend(class_def.referenced_types());
+    }
+    types.difference(&defined).cloned().collect()
+  }
+
+  /// Consistency warnings for the header size, the map list (see
+  /// `MapList::validate`), then for every method, in class order.
+  pub fn validate(&self) -> Vec<Warning> {
+    let mut warnings = vec![];
+    if self.dex_header.header_size != DEX_HEADER_SIZE as u32 {
+      warnings.push(Warning::HeaderSize {
+        header_size: self.dex_header.header_size,
+        expected: DEX_HEADER_SIZE as u32,
+      });
+    }
+    warnings.extend(self.map_list.validate(&self.dex_header));
+    warnings.extend(self.validate_string_ids());
+    warnings.extend(
+      self
+        .class_defs
+        .iter()
+        .filter_map(ClassDefItem::class_data_item)
+        .flat_map(ClassDataItem::methods)
+        .flat_map(EncodedMethod::validate),
+    );
+    warnings
+  }
+
+  /// The spec requires `string_ids` sorted by the UTF-16 code units of the
+  /// strings, without duplicates, and their data in the data section; a
+  /// violation usually means the file was rewritten by hand.
+  fn validate_string_ids(&self) -> Vec<Warning> {
+    let mut warnings = vec![];
+    let data_start = self.dex_header.data_off as u64;
+    let data_end = data_start + self.dex_header.data_size as u64;
+    let string_ids = self.context.string_ids();
+    for (index, string_id) in string_ids.iter().enumerate() {
+      let mut warn = |problem: String| warnings.push(Warning::InvalidStringId { index, problem });
+      let offset = string_id.string_data_off() as u64;
+      if !(data_start..data_end).contains(&offset) {
+        warn(format!(
+          "string_data_off 0x{:x} is outside the data section 0x{:x}..0x{:x}",
+          offset, data_start, data_end
+        ));
+      }
+      let Some(previous) = index.checked_sub(1).map(|previous| &string_ids[previous]) else {
+        continue;
+      };
+      let (string, previous) = (&string_id.string_data, &previous.string_data);
+      match string.encode_utf16().cmp(previous.encode_utf16()) {
+        Ordering::Equal => warn(format!("{:?} duplicates string_ids[{}]", string, index - 1)),
+        Ordering::Less => warn(format!(
+          "{:?} sorts before the previous string {:?}",
+          string, previous
+        )),
+        Ordering::Greater => {}
+      }
+    }
+    warnings
+  }
+
+  /// Direct then virtual methods of every class, in class order.
+  pub fn methods(&self) -> impl Iterator<Item = &EncodedMethod> {
+    self
+      .class_defs
+      .iter()
+      .filter_map(ClassDefItem::class_data_item)
+      .flat_map(ClassDataItem::methods)
+  }
+
+  /// Every method of every class, direct then virtual, in class order, with
+  /// the names needed to tell it apart app-wide; the entry point for
+  /// counting, searching or indexing methods.
+  pub fn all_methods(&self) -> impl Iterator<Item = FullyQualifiedMethod<'_>> {
+    self.methods().map(|method| FullyQualifiedMethod {
+      class_descriptor: method.class_descriptor(),
+      name: method.name(),
+      signature: method.proto().signature(),
+      method,
+    })
+  }
+
+  /// The method named `query`, or with overloads or in several classes its
+  /// name and descriptor, `compute(I)I`, or its smali reference,
+  /// `La/Foo;->compute(I)I`.
+  pub fn find_method(&self, query: &str) -> Result<&EncodedMethod, Error> {
+    let full_name = |method: &EncodedMethod| {
+      format!(
+        "{}->{}{}",
+        method.class_descriptor(),
+        method.name(),
+        method.descriptor()
+      )
+    };
+    let matches: Vec<&EncodedMethod> = self
+      .methods()
+      .filter(|method| {
+        method.name() == query
+          || format!("{}{}", method.name(), method.descriptor()) == query
+          || full_name(method) == query
+      })
+      .collect();
+    match matches[..] {
+      [] => Err(ErrorKind::MethodNotFound {
+        query: query.to_string(),
+      })?,
+      [method] => Ok(method),
+      _ => Err(ErrorKind::AmbiguousMethod {
+        query: query.to_string(),
+        candidates: matches.into_iter().map(full_name).collect(),
+      })?,
+    }
+  }
+
+  /// The methods whose code reads or writes field id `field_idx` with an
+  /// `iget`, `iput`, `sget` or `sput`, each once, in class order.
+  pub fn field_accessors(&self, field_idx: u32) -> Vec<&MethodIdItem> {
+    self
+      .methods()
+      .filter(|method| {
+        method.code().is_some_and(|code_item| {
+          code_item
+            .instructions()
+            .any(|instruction| instruction.reference() == Some((IndexKind::Field, field_idx)))
+        })
+      })
+      .map(EncodedMethod::method_id)
+      .collect()
+  }
+
+  /// Decodes the code of every method and tallies the opcodes found.
+  pub fn opcode_coverage(&self) -> OpcodeCoverage {
+    let mut coverage = OpcodeCoverage::default();
+    let code_items = self
+      .class_defs
+      .iter()
+      .filter_map(ClassDefItem::class_data_item)
+      .flat_map(ClassDataItem::methods)
+      .filter_map(|method| method.code());
+    for code_item in code_items {
+      code_item.instructions().record(&mut coverage);
+    }
+    coverage
+  }
+
+  /// How many times each mnemonic occurs across the code of every method.
+  pub fn opcode_histogram(&self) -> HashMap<&'static str, usize> {
+    self.opcode_coverage().histogram()
+  }
+}
+
+impl CallSiteIdItem {
+  /// Reads a `call_site_id_item` from `bytes` and decodes the
+  /// `call_site_item` at its offset in `dex`. That encoded array must start
+  /// with the bootstrap method handle, the method name and the method type.
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
     bytes: &'a [u8],
-  ) -> nom::IResult<&'a [u8], Self, E>
-  where
-    Self: Sized,
-  {
+    dex: &'a [u8],
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
     let (bytes, call_site_off) = le_u32(bytes)?;
-    Ok((bytes, Self { call_site_off }))
+    let call_site_item = dex
+      .get(call_site_off as usize..)
+      .ok_or_else(|| nom::Err::Error(E::from_error_kind(bytes, nom::error::ErrorKind::Eof)))?;
+    let (_, values) = parse_encoded_array_with_limits(call_site_item, context.limits())?;
+    let mut values = values.into_iter();
+    let (
+      Some(EncodedValue::MethodHandle(bootstrap_method_handle)),
+      Some(EncodedValue::String(method_name_idx)),
+      Some(EncodedValue::MethodType(proto_idx)),
+    ) = (values.next(), values.next(), values.next())
+    else {
+      return Err(nom::Err::Error(E::from_error_kind(
+        call_site_item,
+        nom::error::ErrorKind::Verify,
+      )));
+    };
+    if method_name_idx as usize >= context.string_ids().len()
+      || proto_idx as usize >= context.proto_ids_size()
+    {
+      return Err(nom::Err::Error(E::from_error_kind(
+        call_site_item,
+        nom::error::ErrorKind::Verify,
+      )));
+    }
+    Ok((
+      bytes,
+      Self {
+        call_site_off,
+        bootstrap_method_handle,
+        method_name_idx,
+        method_type: context.get_proto_id(proto_idx as usize),
+        extra_arguments: values.collect(),
+        context: context.clone(),
+      },
+    ))
+  }
+
+  pub fn call_site_off(&self) -> u32 {
+    self.call_site_off
+  }
+
+  /// Index into `method_handles` of the bootstrap method.
+  pub fn bootstrap_method_handle(&self) -> u32 {
+    self.bootstrap_method_handle
+  }
+
+  pub fn method_name(&self) -> &str {
+    self.context.get_str_const(self.method_name_idx as usize)
+  }
+
+  pub fn method_type(&self) -> &ProtoIdItem {
+    &self.method_type
+  }
+
+  /// The bootstrap arguments after the name and type, e.g. the
+  /// implementation method handle of a lambda.
+  pub fn extra_arguments(&self) -> &[EncodedValue] {
+    &self.extra_arguments
   }
 }
 
-impl Parsable for MethodHandleItem {
-  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
+/// `apply()Ljava/util/function/Function; via method handle @0`, then the
+/// extra arguments in brackets.
+impl Display for CallSiteIdItem {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}{} via method handle @{}",
+      self.method_name(),
+      self.method_type.descriptor(),
+      self.bootstrap_method_handle
+    )?;
+    if !self.extra_arguments.is_empty() {
+      let arguments: Vec<String> = self
+        .extra_arguments
+        .iter()
+        .map(|value| value.display(&self.context).to_string())
+        .collect();
+      write!(f, " [{}]", arguments.join(", "))?;
+    }
+    Ok(())
+  }
+}
+
+impl MethodHandleItem {
+  pub fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
     bytes: &'a [u8],
-  ) -> nom::IResult<&'a [u8], Self, E>
-  where
-    Self: Sized,
-  {
-    let (bytes, (method_handle_type, field_or_method_id)) = tuple((le_u16, le_u16))(bytes)?;
+    context: &Arc<ResolveContext>,
+  ) -> nom::IResult<&'a [u8], Self, E> {
+    let (bytes, (method_handle_type, _, field_or_method_id, _)) =
+      tuple((le_u16, le_u16, le_u16, le_u16))(bytes)?;
     Ok((
       bytes,
       Self {
         method_handle_type,
         field_or_method_id,
+        context: context.clone(),
       },
     ))
   }
-}
 
-impl Parsable for TypeIdItem {
-  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
-    bytes: &'a [u8],
-  ) -> nom::IResult<&'a [u8], Self, E>
-  where
-    Self: Sized,
-  {
-    let (bytes, descriptor_idx) = le_u32(bytes)?;
-    Ok((bytes, Self { descriptor_idx }))
+  pub fn method_handle_type(&self) -> u16 {
+    self.method_handle_type
+  }
+
+  pub fn field_or_method_id(&self) -> u16 {
+    self.field_or_method_id
+  }
+
+  /// The JVM `reference_kind` of `method_handle_type`: dex numbers the same
+  /// nine kinds from 0, in a different order. 0 for an unknown type.
+  pub fn reference_kind(&self) -> u8 {
+    match self.method_handle_type {
+      METHOD_HANDLE_TYPE_STATIC_PUT => REF_PUT_STATIC,
+      METHOD_HANDLE_TYPE_STATIC_GET => REF_GET_STATIC,
+      METHOD_HANDLE_TYPE_INSTANCE_PUT => REF_PUT_FIELD,
+      METHOD_HANDLE_TYPE_INSTANCE_GET => REF_GET_FIELD,
+      METHOD_HANDLE_TYPE_INVOKE_STATIC => REF_INVOKE_STATIC,
+      METHOD_HANDLE_TYPE_INVOKE_INSTANCE => REF_INVOKE_VIRTUAL,
+      METHOD_HANDLE_TYPE_INVOKE_CONSTRUCTOR => REF_NEW_INVOKE_SPECIAL,
+      METHOD_HANDLE_TYPE_INVOKE_DIRECT => REF_INVOKE_SPECIAL,
+      METHOD_HANDLE_TYPE_INVOKE_INTERFACE => REF_INVOKE_INTERFACE,
+      _ => 0,
+    }
+  }
+
+  pub fn is_field_accessor(&self) -> bool {
+    is_field_kind(self.reference_kind())
   }
 }
 
-impl Parsable for TypeList {
-  fn parse<'a, E: nom::error::ParseError<&'a [u8]>>(
-    bytes: &'a [u8],
-  ) -> nom::IResult<&'a [u8], Self, E>
-  where
-    Self: Sized,
-  {
-    let (bytes, size) = le_u32(bytes)?;
-    let (bytes, list) = count(le_u16, size as usize)(bytes)?;
-    let list = list
-      .into_iter()
-      .map(|type_idx| get_type_id_ref()[type_idx as usize].clone())
-      .collect();
-    Ok((bytes, Self { size, list }))
+/// `REF_invokeStatic La/Foo;->bar(I)V`, or `REF_getField La/Foo;->count:I`
+/// for a field accessor; an id out of range is left as `method@12`.
+impl Display for MethodHandleItem {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} ", method_handle_kind_name(self.reference_kind()))?;
+    let id = self.field_or_method_id as usize;
+    if self.is_field_accessor() {
+      return match self.context.find_field(id) {
+        Some((class, name, descriptor)) => write!(f, "{}->{}:{}", class, name, descriptor),
+        None => write!(f, "field@{}", id),
+      };
+    }
+    if id >= self.context.method_ids_size() {
+      return write!(f, "method@{}", id);
+    }
+    let method = self.context.get_method_id(id);
+    write!(
+      f,
+      "{}->{}{}",
+      method.class_descriptor(),
+      method.name(),
+      method.proto().descriptor()
+    )
   }
 }
 
 impl Display for DexFile {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "header: {}", self.dex_header)?;
+    self.fmt_indent(f, Indent::TABS)
+  }
+}
+
+/// Only the class dumps are indented; the id lists stay on one line each.
+impl IndentDisplay for DexFile {
+  fn fmt_indent(&self, f: &mut std::fmt::Formatter<'_>, indent: Indent) -> std::fmt::Result {
+    write!(f, "header:\n{}", self.dex_header)?;
 
     writeln!(f, "string_ids: ")?;
-    for string_id in self.string_ids.iter().take(min(10, self.string_ids.len())) {
+    let string_ids = self.context.string_ids();
+    for string_id in string_ids.iter().take(min(10, string_ids.len())) {
       write!(f, "{} ", string_id)?;
     }
     writeln!(f, "\ntype_ids: ")?;
@@ -477,29 +1222,85 @@ impl Display for DexFile {
     writeln!(f, "\nclass_defs: ")?;
     for (class_def, idx) in self.class_defs.iter().zip(0..) {
       writeln!(f, "Class #{}: ", idx)?;
-      write!(f, "{} ", class_def)?;
+      write!(f, "{} ", class_def.display_indent(indent))?;
+    }
+    if !self.call_site_ids.is_empty() {
+      writeln!(f, "\ncall_site_ids: ")?;
+      for (call_site_id, idx) in self.call_site_ids.iter().zip(0..) {
+        writeln!(f, "Call site #{}: {}", idx, call_site_id)?;
+      }
+    }
+    if !self.method_handles.is_empty() {
+      writeln!(f, "\nmethod_handles: ")?;
+      for (method_handle, idx) in self.method_handles.iter().zip(0..) {
+        writeln!(f, "Method handle #{}: {}", idx, method_handle)?;
+      }
     }
     Ok(())
   }
 }
 
+impl DexHeader {
+  /// Name and printed value of each field, in file order: sizes and
+  /// offsets in hex.
+  fn fields(&self) -> Vec<(&'static str, String)> {
+    let signature: String = self
+      .signature
+      .iter()
+      .map(|byte| format!("{:02x}", byte))
+      .collect();
+    let mut fields = vec![
+      ("magic", format!("0x{:08x}", self.magic)),
+      ("checksum", format!("0x{:08x}", self.checksum)),
+      ("signature", signature),
+      ("file_size", format!("0x{:x}", self.file_size)),
+      ("header_size", format!("0x{:x}", self.header_size)),
+      ("endian_tag", format!("0x{:08x}", self.endian_tag)),
+    ];
+    let sections = [
+      ("link_size", self.link_size),
+      ("link_off", self.link_off),
+      ("map_off", self.map_off),
+      ("string_ids_size", self.string_ids_size),
+      ("string_ids_off", self.string_ids_off),
+      ("type_ids_size", self.type_ids_size),
+      ("type_ids_off", self.type_ids_off),
+      ("proto_ids_size", self.proto_ids_size),
+      ("proto_ids_off", self.proto_ids_off),
+      ("field_ids_size", self.field_ids_size),
+      ("field_ids_off", self.field_ids_off),
+      ("method_ids_size", self.method_ids_size),
+      ("method_ids_off", self.method_ids_off),
+      ("class_defs_size", self.class_defs_size),
+      ("class_defs_off", self.class_defs_off),
+      ("data_size", self.data_size),
+      ("data_off", self.data_off),
+    ];
+    fields.extend(
+      sections
+        .into_iter()
+        .map(|(name, value)| (name, format!("0x{:x}", value))),
+    );
+    fields
+  }
+}
+
+/// One `key: value` line per field with the values aligned, or with `{:#}`
+/// all of them on a single line.
 impl Display for DexHeader {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    writeln!(
-      f,
-      "magic: {:x}, check_sum: {}, signature: {:?}， file_size: {}, 
-      header_size: 0x{:x}, endian_tag: 0x{:x}",
-      self.magic, self.checksum, self.signature, self.file_size, self.header_size, self.endian_tag
-    )?;
-    writeln!(
-      f,
-      "link_size: {}, link_off: {}, map_off: {}, string_ids_size: {}, string_ids_off: {}, 
-      type_ids_size: {}, type_ids_off: {}, proto_ids_size: {}, proto_ids_off: {}, field_ids_size: {}, 
-      field_ids_off: {}, method_ids_size: {}, method_ids_off: {}, class_defs_size: {}, 
-      class_defs_off: {}, data_size: {}, data_off: {}",
-      self.link_size, self.link_off, self.map_off, self.string_ids_size, self.string_ids_off, self.type_ids_size, self.type_ids_off, self.proto_ids_size, self.proto_ids_off, self.field_ids_size, self.field_ids_off, self.method_ids_size, self.method_ids_off, self.class_defs_size, self.class_defs_off, self.data_size, self.data_off
-    )?;
-
+    let fields = self.fields();
+    if f.alternate() {
+      let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+      return writeln!(f, "{}", fields.join(", "));
+    }
+    let width = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 1;
+    for (name, value) in &fields {
+      writeln!(f, "{:<width$} {}", format!("{}:", name), value)?;
+    }
     Ok(())
   }
 }
@@ -526,10 +1327,9 @@ impl Display for ProtoIdItem {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     writeln!(
       f,
-      "shorty: {}, return_type: {}, parameters_off: {}",
-      get_str_const(self.shorty_idx as usize),
-      self.return_type.descriptor(),
-      self.parameters_off
+      "shorty: {}, signature: {}",
+      self.shorty(),
+      self.signature()
     )?;
     Ok(())
   }
@@ -542,7 +1342,7 @@ impl Display for FieldIdItem {
       "class: {}, type_item: {}, name: {}",
       self.class.descriptor(),
       self.type_item.descriptor(),
-      get_str_const(self.name_idx as usize)
+      self.name()
     )?;
     Ok(())
   }
@@ -555,7 +1355,7 @@ impl Display for MethodIdItem {
       "class: {}, proto: {}, name: {}",
       self.class.descriptor(),
       self.proto,
-      get_str_const(self.name_idx as usize)
+      self.name()
     )?;
     Ok(())
   }
@@ -0,0 +1,658 @@
+//! Helpers for assembling small dex files in unit tests.
+//!
+//! The layout follows the order `DexFile::parse` expects: header, the id
+//! sections back to back, then a data section holding string data, type
+//! lists, code items, class data and the map list.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use base::error::Warning;
+
+use crate::encode::encode_mutf8;
+
+const HEADER_SIZE: u32 = 0x70;
+const NO_INDEX: u32 = 0xffffffff;
+
+#[derive(Default)]
+pub struct DexBuilder {
+  strings: Vec<String>,
+  string_index: HashMap<String, u32>,
+  types: Vec<u32>,
+  protos: Vec<(u32, u16, Vec<u16>)>,
+  fields: Vec<(u16, u16, u32)>,
+  methods: Vec<(u16, u16, u32)>,
+  classes: Vec<ClassDef>,
+  /// Encoded `call_site_item` arrays.
+  call_sites: Vec<Vec<u8>>,
+  /// `(method_handle_type, field_or_method_id)`.
+  method_handles: Vec<(u16, u16)>,
+  /// `header_size` to write instead of 0x70; the header is zero-padded up
+  /// to it.
+  header_size: Option<u32>,
+}
+
+pub struct ClassDef {
+  class_idx: u16,
+  access_flags: u32,
+  superclass_idx: Option<u16>,
+  interfaces: Vec<u16>,
+  source_file_idx: Option<u32>,
+  static_fields: Vec<(u32, u32)>,
+  instance_fields: Vec<(u32, u32)>,
+  direct_methods: Vec<Method>,
+  virtual_methods: Vec<Method>,
+  hidden_api_flags: Option<Vec<u32>>,
+  static_values: Option<Vec<u8>>,
+}
+
+pub struct Method {
+  method_idx: u32,
+  access_flags: u32,
+  code: Option<Code>,
+}
+
+pub struct Code {
+  registers_size: u16,
+  ins_size: u16,
+  outs_size: u16,
+  insns: Vec<u16>,
+  tries: Vec<TryBlock>,
+}
+
+struct TryBlock {
+  start_addr: u32,
+  insn_count: u16,
+  /// `(type_idx, addr)` of each caught type.
+  handlers: Vec<(u32, u32)>,
+  catch_all_addr: Option<u32>,
+}
+
+/// `warnings` without those about the string table: the builder keeps its
+/// strings in the order they are added rather than sorted as the spec
+/// requires.
+pub fn without_string_id_warnings(warnings: Vec<Warning>) -> Vec<Warning> {
+  warnings
+    .into_iter()
+    .filter(|warning| !matches!(warning, Warning::InvalidStringId { .. }))
+    .collect()
+}
+
+impl DexBuilder {
+  pub fn string(&mut self, value: &str) -> u32 {
+    if let Some(idx) = self.string_index.get(value) {
+      return *idx;
+    }
+    let idx = self.strings.len() as u32;
+    self.strings.push(value.to_string());
+    self.string_index.insert(value.to_string(), idx);
+    idx
+  }
+
+  pub fn type_id(&mut self, descriptor: &str) -> u16 {
+    let string_idx = self.string(descriptor);
+    if let Some(idx) = self.types.iter().position(|idx| *idx == string_idx) {
+      return idx as u16;
+    }
+    self.types.push(string_idx);
+    (self.types.len() - 1) as u16
+  }
+
+  pub fn proto(&mut self, return_type: &str, parameters: &[&str]) -> u16 {
+    let shorty: String = std::iter::once(return_type)
+      .chain(parameters.iter().copied())
+      .map(|descriptor| match descriptor.as_bytes()[0] {
+        b'[' => 'L',
+        c => c as char,
+      })
+      .collect();
+    let shorty_idx = self.string(&shorty);
+    let return_type_idx = self.type_id(return_type);
+    let parameters = parameters.iter().map(|p| self.type_id(p)).collect();
+    self.protos.push((shorty_idx, return_type_idx, parameters));
+    (self.protos.len() - 1) as u16
+  }
+
+  pub fn field(&mut self, class: &str, field_type: &str, name: &str) -> u32 {
+    let class_idx = self.type_id(class);
+    let type_idx = self.type_id(field_type);
+    let name_idx = self.string(name);
+    self.fields.push((class_idx, type_idx, name_idx));
+    (self.fields.len() - 1) as u32
+  }
+
+  pub fn method(&mut self, class: &str, name: &str, return_type: &str, parameters: &[&str]) -> u32 {
+    let class_idx = self.type_id(class);
+    let proto_idx = self.proto(return_type, parameters);
+    let name_idx = self.string(name);
+    self.methods.push((class_idx, proto_idx, name_idx));
+    (self.methods.len() - 1) as u32
+  }
+
+  pub fn class(&mut self, class: ClassDef) {
+    self.classes.push(class);
+  }
+
+  /// Adds a call site from its encoded `call_site_item` array and returns
+  /// its index.
+  pub fn call_site(&mut self, encoded_array: &[u8]) -> u32 {
+    self.call_sites.push(encoded_array.to_vec());
+    self.call_sites.len() as u32 - 1
+  }
+
+  /// Adds a `method_handle_item` and returns its index.
+  pub fn method_handle(&mut self, method_handle_type: u16, field_or_method_id: u16) -> u32 {
+    self
+      .method_handles
+      .push((method_handle_type, field_or_method_id));
+    self.method_handles.len() as u32 - 1
+  }
+
+  pub fn header_size(&mut self, header_size: u32) {
+    self.header_size = Some(header_size);
+  }
+
+  pub fn build(&self) -> Vec<u8> {
+    let header_size = self.header_size.unwrap_or(HEADER_SIZE).max(HEADER_SIZE);
+    let ids_size = self.strings.len() * 4
+      + self.types.len() * 4
+      + self.protos.len() * 12
+      + self.fields.len() * 8
+      + self.methods.len() * 8
+      + self.classes.len() * 32;
+    let data_off = header_size + ids_size as u32;
+    let mut data = Data {
+      base: data_off,
+      bytes: vec![],
+    };
+
+    let string_data_off = data.offset();
+    let string_offs: Vec<u32> = self
+      .strings
+      .iter()
+      .map(|s| {
+        let off = data.offset();
+        write_uleb128(&mut data.bytes, s.encode_utf16().count() as u32);
+        data.bytes.extend_from_slice(&encode_mutf8(s));
+        data.bytes.push(0);
+        off
+      })
+      .collect();
+
+    data.align();
+    let type_list_off = data.offset();
+    let mut type_list_count = 0;
+    let mut write_type_list = |data: &mut Data, list: &[u16]| {
+      if list.is_empty() {
+        return 0;
+      }
+      data.align();
+      type_list_count += 1;
+      let off = data.offset();
+      data.u32(list.len() as u32);
+      for idx in list {
+        data.u16(*idx);
+      }
+      off
+    };
+    let proto_params_offs: Vec<u32> = self
+      .protos
+      .iter()
+      .map(|(_, _, params)| write_type_list(&mut data, params))
+      .collect();
+    let interfaces_offs: Vec<u32> = self
+      .classes
+      .iter()
+      .map(|class| write_type_list(&mut data, &class.interfaces))
+      .collect();
+
+    data.align();
+    let code_item_off = data.offset();
+    let mut code_item_count = 0;
+    let code_offs: Vec<Vec<u32>> = self
+      .classes
+      .iter()
+      .map(|class| {
+        class
+          .direct_methods
+          .iter()
+          .chain(class.virtual_methods.iter())
+          .map(|method| match &method.code {
+            Some(code) => {
+              data.align();
+              code_item_count += 1;
+              let off = data.offset();
+              data.u16(code.registers_size);
+              data.u16(code.ins_size);
+              data.u16(code.outs_size);
+              data.u16(code.tries.len() as u16);
+              data.u32(0);
+              data.u32(code.insns.len() as u32);
+              for unit in &code.insns {
+                data.u16(*unit);
+              }
+              write_tries(&mut data, code);
+              off
+            }
+            None => 0,
+          })
+          .collect()
+      })
+      .collect();
+
+    let class_data_off = data.offset();
+    let mut class_data_count = 0;
+    let class_data_offs: Vec<u32> = self
+      .classes
+      .iter()
+      .zip(code_offs.iter())
+      .map(|(class, code_offs)| {
+        if class.is_empty() {
+          return 0;
+        }
+        class_data_count += 1;
+        let off = data.offset();
+        let bytes = &mut data.bytes;
+        write_uleb128(bytes, class.static_fields.len() as u32);
+        write_uleb128(bytes, class.instance_fields.len() as u32);
+        write_uleb128(bytes, class.direct_methods.len() as u32);
+        write_uleb128(bytes, class.virtual_methods.len() as u32);
+        for fields in [&class.static_fields, &class.instance_fields] {
+          let mut prev = 0;
+          for (field_idx, access_flags) in fields {
+            write_uleb128(bytes, field_idx - prev);
+            write_uleb128(bytes, *access_flags);
+            prev = *field_idx;
+          }
+        }
+        let mut code_offs = code_offs.iter();
+        for methods in [&class.direct_methods, &class.virtual_methods] {
+          let mut prev = 0;
+          for method in methods {
+            write_uleb128(bytes, method.method_idx - prev);
+            write_uleb128(bytes, method.access_flags);
+            write_uleb128(bytes, *code_offs.next().unwrap());
+            prev = method.method_idx;
+          }
+        }
+        off
+      })
+      .collect();
+
+    let static_values_off = data.offset();
+    let mut static_values_count = 0;
+    let static_values_offs: Vec<u32> = self
+      .classes
+      .iter()
+      .map(|class| match &class.static_values {
+        Some(values) => {
+          static_values_count += 1;
+          let off = data.offset();
+          data.bytes.extend_from_slice(values);
+          off
+        }
+        None => 0,
+      })
+      .collect();
+
+    let call_site_item_off = data.offset();
+    let call_site_offs: Vec<u32> = self
+      .call_sites
+      .iter()
+      .map(|encoded_array| {
+        let off = data.offset();
+        data.bytes.extend_from_slice(encoded_array);
+        off
+      })
+      .collect();
+    data.align();
+    let call_site_ids_off = data.offset();
+    for off in call_site_offs {
+      data.u32(off);
+    }
+    let method_handles_off = data.offset();
+    for (method_handle_type, field_or_method_id) in &self.method_handles {
+      data.u16(*method_handle_type);
+      data.u16(0);
+      data.u16(*field_or_method_id);
+      data.u16(0);
+    }
+
+    data.align();
+    let hidden_api_off = data.offset();
+    let has_hidden_api = self
+      .classes
+      .iter()
+      .any(|class| class.hidden_api_flags.is_some());
+    if has_hidden_api {
+      let start = data.bytes.len();
+      data.bytes.resize(start + 4 + self.classes.len() * 4, 0);
+      for (i, class) in self.classes.iter().enumerate() {
+        if let Some(flags) = &class.hidden_api_flags {
+          let offset = data.offset() - hidden_api_off;
+          data.bytes[start + 4 + i * 4..start + 8 + i * 4].copy_from_slice(&offset.to_le_bytes());
+          for flag in flags {
+            write_uleb128(&mut data.bytes, *flag);
+          }
+        }
+      }
+      let size = (data.bytes.len() - start) as u32;
+      data.bytes[start..start + 4].copy_from_slice(&size.to_le_bytes());
+      data.align();
+    }
+
+    let map_off = data.offset();
+    let mut map_items = vec![(0x0000, 1, 0)];
+    let mut section_off = header_size;
+    for (item_type, size, item_size) in [
+      (0x0001, self.strings.len(), 4),
+      (0x0002, self.types.len(), 4),
+      (0x0003, self.protos.len(), 12),
+      (0x0004, self.fields.len(), 8),
+      (0x0005, self.methods.len(), 8),
+      (0x0006, self.classes.len(), 32),
+    ] {
+      if size > 0 {
+        map_items.push((item_type, size as u32, section_off));
+      }
+      section_off += (size * item_size) as u32;
+    }
+    for (item_type, size, off) in [
+      (0x2002, self.strings.len() as u32, string_data_off),
+      (0x1001, type_list_count, type_list_off),
+      (0x2001, code_item_count, code_item_off),
+      (0x2000, class_data_count, class_data_off),
+      (0x2005, static_values_count, static_values_off),
+      (0x2006, self.call_sites.len() as u32, call_site_item_off),
+      (0x0007, self.call_sites.len() as u32, call_site_ids_off),
+      (0x0008, self.method_handles.len() as u32, method_handles_off),
+      (0xf000, has_hidden_api as u32, hidden_api_off),
+      (0x1000, 1, map_off),
+    ] {
+      if size > 0 {
+        map_items.push((item_type, size, off));
+      }
+    }
+    data.u32(map_items.len() as u32);
+    for (item_type, size, off) in map_items {
+      data.u16(item_type);
+      data.u16(0);
+      data.u32(size);
+      data.u32(off);
+    }
+
+    let file_size = data_off + data.bytes.len() as u32;
+    let mut out = Data {
+      base: 0,
+      bytes: vec![],
+    };
+    out.bytes.extend_from_slice(b"dex\n035\0");
+    out.u32(0);
+    out.bytes.extend_from_slice(&[0; 20]);
+    out.u32(file_size);
+    out.u32(self.header_size.unwrap_or(HEADER_SIZE));
+    out.u32(0x12345678);
+    out.u32(0);
+    out.u32(0);
+    out.u32(map_off);
+    let mut section_off = header_size;
+    for (size, item_size) in [
+      (self.strings.len(), 4),
+      (self.types.len(), 4),
+      (self.protos.len(), 12),
+      (self.fields.len(), 8),
+      (self.methods.len(), 8),
+      (self.classes.len(), 32),
+    ] {
+      out.u32(size as u32);
+      out.u32(if size == 0 { 0 } else { section_off });
+      section_off += (size * item_size) as u32;
+    }
+    out.u32(data.bytes.len() as u32);
+    out.u32(data_off);
+    out.bytes.resize(header_size as usize, 0);
+
+    for off in string_offs {
+      out.u32(off);
+    }
+    for string_idx in &self.types {
+      out.u32(*string_idx);
+    }
+    for ((shorty_idx, return_type_idx, _), params_off) in self.protos.iter().zip(proto_params_offs)
+    {
+      out.u32(*shorty_idx);
+      out.u32(*return_type_idx as u32);
+      out.u32(params_off);
+    }
+    for (class_idx, type_idx, name_idx) in &self.fields {
+      out.u16(*class_idx);
+      out.u16(*type_idx);
+      out.u32(*name_idx);
+    }
+    for (class_idx, proto_idx, name_idx) in &self.methods {
+      out.u16(*class_idx);
+      out.u16(*proto_idx);
+      out.u32(*name_idx);
+    }
+    for (((class, interfaces_off), class_data_off), static_values_off) in self
+      .classes
+      .iter()
+      .zip(interfaces_offs)
+      .zip(class_data_offs)
+      .zip(static_values_offs)
+    {
+      out.u32(class.class_idx as u32);
+      out.u32(class.access_flags);
+      out.u32(class.superclass_idx.map_or(NO_INDEX, |idx| idx as u32));
+      out.u32(interfaces_off);
+      out.u32(class.source_file_idx.unwrap_or(NO_INDEX));
+      out.u32(0);
+      out.u32(class_data_off);
+      out.u32(static_values_off);
+    }
+    out.bytes.extend_from_slice(&data.bytes);
+    out.bytes
+  }
+}
+
+impl ClassDef {
+  pub fn new(class_idx: u16) -> Self {
+    Self {
+      class_idx,
+      access_flags: 0x0001,
+      superclass_idx: None,
+      interfaces: vec![],
+      source_file_idx: None,
+      static_fields: vec![],
+      instance_fields: vec![],
+      direct_methods: vec![],
+      virtual_methods: vec![],
+      hidden_api_flags: None,
+      static_values: None,
+    }
+  }
+
+  pub fn access_flags(mut self, access_flags: u32) -> Self {
+    self.access_flags = access_flags;
+    self
+  }
+
+  pub fn superclass(mut self, superclass_idx: u16) -> Self {
+    self.superclass_idx = Some(superclass_idx);
+    self
+  }
+
+  pub fn interface(mut self, interface_idx: u16) -> Self {
+    self.interfaces.push(interface_idx);
+    self
+  }
+
+  pub fn source_file(mut self, string_idx: u32) -> Self {
+    self.source_file_idx = Some(string_idx);
+    self
+  }
+
+  pub fn static_field(mut self, field_idx: u32, access_flags: u32) -> Self {
+    self.static_fields.push((field_idx, access_flags));
+    self
+  }
+
+  pub fn instance_field(mut self, field_idx: u32, access_flags: u32) -> Self {
+    self.instance_fields.push((field_idx, access_flags));
+    self
+  }
+
+  pub fn direct_method(mut self, method: Method) -> Self {
+    self.direct_methods.push(method);
+    self
+  }
+
+  pub fn virtual_method(mut self, method: Method) -> Self {
+    self.virtual_methods.push(method);
+    self
+  }
+
+  /// An encoded `encoded_array_item`, size included.
+  pub fn static_values(mut self, encoded_array: &[u8]) -> Self {
+    self.static_values = Some(encoded_array.to_vec());
+    self
+  }
+
+  /// Hiddenapi flags of every member, in class data order.
+  pub fn hidden_api_flags(mut self, flags: &[u32]) -> Self {
+    self.hidden_api_flags = Some(flags.to_vec());
+    self
+  }
+
+  fn is_empty(&self) -> bool {
+    self.static_fields.is_empty()
+      && self.instance_fields.is_empty()
+      && self.direct_methods.is_empty()
+      && self.virtual_methods.is_empty()
+  }
+}
+
+impl Method {
+  pub fn new(method_idx: u32, access_flags: u32) -> Self {
+    Self {
+      method_idx,
+      access_flags,
+      code: None,
+    }
+  }
+
+  pub fn code(mut self, registers_size: u16, ins_size: u16, outs_size: u16, insns: &[u16]) -> Self {
+    self.code = Some(Code {
+      registers_size,
+      ins_size,
+      outs_size,
+      insns: insns.to_vec(),
+      tries: vec![],
+    });
+    self
+  }
+
+  /// Adds a try block to the code set by `code`, with its own handler.
+  pub fn try_block(
+    mut self,
+    start_addr: u32,
+    insn_count: u16,
+    handlers: &[(u32, u32)],
+    catch_all_addr: Option<u32>,
+  ) -> Self {
+    let code = self.code.as_mut().expect("try_block needs code");
+    code.tries.push(TryBlock {
+      start_addr,
+      insn_count,
+      handlers: handlers.to_vec(),
+      catch_all_addr,
+    });
+    self
+  }
+}
+
+struct Data {
+  base: u32,
+  bytes: Vec<u8>,
+}
+
+impl Data {
+  fn offset(&self) -> u32 {
+    self.base + self.bytes.len() as u32
+  }
+
+  fn align(&mut self) {
+    while !self.offset().is_multiple_of(4) {
+      self.bytes.push(0);
+    }
+  }
+
+  fn u16(&mut self, value: u16) {
+    self.bytes.extend_from_slice(&value.to_le_bytes());
+  }
+
+  fn u32(&mut self, value: u32) {
+    self.bytes.extend_from_slice(&value.to_le_bytes());
+  }
+}
+
+/// The padding, `try_item`s and `encoded_catch_handler_list` after the insns
+/// of `code`, one handler per try in the same order.
+fn write_tries(data: &mut Data, code: &Code) {
+  if code.tries.is_empty() {
+    return;
+  }
+  if code.insns.len() % 2 == 1 {
+    data.u16(0);
+  }
+  let mut list = vec![];
+  write_uleb128(&mut list, code.tries.len() as u32);
+  let mut handler_offs = vec![];
+  for try_block in &code.tries {
+    handler_offs.push(list.len() as u16);
+    let size = try_block.handlers.len() as i32;
+    let size = if try_block.catch_all_addr.is_some() {
+      -size
+    } else {
+      size
+    };
+    write_sleb128(&mut list, size);
+    for (type_idx, addr) in &try_block.handlers {
+      write_uleb128(&mut list, *type_idx);
+      write_uleb128(&mut list, *addr);
+    }
+    if let Some(catch_all_addr) = try_block.catch_all_addr {
+      write_uleb128(&mut list, catch_all_addr);
+    }
+  }
+  for (try_block, handler_off) in code.tries.iter().zip(handler_offs) {
+    data.u32(try_block.start_addr);
+    data.u16(try_block.insn_count);
+    data.u16(handler_off);
+  }
+  data.bytes.extend_from_slice(&list);
+}
+
+fn write_sleb128(bytes: &mut Vec<u8>, mut value: i32) {
+  loop {
+    let byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+      bytes.push(byte);
+      return;
+    }
+    bytes.push(byte | 0x80);
+  }
+}
+
+fn write_uleb128(bytes: &mut Vec<u8>, mut value: u32) {
+  loop {
+    let byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      bytes.push(byte);
+      return;
+    }
+    bytes.push(byte | 0x80);
+  }
+}
@@ -1,11 +1,30 @@
-use base::error::Error;
+use base::{
+  descriptor::normalize_type_name,
+  error::{Error, ErrorKind, Warning},
+  hash::to_hex,
+  indent::{Indent, IndentDisplay},
+};
 use clap::Parser;
+use class_parser::{
+  constant_pool::{ConstantType, CpIndex},
+  diff::ClassDiff,
+  hierarchy::ClassPath,
+  raw_class::ClassFile,
+  verify::Verification,
+};
 use class_parser_tui::{app::App, restore_terminal, run_app, setup_terminal};
+use dex_parser::{limits::DexLimits, ParseEvent};
 use simplelog::*;
 
 extern crate simplelog;
 
-use std::{path::Path, time::Duration};
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{self, BufWriter, Write},
+  path::{Path, PathBuf},
+  time::Duration,
+};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -16,6 +35,88 @@ struct Args {
   class: bool,
   #[clap(short, long, default_value = "false")]
   dex: bool,
+  /// Compare two class files
+  #[clap(long, num_args = 2, value_names = ["OLD", "NEW"])]
+  diff: Option<Vec<String>>,
+  /// Print method/field reference counts of dex files against the 64K limit
+  #[clap(long, num_args = 1.., value_name = "DEX")]
+  limits: Option<Vec<String>>,
+  /// Milliseconds between TUI redraws when no key is pressed
+  #[clap(long, default_value = "1000", value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+  tick_rate: u64,
+  /// Capture mouse events in the TUI, which disables terminal text selection
+  #[clap(long, default_value = "false")]
+  mouse: bool,
+  /// Bytes to skip before the class file, e.g. a length prefix
+  #[clap(long, default_value = "0", value_name = "N")]
+  skip: usize,
+  /// Levels of nested attributes to expand in method details, e.g. a Code
+  /// attribute's StackMapTable; deeper ones show as `...`
+  #[clap(long, value_name = "N")]
+  max_depth: Option<usize>,
+  /// Print the classes a class file references instead of opening the TUI;
+  /// for a dex file, the classes it uses but does not define
+  #[clap(long, default_value = "false")]
+  deps: bool,
+  /// Print the dex header and each class as soon as it is parsed instead of
+  /// dumping the file once it is fully read
+  #[clap(long, default_value = "false")]
+  stream: bool,
+  /// Check the structure of a class file (or with `--dex`, a dex file's map
+  /// and methods), print the issues found and exit with status 1 if any
+  #[clap(long, default_value = "false")]
+  validate: bool,
+  /// With `--validate`, also check each method's code: locals within
+  /// `max_locals` and `ldc` loading a constant it can load
+  #[clap(long, default_value = "false")]
+  check_code: bool,
+  /// Print the SHA-256 of a class file like `sha256sum`, or with `--dex`,
+  /// of each class's class data followed by its descriptor
+  #[clap(long, default_value = "false")]
+  hash: bool,
+  /// Write text output to FILE instead of stdout
+  #[clap(short, long, value_name = "FILE")]
+  output: Option<String>,
+  /// After decoding, print how often each opcode occurred and which are
+  /// unknown; class files print this instead of opening the TUI
+  #[clap(long, default_value = "false")]
+  opcode_coverage: bool,
+  /// Print how often each mnemonic occurs, most frequent first, instead of
+  /// opening the TUI or dumping the dex file
+  #[clap(long, default_value = "false")]
+  histogram: bool,
+  /// Print the Utf8 and String constants of a class file, one per line with
+  /// its index, without parsing anything past the constant pool
+  #[clap(long, default_value = "false")]
+  strings: bool,
+  /// Print the resolved constant pool of a class file sorted by kind, then
+  /// value, one constant per line without indices, so the output of two
+  /// builds can be diffed
+  #[clap(long, default_value = "false")]
+  sorted_pool: bool,
+  /// Write the raw bytecode of METHOD (a name, or a name and descriptor like
+  /// `compute(I)I`) to the output instead of opening the TUI or dumping the
+  /// dex file; for dex, the little-endian `insns` code units
+  #[clap(long, value_name = "METHOD")]
+  extract_code: Option<String>,
+  /// Indent the dex dump with N spaces per level instead of tabs, also
+  /// between a label and its value
+  #[clap(long, value_name = "N")]
+  indent: Option<usize>,
+  /// List CLASS's fields and methods with those it inherits, marking where
+  /// each is declared; the superclasses are looked up in the --path file or
+  /// directory of class files (dex files with --dex, e.g. a multidex app)
+  #[clap(long, value_name = "CLASS")]
+  follow_superclass: Option<String>,
+  /// List the methods of a dex file that read or write FIELD, a name or a
+  /// smali reference like `La/Foo;->count:I`, under each matching field
+  #[clap(long, value_name = "FIELD")]
+  field_xref: Option<String>,
+  /// Print only tallies of classes, members, strings and types, and for a
+  /// class file its constant pool size and attributes by name, one
+  /// `key: value` per line
+  #[clap(long, default_value = "false")]
+  count: bool,
 }
 
 fn parse_file(path: String) -> Result<Vec<u8>, Error> {
@@ -24,18 +125,95 @@ fn parse_file(path: String) -> Result<Vec<u8>, Error> {
   Ok(bytes)
 }
 
-fn run_class(arg: Args) -> Result<(), Error> {
+/// One `mnemonic<TAB>count` line per mnemonic, most frequent first and ties
+/// in name order.
+fn write_histogram(out: &mut dyn Write, histogram: HashMap<&str, usize>) -> io::Result<()> {
+  let mut histogram: Vec<_> = histogram.into_iter().collect();
+  histogram.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+  for (mnemonic, count) in histogram {
+    writeln!(out, "{}\t{}", mnemonic, count)?;
+  }
+  Ok(())
+}
+
+/// `path` itself, or the files with `extension` under the directory `path`,
+/// in name order.
+fn input_files(path: &Path, extension: &str) -> Result<Vec<PathBuf>, Error> {
+  if !path.is_dir() {
+    return Ok(vec![path.to_path_buf()]);
+  }
+  let mut files = vec![];
+  let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<_, _>>()?;
+  entries.sort();
+  for entry in entries {
+    if entry.is_dir() {
+      files.extend(input_files(&entry, extension)?);
+    } else if entry.extension().is_some_and(|ext| ext == extension) {
+      files.push(entry);
+    }
+  }
+  Ok(files)
+}
+
+/// Where text output goes: `path` if given, stdout otherwise.
+fn open_output(path: Option<&str>) -> Result<Box<dyn Write>, Error> {
+  Ok(match path {
+    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+    None => Box::new(io::stdout()),
+  })
+}
+
+fn run_class(arg: Args, out: &mut dyn Write) -> Result<(), Error> {
   let class_file = parse_file(arg.path)?;
-  let class_file = class_parser::parse(&class_file)?;
-  let mut terminal = setup_terminal()?;
+  let (class_file, remainder) = class_parser::parse_at_offset(&class_file, arg.skip)?;
+  let mut warnings = class_file.validate();
+  if !remainder.is_empty() {
+    warnings.push(Warning::TrailingBytes {
+      count: remainder.len(),
+    });
+  }
+  for warning in warnings {
+    eprintln!("warning: {}", warning);
+  }
+  if arg.opcode_coverage {
+    write!(out, "{}", class_file.opcode_coverage())?;
+    return Ok(());
+  }
+  if arg.histogram {
+    write_histogram(out, class_file.opcode_histogram())?;
+    return Ok(());
+  }
+  if arg.deps {
+    for name in class_file.referenced_types() {
+      writeln!(out, "{}", name)?;
+    }
+    return Ok(());
+  }
+  if arg.sorted_pool {
+    for constant in class_file.constant_pool_sorted() {
+      writeln!(out, "{}", constant)?;
+    }
+    return Ok(());
+  }
+  if let Some(query) = &arg.extract_code {
+    let method = class_file.find_method(query)?;
+    let code = method.code().ok_or_else(|| ErrorKind::NoCode {
+      method: format!("{}{}", method.name(), method.descriptor()),
+    })?;
+    out.write_all(code.raw_code())?;
+    return Ok(());
+  }
+  let mut terminal = setup_terminal(arg.mouse)?;
 
   // create app and run it
-  let tick_rate = Duration::from_millis(250);
-  let app = App::new(&class_file);
+  let tick_rate = Duration::from_millis(arg.tick_rate);
+  let app = App::new(&class_file, arg.max_depth);
   let res = run_app(&mut terminal, app, tick_rate);
 
   // restore terminal
-  restore_terminal(terminal)?;
+  restore_terminal(terminal, arg.mouse)?;
 
   if let Err(err) = res {
     println!("{:?}", err)
@@ -44,28 +222,285 @@ fn run_class(arg: Args) -> Result<(), Error> {
   Ok(())
 }
 
-fn run_dex(arg: Args) -> Result<(), Error> {
+fn run_strings(arg: Args, out: &mut dyn Write) -> Result<(), Error> {
+  let bytes = parse_file(arg.path)?;
+  let len = bytes.len();
+  let bytes = bytes.get(arg.skip..).ok_or(ErrorKind::OffsetOutOfRange {
+    offset: arg.skip,
+    len,
+  })?;
+  let constant_pool = ClassFile::parse_constant_pool_only(bytes)?;
+  for (index, constant) in (1u16..).zip(&constant_pool) {
+    match constant.info() {
+      ConstantType::Utf8(value) => writeln!(out, "#{}\tUtf8\t{:?}", index, value)?,
+      ConstantType::String(string_index) => {
+        let value = CpIndex(*string_index).resolve_utf8(&constant_pool);
+        writeln!(out, "#{}\tString\t{:?}", index, value.unwrap_or("?"))?
+      }
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+/// Prints the checks' outcome and returns whether they all passed.
+fn run_validate(arg: Args, out: &mut dyn Write) -> Result<bool, Error> {
+  let bytes = parse_file(arg.path)?;
+  let len = bytes.len();
+  let bytes = bytes.get(arg.skip..).ok_or(ErrorKind::OffsetOutOfRange {
+    offset: arg.skip,
+    len,
+  })?;
+  if arg.dex {
+    let issues: Vec<String> = match dex_parser::parse_catching(bytes) {
+      Ok(dex_file) => dex_file
+        .validate()
+        .iter()
+        .map(ToString::to_string)
+        .collect(),
+      Err(error) => vec![format!("file: {}", error)],
+    };
+    if issues.is_empty() {
+      writeln!(out, "PASS")?;
+    } else {
+      writeln!(out, "FAIL: {} issue(s)", issues.len())?;
+      for issue in &issues {
+        writeln!(out, "  {}", issue)?;
+      }
+    }
+    return Ok(issues.is_empty());
+  }
+  let verification = if arg.check_code {
+    Verification::of_bytes_with_code_checks(bytes)
+  } else {
+    Verification::of_bytes(bytes)
+  };
+  write!(out, "{}", verification)?;
+  Ok(verification.passed())
+}
+
+fn run_count(arg: Args, out: &mut dyn Write) -> Result<(), Error> {
+  let bytes = parse_file(arg.path)?;
+  let len = bytes.len();
+  let bytes = bytes.get(arg.skip..).ok_or(ErrorKind::OffsetOutOfRange {
+    offset: arg.skip,
+    len,
+  })?;
+  let counts = if arg.dex {
+    dex_parser::parse_catching(bytes)?.counts()
+  } else {
+    class_parser::parse(bytes)?.counts()
+  };
+  for (key, value) in counts {
+    writeln!(out, "{}: {}", key, value)?;
+  }
+  Ok(())
+}
+
+fn run_hash(arg: Args, out: &mut dyn Write) -> Result<(), Error> {
+  let bytes = parse_file(arg.path.clone())?;
+  let len = bytes.len();
+  let bytes = bytes.get(arg.skip..).ok_or(ErrorKind::OffsetOutOfRange {
+    offset: arg.skip,
+    len,
+  })?;
+  if arg.dex {
+    let dex_file = dex_parser::parse_catching(bytes)?;
+    for class_def in dex_file.class_defs() {
+      let hash = class_def.content_hash(bytes);
+      writeln!(out, "{}  {}", to_hex(&hash), class_def.descriptor())?;
+    }
+    return Ok(());
+  }
+  let hash = ClassFile::content_hash(bytes);
+  writeln!(out, "{}  {}", to_hex(&hash), arg.path)?;
+  Ok(())
+}
+
+fn run_dex(arg: Args, out: &mut dyn Write) -> Result<(), Error> {
+  // Keep the log out of stdout, which may be piped: extracted bytecode, the
+  // histogram, dependency and xref listings.
   CombinedLogger::init(vec![TermLogger::new(
     LevelFilter::Info,
     Config::default(),
-    TerminalMode::Mixed,
+    TerminalMode::Stderr,
     ColorChoice::Auto,
   )])
   .unwrap();
 
-  let dex_file = parse_file(arg.path)?;
-  let dex_file = dex_parser::parse(&dex_file)?;
-  print!("{}", dex_file);
+  let indent = Indent::new(arg.indent);
+  if arg.stream {
+    // The visitor can't fail, so keep the first write error for afterwards.
+    let mut written = Ok(());
+    dex_parser::parse_with_visitor_catching(&parse_file(arg.path)?, |event| {
+      if written.is_err() {
+        return;
+      }
+      written = match event {
+        ParseEvent::HeaderParsed(header) => write!(out, "header:\n{}", header),
+        ParseEvent::StringParsed(..) => Ok(()),
+        ParseEvent::ClassParsed(index, class_def) => {
+          write!(
+            out,
+            "Class #{}: \n{} ",
+            index,
+            class_def.display_indent(indent)
+          )
+        }
+      };
+    })?;
+    return Ok(written?);
+  }
+
+  #[cfg(feature = "mmap")]
+  let dex_file = dex_parser::parse_mmap_catching(&arg.path)?;
+  #[cfg(not(feature = "mmap"))]
+  let dex_file = dex_parser::parse_catching(&parse_file(arg.path)?)?;
+  if arg.deps {
+    for name in dex_file.referenced_types() {
+      writeln!(out, "{}", name)?;
+    }
+    return Ok(());
+  }
+  if arg.histogram {
+    write_histogram(out, dex_file.opcode_histogram())?;
+    return Ok(());
+  }
+  if let Some(query) = &arg.field_xref {
+    // Each matching field as `La/Foo;->count:I`, then the methods accessing
+    // it, one indented smali reference per line.
+    let context = dex_file.context();
+    let mut found = false;
+    for field_idx in 0..dex_file.field_ref_count() {
+      let field = context.get_field_id(field_idx);
+      let reference = format!(
+        "{}->{}:{}",
+        field.class_descriptor(),
+        field.name(),
+        field.descriptor()
+      );
+      if field.name() != query && reference != *query {
+        continue;
+      }
+      found = true;
+      writeln!(out, "{}", reference)?;
+      for method in dex_file.field_accessors(field_idx as u32) {
+        writeln!(
+          out,
+          "\t{}->{}{}",
+          method.class_descriptor(),
+          method.name(),
+          method.proto().descriptor()
+        )?;
+      }
+    }
+    if !found {
+      eprintln!("no field matches {}", query);
+    }
+    return Ok(());
+  }
+  if let Some(query) = &arg.extract_code {
+    let method = dex_file.find_method(query)?;
+    let code_item = method.code().ok_or_else(|| ErrorKind::NoCode {
+      method: format!("{}{}", method.name(), method.descriptor()),
+    })?;
+    let bytes: Vec<u8> = code_item
+      .raw_insns()
+      .iter()
+      .flat_map(|unit| unit.to_le_bytes())
+      .collect();
+    out.write_all(&bytes)?;
+    return Ok(());
+  }
+  write!(out, "{}", dex_file.display_indent(indent))?;
+  for warning in dex_file.validate() {
+    eprintln!("warning: {}", warning);
+  }
+  if arg.opcode_coverage {
+    write!(out, "{}", dex_file.opcode_coverage())?;
+  }
 
   Ok(())
 }
 
+fn run_diff(old: String, new: String, out: &mut dyn Write) -> Result<(), Error> {
+  let old = parse_file(old)?;
+  let old = class_parser::parse(&old)?;
+  let new = parse_file(new)?;
+  let new = class_parser::parse(&new)?;
+  write!(out, "{}", ClassDiff::new(&old, &new))?;
+  Ok(())
+}
+
+fn run_follow_superclass(arg: Args, class: &str, out: &mut dyn Write) -> Result<(), Error> {
+  let path = Path::new(&arg.path);
+  if path.extension().is_some_and(|ext| ext == "jar") {
+    Err(ErrorKind::UnsupportedFormat("jar".to_string()))?;
+  }
+  let not_found = || ErrorKind::ClassNotFound {
+    name: class.to_string(),
+  };
+  if arg.dex {
+    let mut dex_files = vec![];
+    for file in input_files(path, "dex")? {
+      dex_files.push(dex_parser::parse_catching(&std::fs::read(file)?)?);
+    }
+    let descriptor = normalize_type_name(&class.replace('.', "/"));
+    let members =
+      dex_parser::hierarchy::inherited_members(&dex_files, &descriptor).ok_or_else(not_found)?;
+    write!(out, "{}", members)?;
+    return Ok(());
+  }
+  let mut classes = vec![];
+  for file in input_files(path, "class")? {
+    classes.push(class_parser::parse(&std::fs::read(file)?)?);
+  }
+  let class_path = ClassPath::new(&classes);
+  let class = class_path.get(class).ok_or_else(not_found)?;
+  write!(out, "{}", class_path.inherited_members(class))?;
+  Ok(())
+}
+
+fn run_limits(paths: Vec<String>, out: &mut dyn Write) -> Result<(), Error> {
+  let mut dex_files = vec![];
+  for path in paths {
+    dex_files.push(dex_parser::parse_catching(&parse_file(path)?)?);
+  }
+  let limits = DexLimits::new(&dex_files);
+  write!(out, "{}", limits)?;
+  for warning in limits.warnings() {
+    writeln!(out, "warning: {}", warning)?;
+  }
+  Ok(())
+}
+
 fn main() -> Result<(), Error> {
   let arg = Args::parse();
-  if arg.class {
-    run_class(arg)?
+  let mut out = open_output(arg.output.as_deref())?;
+  if let Some(mut paths) = arg.diff {
+    let new = paths.pop().unwrap();
+    let old = paths.pop().unwrap();
+    run_diff(old, new, &mut out)?
+  } else if let Some(paths) = arg.limits {
+    run_limits(paths, &mut out)?
+  } else if arg.validate {
+    if !run_validate(arg, &mut out)? {
+      out.flush()?;
+      std::process::exit(1);
+    }
+  } else if arg.hash {
+    run_hash(arg, &mut out)?
+  } else if arg.count {
+    run_count(arg, &mut out)?
+  } else if let Some(class) = arg.follow_superclass.clone() {
+    run_follow_superclass(arg, &class, &mut out)?
+  } else if arg.strings {
+    run_strings(arg, &mut out)?
+  } else if arg.class {
+    run_class(arg, &mut out)?
   } else if arg.dex {
-    run_dex(arg)?
+    run_dex(arg, &mut out)?
   }
+  out.flush()?;
   Ok(())
 }